@@ -0,0 +1,2 @@
+pub const CONTRACT_NAME: &str = "eris-cw20-wrapper";
+pub const CONTRACT_VERSION: &str = env!("CARGO_PKG_VERSION");
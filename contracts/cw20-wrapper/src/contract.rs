@@ -0,0 +1,108 @@
+use cosmwasm_std::{
+    entry_point, to_binary, Binary, Deps, DepsMut, Env, MessageInfo, Response, StdResult,
+};
+use cw2::set_contract_version;
+use cw20_base::allowances::{
+    execute_decrease_allowance, execute_increase_allowance, execute_send_from,
+    execute_transfer_from, query_allowance,
+};
+use cw20_base::contract::{execute_send, execute_transfer, query_balance, query_token_info};
+use cw20_base::enumerable::{query_all_accounts, query_all_allowances};
+
+use eris::cw20_wrapper::{ExecuteMsg, InstantiateMsg, MigrateMsg, QueryMsg};
+
+use crate::constants::{CONTRACT_NAME, CONTRACT_VERSION};
+use crate::error::ContractResult;
+use crate::{execute, queries};
+
+#[entry_point]
+pub fn instantiate(
+    mut deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    msg: InstantiateMsg,
+) -> ContractResult {
+    let res = execute::instantiate(deps.branch(), env, info, msg)?;
+    set_contract_version(deps.storage, CONTRACT_NAME, CONTRACT_VERSION)?;
+    Ok(res)
+}
+
+#[entry_point]
+pub fn execute(deps: DepsMut, env: Env, info: MessageInfo, msg: ExecuteMsg) -> ContractResult {
+    match msg {
+        ExecuteMsg::Wrap {
+            receiver,
+        } => execute::wrap(deps, env, info, receiver),
+        ExecuteMsg::Unwrap {
+            amount,
+            receiver,
+        } => execute::unwrap(deps, env, info, amount, receiver),
+        ExecuteMsg::Transfer {
+            recipient,
+            amount,
+        } => Ok(execute_transfer(deps, env, info, recipient, amount)?),
+        ExecuteMsg::Send {
+            contract,
+            amount,
+            msg,
+        } => Ok(execute_send(deps, env, info, contract, amount, msg)?),
+        ExecuteMsg::IncreaseAllowance {
+            spender,
+            amount,
+            expires,
+        } => Ok(execute_increase_allowance(deps, env, info, spender, amount, expires)?),
+        ExecuteMsg::DecreaseAllowance {
+            spender,
+            amount,
+            expires,
+        } => Ok(execute_decrease_allowance(deps, env, info, spender, amount, expires)?),
+        ExecuteMsg::TransferFrom {
+            owner,
+            recipient,
+            amount,
+        } => Ok(execute_transfer_from(deps, env, info, owner, recipient, amount)?),
+        ExecuteMsg::SendFrom {
+            owner,
+            contract,
+            amount,
+            msg,
+        } => Ok(execute_send_from(deps, env, info, owner, contract, amount, msg)?),
+    }
+}
+
+#[entry_point]
+pub fn query(deps: Deps, _env: Env, msg: QueryMsg) -> StdResult<Binary> {
+    match msg {
+        QueryMsg::Config {} => to_binary(&queries::config(deps)?),
+        QueryMsg::ExchangeRate {} => to_binary(&queries::exchange_rate(deps)?),
+        QueryMsg::UnderlyingBalance {
+            address,
+        } => to_binary(&queries::underlying_balance(deps, address)?),
+        QueryMsg::Balance {
+            address,
+        } => to_binary(&query_balance(deps, address)?),
+        QueryMsg::TokenInfo {} => to_binary(&query_token_info(deps)?),
+        QueryMsg::Allowance {
+            owner,
+            spender,
+        } => to_binary(&query_allowance(deps, owner, spender)?),
+        QueryMsg::AllAllowances {
+            owner,
+            start_after,
+            limit,
+        } => to_binary(&query_all_allowances(deps, owner, start_after, limit)?),
+        QueryMsg::AllAccounts {
+            start_after,
+            limit,
+        } => to_binary(&query_all_accounts(deps, start_after, limit)?),
+    }
+}
+
+#[entry_point]
+pub fn migrate(deps: DepsMut, _env: Env, _msg: MigrateMsg) -> ContractResult {
+    set_contract_version(deps.storage, CONTRACT_NAME, CONTRACT_VERSION)?;
+
+    Ok(Response::new()
+        .add_attribute("new_contract_name", CONTRACT_NAME)
+        .add_attribute("new_contract_version", CONTRACT_VERSION))
+}
@@ -0,0 +1,15 @@
+use cosmwasm_std::{Response, StdError};
+use cw20_base::ContractError as Cw20BaseError;
+use thiserror::Error;
+
+pub type ContractResult = Result<Response, ContractError>;
+
+/// This enum describes cw20 wrapper contract errors
+#[derive(Error, Debug, PartialEq)]
+pub enum ContractError {
+    #[error("{0}")]
+    Std(#[from] StdError),
+
+    #[error("{0}")]
+    Cw20Base(#[from] Cw20BaseError),
+}
@@ -0,0 +1,110 @@
+use cosmwasm_std::{coins, BankMsg, Coin, DepsMut, Env, MessageInfo, StdError, StdResult, Uint128};
+use cw20::MinterResponse;
+use cw20_base::contract::{execute_burn, execute_mint};
+use cw20_base::msg::InstantiateMsg as Cw20InstantiateMsg;
+
+use eris::cw20_wrapper::InstantiateMsg;
+use eris::hub::{QueryMsg as HubQueryMsg, StakeTokenResponse};
+
+use crate::error::ContractResult;
+use crate::state::State;
+
+//--------------------------------------------------------------------------------------------------
+// Instantiation
+//--------------------------------------------------------------------------------------------------
+
+pub fn instantiate(
+    mut deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    msg: InstantiateMsg,
+) -> ContractResult {
+    let state = State::default();
+
+    let hub = deps.api.addr_validate(&msg.hub)?;
+    let stake_token: StakeTokenResponse =
+        deps.querier.query_wasm_smart(&hub, &HubQueryMsg::StakeToken {})?;
+
+    state.hub.save(deps.storage, &hub)?;
+    state.stake_denom.save(deps.storage, &stake_token.denom)?;
+
+    // the contract itself is the only minter, minting only against locked stake tokens
+    let cw20_msg = Cw20InstantiateMsg {
+        name: msg.name,
+        symbol: msg.symbol,
+        decimals: msg.decimals,
+        initial_balances: vec![],
+        mint: Some(MinterResponse {
+            minter: env.contract.address.to_string(),
+            cap: None,
+        }),
+        marketing: None,
+    };
+
+    Ok(cw20_base::contract::instantiate(deps.branch(), env, info, cw20_msg)?)
+}
+
+//--------------------------------------------------------------------------------------------------
+// Wrapping
+//--------------------------------------------------------------------------------------------------
+
+pub fn wrap(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    receiver: Option<String>,
+) -> ContractResult {
+    let state = State::default();
+
+    let stake_denom = state.stake_denom.load(deps.storage)?;
+    let amount = parse_received_fund(&info.funds, &stake_denom)?;
+    let receiver = receiver.unwrap_or_else(|| info.sender.to_string());
+
+    let minter = MessageInfo {
+        sender: env.contract.address.clone(),
+        funds: vec![],
+    };
+    let res = execute_mint(deps, env, minter, receiver.clone(), amount)?;
+
+    Ok(res
+        .add_attribute("action", "eriscw20/wrap")
+        .add_attribute("sender", info.sender)
+        .add_attribute("receiver", receiver))
+}
+
+pub fn unwrap(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    amount: Uint128,
+    receiver: Option<String>,
+) -> ContractResult {
+    let state = State::default();
+
+    let stake_denom = state.stake_denom.load(deps.storage)?;
+    let receiver = match receiver {
+        Some(receiver) => deps.api.addr_validate(&receiver)?,
+        None => info.sender.clone(),
+    };
+
+    let res = execute_burn(deps, env, info, amount)?;
+
+    let msg = BankMsg::Send {
+        to_address: receiver.to_string(),
+        amount: coins(amount.u128(), stake_denom),
+    };
+
+    Ok(res
+        .add_message(msg)
+        .add_attribute("action", "eriscw20/unwrap")
+        .add_attribute("receiver", receiver))
+}
+
+fn parse_received_fund(funds: &[Coin], denom: &str) -> StdResult<Uint128> {
+    match funds {
+        [fund] if fund.denom == denom && !fund.amount.is_zero() => Ok(fund.amount),
+        _ => {
+            Err(StdError::generic_err(format!("must deposit a non-zero amount of {} only", denom)))
+        },
+    }
+}
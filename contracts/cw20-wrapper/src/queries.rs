@@ -0,0 +1,38 @@
+use cosmwasm_std::{Deps, StdResult};
+use cw20_base::contract::{query_balance, query_token_info};
+
+use eris::cw20_wrapper::{ConfigResponse, ExchangeRateResponse, UnderlyingBalanceResponse};
+use eris::hub::{QueryMsg as HubQueryMsg, StateResponse};
+
+use crate::state::State;
+
+pub fn config(deps: Deps) -> StdResult<ConfigResponse> {
+    let state = State::default();
+
+    Ok(ConfigResponse {
+        hub: state.hub.load(deps.storage)?,
+        stake_denom: state.stake_denom.load(deps.storage)?,
+    })
+}
+
+pub fn exchange_rate(deps: Deps) -> StdResult<ExchangeRateResponse> {
+    Ok(ExchangeRateResponse {
+        exchange_rate: query_hub_state(deps)?.exchange_rate,
+        total_wrapped: query_token_info(deps)?.total_supply,
+    })
+}
+
+pub fn underlying_balance(deps: Deps, address: String) -> StdResult<UnderlyingBalanceResponse> {
+    let balance = query_balance(deps, address)?.balance;
+    let exchange_rate = query_hub_state(deps)?.exchange_rate;
+
+    Ok(UnderlyingBalanceResponse {
+        balance,
+        utoken: balance * exchange_rate,
+    })
+}
+
+fn query_hub_state(deps: Deps) -> StdResult<StateResponse> {
+    let hub = State::default().hub.load(deps.storage)?;
+    deps.querier.query_wasm_smart(hub, &HubQueryMsg::State {})
+}
@@ -0,0 +1,18 @@
+use cosmwasm_std::Addr;
+use cw_storage_plus::Item;
+
+pub(crate) struct State<'a> {
+    /// Hub whose stake token is wrapped
+    pub hub: Item<'a, Addr>,
+    /// Denom of the wrapped stake token
+    pub stake_denom: Item<'a, String>,
+}
+
+impl Default for State<'static> {
+    fn default() -> Self {
+        Self {
+            hub: Item::new("hub"),
+            stake_denom: Item::new("stake_denom"),
+        }
+    }
+}
@@ -0,0 +1,383 @@
+use std::str::FromStr;
+
+use cosmwasm_std::testing::{
+    mock_dependencies, mock_env, mock_info, MockApi, MockQuerier, MockStorage, MOCK_CONTRACT_ADDR,
+};
+use cosmwasm_std::{
+    coin, coins, from_binary, to_binary, Addr, BankMsg, ContractResult, CosmosMsg, Decimal, Deps,
+    OwnedDeps, StdError, SubMsg, SystemError, SystemResult, Uint128, WasmQuery,
+};
+use cw20::{BalanceResponse, MinterResponse, TokenInfoResponse};
+use cw20_base::ContractError as Cw20BaseError;
+use serde::de::DeserializeOwned;
+
+use eris::cw20_wrapper::{
+    ConfigResponse, ExchangeRateResponse, ExecuteMsg, InstantiateMsg, QueryMsg,
+    UnderlyingBalanceResponse,
+};
+use eris::hub::{QueryMsg as HubQueryMsg, StakeTokenResponse, StateResponse};
+
+use crate::contract::{execute, instantiate, query};
+use crate::error::ContractError;
+
+//--------------------------------------------------------------------------------------------------
+// Test setup
+//--------------------------------------------------------------------------------------------------
+
+const STAKE_DENOM: &str = "factory/hub/ampKUJI";
+
+fn hub_state(exchange_rate: Decimal) -> StateResponse {
+    StateResponse {
+        total_ustake: Uint128::new(1000),
+        total_utoken: Uint128::new(1000) * exchange_rate,
+        exchange_rate,
+        unlocked_coins: vec![],
+        unbonding: Uint128::zero(),
+        available: Uint128::zero(),
+        tvl_utoken: Uint128::new(1000) * exchange_rate,
+        ustake_escrowed: Uint128::zero(),
+        utoken_unvested: Uint128::zero(),
+        utoken_dust_recycled: Uint128::zero(),
+    }
+}
+
+fn setup_test() -> OwnedDeps<MockStorage, MockApi, MockQuerier> {
+    let mut deps = mock_dependencies();
+
+    deps.querier.update_wasm(|query| match query {
+        WasmQuery::Smart {
+            contract_addr,
+            msg,
+        } if contract_addr == "hub" => {
+            let res = match from_binary(msg).unwrap() {
+                HubQueryMsg::StakeToken {} => to_binary(&StakeTokenResponse {
+                    denom: STAKE_DENOM.to_string(),
+                    total_supply: Uint128::new(1000),
+                    bank_supply: Uint128::new(1000),
+                    in_sync: true,
+                }),
+                HubQueryMsg::State {} => to_binary(&hub_state(Decimal::from_str("1.1").unwrap())),
+                _ => panic!("unsupported hub query"),
+            };
+            SystemResult::Ok(ContractResult::Ok(res.unwrap()))
+        },
+        _ => SystemResult::Err(SystemError::UnsupportedRequest {
+            kind: "wasm".to_string(),
+        }),
+    });
+
+    instantiate(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("deployer", &[]),
+        InstantiateMsg {
+            hub: "hub".to_string(),
+            name: "Eris Amplified KUJI".to_string(),
+            symbol: "ampKUJI".to_string(),
+            decimals: 6,
+        },
+    )
+    .unwrap();
+
+    deps
+}
+
+fn query_helper<T: DeserializeOwned>(deps: Deps, msg: QueryMsg) -> T {
+    from_binary(&query(deps, mock_env(), msg).unwrap()).unwrap()
+}
+
+fn balance(deps: Deps, address: &str) -> Uint128 {
+    let res: BalanceResponse = query_helper(
+        deps,
+        QueryMsg::Balance {
+            address: address.to_string(),
+        },
+    );
+    res.balance
+}
+
+//--------------------------------------------------------------------------------------------------
+// Execution
+//--------------------------------------------------------------------------------------------------
+
+#[test]
+fn proper_instantiation() {
+    let deps = setup_test();
+
+    let res: ConfigResponse = query_helper(deps.as_ref(), QueryMsg::Config {});
+    assert_eq!(
+        res,
+        ConfigResponse {
+            hub: Addr::unchecked("hub"),
+            stake_denom: STAKE_DENOM.to_string(),
+        }
+    );
+
+    let res: TokenInfoResponse = query_helper(deps.as_ref(), QueryMsg::TokenInfo {});
+    assert_eq!(
+        res,
+        TokenInfoResponse {
+            name: "Eris Amplified KUJI".to_string(),
+            symbol: "ampKUJI".to_string(),
+            decimals: 6,
+            total_supply: Uint128::zero(),
+        }
+    );
+
+    let minter = cw20_base::contract::query_minter(deps.as_ref()).unwrap();
+    assert_eq!(
+        minter,
+        Some(MinterResponse {
+            minter: MOCK_CONTRACT_ADDR.to_string(),
+            cap: None,
+        })
+    );
+}
+
+#[test]
+fn wrapping() {
+    let mut deps = setup_test();
+
+    let err = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("alice", &coins(100, "ukuji")),
+        ExecuteMsg::Wrap {
+            receiver: None,
+        },
+    )
+    .unwrap_err();
+    assert_eq!(
+        err,
+        ContractError::Std(StdError::generic_err(format!(
+            "must deposit a non-zero amount of {} only",
+            STAKE_DENOM
+        )))
+    );
+
+    let err = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("alice", &[coin(100, STAKE_DENOM), coin(100, "ukuji")]),
+        ExecuteMsg::Wrap {
+            receiver: None,
+        },
+    )
+    .unwrap_err();
+    assert!(matches!(err, ContractError::Std(_)));
+
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("alice", &coins(100, STAKE_DENOM)),
+        ExecuteMsg::Wrap {
+            receiver: None,
+        },
+    )
+    .unwrap();
+
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("alice", &coins(50, STAKE_DENOM)),
+        ExecuteMsg::Wrap {
+            receiver: Some("bob".to_string()),
+        },
+    )
+    .unwrap();
+
+    assert_eq!(balance(deps.as_ref(), "alice"), Uint128::new(100));
+    assert_eq!(balance(deps.as_ref(), "bob"), Uint128::new(50));
+
+    let res: TokenInfoResponse = query_helper(deps.as_ref(), QueryMsg::TokenInfo {});
+    assert_eq!(res.total_supply, Uint128::new(150));
+}
+
+#[test]
+fn unwrapping() {
+    let mut deps = setup_test();
+
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("alice", &coins(100, STAKE_DENOM)),
+        ExecuteMsg::Wrap {
+            receiver: None,
+        },
+    )
+    .unwrap();
+
+    let err = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("bob", &[]),
+        ExecuteMsg::Unwrap {
+            amount: Uint128::new(1),
+            receiver: None,
+        },
+    )
+    .unwrap_err();
+    assert!(matches!(err, ContractError::Cw20Base(Cw20BaseError::Std(StdError::Overflow { .. }))));
+
+    let err = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("alice", &[]),
+        ExecuteMsg::Unwrap {
+            amount: Uint128::zero(),
+            receiver: None,
+        },
+    )
+    .unwrap_err();
+    assert_eq!(err, ContractError::Cw20Base(Cw20BaseError::InvalidZeroAmount {}));
+
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("alice", &[]),
+        ExecuteMsg::Unwrap {
+            amount: Uint128::new(60),
+            receiver: None,
+        },
+    )
+    .unwrap();
+    assert_eq!(
+        res.messages,
+        vec![SubMsg::new(CosmosMsg::Bank(BankMsg::Send {
+            to_address: "alice".to_string(),
+            amount: coins(60, STAKE_DENOM),
+        }))]
+    );
+
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("alice", &[]),
+        ExecuteMsg::Unwrap {
+            amount: Uint128::new(40),
+            receiver: Some("bob".to_string()),
+        },
+    )
+    .unwrap();
+    assert_eq!(
+        res.messages,
+        vec![SubMsg::new(CosmosMsg::Bank(BankMsg::Send {
+            to_address: "bob".to_string(),
+            amount: coins(40, STAKE_DENOM),
+        }))]
+    );
+
+    assert_eq!(balance(deps.as_ref(), "alice"), Uint128::zero());
+
+    let res: TokenInfoResponse = query_helper(deps.as_ref(), QueryMsg::TokenInfo {});
+    assert_eq!(res.total_supply, Uint128::zero());
+}
+
+#[test]
+fn transferring() {
+    let mut deps = setup_test();
+
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("alice", &coins(100, STAKE_DENOM)),
+        ExecuteMsg::Wrap {
+            receiver: None,
+        },
+    )
+    .unwrap();
+
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("alice", &[]),
+        ExecuteMsg::Transfer {
+            recipient: "bob".to_string(),
+            amount: Uint128::new(30),
+        },
+    )
+    .unwrap();
+
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("alice", &[]),
+        ExecuteMsg::IncreaseAllowance {
+            spender: "charlie".to_string(),
+            amount: Uint128::new(20),
+            expires: None,
+        },
+    )
+    .unwrap();
+
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("charlie", &[]),
+        ExecuteMsg::TransferFrom {
+            owner: "alice".to_string(),
+            recipient: "charlie".to_string(),
+            amount: Uint128::new(20),
+        },
+    )
+    .unwrap();
+
+    assert_eq!(balance(deps.as_ref(), "alice"), Uint128::new(50));
+    assert_eq!(balance(deps.as_ref(), "bob"), Uint128::new(30));
+    assert_eq!(balance(deps.as_ref(), "charlie"), Uint128::new(20));
+
+    // the new holders can unwrap as well
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("bob", &[]),
+        ExecuteMsg::Unwrap {
+            amount: Uint128::new(30),
+            receiver: None,
+        },
+    )
+    .unwrap();
+    assert_eq!(res.messages.len(), 1);
+}
+
+//--------------------------------------------------------------------------------------------------
+// Queries
+//--------------------------------------------------------------------------------------------------
+
+#[test]
+fn querying_exchange_rate() {
+    let mut deps = setup_test();
+
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("alice", &coins(100, STAKE_DENOM)),
+        ExecuteMsg::Wrap {
+            receiver: None,
+        },
+    )
+    .unwrap();
+
+    let res: ExchangeRateResponse = query_helper(deps.as_ref(), QueryMsg::ExchangeRate {});
+    assert_eq!(
+        res,
+        ExchangeRateResponse {
+            exchange_rate: Decimal::from_str("1.1").unwrap(),
+            total_wrapped: Uint128::new(100),
+        }
+    );
+
+    let res: UnderlyingBalanceResponse = query_helper(
+        deps.as_ref(),
+        QueryMsg::UnderlyingBalance {
+            address: "alice".to_string(),
+        },
+    );
+    assert_eq!(
+        res,
+        UnderlyingBalanceResponse {
+            balance: Uint128::new(100),
+            utoken: Uint128::new(110),
+        }
+    );
+}
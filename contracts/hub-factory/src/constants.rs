@@ -0,0 +1,4 @@
+pub const CONTRACT_NAME: &str = "eris-hub-factory";
+pub const CONTRACT_VERSION: &str = env!("CARGO_PKG_VERSION");
+/// Id of the reply to the instantiation of a hub
+pub const INSTANTIATE_HUB_REPLY_ID: u64 = 1;
@@ -0,0 +1,87 @@
+use cosmwasm_std::{
+    entry_point, to_binary, Binary, Deps, DepsMut, Env, MessageInfo, Reply, Response, StdError,
+    StdResult,
+};
+use cw2::set_contract_version;
+
+use eris::hub_factory::{ExecuteMsg, InstantiateMsg, MigrateMsg, QueryMsg};
+
+use crate::constants::{CONTRACT_NAME, CONTRACT_VERSION, INSTANTIATE_HUB_REPLY_ID};
+use crate::error::{ContractError, ContractResult};
+use crate::{execute, queries};
+
+#[entry_point]
+pub fn instantiate(
+    deps: DepsMut,
+    _env: Env,
+    _info: MessageInfo,
+    msg: InstantiateMsg,
+) -> ContractResult {
+    set_contract_version(deps.storage, CONTRACT_NAME, CONTRACT_VERSION)?;
+    execute::instantiate(deps, msg)
+}
+
+#[entry_point]
+pub fn execute(deps: DepsMut, env: Env, info: MessageInfo, msg: ExecuteMsg) -> ContractResult {
+    match msg {
+        ExecuteMsg::CreateHub {
+            label,
+            denom,
+            validators,
+            protocol_reward_fee,
+        } => execute::create_hub(deps, env, info, label, denom, validators, protocol_reward_fee),
+        ExecuteMsg::UpdateConfig {
+            hub_code_id,
+            hub_preset,
+            guardrails,
+        } => execute::update_config(deps, info.sender, hub_code_id, hub_preset, guardrails),
+        ExecuteMsg::MigrateHub {
+            hub,
+            msg,
+        } => execute::migrate_hub(deps, info.sender, hub, msg),
+        ExecuteMsg::UpdateHubAdmin {
+            hub,
+            admin,
+        } => execute::update_hub_admin(deps, info.sender, hub, admin),
+        ExecuteMsg::TransferOwnership {
+            new_owner,
+        } => execute::transfer_ownership(deps, info.sender, new_owner),
+        ExecuteMsg::AcceptOwnership {} => execute::accept_ownership(deps, info.sender),
+        ExecuteMsg::DropOwnershipProposal {} => execute::drop_ownership_proposal(deps, info.sender),
+    }
+}
+
+#[entry_point]
+pub fn reply(deps: DepsMut, env: Env, reply: Reply) -> ContractResult {
+    match reply.id {
+        INSTANTIATE_HUB_REPLY_ID => execute::register_hub(
+            deps,
+            env,
+            reply.result.into_result().map_err(StdError::generic_err)?,
+        ),
+        id => Err(ContractError::InvalidReplyId(id)),
+    }
+}
+
+#[entry_point]
+pub fn query(deps: Deps, _env: Env, msg: QueryMsg) -> StdResult<Binary> {
+    match msg {
+        QueryMsg::Config {} => to_binary(&queries::config(deps)?),
+        QueryMsg::Hub {
+            hub,
+        } => to_binary(&queries::hub(deps, hub)?),
+        QueryMsg::Hubs {
+            start_after,
+            limit,
+        } => to_binary(&queries::hubs(deps, start_after, limit)?),
+    }
+}
+
+#[entry_point]
+pub fn migrate(deps: DepsMut, _env: Env, _msg: MigrateMsg) -> ContractResult {
+    set_contract_version(deps.storage, CONTRACT_NAME, CONTRACT_VERSION)?;
+
+    Ok(Response::new()
+        .add_attribute("new_contract_name", CONTRACT_NAME)
+        .add_attribute("new_contract_version", CONTRACT_VERSION))
+}
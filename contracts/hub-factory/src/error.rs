@@ -0,0 +1,95 @@
+use std::fmt;
+
+use cosmwasm_std::{Addr, Decimal, Response, StdError};
+use eris::ownership::OwnershipError;
+use thiserror::Error;
+
+pub type ContractResult = Result<Response, ContractError>;
+
+/// Roles that can be required to execute privileged actions
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Role {
+    Owner,
+    NewOwner,
+}
+
+impl fmt::Display for Role {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Role::Owner => write!(f, "owner"),
+            Role::NewOwner => write!(f, "new owner"),
+        }
+    }
+}
+
+/// This enum describes hub factory contract errors
+#[derive(Error, Debug, PartialEq)]
+pub enum ContractError {
+    #[error("{0}")]
+    Std(#[from] StdError),
+
+    #[error("Unauthorized: sender {sender} is not {required_role}")]
+    Unauthorized {
+        required_role: Role,
+        sender: String,
+    },
+
+    #[error("Unauthorized: ownership has been renounced")]
+    OwnershipRenounced {},
+
+    #[error("Protocol reward fee must be between {min} and {max}")]
+    ProtocolRewardFeeOutOfRange {
+        min: Decimal,
+        max: Decimal,
+    },
+
+    #[error("A hub needs 1 to {0} validators")]
+    InvalidValidatorCount(u32),
+
+    #[error("Duplicate validator {0}")]
+    DuplicateValidator(String),
+
+    #[error("Invalid guardrails: {0}")]
+    InvalidGuardrails(String),
+
+    #[error("Hub {0} was not created by the factory")]
+    HubNotFound(String),
+
+    #[error("The admin of hub {0} is not managed by the factory")]
+    HubAdminNotManaged(String),
+
+    #[error("Invalid reply id: {0}")]
+    InvalidReplyId(u64),
+
+    #[error("Cannot find `instantiate` event")]
+    CannotFindInstantiateEvent {},
+
+    #[error("Cannot find `_contract_address` attribute")]
+    CannotFindContractAddress {},
+}
+
+impl ContractError {
+    pub fn unauthorized(required_role: Role, sender: &Addr) -> Self {
+        ContractError::Unauthorized {
+            required_role,
+            sender: sender.to_string(),
+        }
+    }
+}
+
+impl From<OwnershipError> for ContractError {
+    fn from(err: OwnershipError) -> Self {
+        match err {
+            OwnershipError::Std(err) => ContractError::Std(err),
+            OwnershipError::NotOwner(sender) => ContractError::Unauthorized {
+                required_role: Role::Owner,
+                sender,
+            },
+            OwnershipError::NotNewOwner(sender) => ContractError::Unauthorized {
+                required_role: Role::NewOwner,
+                sender,
+            },
+            OwnershipError::Renounced {} => ContractError::OwnershipRenounced {},
+        }
+    }
+}
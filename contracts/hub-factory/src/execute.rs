@@ -0,0 +1,294 @@
+use std::collections::HashSet;
+
+use cosmwasm_std::{
+    to_binary, Addr, Api, Binary, Decimal, Deps, DepsMut, Env, Event, MessageInfo, Response,
+    SubMsg, SubMsgResponse, WasmMsg,
+};
+
+use eris::helpers::denom::validate_subdenom;
+use eris::hub::InstantiateMsg as HubInstantiateMsg;
+use eris::hub_factory::{Guardrails, HubInfo, HubPreset, InstantiateMsg};
+
+use crate::constants::INSTANTIATE_HUB_REPLY_ID;
+use crate::error::{ContractError, ContractResult};
+use crate::state::{PendingHub, State};
+
+//--------------------------------------------------------------------------------------------------
+// Instantiation
+//--------------------------------------------------------------------------------------------------
+
+pub fn instantiate(deps: DepsMut, msg: InstantiateMsg) -> ContractResult {
+    let state = State::default();
+
+    validate_hub_preset(deps.api, &msg.hub_preset)?;
+    validate_guardrails(&msg.guardrails)?;
+
+    state.ownership.initialize(deps.storage, deps.api, &msg.owner)?;
+    state.hub_code_id.save(deps.storage, &msg.hub_code_id)?;
+    state.hub_preset.save(deps.storage, &msg.hub_preset)?;
+    state.guardrails.save(deps.storage, &msg.guardrails)?;
+    state.manage_admin.save(deps.storage, &msg.manage_admin)?;
+
+    Ok(Response::new())
+}
+
+fn validate_hub_preset(api: &dyn Api, preset: &HubPreset) -> Result<(), ContractError> {
+    api.addr_validate(&preset.fin_multi_contract)?;
+    api.addr_validate(&preset.operator)?;
+    api.addr_validate(&preset.protocol_fee_contract)?;
+    Ok(())
+}
+
+fn validate_guardrails(guardrails: &Guardrails) -> Result<(), ContractError> {
+    if guardrails.max_protocol_reward_fee > Decimal::one() {
+        return Err(ContractError::InvalidGuardrails(
+            "max protocol reward fee must be at most 1".to_string(),
+        ));
+    }
+    if guardrails.min_protocol_reward_fee > guardrails.max_protocol_reward_fee {
+        return Err(ContractError::InvalidGuardrails(
+            "min protocol reward fee must not exceed the max".to_string(),
+        ));
+    }
+    if guardrails.max_validators == 0 {
+        return Err(ContractError::InvalidGuardrails(
+            "max validators must be at least 1".to_string(),
+        ));
+    }
+    Ok(())
+}
+
+//--------------------------------------------------------------------------------------------------
+// Hub creation
+//--------------------------------------------------------------------------------------------------
+
+pub fn create_hub(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    label: String,
+    denom: String,
+    validators: Vec<String>,
+    protocol_reward_fee: Decimal,
+) -> ContractResult {
+    let state = State::default();
+
+    let guardrails = state.guardrails.load(deps.storage)?;
+    if protocol_reward_fee < guardrails.min_protocol_reward_fee
+        || protocol_reward_fee > guardrails.max_protocol_reward_fee
+    {
+        return Err(ContractError::ProtocolRewardFeeOutOfRange {
+            min: guardrails.min_protocol_reward_fee,
+            max: guardrails.max_protocol_reward_fee,
+        });
+    }
+
+    if validators.is_empty() || validators.len() > guardrails.max_validators as usize {
+        return Err(ContractError::InvalidValidatorCount(guardrails.max_validators));
+    }
+
+    let mut seen = HashSet::new();
+    for validator in &validators {
+        if !seen.insert(validator) {
+            return Err(ContractError::DuplicateValidator(validator.clone()));
+        }
+    }
+
+    validate_subdenom(&denom)?;
+
+    let preset = state.hub_preset.load(deps.storage)?;
+    let code_id = state.hub_code_id.load(deps.storage)?;
+    let manage_admin = state.manage_admin.load(deps.storage)?;
+
+    state.pending_hub.save(
+        deps.storage,
+        &PendingHub {
+            creator: info.sender.clone(),
+            denom: denom.clone(),
+            code_id,
+            admin_managed: manage_admin,
+        },
+    )?;
+
+    let admin = if manage_admin {
+        env.contract.address.to_string()
+    } else {
+        info.sender.to_string()
+    };
+
+    let instantiate_msg = HubInstantiateMsg {
+        fin_multi_contract: preset.fin_multi_contract,
+        owner: info.sender.to_string(),
+        operator: preset.operator,
+        stages_preset: None,
+        denom,
+        epoch_period: preset.epoch_period,
+        unbond_period: preset.unbond_period,
+        validators,
+        protocol_fee_contract: preset.protocol_fee_contract,
+        protocol_reward_fee,
+        delegation_strategy: None,
+        vote_operator: None,
+        bootstrap: None,
+        max_query_limit: None,
+    };
+
+    let msg = SubMsg::reply_on_success(
+        WasmMsg::Instantiate {
+            admin: Some(admin),
+            code_id,
+            msg: to_binary(&instantiate_msg)?,
+            funds: info.funds,
+            label,
+        },
+        INSTANTIATE_HUB_REPLY_ID,
+    );
+
+    Ok(Response::new().add_submessage(msg).add_attribute("action", "erisfactory/create_hub"))
+}
+
+pub fn register_hub(deps: DepsMut, env: Env, response: SubMsgResponse) -> ContractResult {
+    let state = State::default();
+
+    let event = response
+        .events
+        .iter()
+        .find(|event| event.ty == "instantiate")
+        .ok_or(ContractError::CannotFindInstantiateEvent {})?;
+
+    let contract_address = event
+        .attributes
+        .iter()
+        .find(|attr| attr.key == "_contract_address")
+        .map(|attr| attr.value.clone())
+        .ok_or(ContractError::CannotFindContractAddress {})?;
+
+    let hub = deps.api.addr_validate(&contract_address)?;
+    let pending = state.pending_hub.load(deps.storage)?;
+    state.pending_hub.remove(deps.storage);
+
+    let info = HubInfo {
+        stake_denom: format!("factory/{0}/{1}", hub, pending.denom),
+        hub: hub.clone(),
+        creator: pending.creator,
+        code_id: pending.code_id,
+        created_at: env.block.time.seconds(),
+        admin_managed: pending.admin_managed,
+    };
+    state.hubs.save(deps.storage, &hub, &info)?;
+
+    let event = Event::new("erisfactory/hub_created")
+        .add_attribute("hub", hub)
+        .add_attribute("creator", info.creator)
+        .add_attribute("stake_denom", info.stake_denom);
+
+    Ok(Response::new().add_event(event).add_attribute("action", "erisfactory/register_hub"))
+}
+
+//--------------------------------------------------------------------------------------------------
+// Hub administration
+//--------------------------------------------------------------------------------------------------
+
+fn load_managed_hub(deps: Deps, hub: &str) -> Result<HubInfo, ContractError> {
+    let state = State::default();
+
+    let addr = deps.api.addr_validate(hub)?;
+    let info = state
+        .hubs
+        .may_load(deps.storage, &addr)?
+        .ok_or_else(|| ContractError::HubNotFound(hub.to_string()))?;
+
+    if !info.admin_managed {
+        return Err(ContractError::HubAdminNotManaged(hub.to_string()));
+    }
+
+    Ok(info)
+}
+
+pub fn migrate_hub(deps: DepsMut, sender: Addr, hub: String, msg: Binary) -> ContractResult {
+    let state = State::default();
+
+    state.assert_owner(deps.storage, &sender)?;
+
+    let mut info = load_managed_hub(deps.as_ref(), &hub)?;
+    info.code_id = state.hub_code_id.load(deps.storage)?;
+    state.hubs.save(deps.storage, &info.hub, &info)?;
+
+    let msg = WasmMsg::Migrate {
+        contract_addr: info.hub.to_string(),
+        new_code_id: info.code_id,
+        msg,
+    };
+
+    Ok(Response::new()
+        .add_message(msg)
+        .add_attribute("action", "erisfactory/migrate_hub")
+        .add_attribute("hub", info.hub)
+        .add_attribute("code_id", info.code_id.to_string()))
+}
+
+pub fn update_hub_admin(deps: DepsMut, sender: Addr, hub: String, admin: String) -> ContractResult {
+    let state = State::default();
+
+    state.assert_owner(deps.storage, &sender)?;
+
+    let mut info = load_managed_hub(deps.as_ref(), &hub)?;
+    let admin = deps.api.addr_validate(&admin)?;
+    info.admin_managed = false;
+    state.hubs.save(deps.storage, &info.hub, &info)?;
+
+    let msg = WasmMsg::UpdateAdmin {
+        contract_addr: info.hub.to_string(),
+        admin: admin.to_string(),
+    };
+
+    Ok(Response::new()
+        .add_message(msg)
+        .add_attribute("action", "erisfactory/update_hub_admin")
+        .add_attribute("hub", info.hub)
+        .add_attribute("admin", admin))
+}
+
+//--------------------------------------------------------------------------------------------------
+// Ownership and management logics
+//--------------------------------------------------------------------------------------------------
+
+pub fn update_config(
+    deps: DepsMut,
+    sender: Addr,
+    hub_code_id: Option<u64>,
+    hub_preset: Option<HubPreset>,
+    guardrails: Option<Guardrails>,
+) -> ContractResult {
+    let state = State::default();
+
+    state.assert_owner(deps.storage, &sender)?;
+
+    if let Some(hub_code_id) = hub_code_id {
+        state.hub_code_id.save(deps.storage, &hub_code_id)?;
+    }
+
+    if let Some(hub_preset) = hub_preset {
+        validate_hub_preset(deps.api, &hub_preset)?;
+        state.hub_preset.save(deps.storage, &hub_preset)?;
+    }
+
+    if let Some(guardrails) = guardrails {
+        validate_guardrails(&guardrails)?;
+        state.guardrails.save(deps.storage, &guardrails)?;
+    }
+
+    Ok(Response::new().add_attribute("action", "erisfactory/update_config"))
+}
+
+pub fn transfer_ownership(deps: DepsMut, sender: Addr, new_owner: String) -> ContractResult {
+    Ok(State::default().ownership.transfer_ownership(deps, &sender, new_owner)?)
+}
+
+pub fn drop_ownership_proposal(deps: DepsMut, sender: Addr) -> ContractResult {
+    Ok(State::default().ownership.drop_ownership_proposal(deps, &sender)?)
+}
+
+pub fn accept_ownership(deps: DepsMut, sender: Addr) -> ContractResult {
+    Ok(State::default().ownership.accept_ownership(deps, &sender)?)
+}
@@ -0,0 +1,11 @@
+#[cfg(not(feature = "library"))]
+pub mod contract;
+
+pub mod execute;
+pub mod queries;
+pub mod state;
+
+mod constants;
+pub mod error;
+#[cfg(test)]
+mod testing;
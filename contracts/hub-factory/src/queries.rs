@@ -0,0 +1,51 @@
+use cosmwasm_std::{Deps, Order, StdError, StdResult};
+
+use eris::helpers::pagination::{get_limit, start_after_bound, validate_start_after_addr};
+use eris::hub_factory::{ConfigResponse, HubInfo};
+
+use crate::state::State;
+
+pub fn config(deps: Deps) -> StdResult<ConfigResponse> {
+    let state = State::default();
+
+    Ok(ConfigResponse {
+        owner: state.ownership.owner.load(deps.storage)?.into(),
+        new_owner: state.ownership.query(deps.storage)?.new_owner,
+        hub_code_id: state.hub_code_id.load(deps.storage)?,
+        hub_preset: state.hub_preset.load(deps.storage)?,
+        guardrails: state.guardrails.load(deps.storage)?,
+        manage_admin: state.manage_admin.load(deps.storage)?,
+    })
+}
+
+pub fn hub(deps: Deps, hub: String) -> StdResult<HubInfo> {
+    let state = State::default();
+
+    let addr = deps.api.addr_validate(&hub)?;
+    state
+        .hubs
+        .may_load(deps.storage, &addr)?
+        .ok_or_else(|| StdError::generic_err(format!("Hub {} was not created by the factory", hub)))
+}
+
+pub fn hubs(
+    deps: Deps,
+    start_after: Option<String>,
+    limit: Option<u32>,
+) -> StdResult<Vec<HubInfo>> {
+    let state = State::default();
+
+    let limit = get_limit(limit);
+    let start_after = validate_start_after_addr(deps.api, start_after);
+    let start = start_after_bound(start_after.as_ref());
+
+    state
+        .hubs
+        .range(deps.storage, start, None, Order::Ascending)
+        .take(limit)
+        .map(|item| {
+            let (_, v) = item?;
+            Ok(v)
+        })
+        .collect()
+}
@@ -0,0 +1,54 @@
+use cosmwasm_schema::cw_serde;
+use cosmwasm_std::{Addr, Storage};
+use cw_storage_plus::{Item, Map};
+
+use eris::hub_factory::{Guardrails, HubInfo, HubPreset};
+use eris::ownership::Ownership;
+
+use crate::error::ContractError;
+
+/// Hub that is being instantiated, until the reply with its address is received
+#[cw_serde]
+pub struct PendingHub {
+    pub creator: Addr,
+    pub denom: String,
+    pub code_id: u64,
+    pub admin_managed: bool,
+}
+
+pub(crate) struct State<'a> {
+    /// Account who can update the presets and guardrails, with its pending ownership transfer
+    pub ownership: Ownership<'a>,
+    /// Code id of the hub contract that is instantiated
+    pub hub_code_id: Item<'a, u64>,
+    /// Settings shared by all hubs created by the factory
+    pub hub_preset: Item<'a, HubPreset>,
+    /// Limits for the settings chosen by the creator of a hub
+    pub guardrails: Item<'a, Guardrails>,
+    /// If the factory is the admin of the created hubs
+    pub manage_admin: Item<'a, bool>,
+    /// Hub that is being instantiated
+    pub pending_hub: Item<'a, PendingHub>,
+    /// Hubs created by the factory, by address
+    pub hubs: Map<'a, &'a Addr, HubInfo>,
+}
+
+impl Default for State<'static> {
+    fn default() -> Self {
+        Self {
+            ownership: Ownership::new("erisfactory"),
+            hub_code_id: Item::new("hub_code_id"),
+            hub_preset: Item::new("hub_preset"),
+            guardrails: Item::new("guardrails"),
+            manage_admin: Item::new("manage_admin"),
+            pending_hub: Item::new("pending_hub"),
+            hubs: Map::new("hubs"),
+        }
+    }
+}
+
+impl<'a> State<'a> {
+    pub fn assert_owner(&self, storage: &dyn Storage, sender: &Addr) -> Result<(), ContractError> {
+        Ok(self.ownership.assert_owner(storage, sender)?)
+    }
+}
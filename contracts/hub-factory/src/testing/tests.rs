@@ -0,0 +1,444 @@
+use cosmwasm_std::testing::{
+    mock_dependencies, mock_env, mock_info, MockApi, MockQuerier, MockStorage, MOCK_CONTRACT_ADDR,
+};
+use cosmwasm_std::{
+    coin, from_binary, to_binary, Addr, Binary, CosmosMsg, Decimal, Deps, Event, OwnedDeps, Reply,
+    StdError, SubMsg, SubMsgResponse, SubMsgResult, WasmMsg,
+};
+use serde::de::DeserializeOwned;
+
+use eris::hub::InstantiateMsg as HubInstantiateMsg;
+use eris::hub_factory::{
+    ConfigResponse, ExecuteMsg, Guardrails, HubInfo, HubPreset, InstantiateMsg, QueryMsg,
+};
+
+use crate::constants::INSTANTIATE_HUB_REPLY_ID;
+use crate::contract::{execute, instantiate, query, reply};
+use crate::error::{ContractError, Role};
+
+//--------------------------------------------------------------------------------------------------
+// Test setup
+//--------------------------------------------------------------------------------------------------
+
+fn hub_preset() -> HubPreset {
+    HubPreset {
+        fin_multi_contract: "fin_multi".to_string(),
+        operator: "operator".to_string(),
+        protocol_fee_contract: "fee".to_string(),
+        epoch_period: 259200,
+        unbond_period: 1814400,
+    }
+}
+
+fn guardrails() -> Guardrails {
+    Guardrails {
+        min_protocol_reward_fee: Decimal::percent(1),
+        max_protocol_reward_fee: Decimal::percent(5),
+        max_validators: 2,
+    }
+}
+
+fn setup_test(manage_admin: bool) -> OwnedDeps<MockStorage, MockApi, MockQuerier> {
+    let mut deps = mock_dependencies();
+
+    instantiate(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("deployer", &[]),
+        InstantiateMsg {
+            owner: "owner".to_string(),
+            hub_code_id: 7,
+            hub_preset: hub_preset(),
+            guardrails: guardrails(),
+            manage_admin,
+        },
+    )
+    .unwrap();
+
+    deps
+}
+
+fn query_helper<T: DeserializeOwned>(deps: Deps, msg: QueryMsg) -> T {
+    from_binary(&query(deps, mock_env(), msg).unwrap()).unwrap()
+}
+
+fn create_hub_msg(denom: &str, validators: &[&str], fee: Decimal) -> ExecuteMsg {
+    ExecuteMsg::CreateHub {
+        label: format!("Eris Amplifier {}", denom),
+        denom: denom.to_string(),
+        validators: validators.iter().map(|v| v.to_string()).collect(),
+        protocol_reward_fee: fee,
+    }
+}
+
+fn instantiate_reply(hub: &str) -> Reply {
+    Reply {
+        id: INSTANTIATE_HUB_REPLY_ID,
+        result: SubMsgResult::Ok(SubMsgResponse {
+            events: vec![Event::new("instantiate")
+                .add_attribute("_contract_address", hub)
+                .add_attribute("code_id", "7")],
+            data: None,
+        }),
+    }
+}
+
+//--------------------------------------------------------------------------------------------------
+// Execution
+//--------------------------------------------------------------------------------------------------
+
+#[test]
+fn proper_instantiation() {
+    let deps = setup_test(true);
+
+    let res: ConfigResponse = query_helper(deps.as_ref(), QueryMsg::Config {});
+    assert_eq!(
+        res,
+        ConfigResponse {
+            owner: "owner".to_string(),
+            new_owner: None,
+            hub_code_id: 7,
+            hub_preset: hub_preset(),
+            guardrails: guardrails(),
+            manage_admin: true,
+        }
+    );
+
+    let err = instantiate(
+        mock_dependencies().as_mut(),
+        mock_env(),
+        mock_info("deployer", &[]),
+        InstantiateMsg {
+            owner: "owner".to_string(),
+            hub_code_id: 7,
+            hub_preset: hub_preset(),
+            guardrails: Guardrails {
+                min_protocol_reward_fee: Decimal::percent(6),
+                ..guardrails()
+            },
+            manage_admin: true,
+        },
+    )
+    .unwrap_err();
+    assert_eq!(
+        err,
+        ContractError::InvalidGuardrails(
+            "min protocol reward fee must not exceed the max".to_string()
+        )
+    );
+}
+
+#[test]
+fn creating_hub() {
+    let mut deps = setup_test(true);
+
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("creator", &[coin(100_000000, "ukuji")]),
+        create_hub_msg("ampTOKEN", &["alice", "bob"], Decimal::percent(2)),
+    )
+    .unwrap();
+
+    assert_eq!(res.messages.len(), 1);
+    assert_eq!(
+        res.messages[0],
+        SubMsg::reply_on_success(
+            WasmMsg::Instantiate {
+                admin: Some(MOCK_CONTRACT_ADDR.to_string()),
+                code_id: 7,
+                msg: to_binary(&HubInstantiateMsg {
+                    fin_multi_contract: "fin_multi".to_string(),
+                    owner: "creator".to_string(),
+                    operator: "operator".to_string(),
+                    stages_preset: None,
+                    denom: "ampTOKEN".to_string(),
+                    epoch_period: 259200,
+                    unbond_period: 1814400,
+                    validators: vec!["alice".to_string(), "bob".to_string()],
+                    protocol_fee_contract: "fee".to_string(),
+                    protocol_reward_fee: Decimal::percent(2),
+                    delegation_strategy: None,
+                    vote_operator: None,
+                    bootstrap: None,
+                    max_query_limit: None,
+                })
+                .unwrap(),
+                funds: vec![coin(100_000000, "ukuji")],
+                label: "Eris Amplifier ampTOKEN".to_string(),
+            },
+            INSTANTIATE_HUB_REPLY_ID
+        )
+    );
+
+    let res = reply(deps.as_mut(), mock_env(), instantiate_reply("hub1")).unwrap();
+    assert_eq!(
+        res.events,
+        vec![Event::new("erisfactory/hub_created")
+            .add_attribute("hub", "hub1")
+            .add_attribute("creator", "creator")
+            .add_attribute("stake_denom", "factory/hub1/ampTOKEN")]
+    );
+
+    let expected = HubInfo {
+        hub: Addr::unchecked("hub1"),
+        creator: Addr::unchecked("creator"),
+        stake_denom: "factory/hub1/ampTOKEN".to_string(),
+        code_id: 7,
+        created_at: mock_env().block.time.seconds(),
+        admin_managed: true,
+    };
+
+    let res: HubInfo = query_helper(
+        deps.as_ref(),
+        QueryMsg::Hub {
+            hub: "hub1".to_string(),
+        },
+    );
+    assert_eq!(res, expected);
+
+    let res: Vec<HubInfo> = query_helper(
+        deps.as_ref(),
+        QueryMsg::Hubs {
+            start_after: None,
+            limit: None,
+        },
+    );
+    assert_eq!(res, vec![expected]);
+
+    let err = reply(
+        deps.as_mut(),
+        mock_env(),
+        Reply {
+            id: 2,
+            result: SubMsgResult::Err("failed".to_string()),
+        },
+    )
+    .unwrap_err();
+    assert_eq!(err, ContractError::InvalidReplyId(2));
+}
+
+#[test]
+fn creating_hub_without_managed_admin() {
+    let mut deps = setup_test(false);
+
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("creator", &[]),
+        create_hub_msg("ampTOKEN", &["alice"], Decimal::percent(1)),
+    )
+    .unwrap();
+
+    match &res.messages[0].msg {
+        CosmosMsg::Wasm(WasmMsg::Instantiate {
+            admin,
+            ..
+        }) => assert_eq!(admin, &Some("creator".to_string())),
+        msg => panic!("unexpected message: {:?}", msg),
+    }
+
+    reply(deps.as_mut(), mock_env(), instantiate_reply("hub1")).unwrap();
+
+    let err = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("owner", &[]),
+        ExecuteMsg::MigrateHub {
+            hub: "hub1".to_string(),
+            msg: Binary::default(),
+        },
+    )
+    .unwrap_err();
+    assert_eq!(err, ContractError::HubAdminNotManaged("hub1".to_string()));
+}
+
+#[test]
+fn creating_hub_outside_guardrails() {
+    let mut deps = setup_test(true);
+
+    let err = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("creator", &[]),
+        create_hub_msg("ampTOKEN", &["alice"], Decimal::percent(10)),
+    )
+    .unwrap_err();
+    assert_eq!(
+        err,
+        ContractError::ProtocolRewardFeeOutOfRange {
+            min: Decimal::percent(1),
+            max: Decimal::percent(5),
+        }
+    );
+
+    let err = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("creator", &[]),
+        create_hub_msg("ampTOKEN", &[], Decimal::percent(1)),
+    )
+    .unwrap_err();
+    assert_eq!(err, ContractError::InvalidValidatorCount(2));
+
+    let err = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("creator", &[]),
+        create_hub_msg("ampTOKEN", &["alice", "bob", "charlie"], Decimal::percent(1)),
+    )
+    .unwrap_err();
+    assert_eq!(err, ContractError::InvalidValidatorCount(2));
+
+    let err = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("creator", &[]),
+        create_hub_msg("ampTOKEN", &["alice", "alice"], Decimal::percent(1)),
+    )
+    .unwrap_err();
+    assert_eq!(err, ContractError::DuplicateValidator("alice".to_string()));
+
+    let err = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("creator", &[]),
+        create_hub_msg("amp token", &["alice"], Decimal::percent(1)),
+    )
+    .unwrap_err();
+    assert!(matches!(err, ContractError::Std(StdError::GenericErr { .. })));
+}
+
+#[test]
+fn migrating_hub() {
+    let mut deps = setup_test(true);
+
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("creator", &[]),
+        create_hub_msg("ampTOKEN", &["alice"], Decimal::percent(1)),
+    )
+    .unwrap();
+    reply(deps.as_mut(), mock_env(), instantiate_reply("hub1")).unwrap();
+
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("owner", &[]),
+        ExecuteMsg::UpdateConfig {
+            hub_code_id: Some(8),
+            hub_preset: None,
+            guardrails: None,
+        },
+    )
+    .unwrap();
+
+    let err = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("creator", &[]),
+        ExecuteMsg::MigrateHub {
+            hub: "hub1".to_string(),
+            msg: Binary::default(),
+        },
+    )
+    .unwrap_err();
+    assert_eq!(err, ContractError::unauthorized(Role::Owner, &Addr::unchecked("creator")));
+
+    let err = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("owner", &[]),
+        ExecuteMsg::MigrateHub {
+            hub: "hub2".to_string(),
+            msg: Binary::default(),
+        },
+    )
+    .unwrap_err();
+    assert_eq!(err, ContractError::HubNotFound("hub2".to_string()));
+
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("owner", &[]),
+        ExecuteMsg::MigrateHub {
+            hub: "hub1".to_string(),
+            msg: to_binary(&eris::hub::MigrateMsg {}).unwrap(),
+        },
+    )
+    .unwrap();
+    assert_eq!(
+        res.messages,
+        vec![SubMsg::new(WasmMsg::Migrate {
+            contract_addr: "hub1".to_string(),
+            new_code_id: 8,
+            msg: to_binary(&eris::hub::MigrateMsg {}).unwrap(),
+        })]
+    );
+
+    let res: HubInfo = query_helper(
+        deps.as_ref(),
+        QueryMsg::Hub {
+            hub: "hub1".to_string(),
+        },
+    );
+    assert_eq!(res.code_id, 8);
+
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("owner", &[]),
+        ExecuteMsg::UpdateHubAdmin {
+            hub: "hub1".to_string(),
+            admin: "creator".to_string(),
+        },
+    )
+    .unwrap();
+    assert_eq!(
+        res.messages,
+        vec![SubMsg::new(WasmMsg::UpdateAdmin {
+            contract_addr: "hub1".to_string(),
+            admin: "creator".to_string(),
+        })]
+    );
+
+    let res: HubInfo = query_helper(
+        deps.as_ref(),
+        QueryMsg::Hub {
+            hub: "hub1".to_string(),
+        },
+    );
+    assert!(!res.admin_managed);
+}
+
+#[test]
+fn transferring_ownership() {
+    let mut deps = setup_test(true);
+
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("owner", &[]),
+        ExecuteMsg::TransferOwnership {
+            new_owner: "jake".to_string(),
+        },
+    )
+    .unwrap();
+
+    let err = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("pumpkin", &[]),
+        ExecuteMsg::AcceptOwnership {},
+    )
+    .unwrap_err();
+    assert_eq!(err, ContractError::unauthorized(Role::NewOwner, &Addr::unchecked("pumpkin")));
+
+    execute(deps.as_mut(), mock_env(), mock_info("jake", &[]), ExecuteMsg::AcceptOwnership {})
+        .unwrap();
+
+    let res: ConfigResponse = query_helper(deps.as_ref(), QueryMsg::Config {});
+    assert_eq!(res.owner, "jake".to_string());
+    assert_eq!(res.new_owner, None);
+}
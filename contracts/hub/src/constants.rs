@@ -3,8 +3,40 @@ use cosmwasm_std::Decimal;
 pub const CONTRACT_NAME: &str = "eris-staking-hub";
 pub const CONTRACT_VERSION: &str = env!("CARGO_PKG_VERSION");
 pub const CONTRACT_DENOM: &str = "ukuji";
+/// Symbol of the utoken in the oracle module and its decimals, to value fees in USD
+pub const ORACLE_SYMBOL: &str = "KUJI";
+pub const ORACLE_SYMBOL_DECIMALS: u32 = 6;
+pub const RENOUNCE_OWNERSHIP_CONFIRMATION: &str = "renounce ownership";
+/// Number of (time, exchange_rate) observations kept for the TWAP
+pub const MAX_EXCHANGE_RATE_OBSERVATIONS: usize = 48;
+/// Seconds of a 365 day year, to annualize the exchange rate growth
+pub const SECONDS_PER_YEAR: u64 = 31_536_000;
+/// Maximum unbond fee, 5%
+pub const UNBOND_FEE_MAX_BPS: u16 = 500;
+/// Largest drift between the tracked and the bank supply of the stake token that `SyncSupply`
+/// repairs, 0.1%. A larger drift trips the circuit breaker.
+pub const MAX_SUPPLY_DRIFT_BPS: u16 = 10;
+/// Maximum number of pending undelegations per validator of the staking module
+pub const MAX_UNBONDING_ENTRIES: usize = 7;
+/// Number of privileged actions kept in the audit log
+pub const AUDIT_LOG_SIZE: u64 = 200;
+/// Number of previous batches outside the requested time range that a `PreviousBatches` query
+/// skips before it returns the page, so that a narrow range can't scan the whole map
+pub const MAX_BATCHES_SKIPPED: usize = 100;
+/// Number of epochs (submitted batches) over which a newly added validator ramps up to full weight
+pub const VALIDATOR_RAMP_UP_EPOCHS: u64 = 4;
 
 pub fn get_reward_fee_cap() -> Decimal {
     // 10% max reward fee
     Decimal::from_ratio(10_u128, 100_u128)
 }
+
+pub fn get_validator_fee_share_cap() -> Decimal {
+    // at most half of the protocol fee goes to the validators
+    Decimal::percent(50)
+}
+
+pub fn get_default_swap_max_spread() -> Decimal {
+    // 5% max spread for permissionless custom stages
+    Decimal::from_ratio(5_u128, 100_u128)
+}
@@ -1,9 +1,13 @@
 use cosmwasm_std::{
-    entry_point, to_binary, Binary, Deps, DepsMut, Env, MessageInfo, Response, StdResult,
+    entry_point, to_binary, Addr, Binary, Deps, DepsMut, Env, MessageInfo, Order, Response,
+    StdResult, Storage, Uint128,
 };
 use cw2::set_contract_version;
 
-use eris::hub::{CallbackMsg, ExecuteMsg, InstantiateMsg, MigrateMsg, QueryMsg};
+use eris::helper::assert_callback_sender;
+use eris::hub::{
+    CallbackMsg, ExecuteMsg, InstantiateMsg, MigrateMsg, QueryMsg, RemoveValidatorMode,
+};
 
 use crate::constants::{CONTRACT_DENOM, CONTRACT_NAME, CONTRACT_VERSION};
 use crate::error::{ContractError, ContractResult};
@@ -22,53 +26,237 @@ pub fn instantiate(
 }
 
 #[entry_point]
-pub fn execute(deps: DepsMut, env: Env, info: MessageInfo, msg: ExecuteMsg) -> ContractResult {
+pub fn execute(mut deps: DepsMut, env: Env, info: MessageInfo, msg: ExecuteMsg) -> ContractResult {
+    // privileged actions are recorded in the audit log once they succeeded
+    let audited = match privileged_action(deps.storage, &info.sender, &msg)? {
+        Some(action) => Some((action, to_binary(&msg)?)),
+        None => None,
+    };
+    let sender = info.sender.clone();
+    let timestamp = env.block.time.seconds();
+
+    let res = dispatch(deps.branch(), env, info, msg)?;
+
+    if let Some((action, params)) = audited {
+        State::default().record_audit(deps.storage, action, sender, timestamp, &params)?;
+    }
+    Ok(res)
+}
+
+/// Name of the owner, operator and guardian actions that are recorded in the audit log. Every
+/// message is listed, so that a new one has to be classified by its gate
+pub(crate) fn privileged_action(
+    storage: &dyn Storage,
+    sender: &Addr,
+    msg: &ExecuteMsg,
+) -> StdResult<Option<&'static str>> {
+    let action = match msg {
+        // callable by anyone
+        ExecuteMsg::Bond {
+            ..
+        }
+        | ExecuteMsg::Donate {}
+        | ExecuteMsg::WithdrawUnbonded {
+            ..
+        }
+        | ExecuteMsg::TransferUnbondRequest {
+            ..
+        }
+        | ExecuteMsg::SendUnbondRequest {
+            ..
+        }
+        | ExecuteMsg::QueueUnbond {
+            ..
+        }
+        | ExecuteMsg::Reconcile {
+            ..
+        }
+        | ExecuteMsg::SyncSupply {}
+        | ExecuteMsg::SubmitBatch {}
+        | ExecuteMsg::Callback(_) => return Ok(None),
+        // callable by anyone, but the operator harvests with unvalidated withdrawals and stages
+        ExecuteMsg::Harvest {
+            ..
+        } => {
+            if State::default().operator.may_load(storage)?.as_ref() != Some(sender) {
+                return Ok(None);
+            }
+            "harvest"
+        },
+        // owner
+        ExecuteMsg::AddValidator {
+            ..
+        } => "add_validator",
+        ExecuteMsg::RemoveValidator {
+            ..
+        } => "remove_validator",
+        ExecuteMsg::SetWithdrawTemplate {
+            ..
+        } => "set_withdraw_template",
+        ExecuteMsg::TransferOwnership {
+            ..
+        } => "transfer_ownership",
+        ExecuteMsg::DropOwnershipProposal {} => "drop_ownership_proposal",
+        ExecuteMsg::RenounceOwnership {
+            ..
+        } => "renounce_ownership",
+        ExecuteMsg::TuneDelegations {} => "tune_delegations",
+        ExecuteMsg::Rebalance {
+            ..
+        } => "rebalance",
+        ExecuteMsg::UpdateConfig {
+            ..
+        } => "update_config",
+        ExecuteMsg::ImportConfig {
+            ..
+        } => "import_config",
+        ExecuteMsg::SetStateExport {
+            ..
+        } => "set_state_export",
+        ExecuteMsg::ImportState {
+            ..
+        } => "import_state",
+        ExecuteMsg::CompleteImport {} => "complete_import",
+        // owner or the current payout address
+        ExecuteMsg::SetValidatorPayout {
+            ..
+        } => "set_validator_payout",
+        // proposed owner, operator and vote operator
+        ExecuteMsg::AcceptOwnership {} => "accept_ownership",
+        ExecuteMsg::AcceptOperator {} => "accept_operator",
+        ExecuteMsg::AcceptVoteOperator {} => "accept_vote_operator",
+        // operator
+        ExecuteMsg::Reinvest {
+            ..
+        } => "reinvest",
+        // vote operator
+        ExecuteMsg::Vote {
+            ..
+        } => "vote",
+        ExecuteMsg::VoteWeighted {
+            ..
+        } => "vote_weighted",
+        // guardian
+        ExecuteMsg::ClearCircuitBreaker {} => "clear_circuit_breaker",
+    };
+    Ok(Some(action))
+}
+
+fn dispatch(deps: DepsMut, env: Env, info: MessageInfo, msg: ExecuteMsg) -> ContractResult {
     let api = deps.api;
     match msg {
         ExecuteMsg::Bond {
             receiver,
+            min_ustake_received,
         } => execute::bond(
             deps,
             env,
+            info.sender.clone(),
             receiver.map(|s| api.addr_validate(&s)).transpose()?.unwrap_or(info.sender),
             parse_received_fund(&info.funds, CONTRACT_DENOM)?,
             false,
+            min_ustake_received,
         ),
         ExecuteMsg::Donate {} => execute::bond(
             deps,
             env,
+            info.sender.clone(),
             info.sender,
             parse_received_fund(&info.funds, CONTRACT_DENOM)?,
             true,
+            None,
         ),
         ExecuteMsg::WithdrawUnbonded {
             receiver,
+            receiver_msg,
         } => execute::withdraw_unbonded(
             deps,
             env,
             info.sender.clone(),
             receiver.map(|s| api.addr_validate(&s)).transpose()?.unwrap_or(info.sender),
+            receiver_msg,
+        ),
+        ExecuteMsg::TransferUnbondRequest {
+            id,
+            recipient,
+            shares,
+        } => execute::transfer_unbond_request(
+            deps,
+            info.sender,
+            id,
+            api.addr_validate(&recipient)?,
+            shares,
+            None,
+        ),
+        ExecuteMsg::SendUnbondRequest {
+            id,
+            contract,
+            shares,
+            msg,
+        } => execute::transfer_unbond_request(
+            deps,
+            info.sender,
+            id,
+            api.addr_validate(&contract)?,
+            shares,
+            Some(msg),
         ),
         ExecuteMsg::AddValidator {
             validator,
         } => execute::add_validator(deps, info.sender, validator),
         ExecuteMsg::RemoveValidator {
             validator,
-        } => execute::remove_validator(deps, env, info.sender, validator),
+            mode,
+        } => execute::remove_validator(
+            deps,
+            env,
+            info.sender,
+            validator,
+            mode.unwrap_or(RemoveValidatorMode::Redelegate),
+        ),
+        ExecuteMsg::SetValidatorPayout {
+            validator,
+            payout,
+        } => execute::set_validator_payout(deps, info.sender, validator, payout),
+        ExecuteMsg::SetWithdrawTemplate {
+            name,
+            template,
+        } => execute::set_withdraw_template(deps, env, info.sender, name, template),
         ExecuteMsg::TransferOwnership {
             new_owner,
         } => execute::transfer_ownership(deps, info.sender, new_owner),
         ExecuteMsg::DropOwnershipProposal {} => execute::drop_ownership_proposal(deps, info.sender),
         ExecuteMsg::AcceptOwnership {} => execute::accept_ownership(deps, info.sender),
+        ExecuteMsg::AcceptOperator {} => execute::accept_operator(deps, info.sender),
+        ExecuteMsg::AcceptVoteOperator {} => execute::accept_vote_operator(deps, info.sender),
+        ExecuteMsg::RenounceOwnership {
+            confirmation,
+        } => execute::renounce_ownership(deps, info.sender, confirmation),
         ExecuteMsg::Harvest {
             withdrawals,
             stages,
-        } => execute::harvest(deps, env, withdrawals, stages, info.sender),
+            min_received,
+            withdraw_limits,
+        } => execute::harvest(
+            deps,
+            env,
+            withdrawals,
+            withdraw_limits,
+            stages,
+            min_received,
+            info.sender,
+        ),
+        ExecuteMsg::Reinvest {
+            denoms,
+        } => execute::reinvest_denoms(deps, env, info.sender, denoms),
         ExecuteMsg::TuneDelegations {} => execute::tune_delegations(deps, env, info.sender),
         ExecuteMsg::Rebalance {
             min_redelegation,
         } => execute::rebalance(deps, env, info.sender, min_redelegation),
-        ExecuteMsg::Reconcile {} => execute::reconcile(deps, env),
+        ExecuteMsg::Reconcile {
+            limit,
+        } => execute::reconcile(deps, env, limit),
+        ExecuteMsg::SyncSupply {} => execute::sync_supply(deps),
         ExecuteMsg::SubmitBatch {} => execute::submit_batch(deps, env),
         ExecuteMsg::Vote {
             proposal_id,
@@ -85,20 +273,62 @@ pub fn execute(deps: DepsMut, env: Env, info: MessageInfo, msg: ExecuteMsg) -> C
             protocol_reward_fee,
             operator,
             stages_preset,
+            swap_allowlist,
+            swap_max_spread,
             allow_donations,
             delegation_strategy,
             vote_operator,
+            guardian,
+            exchange_rate_tolerance,
+            max_reinvest_per_tx,
+            unbond_allowlist,
+            unbond_fee,
+            min_hold_seconds,
+            reward_smoothing_period,
+            validator_fee_share,
+            max_harvest_staleness,
+            protocol_fee_schedule,
+            action_cooldowns,
+            express_epoch,
+            rebalance_tolerance,
         } => execute::update_config(
             deps,
+            env,
             info.sender,
             protocol_fee_contract,
             protocol_reward_fee,
             operator,
             stages_preset,
+            swap_allowlist,
+            swap_max_spread,
             allow_donations,
             delegation_strategy,
             vote_operator,
+            guardian,
+            exchange_rate_tolerance,
+            max_reinvest_per_tx,
+            unbond_allowlist,
+            unbond_fee,
+            min_hold_seconds,
+            reward_smoothing_period,
+            validator_fee_share,
+            max_harvest_staleness,
+            protocol_fee_schedule,
+            action_cooldowns,
+            express_epoch,
+            rebalance_tolerance,
         ),
+        ExecuteMsg::ClearCircuitBreaker {} => execute::clear_circuit_breaker(deps, info.sender),
+        ExecuteMsg::ImportConfig {
+            config,
+        } => execute::import_config(deps, env, info.sender, config),
+        ExecuteMsg::SetStateExport {
+            enabled,
+        } => execute::set_state_export(deps, info.sender, enabled),
+        ExecuteMsg::ImportState {
+            export,
+        } => execute::import_state(deps, info.sender, export),
+        ExecuteMsg::CompleteImport {} => execute::complete_import(deps, info.sender),
         ExecuteMsg::QueueUnbond {
             receiver,
         } => {
@@ -116,6 +346,7 @@ pub fn execute(deps: DepsMut, env: Env, info: MessageInfo, msg: ExecuteMsg) -> C
             execute::queue_unbond(
                 deps,
                 env,
+                info.sender.clone(),
                 api.addr_validate(&receiver.unwrap_or_else(|| info.sender.to_string()))?,
                 info.funds[0].amount,
             )
@@ -129,19 +360,23 @@ fn callback(
     info: MessageInfo,
     callback_msg: CallbackMsg,
 ) -> ContractResult {
-    if env.contract.address != info.sender {
-        return Err(ContractError::CallbackOnlyCalledByContract {});
-    }
+    assert_callback_sender(&env, &info.sender)?;
 
     match callback_msg {
         CallbackMsg::Reinvest {} => execute::reinvest(deps, env),
         CallbackMsg::ClaimFunds {
             withdrawals,
-        } => execute::claim_funds(deps, env, withdrawals),
+            withdraw_limits,
+        } => execute::claim_funds(deps, env, withdrawals, withdraw_limits),
         CallbackMsg::Swap {
             sender,
             stages,
-        } => execute::swap(deps, env, stages, sender),
+            min_received,
+        } => execute::swap(deps, env, stages, min_received, sender),
+        CallbackMsg::AssertMinimumReceived {
+            snapshot,
+            min_received,
+        } => execute::assert_minimum_received(deps, env, snapshot, min_received),
         CallbackMsg::CheckReceivedCoin {
             snapshot,
             snapshot_stake,
@@ -154,17 +389,68 @@ pub fn query(deps: Deps, env: Env, msg: QueryMsg) -> StdResult<Binary> {
     match msg {
         QueryMsg::Config {} => to_binary(&queries::config(deps)?),
         QueryMsg::State {} => to_binary(&queries::state(deps, env)?),
+        QueryMsg::ExchangeRateTwap {
+            window_seconds,
+        } => to_binary(&queries::exchange_rate_twap(deps, env, window_seconds)?),
+        QueryMsg::Apy {
+            window,
+        } => to_binary(&queries::apy(deps, env, window)?),
+        QueryMsg::SimulateHarvest {} => to_binary(&queries::simulate_harvest(deps, env)?),
+        QueryMsg::BuildBondMsg {
+            amount,
+            receiver,
+            min_ustake_received,
+        } => to_binary(&queries::build_bond_msg(deps, env, amount, receiver, min_ustake_received)?),
+        QueryMsg::BuildQueueUnbondMsg {
+            amount,
+            receiver,
+        } => to_binary(&queries::build_queue_unbond_msg(deps, env, amount, receiver)?),
+        QueryMsg::StakeToken {} => to_binary(&queries::stake_token(deps)?),
+        QueryMsg::ExportConfig {} => to_binary(&queries::export_config(deps)?),
+        QueryMsg::ExportState {
+            section,
+        } => to_binary(&queries::export_state(deps, section)?),
+        QueryMsg::LastHarvest {} => to_binary(&queries::last_harvest(deps)?),
+        QueryMsg::Health {} => to_binary(&queries::health(deps, env)?),
         QueryMsg::PendingBatch {} => to_binary(&queries::pending_batch(deps)?),
         QueryMsg::PreviousBatch(id) => to_binary(&queries::previous_batch(deps, id)?),
         QueryMsg::PreviousBatches {
             start_after,
             limit,
-        } => to_binary(&queries::previous_batches(deps, start_after, limit)?),
+            order,
+            start_time,
+            end_time,
+        } => to_binary(&queries::previous_batches(
+            deps,
+            start_after,
+            limit,
+            order,
+            start_time,
+            end_time,
+        )?),
         QueryMsg::UnbondRequestsByBatch {
             id,
             start_after,
             limit,
         } => to_binary(&queries::unbond_requests_by_batch(deps, id, start_after, limit)?),
+        QueryMsg::UnbondRequestsByBatchTotals {
+            id,
+        } => to_binary(&queries::unbond_requests_by_batch_totals(deps, id)?),
+        QueryMsg::AllowlistUnbondTotals {
+            id,
+        } => to_binary(&queries::allowlist_unbond_totals(deps, id)?),
+        QueryMsg::ValidatorPayouts {} => to_binary(&queries::validator_payouts(deps)?),
+        QueryMsg::WithdrawTemplates {} => to_binary(&queries::withdraw_templates(deps)?),
+        QueryMsg::VoteHistory {
+            start_after,
+            limit,
+            order,
+        } => to_binary(&queries::vote_history(deps, start_after, limit, order)?),
+        QueryMsg::AuditLog {
+            start_after,
+            limit,
+            order,
+        } => to_binary(&queries::audit_log(deps, start_after, limit, order)?),
         QueryMsg::UnbondRequestsByUser {
             user,
             start_after,
@@ -182,6 +468,10 @@ pub fn query(deps: Deps, env: Env, msg: QueryMsg) -> StdResult<Binary> {
             limit,
             env,
         )?),
+        QueryMsg::SimulateRebalance {
+            min_redelegation,
+        } => to_binary(&queries::simulate_rebalance(deps, env, min_redelegation)?),
+        QueryMsg::DelegationGoal {} => to_binary(&queries::delegation_goal(deps)?),
         QueryMsg::WantedDelegations {} => to_binary(&queries::wanted_delegations(deps, env)?),
         QueryMsg::SimulateWantedDelegations {
             period,
@@ -195,6 +485,54 @@ pub fn migrate(deps: DepsMut, _env: Env, _msg: MigrateMsg) -> ContractResult {
 
     set_contract_version(deps.storage, CONTRACT_NAME, CONTRACT_VERSION)?;
 
+    // stake tokens queued before the escrow was tracked are the ones of the pending batch
+    let state = State::default();
+    if state.ustake_escrowed.may_load(deps.storage)?.is_none() {
+        let pending_batch = state.pending_batch.load(deps.storage)?;
+        state.ustake_escrowed.save(deps.storage, &pending_batch.ustake_to_burn)?;
+    }
+
+    // the claims of the batches submitted before the total was tracked are summed once
+    if state.utoken_unclaimed.may_load(deps.storage)?.is_none() {
+        let utoken_unclaimed = state
+            .previous_batches
+            .range(deps.storage, None, None, Order::Ascending)
+            .map(|item| item.map(|(_, batch)| batch.utoken_unclaimed))
+            .sum::<StdResult<Uint128>>()?;
+        state.utoken_unclaimed.save(deps.storage, &utoken_unclaimed)?;
+    }
+
+    // the totals are rebuilt from the requests, the ones queued before they were tracked are missing
+    for unbond_totals in [&state.unbond_requests_totals, &state.allowlist_unbond_totals] {
+        let ids = unbond_totals
+            .keys(deps.storage, None, None, Order::Ascending)
+            .collect::<StdResult<Vec<_>>>()?;
+        for id in ids {
+            unbond_totals.remove(deps.storage, id);
+        }
+    }
+    let requests = state
+        .unbond_requests
+        .range(deps.storage, None, None, Order::Ascending)
+        .map(|item| item.map(|(_, request)| request))
+        .collect::<StdResult<Vec<_>>>()?;
+    // requests queued before their totals were recorded are counted by the current allow-list
+    let allowlist = state.unbond_allowlist.may_load(deps.storage)?.unwrap_or_default();
+    for request in requests {
+        let key = (request.id, &request.user);
+        if state.unbond_request_allowlisted.may_load(deps.storage, key)?.is_none() {
+            let allowlisted = allowlist.contains(&request.user);
+            state.unbond_request_allowlisted.save(deps.storage, key, &allowlisted)?;
+        }
+        let unbond_totals = state.unbond_totals_of(deps.storage, request.id, &request.user)?;
+        unbond_totals.update(deps.storage, request.id, |x| -> StdResult<_> {
+            let mut totals = x.unwrap_or_default();
+            totals.count += 1;
+            totals.shares = totals.shares.checked_add(request.shares)?;
+            Ok(totals)
+        })?;
+    }
+
     Ok(Response::new()
         // .add_attribute("previous_contract_name", &contract_version.contract)
         // .add_attribute("previous_contract_version", &contract_version.version)
@@ -1,10 +1,40 @@
-use cosmwasm_std::{OverflowError, Response, StdError};
+use std::fmt;
+
+use cosmwasm_std::{Addr, OverflowError, Response, StdError, Uint128};
 use cw20_base::ContractError as cw20baseError;
+use eris::helper::CallbackUnauthorized;
+use eris::ownership::OwnershipError;
 use kujira::msg::KujiraMsg;
 use thiserror::Error;
 
 pub type ContractResult = Result<Response<KujiraMsg>, ContractError>;
 
+/// Roles that can be required to execute privileged actions
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Role {
+    Owner,
+    NewOwner,
+    Operator,
+    NewOperator,
+    VoteOperator,
+    NewVoteOperator,
+    Guardian,
+}
+
+impl fmt::Display for Role {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Role::Owner => write!(f, "owner"),
+            Role::NewOwner => write!(f, "new owner"),
+            Role::Operator => write!(f, "operator"),
+            Role::NewOperator => write!(f, "new operator"),
+            Role::VoteOperator => write!(f, "vote operator"),
+            Role::NewVoteOperator => write!(f, "new vote operator"),
+            Role::Guardian => write!(f, "guardian"),
+        }
+    }
+}
+
 /// This enum describes hub contract errors
 #[derive(Error, Debug, PartialEq)]
 pub enum ContractError {
@@ -17,17 +47,53 @@ pub enum ContractError {
     #[error("{0}")]
     Overflow(#[from] OverflowError),
 
-    #[error("Unauthorized: sender is not owner")]
-    Unauthorized {},
+    #[error("Unauthorized: sender {sender} is not {required_role}")]
+    Unauthorized {
+        required_role: Role,
+        sender: String,
+    },
+
+    #[error("Unauthorized: ownership has been renounced")]
+    OwnershipRenounced {},
+
+    #[error("Renouncing ownership requires the confirmation \"{0}\"")]
+    InvalidRenounceConfirmation(String),
+
+    #[error("Renouncing ownership requires a guardian, nobody could clear the circuit breaker")]
+    RenounceWithoutGuardian {},
+
+    #[error("Bonding and unbonding are paused: exchange rate dropped beyond tolerance")]
+    CircuitBreakerTripped {},
+
+    #[error("Bonding is paused until the next harvest: last harvest was {0} seconds ago")]
+    HarvestStale(u64),
+
+    #[error("Bonding, unbonding and withdrawals are paused while the state is migrated")]
+    MigrationInProgress {},
+
+    #[error("State can only be imported while the hub is bootstrapped from an export")]
+    NotImportingState {},
 
-    #[error("Unauthorized: sender is not new owner")]
-    UnauthorizedSenderNotNewOwner {},
+    #[error("The pending batch must not contain unbonding requests")]
+    PendingBatchNotEmpty {},
 
-    #[error("Unauthorized: sender is not vote operator")]
-    UnauthorizedSenderNotVoteOperator {},
+    #[error("Batch {0} must be imported before its unbonding requests")]
+    BatchNotImported(u64),
 
-    #[error("Unauthorized: sender is not operator")]
-    UnauthorizedSenderNotOperator {},
+    #[error("Batch {0} must be older than the pending batch")]
+    BatchNotSubmitted(u64),
+
+    #[error("Exchange rate tolerance must be at most 1")]
+    ExchangeRateToleranceTooHigh {},
+
+    #[error("Rebalance tolerance must be at most 1")]
+    RebalanceToleranceTooHigh {},
+
+    #[error("Minimum ustake not received: expected at least {expected}, received {received}")]
+    MinimumUstakeNotReceived {
+        expected: Uint128,
+        received: Uint128,
+    },
 
     #[error("Expecting only single coin")]
     ExpectingSingleCoin {},
@@ -38,12 +104,45 @@ pub enum ContractError {
     #[error("Protocol_reward_fee greater than max")]
     ProtocolRewardFeeTooHigh {},
 
+    #[error("Protocol_reward_fee must be a whole number of basis points")]
+    ProtocolRewardFeePrecision {},
+
+    #[error("Validator_fee_share greater than max")]
+    ValidatorFeeShareTooHigh {},
+
+    #[error("Invalid instantiation: {}", .0.join("; "))]
+    InvalidInstantiate(Vec<String>),
+
+    #[error("{field} address {addr} is invalid")]
+    InvalidAddress {
+        field: String,
+        addr: String,
+    },
+
+    #[error("validator {0} does not exist")]
+    ValidatorNotFound(String),
+
     #[error("{0} can't be zero")]
     CantBeZero(String),
 
+    #[error("No unbonding request of the sender in batch {0}")]
+    UnbondRequestNotFound(u64),
+
+    #[error("Unbonding request only has {0} shares")]
+    InsufficientUnbondShares(Uint128),
+
     #[error("Batch can only be submitted for unbonding after {0}")]
     SubmitBatchAfter(u64),
 
+    #[error("Batch is too large for the express epoch, max {0} utoken")]
+    ExpressBatchTooLarge(Uint128),
+
+    #[error("No undelegation entry left for an express batch at validator {0}")]
+    UndelegationEntriesExhausted(String),
+
+    #[error("Express epoch period must be shorter than the epoch period")]
+    InvalidExpressEpoch {},
+
     #[error("Callbacks can only be invoked by the contract itself")]
     CallbackOnlyCalledByContract {},
 
@@ -62,9 +161,79 @@ pub enum ContractError {
     #[error("validator {0} is not whitelisted")]
     ValidatorNotWhitelisted(String),
 
+    #[error("validator {0} has a share in the defined delegation strategy, update it first")]
+    ValidatorHasDefinedShare(String),
+
+    #[error("validator {0} still has a delegation, remove it through RemoveValidator")]
+    ValidatorStillDelegated(String),
+
     #[error("Swap from {0} is not allowed")]
     SwapFromNotAllowed(String),
 
+    #[error("Invalid swap stage {stage}: {reason}")]
+    InvalidSwapStage {
+        stage: usize,
+        reason: String,
+    },
+
+    #[error("Swap of {denom} through {pair} is not allow-listed")]
+    SwapNotAllowlisted {
+        pair: String,
+        denom: String,
+    },
+
+    #[error("Swaps received {received}{denom}, expected at least {expected}{denom}")]
+    MinimumReceivedNotMet {
+        denom: String,
+        expected: Uint128,
+        received: Uint128,
+    },
+
+    #[error("Simulated swap through {0} is below the minimum output")]
+    SwapBelowMinimumOutput(String),
+
+    #[error("Bonded stake tokens can't be unbonded before {0}")]
+    HoldPeriodNotElapsed(u64),
+
+    #[error("{action} is rate limited until {until}")]
+    ActionCooldown {
+        action: String,
+        until: u64,
+    },
+
+    #[error("Duplicate cooldown for {0}")]
+    DuplicateActionCooldown(String),
+
+    #[error("Invalid protocol fee schedule: {0}")]
+    InvalidProtocolFeeSchedule(String),
+
+    #[error("Unbond fee must be at most {0} bps")]
+    UnbondFeeTooHigh(u16),
+
+    #[error("Swap max spread must be at most 1")]
+    SwapMaxSpreadTooHigh {},
+
+    #[error("No active validator to delegate to, all validators are removed or jailed")]
+    NoActiveValidator {},
+
+    #[error("{0} can't reference the hub itself")]
+    SelfReference(String),
+
+    #[error("{field} address {addr} is not a contract")]
+    NotAContract {
+        field: String,
+        addr: String,
+    },
+
+    #[error("Withdraw template {0} is not registered")]
+    WithdrawTemplateNotFound(String),
+
+    #[error("Withdraw template {template} can only withdraw from {contract}")]
+    WithdrawTemplateContractMismatch {
+        template: String,
+        contract: String,
+    },
+
     #[error("cannot find `instantiate` event")]
     CannotFindInstantiateEvent {},
 
@@ -77,3 +246,35 @@ pub enum ContractError {
     #[error("Contract can't be migrated!")]
     MigrationError {},
 }
+
+impl ContractError {
+    pub fn unauthorized(required_role: Role, sender: &Addr) -> Self {
+        ContractError::Unauthorized {
+            required_role,
+            sender: sender.to_string(),
+        }
+    }
+}
+
+impl From<OwnershipError> for ContractError {
+    fn from(err: OwnershipError) -> Self {
+        match err {
+            OwnershipError::Std(err) => ContractError::Std(err),
+            OwnershipError::NotOwner(sender) => ContractError::Unauthorized {
+                required_role: Role::Owner,
+                sender,
+            },
+            OwnershipError::NotNewOwner(sender) => ContractError::Unauthorized {
+                required_role: Role::NewOwner,
+                sender,
+            },
+            OwnershipError::Renounced {} => ContractError::OwnershipRenounced {},
+        }
+    }
+}
+
+impl From<CallbackUnauthorized> for ContractError {
+    fn from(_: CallbackUnauthorized) -> Self {
+        ContractError::CallbackOnlyCalledByContract {}
+    }
+}
@@ -1,33 +1,47 @@
+use std::collections::{HashMap, HashSet};
+
 use cosmwasm_std::{
-    attr, to_binary, Addr, BankMsg, Coin, CosmosMsg, Decimal, DepsMut, DistributionMsg, Env, Event,
-    Order, Response, StdError, StdResult, Storage, Uint128, WasmMsg,
+    attr, to_binary, Addr, BankMsg, Binary, Coin, CosmosMsg, Decimal, Deps, DepsMut,
+    DistributionMsg, Env, Event, Fraction, Order, OverflowError, OverflowOperation, QuerierWrapper,
+    Response, StdResult, Storage, Uint128, WasmMsg,
 };
 use cw2::set_contract_version;
 use eris::adapters::bow_vault::BowVault;
 use eris::{CustomResponse, DecimalCheckedOps};
 
 use eris::adapters::bw_vault::BlackWhaleVault;
-use eris::adapters::fin_multi::FinMulti;
+use eris::adapters::fin_multi::{FinMulti, FinMultiStages};
+use eris::helpers::bps::BasicPoints;
+use eris::helpers::denom::validate_subdenom;
 use eris::hub::{
-    Batch, CallbackMsg, DelegationStrategy, ExecuteMsg, FeeConfig, InstantiateMsg, PendingBatch,
-    StakeToken, UnbondRequest, WithdrawType,
+    ActionCooldown, Batch, CallbackMsg, DelegationStrategy, EpochCounters, ExecuteMsg,
+    ExpressEpoch, FeeConfig, HubConfig, InstantiateMsg, LastHarvest, MigrationMode, PendingBatch,
+    ProtocolFeePeriod, RateLimitedAction, ReconcileCursor, RemoveValidatorMode, RewardsSchedule,
+    StakeToken, StateExport, UnbondFee, UnbondFeeRecipient, UnbondRequest, UnbondRequestReceiveMsg,
+    WantedDelegationsShare, WithdrawLimit, WithdrawTemplate, WithdrawType,
 };
+use eris::querier::{query_fin_pair, query_oracle_rate};
 use kujira::denom::Denom;
 use kujira::msg::{DenomMsg, KujiraMsg};
 
-use crate::constants::{get_reward_fee_cap, CONTRACT_DENOM};
-use crate::error::{ContractError, ContractResult};
+use crate::constants::{
+    get_default_swap_max_spread, get_reward_fee_cap, get_validator_fee_share_cap, CONTRACT_DENOM,
+    MAX_EXCHANGE_RATE_OBSERVATIONS, MAX_SUPPLY_DRIFT_BPS, MAX_UNBONDING_ENTRIES, ORACLE_SYMBOL,
+    ORACLE_SYMBOL_DECIMALS, RENOUNCE_OWNERSHIP_CONFIRMATION, UNBOND_FEE_MAX_BPS,
+};
+use crate::error::{ContractError, ContractResult, Role};
 use crate::helpers::{
-    assert_validator_exists, assert_validators_exists, dedupe, get_wanted_delegations,
-    query_all_delegations, query_delegation, query_delegations,
+    assert_contract_exists, assert_validator_exists, assert_validators_exists, dedupe,
+    get_ramp_up_weights, get_wanted_delegations, query_all_delegations, query_delegation,
+    query_delegations, simulate_stages,
 };
 use crate::math::{
     compute_mint_amount, compute_redelegations_for_rebalancing, compute_redelegations_for_removal,
-    compute_unbond_amount, compute_undelegations, mark_reconciled_batches, reconcile_batches,
+    compute_unbond_amount, compute_undelegations, compute_utoken_bonded, reconcile_batch,
 };
 use crate::state::State;
 // use crate::types::gauges::TuneInfoGaugeLoader;
-use crate::types::{Coins, Delegation, SendFee};
+use crate::types::{Coins, Delegation, SendFee, Undelegation};
 
 const CONTRACT_NAME: &str = "eris-hub";
 const CONTRACT_VERSION: &str = env!("CARGO_PKG_VERSION");
@@ -41,23 +55,17 @@ pub fn instantiate(deps: DepsMut, env: Env, msg: InstantiateMsg) -> ContractResu
 
     let state = State::default();
 
-    if msg.protocol_reward_fee.gt(&get_reward_fee_cap()) {
-        return Err(ContractError::ProtocolRewardFeeTooHigh {});
-    }
-
-    if msg.epoch_period == 0 {
-        return Err(ContractError::CantBeZero("epoch_period".into()));
-    }
-
-    if msg.unbond_period == 0 {
-        return Err(ContractError::CantBeZero("unbond_period".into()));
-    }
+    validate_instantiate(deps.as_ref(), &env.contract.address, &msg)?;
 
-    state.owner.save(deps.storage, &deps.api.addr_validate(&msg.owner)?)?;
+    state.ownership.initialize(deps.storage, deps.api, &msg.owner)?;
     state.operator.save(deps.storage, &deps.api.addr_validate(&msg.operator)?)?;
     state.epoch_period.save(deps.storage, &msg.epoch_period)?;
     state.unbond_period.save(deps.storage, &msg.unbond_period)?;
 
+    if let Some(max_query_limit) = msg.max_query_limit {
+        state.max_query_limit.save(deps.storage, &max_query_limit)?;
+    }
+
     if let Some(vote_operator) = msg.vote_operator {
         state.vote_operator.save(deps.storage, &deps.api.addr_validate(&vote_operator)?)?;
     }
@@ -66,37 +74,44 @@ pub fn instantiate(deps: DepsMut, env: Env, msg: InstantiateMsg) -> ContractResu
     state.allow_donations.save(deps.storage, &false)?;
 
     let mut validators = msg.validators;
-
     dedupe(&mut validators);
-    assert_validators_exists(&deps.querier, &validators)?;
+
+    let protocol_fee_contract = deps.api.addr_validate(&msg.protocol_fee_contract)?;
+    let fin_multi_contract = deps.api.addr_validate(&msg.fin_multi_contract)?;
 
     state.validators.save(deps.storage, &validators)?;
     state.unlocked_coins.save(deps.storage, &vec![])?;
     state.fee_config.save(
         deps.storage,
         &FeeConfig {
-            protocol_fee_contract: deps.api.addr_validate(&msg.protocol_fee_contract)?,
+            protocol_fee_contract,
             protocol_reward_fee: msg.protocol_reward_fee,
+            unbond_fee: None,
+            validator_fee_share: None,
         },
     )?;
 
-    state.pending_batch.save(
-        deps.storage,
-        &PendingBatch {
+    let pending_batch = match msg.bootstrap {
+        // the batches and unbonding requests of the other hub are imported through `ImportState`
+        Some(bootstrap) => {
+            state.migration_mode.save(deps.storage, &MigrationMode::Import)?;
+            bootstrap.pending_batch
+        },
+        None => PendingBatch {
             id: 1,
             ustake_to_burn: Uint128::zero(),
             est_unbond_start_time: env.block.time.seconds() + msg.epoch_period,
         },
-    )?;
+    };
+    state.pending_batch.save(deps.storage, &pending_batch)?;
+    state.ustake_escrowed.save(deps.storage, &Uint128::zero())?;
 
     let delegation_strategy = msg.delegation_strategy.unwrap_or(DelegationStrategy::Uniform);
     state
         .delegation_strategy
         .save(deps.storage, &delegation_strategy.validate(deps.api, &validators)?)?;
 
-    state
-        .fin_multi
-        .save(deps.storage, &FinMulti(deps.api.addr_validate(&msg.fin_multi_contract)?))?;
+    state.fin_multi.save(deps.storage, &FinMulti(fin_multi_contract))?;
 
     let addr = env.contract.address;
     let denom = format!("factory/{0}/{1}", addr, msg.denom);
@@ -117,6 +132,100 @@ pub fn instantiate(deps: DepsMut, env: Env, msg: InstantiateMsg) -> ContractResu
     }))
 }
 
+/// Checks the whole instantiate message and reports every violation at once, instead of failing on
+/// the first misconfiguration. Checks that depend on the saved state are left to `instantiate`.
+fn validate_instantiate(
+    deps: Deps,
+    contract: &Addr,
+    msg: &InstantiateMsg,
+) -> Result<(), ContractError> {
+    let mut violations: Vec<String> = vec![];
+    let mut check = |result: Result<(), ContractError>| {
+        if let Err(err) = result {
+            violations.push(err.to_string());
+        }
+    };
+
+    check(validate_protocol_reward_fee(msg.protocol_reward_fee));
+    check(validate_period(msg.epoch_period, "epoch_period"));
+    check(validate_period(msg.unbond_period, "unbond_period"));
+    if msg.max_query_limit == Some(0) {
+        check(Err(ContractError::CantBeZero("max_query_limit".into())));
+    }
+
+    check(validate_subdenom(&msg.denom).map_err(Into::into));
+
+    for (field, addr) in [("owner", &msg.owner), ("operator", &msg.operator)] {
+        check(deps.api.addr_validate(addr).map(|_| ()).map_err(|_| invalid_address(field, addr)));
+    }
+    if let Some(vote_operator) = &msg.vote_operator {
+        check(
+            deps.api
+                .addr_validate(vote_operator)
+                .map(|_| ())
+                .map_err(|_| invalid_address("vote_operator", vote_operator)),
+        );
+    }
+
+    for (field, addr) in [
+        ("protocol_fee_contract", &msg.protocol_fee_contract),
+        ("fin_multi_contract", &msg.fin_multi_contract),
+    ] {
+        check(match deps.api.addr_validate(addr) {
+            Ok(addr) => validate_external_contracts(&deps.querier, contract, [&addr], field),
+            Err(_) => Err(invalid_address(field, addr)),
+        });
+    }
+
+    let mut validators = msg.validators.clone();
+    dedupe(&mut validators);
+    for validator in &validators {
+        check(
+            assert_validator_exists(&deps.querier, validator)
+                .map_err(|_| ContractError::ValidatorNotFound(validator.clone())),
+        );
+    }
+
+    if let Some(delegation_strategy) = &msg.delegation_strategy {
+        check(
+            delegation_strategy
+                .clone()
+                .validate(deps.api, &validators)
+                .map(|_| ())
+                .map_err(Into::into),
+        );
+    }
+
+    if let Some(stages_preset) = &msg.stages_preset {
+        check(validate_external_contracts(
+            &deps.querier,
+            contract,
+            stages_preset.iter().flatten().map(|s| &s.0),
+            "stages",
+        ));
+        check(validate_stage_routes(&deps.querier, stages_preset));
+    }
+
+    if let Some(bootstrap) = &msg.bootstrap {
+        if !bootstrap.pending_batch.ustake_to_burn.is_zero() {
+            check(Err(ContractError::PendingBatchNotEmpty {}));
+        }
+    }
+
+    if violations.is_empty() {
+        Ok(())
+    } else {
+        Err(ContractError::InvalidInstantiate(violations))
+    }
+}
+
+fn invalid_address(field: &str, addr: &str) -> ContractError {
+    ContractError::InvalidAddress {
+        field: field.to_string(),
+        addr: addr.to_string(),
+    }
+}
+
 //--------------------------------------------------------------------------------------------------
 // Bonding and harvesting logics
 //--------------------------------------------------------------------------------------------------
@@ -132,11 +241,20 @@ pub fn instantiate(deps: DepsMut, env: Env, msg: InstantiateMsg) -> ContractResu
 pub fn bond(
     deps: DepsMut,
     env: Env,
+    sender: Addr,
     receiver: Addr,
     token_to_bond: Uint128,
     donate: bool,
+    min_ustake_received: Option<Uint128>,
 ) -> ContractResult {
     let state = State::default();
+    if !donate {
+        state.assert_not_tripped(deps.storage)?;
+        state.assert_not_migrating(deps.storage)?;
+        // unharvested rewards would mint too many stake tokens
+        state.assert_harvest_not_stale(deps.storage, env.block.time.seconds())?;
+    }
+
     let mut stake = state.stake_token.load(deps.storage)?;
     let (new_delegation, delegations) = find_new_delegation(&state, &deps, &env, token_to_bond)?;
 
@@ -151,9 +269,31 @@ pub fn bond(
         }
         Uint128::zero()
     } else {
-        compute_mint_amount(ustake_supply, token_to_bond, &delegations)
+        let utoken_undelegated =
+            state.utoken_undelegated.may_load(deps.storage)?.unwrap_or_default();
+        let utoken_unvested = state.utoken_unvested(deps.storage, env.block.time.seconds())?;
+        compute_mint_amount(
+            ustake_supply,
+            token_to_bond,
+            &delegations,
+            utoken_undelegated,
+            utoken_unvested,
+        )
     };
 
+    if let Some(min_ustake_received) = min_ustake_received {
+        if ustake_to_mint < min_ustake_received {
+            return Err(ContractError::MinimumUstakeNotReceived {
+                expected: min_ustake_received,
+                received: ustake_to_mint,
+            });
+        }
+    }
+
+    let mut counters = state.epoch_counters.may_load(deps.storage)?.unwrap_or_default();
+    counters.utoken_bonded = counters.utoken_bonded.checked_add(token_to_bond)?;
+    state.epoch_counters.save(deps.storage, &counters)?;
+
     let event = Event::new("erishub/bonded")
         .add_attribute("receiver", receiver.clone())
         .add_attribute("token_bonded", token_to_bond)
@@ -165,6 +305,10 @@ pub fn bond(
         // create mint message and add to stored total supply
         stake.total_supply = stake.total_supply.checked_add(ustake_to_mint)?;
         state.stake_token.save(deps.storage, &stake)?;
+        // bonding for someone else must not extend their hold period
+        if sender == receiver {
+            state.last_bond_time.save(deps.storage, &receiver, &env.block.time.seconds())?;
+        }
 
         Some(
             DenomMsg::Mint {
@@ -188,11 +332,48 @@ pub fn harvest(
     deps: DepsMut,
     env: Env,
     withdrawals: Option<Vec<(WithdrawType, Addr, Denom)>>,
+    withdraw_limits: Option<Vec<WithdrawLimit>>,
     stages: Option<Vec<Vec<(Addr, Denom)>>>,
+    min_received: Option<Vec<Coin>>,
     sender: Addr,
 ) -> ContractResult {
     let state = State::default();
 
+    // warn monitoring if the previous harvest is older than an epoch
+    let mut attributes = vec![];
+    if let Some(last_harvest) = state.last_harvest.may_load(deps.storage)? {
+        let epoch_period = state.epoch_period.load(deps.storage)?;
+        let since_last_harvest = env.block.time.seconds().saturating_sub(last_harvest.timestamp);
+        if since_last_harvest > epoch_period {
+            attributes.push(attr("warning", "harvest_stale"));
+            attributes.push(attr("seconds_since_last_harvest", since_last_harvest.to_string()));
+        }
+    }
+
+    // bonding was paused by the stale harvest, this harvest resumes it
+    let resumed_event = state.harvest_staleness(deps.storage, env.block.time.seconds())?.map(
+        |since_last_harvest| {
+            Event::new("erishub/bonding_resumed")
+                .add_attribute("seconds_since_last_harvest", since_last_harvest.to_string())
+        },
+    );
+
+    state.last_harvest.save(
+        deps.storage,
+        &LastHarvest {
+            timestamp: env.block.time.seconds(),
+            caller: sender.clone(),
+            utoken_bonded: Uint128::zero(),
+            utoken_protocol_fee: Uint128::zero(),
+        },
+    )?;
+
+    if let Some(DelegationStrategy::Performance) =
+        state.delegation_strategy.may_load(deps.storage)?
+    {
+        record_validator_performance(&state, deps.storage, &deps.querier)?;
+    }
+
     // 1. withdraw delegation rewards
     let withdraw_submsgs: Vec<CosmosMsg<KujiraMsg>> =
         query_all_delegations(&deps.querier, &env.contract.address)?
@@ -204,13 +385,39 @@ pub fn harvest(
             })
             .collect::<Vec<_>>();
 
+    if let Some(withdrawals) = &withdrawals {
+        validate_no_self_reference(
+            &env.contract.address,
+            withdrawals.iter().map(|w| &w.1),
+            "withdrawals",
+        )?;
+    }
+    if let Some(stages) = &stages {
+        validate_no_self_reference(
+            &env.contract.address,
+            stages.iter().flatten().map(|s| &s.0),
+            "stages",
+        )?;
+        // the stages of the operator are not validated, so its harvests through them are rate
+        // limited
+        if state.assert_operator(deps.storage, &sender).is_ok() {
+            state.enforce_cooldown(
+                deps.storage,
+                RateLimitedAction::OperatorSwap,
+                env.block.time.seconds(),
+            )?;
+        }
+    }
+
     let claim_funds_msg = withdrawals.map(|w| CallbackMsg::ClaimFunds {
         withdrawals: Some(w),
+        withdraw_limits,
     });
 
     let swap_msg = stages.map(|s| CallbackMsg::Swap {
         stages: Some(s),
         sender,
+        min_received,
     });
 
     Ok(Response::new()
@@ -229,30 +436,122 @@ pub fn harvest(
         )?)
         // 5. restake unlocked_coins
         .add_callback(&env, CallbackMsg::Reinvest {})?
-        .add_attribute("action", "erishub/harvest"))
+        .add_events(resumed_event)
+        .add_attribute("action", "erishub/harvest")
+        .add_attributes(attributes))
+}
+
+pub fn reinvest_denoms(
+    deps: DepsMut,
+    env: Env,
+    sender: Addr,
+    denoms: Option<Vec<String>>,
+) -> ContractResult {
+    let state = State::default();
+    state.assert_operator(deps.storage, &sender)?;
+    state.enforce_cooldown(deps.storage, RateLimitedAction::Reinvest, env.block.time.seconds())?;
+
+    let stages = match denoms {
+        Some(denoms) => Some(stages_from_denoms(
+            &deps.querier,
+            state.stages_preset.load(deps.storage)?,
+            denoms,
+        )?),
+        None => None,
+    };
+
+    // without denoms the whole preset is swapped, which is skipped if there is nothing to swap
+    let swap_msg = if matches!(&stages, Some(stages) if stages.is_empty()) {
+        None
+    } else {
+        Some(CallbackMsg::Swap {
+            stages,
+            sender,
+            min_received: None,
+        })
+    };
+
+    Ok(Response::new()
+        .add_optional_callback(&env, swap_msg)?
+        .add_message(check_received_coin_msg(
+            &deps,
+            &env,
+            state.stake_token.load(deps.storage)?,
+            None,
+        )?)
+        .add_callback(&env, CallbackMsg::Reinvest {})?
+        .add_attribute("action", "erishub/reinvest"))
+}
+
+/// The swaps of the stages that start from one of the denoms, including the swaps of the denoms
+/// they return in later stages
+fn stages_from_denoms(
+    querier: &QuerierWrapper,
+    stages: Vec<Vec<(Addr, Denom)>>,
+    denoms: Vec<String>,
+) -> StdResult<Vec<Vec<(Addr, Denom)>>> {
+    let mut routed: HashSet<String> = denoms.into_iter().collect();
+    let mut filtered = vec![];
+
+    for stage in stages {
+        let stage: Vec<(Addr, Denom)> =
+            stage.into_iter().filter(|(_, denom)| routed.contains(&denom.to_string())).collect();
+        for (pair, _) in &stage {
+            routed.extend(query_fin_pair(querier, pair)?.denoms.map(|d| d.to_string()));
+        }
+        if !stage.is_empty() {
+            filtered.push(stage);
+        }
+    }
+
+    Ok(filtered)
 }
 
 pub fn claim_funds(
     deps: DepsMut,
     env: Env,
     withdrawals: Option<Vec<(WithdrawType, Addr, Denom)>>,
+    withdraw_limits: Option<Vec<WithdrawLimit>>,
 ) -> ContractResult {
+    let state = State::default();
     let mut withdraw_msgs: Vec<CosmosMsg<KujiraMsg>> = vec![];
     if let Some(withdrawals) = withdrawals {
         let balances = deps.querier.query_all_balances(env.contract.address)?;
+        let withdraw_limits = withdraw_limits.unwrap_or_default();
 
         for (withdraw_type, addr, denom) in withdrawals {
             let balance = balances.iter().find(|b| b.denom == denom.to_string());
 
             if let Some(coin) = balance {
-                if !coin.amount.is_zero() {
+                let amount = match withdraw_limits.iter().find(|limit| limit.denom == denom) {
+                    Some(limit) => limit.apply(coin.amount),
+                    None => coin.amount,
+                };
+
+                if !amount.is_zero() {
                     match withdraw_type {
                         WithdrawType::BlackWhale => {
-                            withdraw_msgs
-                                .push(BlackWhaleVault(addr).withdraw_msg(denom, coin.amount)?);
+                            withdraw_msgs.push(BlackWhaleVault(addr).withdraw_msg(denom, amount)?);
                         },
                         WithdrawType::Bow => {
-                            withdraw_msgs.push(BowVault(addr).withdraw_msg(denom, coin.amount)?);
+                            withdraw_msgs.push(BowVault(addr).withdraw_msg(denom, amount)?);
+                        },
+                        WithdrawType::Generic {
+                            msg_template,
+                        } => {
+                            let template = state
+                                .withdraw_templates
+                                .may_load(deps.storage, &msg_template)?
+                                .ok_or_else(|| {
+                                    ContractError::WithdrawTemplateNotFound(msg_template.clone())
+                                })?;
+                            if addr.as_str() != template.contract {
+                                return Err(ContractError::WithdrawTemplateContractMismatch {
+                                    template: msg_template,
+                                    contract: template.contract,
+                                });
+                            }
+                            withdraw_msgs.push(template.withdraw_msg(&denom, amount));
                         },
                     }
                 }
@@ -268,26 +567,236 @@ pub fn swap(
     deps: DepsMut,
     env: Env,
     mut stages: Option<Vec<Vec<(Addr, Denom)>>>,
+    min_received: Option<Vec<Coin>>,
     sender: Addr,
 ) -> ContractResult {
     let state = State::default();
 
-    if stages.is_some() {
-        state.assert_operator(deps.storage, &sender)?
-    } else {
+    let is_custom = stages.is_some();
+    if !is_custom {
         stages = Some(state.stages_preset.load(deps.storage)?);
     }
 
     validate_no_utoken_or_ustake_swap(&stages, &state, deps.storage)?;
 
+    let balances = deps.querier.query_all_balances(&env.contract.address)?;
     let fin_multi = if let Some(stages) = stages {
-        let balances = deps.querier.query_all_balances(env.contract.address)?;
-        Some(state.fin_multi.load(deps.storage)?.swap_msg(stages, balances)?)
+        let stages = FinMultiStages::from_stages(stages);
+
+        if !is_custom && stages.funds(&balances).is_empty() {
+            // nothing to swap from the preset, e.g. no rewards received in the last epoch
+            None
+        } else {
+            // the stages of the operator are not validated, its harvests are rate limited instead
+            if is_custom && state.assert_operator(deps.storage, &sender).is_err() {
+                validate_permissionless_stages(&deps, &state, stages.stages(), balances.clone())?;
+            }
+            Some(state.fin_multi.load(deps.storage)?.swap_msg(stages, balances.clone())?)
+        }
     } else {
         None
     };
 
-    Ok(Response::new().add_optional_message(fin_multi).add_attribute("action", "erishub/swap"))
+    // the minimums are checked against the balances before the swap, once it has been executed
+    let assert_msg = match (&fin_multi, min_received) {
+        (Some(_), Some(min_received)) if !min_received.is_empty() => {
+            let snapshot = min_received
+                .iter()
+                .map(|min| {
+                    let amount = balances
+                        .iter()
+                        .find(|b| b.denom == min.denom)
+                        .map(|b| b.amount)
+                        .unwrap_or_default();
+                    Coin::new(amount.u128(), min.denom.clone())
+                })
+                .collect();
+            Some(CallbackMsg::AssertMinimumReceived {
+                snapshot,
+                min_received,
+            })
+        },
+        _ => None,
+    };
+
+    Ok(Response::new()
+        .add_optional_message(fin_multi)
+        .add_optional_callback(&env, assert_msg)?
+        .add_attribute("action", "erishub/swap"))
+}
+
+pub fn assert_minimum_received(
+    deps: DepsMut,
+    env: Env,
+    snapshot: Vec<Coin>,
+    min_received: Vec<Coin>,
+) -> ContractResult {
+    for (before, min) in snapshot.iter().zip(min_received.iter()) {
+        let balance = deps.querier.query_balance(&env.contract.address, &min.denom)?.amount;
+        let received = balance.saturating_sub(before.amount);
+        if received < min.amount {
+            return Err(ContractError::MinimumReceivedNotMet {
+                denom: min.denom.clone(),
+                expected: min.amount,
+                received,
+            });
+        }
+    }
+
+    Ok(Response::new().add_attribute("action", "erishub/assert_minimum_received"))
+}
+
+/// Custom stages of non-operators may only use allow-listed swaps, each simulated swap must not
+/// lose more than the max spread against the pair's price.
+fn validate_permissionless_stages(
+    deps: &DepsMut,
+    state: &State,
+    stages: &[Vec<(Addr, Denom)>],
+    balances: Vec<Coin>,
+) -> Result<(), ContractError> {
+    let swap_allowlist = state.swap_allowlist.may_load(deps.storage)?.unwrap_or_default();
+    for (pair, denom) in stages.iter().flatten() {
+        if !swap_allowlist.iter().any(|(p, d)| p == pair && d == denom) {
+            return Err(ContractError::SwapNotAllowlisted {
+                pair: pair.to_string(),
+                denom: denom.to_string(),
+            });
+        }
+    }
+
+    let max_spread =
+        state.swap_max_spread.may_load(deps.storage)?.unwrap_or_else(get_default_swap_max_spread);
+
+    for swap in simulate_stages(&deps.querier, stages, balances)? {
+        let expected = swap.ask.amount.checked_add(swap.spread)?;
+        if swap.ask.amount.is_zero() || Decimal::from_ratio(swap.spread, expected) > max_spread {
+            return Err(ContractError::SwapBelowMinimumOutput(swap.pair.to_string()));
+        }
+    }
+
+    Ok(())
+}
+
+/// Swaps and withdrawals must not execute the hub itself, which would run its callbacks from the
+/// outside or loop funds back into the contract.
+fn validate_no_self_reference<'a>(
+    contract: &Addr,
+    addrs: impl IntoIterator<Item = &'a Addr>,
+    field: &str,
+) -> Result<(), ContractError> {
+    if addrs.into_iter().any(|addr| addr == contract) {
+        return Err(ContractError::SelfReference(field.to_string()));
+    }
+    Ok(())
+}
+
+/// Configured contracts must not be the hub itself and must exist.
+fn validate_external_contracts<'a>(
+    querier: &QuerierWrapper,
+    contract: &Addr,
+    addrs: impl IntoIterator<Item = &'a Addr>,
+    field: &str,
+) -> Result<(), ContractError> {
+    for addr in addrs {
+        validate_no_self_reference(contract, [addr], field)?;
+        assert_contract_exists(querier, addr).map_err(|_| ContractError::NotAContract {
+            field: field.to_string(),
+            addr: addr.to_string(),
+        })?;
+    }
+    Ok(())
+}
+
+fn validate_protocol_reward_fee(protocol_reward_fee: Decimal) -> Result<(), ContractError> {
+    if protocol_reward_fee.gt(&get_reward_fee_cap()) {
+        return Err(ContractError::ProtocolRewardFeeTooHigh {});
+    }
+    let bps = BasicPoints::from_ratio(
+        protocol_reward_fee.numerator(),
+        protocol_reward_fee.denominator(),
+    )?;
+    if bps.decimal() != protocol_reward_fee {
+        return Err(ContractError::ProtocolRewardFeePrecision {});
+    }
+    Ok(())
+}
+
+/// Periods must be sorted by time and must not overlap
+fn validate_protocol_fee_schedule(schedule: &[ProtocolFeePeriod]) -> Result<(), ContractError> {
+    for period in schedule {
+        if period.start_time >= period.end_time {
+            return Err(ContractError::InvalidProtocolFeeSchedule(
+                "a period must end after its start".to_string(),
+            ));
+        }
+        validate_protocol_reward_fee(period.fee())?;
+    }
+    if schedule.windows(2).any(|pair| pair[0].end_time > pair[1].start_time) {
+        return Err(ContractError::InvalidProtocolFeeSchedule(
+            "periods must be sorted and must not overlap".to_string(),
+        ));
+    }
+    Ok(())
+}
+
+fn validate_action_cooldowns(cooldowns: &[ActionCooldown]) -> Result<(), ContractError> {
+    let mut actions = HashSet::new();
+    for cooldown in cooldowns {
+        if !actions.insert(cooldown.action) {
+            return Err(ContractError::DuplicateActionCooldown(cooldown.action.key().to_string()));
+        }
+    }
+    Ok(())
+}
+
+/// An express epoch with a zero period is no express epoch
+fn save_express_epoch(
+    storage: &mut dyn Storage,
+    state: &State,
+    express_epoch: Option<ExpressEpoch>,
+    epoch_period: u64,
+) -> Result<(), ContractError> {
+    match express_epoch.filter(|express_epoch| express_epoch.period > 0) {
+        Some(express_epoch) if express_epoch.period >= epoch_period => {
+            Err(ContractError::InvalidExpressEpoch {})
+        },
+        Some(express_epoch) => Ok(state.express_epoch.save(storage, &express_epoch)?),
+        None => {
+            state.express_epoch.remove(storage);
+            Ok(())
+        },
+    }
+}
+
+fn validate_period(period: u64, field: &str) -> Result<(), ContractError> {
+    if period == 0 {
+        return Err(ContractError::CantBeZero(field.into()));
+    }
+    Ok(())
+}
+
+/// A share of zero is no share
+fn validate_validator_fee_share(
+    validator_fee_share: Option<Decimal>,
+) -> Result<Option<Decimal>, ContractError> {
+    match validator_fee_share {
+        Some(share) if share > get_validator_fee_share_cap() => {
+            Err(ContractError::ValidatorFeeShareTooHigh {})
+        },
+        Some(share) if share.is_zero() => Ok(None),
+        validator_fee_share => Ok(validator_fee_share),
+    }
+}
+
+/// A fee of zero bps is no fee
+fn validate_unbond_fee(unbond_fee: Option<UnbondFee>) -> Result<Option<UnbondFee>, ContractError> {
+    match unbond_fee {
+        Some(fee) if fee.bps > UNBOND_FEE_MAX_BPS => {
+            Err(ContractError::UnbondFeeTooHigh(UNBOND_FEE_MAX_BPS))
+        },
+        Some(fee) if fee.bps == 0 => Ok(None),
+        unbond_fee => Ok(unbond_fee),
+    }
 }
 
 fn validate_no_utoken_or_ustake_swap(
@@ -309,6 +818,61 @@ fn validate_no_utoken_or_ustake_swap(
     Ok(())
 }
 
+/// Checks that every stage swaps through a FIN pair trading the offered denom, and that the stages
+/// form a plausible route: a denom is only swapped again after a later stage returned it, and every
+/// returned denom other than utoken is swapped by a later stage.
+fn validate_stage_routes(
+    querier: &QuerierWrapper,
+    stages: &[Vec<(Addr, Denom)>],
+) -> Result<(), ContractError> {
+    let invalid = |stage: usize, reason: String| ContractError::InvalidSwapStage {
+        stage,
+        reason,
+    };
+
+    // stage that last swapped each denom, and the denom returned by each swap
+    let mut swapped_in: HashMap<String, usize> = HashMap::new();
+    let mut returned: Vec<(usize, &Addr, String)> = vec![];
+
+    for (i, stage) in stages.iter().enumerate() {
+        let mut stage_returned = vec![];
+        for (pair, denom) in stage {
+            let denom = denom.to_string();
+            let denoms = query_fin_pair(querier, pair)
+                .map_err(|_| invalid(i, format!("{} is not a FIN pair", pair)))?
+                .denoms
+                .map(|d| d.to_string());
+            let ask_denom = match denoms.iter().position(|d| *d == denom) {
+                Some(index) => denoms[1 - index].clone(),
+                None => return Err(invalid(i, format!("{} does not trade {}", pair, denom))),
+            };
+
+            if let Some(j) = swapped_in.get(&denom).copied() {
+                let returned_since =
+                    returned.iter().any(|(k, _, ask)| *k >= j && *k < i && *ask == denom);
+                if !returned_since {
+                    return Err(invalid(i, format!("{} is already swapped in stage {}", denom, j)));
+                }
+            }
+
+            swapped_in.insert(denom, i);
+            stage_returned.push((i, pair, ask_denom));
+        }
+        returned.extend(stage_returned);
+    }
+
+    for (i, pair, ask_denom) in returned {
+        if ask_denom != CONTRACT_DENOM && !matches!(swapped_in.get(&ask_denom), Some(j) if *j > i) {
+            return Err(invalid(
+                i,
+                format!("{} returned by {} is not swapped in a later stage", ask_denom, pair),
+            ));
+        }
+    }
+
+    Ok(())
+}
+
 /// This callback is used to take a current snapshot of the balance and add the received balance to the unlocked_coins state after the execution
 fn check_received_coin_msg(
     deps: &DepsMut,
@@ -358,31 +922,198 @@ pub fn reinvest(deps: DepsMut, env: Env) -> ContractResult {
         .ok_or_else(|| ContractError::NoTokensAvailable(CONTRACT_DENOM.into()))?
         .amount;
 
-    let protocol_fee_amount = fee_config.protocol_reward_fee.checked_mul_uint(utoken_available)?;
-    let utoken_to_bond = utoken_available.saturating_sub(protocol_fee_amount);
+    // large amounts (e.g. after downtime) are spread over multiple reinvests
+    let utoken_to_reinvest = match state.max_reinvest_per_tx.may_load(deps.storage)? {
+        Some(max_reinvest_per_tx) => utoken_available.min(max_reinvest_per_tx),
+        None => utoken_available,
+    };
+    let utoken_carried_over = utoken_available.checked_sub(utoken_to_reinvest)?;
+
+    // a scheduled fee period, e.g. a fee holiday, replaces the configured fee
+    let protocol_reward_fee =
+        state.protocol_reward_fee(deps.storage, &fee_config, env.block.time.seconds())?;
+    // integer bps math rounds the fee down, in favor of the stakers
+    let protocol_fee_amount = protocol_reward_fee * utoken_to_reinvest;
+    let utoken_to_bond = utoken_to_reinvest.saturating_sub(protocol_fee_amount);
+
+    let (new_delegation, delegations) = find_new_delegation(&state, &deps, &env, utoken_to_bond)?;
+
+    let validator_fees = match fee_config.validator_fee_share {
+        Some(share) => compute_validator_fees(
+            &state,
+            deps.storage,
+            &delegations,
+            share.checked_mul_uint(protocol_fee_amount)?,
+        )?,
+        None => vec![],
+    };
+    let utoken_validator_fee: u128 = validator_fees.iter().map(|fee| fee.amount).sum();
+    let utoken_fee_contract = protocol_fee_amount.u128() - utoken_validator_fee;
+
+    // with smoothing, the reinvested rewards are released linearly over the configured period
+    // together with the ones not released yet, instead of raising the exchange rate at once
+    let current_time = env.block.time.seconds();
+    let utoken_unvested = state.utoken_unvested(deps.storage, current_time)?;
+    let reward_smoothing_period =
+        state.reward_smoothing_period.may_load(deps.storage)?.unwrap_or_default();
+    let utoken_released = if reward_smoothing_period > 0 {
+        state.rewards_schedule.save(
+            deps.storage,
+            &RewardsSchedule {
+                utoken: utoken_unvested.checked_add(utoken_to_bond)?,
+                start_time: current_time,
+                end_time: current_time + reward_smoothing_period,
+            },
+        )?;
+        Uint128::zero()
+    } else {
+        utoken_to_bond
+    };
 
-    let (new_delegation, _) = find_new_delegation(&state, &deps, &env, utoken_to_bond)?;
+    // record the exchange rate after the reinvest for the TWAP
+    let mut circuit_breaker_event = None;
+    let utoken_bonded = compute_utoken_bonded(
+        &delegations,
+        state.utoken_undelegated.may_load(deps.storage)?.unwrap_or_default(),
+        utoken_unvested,
+    );
+    let ustake_supply = state.stake_token.load(deps.storage)?.total_supply;
+    if !ustake_supply.is_zero() {
+        let exchange_rate = Decimal::from_ratio(
+            Uint128::new(utoken_bonded).checked_add(utoken_released)?,
+            ustake_supply,
+        );
+        circuit_breaker_event =
+            check_exchange_rate_regression(deps.storage, &state, exchange_rate)?;
+        record_exchange_rate(deps.storage, &state, current_time, exchange_rate)?;
+    }
 
     unlocked_coins.retain(|coin| coin.denom != CONTRACT_DENOM);
+    if !utoken_carried_over.is_zero() {
+        unlocked_coins.push(Coin::new(utoken_carried_over.u128(), CONTRACT_DENOM));
+    }
     state.unlocked_coins.save(deps.storage, &unlocked_coins)?;
 
-    let event = Event::new("erishub/harvested")
+    let mut counters = state.epoch_counters.may_load(deps.storage)?.unwrap_or_default();
+    counters.utoken_rewards = counters.utoken_rewards.checked_add(utoken_to_bond)?;
+    counters.utoken_fees = counters.utoken_fees.checked_add(protocol_fee_amount)?;
+    state.epoch_counters.save(deps.storage, &counters)?;
+
+    if let Some(mut last_harvest) = state.last_harvest.may_load(deps.storage)? {
+        last_harvest.utoken_bonded = utoken_to_bond;
+        last_harvest.utoken_protocol_fee = protocol_fee_amount;
+        state.last_harvest.save(deps.storage, &last_harvest)?;
+    }
+
+    let mut event = Event::new("erishub/harvested")
         .add_attribute("utoken_bonded", utoken_to_bond)
-        .add_attribute("utoken_protocol_fee", protocol_fee_amount);
+        .add_attribute("utoken_protocol_fee", protocol_fee_amount)
+        .add_attribute("utoken_validator_fee", utoken_validator_fee.to_string())
+        .add_attribute("utoken_carried_over", utoken_carried_over);
+
+    // the valuation is best effort, the oracle is not available on every chain
+    if let Ok(rate) = query_oracle_rate(&deps.querier, ORACLE_SYMBOL) {
+        let fee = Decimal::from_ratio(protocol_fee_amount, 10u128.pow(ORACLE_SYMBOL_DECIMALS));
+        event = event.add_attribute("fee_usd", fee.checked_mul(rate)?.to_string());
+    }
 
     let mut msgs = vec![new_delegation.to_cosmos_msg()];
 
-    if !protocol_fee_amount.is_zero() {
-        let send_fee = SendFee::new(fee_config.protocol_fee_contract, protocol_fee_amount.u128());
+    if utoken_fee_contract > 0 {
+        let send_fee = SendFee::new(fee_config.protocol_fee_contract, utoken_fee_contract);
         msgs.push(send_fee.to_cosmos_msg());
     }
+    msgs.extend(validator_fees.iter().map(|fee| fee.to_cosmos_msg()));
 
     Ok(Response::new()
         .add_messages(msgs)
         .add_event(event)
+        .add_events(circuit_breaker_event)
         .add_attribute("action", "erishub/reinvest"))
 }
 
+/// Splits `utoken_to_share` pro-rata to the delegations of the whitelisted validators with a payout
+/// address. Rounding remainders stay with the protocol fee contract.
+fn compute_validator_fees(
+    state: &State,
+    storage: &dyn Storage,
+    delegations: &[Delegation],
+    utoken_to_share: Uint128,
+) -> StdResult<Vec<SendFee>> {
+    let validators = state.validators.load(storage)?;
+
+    let mut eligible = vec![];
+    for d in delegations.iter().filter(|d| d.amount > 0 && validators.contains(&d.validator)) {
+        if let Some(payout) = state.validator_payouts.may_load(storage, &d.validator)? {
+            eligible.push((payout, d.amount));
+        }
+    }
+
+    let utoken_delegated: u128 = eligible.iter().map(|(_, amount)| amount).sum();
+    Ok(eligible
+        .into_iter()
+        .map(|(payout, amount)| {
+            SendFee::new(payout, utoken_to_share.multiply_ratio(amount, utoken_delegated).u128())
+        })
+        .filter(|fee| fee.amount > 0)
+        .collect())
+}
+
+/// Trips the circuit breaker if the exchange rate dropped more than the configured tolerance since
+/// the last observation, returning the alert event
+fn check_exchange_rate_regression(
+    storage: &mut dyn Storage,
+    state: &State,
+    exchange_rate: Decimal,
+) -> StdResult<Option<Event>> {
+    let tolerance = match state.exchange_rate_tolerance.may_load(storage)? {
+        Some(tolerance) => tolerance,
+        None => return Ok(None),
+    };
+
+    let previous_rate =
+        match state.exchange_rate_history.may_load(storage)?.and_then(|h| h.last().copied()) {
+            Some((_, rate)) => rate,
+            None => return Ok(None),
+        };
+
+    let min_rate = previous_rate * (Decimal::one() - tolerance);
+    if exchange_rate >= min_rate {
+        return Ok(None);
+    }
+
+    state.circuit_breaker_tripped.save(storage, &true)?;
+
+    Ok(Some(
+        Event::new("erishub/circuit_breaker_tripped")
+            .add_attribute("previous_exchange_rate", previous_rate.to_string())
+            .add_attribute("exchange_rate", exchange_rate.to_string()),
+    ))
+}
+
+/// Appends an exchange rate observation, dropping the oldest ones above the buffer size
+fn record_exchange_rate(
+    storage: &mut dyn Storage,
+    state: &State,
+    time: u64,
+    exchange_rate: Decimal,
+) -> StdResult<()> {
+    let mut history = state.exchange_rate_history.may_load(storage)?.unwrap_or_default();
+
+    match history.last_mut() {
+        // only keep a single observation per block
+        Some(last) if last.0 == time => last.1 = exchange_rate,
+        _ => history.push((time, exchange_rate)),
+    }
+
+    if history.len() > MAX_EXCHANGE_RATE_OBSERVATIONS {
+        let overflow = history.len() - MAX_EXCHANGE_RATE_OBSERVATIONS;
+        history.drain(..overflow);
+    }
+
+    state.exchange_rate_history.save(storage, &history)
+}
+
 pub fn callback_received_coins(
     deps: DepsMut,
     env: Env,
@@ -448,14 +1179,37 @@ fn find_new_delegation(
     deps: &DepsMut,
     env: &Env,
     uluna_to_bond: Uint128,
-) -> Result<(Delegation, Vec<Delegation>), StdError> {
+) -> Result<(Delegation, Vec<Delegation>), ContractError> {
     let delegation_strategy =
         state.delegation_strategy.may_load(deps.storage)?.unwrap_or(DelegationStrategy::Uniform {});
 
-    let delegations = match delegation_strategy {
-        DelegationStrategy::Uniform {} => {
+    let (delegations, weights) = match delegation_strategy {
+        DelegationStrategy::Uniform {}
+        | DelegationStrategy::Registry {
+            ..
+        } => {
             let validators = state.validators.load(deps.storage)?;
-            query_delegations(&deps.querier, &validators, &env.contract.address)?
+            let weights: HashMap<_, _> =
+                get_ramp_up_weights(state, deps.storage, &validators)?.into_iter().collect();
+            (query_delegations(&deps.querier, &validators, &env.contract.address)?, weights)
+        },
+        DelegationStrategy::Performance => {
+            // validators are weighted by their share of the goal, the ones without are skipped
+            let goal = state.delegation_goal.may_load(deps.storage)?;
+            let weights: HashMap<_, _> = goal
+                .map(|goal| goal.shares)
+                .unwrap_or_default()
+                .into_iter()
+                .filter(|(_, share)| !share.is_zero())
+                .map(|(validator, share)| (validator, share.numerator().u128()))
+                .collect();
+            let validators: Vec<String> = state
+                .validators
+                .load(deps.storage)?
+                .into_iter()
+                .filter(|validator| weights.contains_key(validator))
+                .collect();
+            (query_delegations(&deps.querier, &validators, &env.contract.address)?, weights)
         },
         // DelegationStrategy::Gauges {
         //     ..
@@ -469,28 +1223,41 @@ fn find_new_delegation(
             if delegations.is_empty() {
                 let validators = state.validators.load(deps.storage)?;
 
-                delegations = vec![Delegation {
-                    amount: 0,
-                    validator: validators.first().unwrap().to_string(),
-                }]
+                delegations = validators
+                    .first()
+                    .map(|validator| {
+                        vec![Delegation {
+                            amount: 0,
+                            validator: validator.to_string(),
+                        }]
+                    })
+                    .unwrap_or_default();
             }
-            delegations
+            (delegations, HashMap::new())
         },
     };
 
-    // Query the current delegations made to validators, and find the validator with the smallest
-    // delegated amount through a linear search
-    // The code for linear search is a bit uglier than using `sort_by` but cheaper: O(n) vs O(n * log(n))
-    let mut validator = &delegations[0].validator;
-    let mut amount = delegations[0].amount;
+    // validators ramping up have a lower weight, so their delegation counts as larger
+    let weighted_amount = |d: &Delegation| {
+        let weight = weights.get(&d.validator).copied().unwrap_or(1u128);
+        Decimal::from_ratio(d.amount, weight)
+    };
+
+    // Find the validator with the smallest weighted delegated amount. Jailed validators are not
+    // part of the active set and are skipped, usually only the first candidate needs to be queried.
+    let mut candidates: Vec<&Delegation> = delegations.iter().collect();
+    candidates.sort_by_key(|d| weighted_amount(d));
 
-    for d in &delegations[1..] {
-        if d.amount < amount {
-            validator = &d.validator;
-            amount = d.amount;
+    let mut validator = None;
+    for d in candidates {
+        if deps.querier.query_validator(&d.validator)?.is_some() {
+            validator = Some(d.validator.clone());
+            break;
         }
     }
-    let new_delegation = Delegation::new(validator, uluna_to_bond.u128());
+
+    let validator = validator.ok_or(ContractError::NoActiveValidator {})?;
+    let new_delegation = Delegation::new(&validator, uluna_to_bond.u128());
 
     Ok((new_delegation, delegations))
 }
@@ -502,29 +1269,53 @@ fn find_new_delegation(
 pub fn queue_unbond(
     deps: DepsMut,
     env: Env,
+    sender: Addr,
     receiver: Addr,
     ustake_to_burn: Uint128,
 ) -> ContractResult {
     let state = State::default();
+    state.assert_not_tripped(deps.storage)?;
+    state.assert_not_migrating(deps.storage)?;
+    state.assert_hold_period_elapsed(deps.storage, &sender, env.block.time.seconds())?;
 
     let mut pending_batch = state.pending_batch.load(deps.storage)?;
     pending_batch.ustake_to_burn += ustake_to_burn;
     state.pending_batch.save(deps.storage, &pending_batch)?;
+    let ustake_escrowed = state.ustake_escrowed.may_load(deps.storage)?.unwrap_or_default();
+    state.ustake_escrowed.save(deps.storage, &ustake_escrowed.checked_add(ustake_to_burn)?)?;
 
+    let mut is_new_request = false;
     state.unbond_requests.update(
         deps.storage,
         (pending_batch.id, &receiver),
         |x| -> StdResult<_> {
-            let mut request = x.unwrap_or_else(|| UnbondRequest {
-                id: pending_batch.id,
-                user: receiver.clone(),
-                shares: Uint128::zero(),
+            let mut request = x.unwrap_or_else(|| {
+                is_new_request = true;
+                UnbondRequest {
+                    id: pending_batch.id,
+                    user: receiver.clone(),
+                    shares: Uint128::zero(),
+                }
             });
             request.shares += ustake_to_burn;
             Ok(request)
         },
     )?;
 
+    let unbond_totals = if is_new_request {
+        state.count_new_unbond_request(deps.storage, pending_batch.id, &receiver)?
+    } else {
+        state.unbond_totals_of(deps.storage, pending_batch.id, &receiver)?
+    };
+    unbond_totals.update(deps.storage, pending_batch.id, |x| -> StdResult<_> {
+        let mut totals = x.unwrap_or_default();
+        if is_new_request {
+            totals.count += 1;
+        }
+        totals.shares = totals.shares.checked_add(ustake_to_burn)?;
+        Ok(totals)
+    })?;
+
     let mut msgs: Vec<CosmosMsg<KujiraMsg>> = vec![];
     let mut start_time = pending_batch.est_unbond_start_time.to_string();
     if env.block.time.seconds() >= pending_batch.est_unbond_start_time {
@@ -550,36 +1341,102 @@ pub fn queue_unbond(
 
 pub fn submit_batch(deps: DepsMut, env: Env) -> ContractResult {
     let state = State::default();
+    state.assert_not_migrating(deps.storage)?;
+
     let mut stake = state.stake_token.load(deps.storage)?;
     let validators = state.validators.load(deps.storage)?;
     let unbond_period = state.unbond_period.load(deps.storage)?;
     let pending_batch = state.pending_batch.load(deps.storage)?;
 
     let current_time = env.block.time.seconds();
-    if current_time < pending_batch.est_unbond_start_time {
-        return Err(ContractError::SubmitBatchAfter(pending_batch.est_unbond_start_time));
-    }
+    let express_epoch = if current_time < pending_batch.est_unbond_start_time {
+        // before the end of the epoch, only small batches may be submitted as express batch
+        let last_submit_time =
+            state.last_batch_submit_time.may_load(deps.storage)?.unwrap_or_default();
+        match state.express_epoch.may_load(deps.storage)? {
+            Some(express_epoch)
+                if !pending_batch.ustake_to_burn.is_zero()
+                    && current_time >= last_submit_time + express_epoch.period =>
+            {
+                Some(express_epoch)
+            },
+            _ => return Err(ContractError::SubmitBatchAfter(pending_batch.est_unbond_start_time)),
+        }
+    } else {
+        None
+    };
 
     let delegations = query_all_delegations(&deps.querier, &env.contract.address)?;
     let ustake_supply = stake.total_supply;
 
-    let utoken_to_unbond =
-        compute_unbond_amount(ustake_supply, pending_batch.ustake_to_burn, &delegations);
-    let new_undelegations =
-        compute_undelegations(&state, deps.storage, utoken_to_unbond, &delegations, validators)?;
+    // the fee is taken from the stake tokens of the batch, only the remainder is unbonded. The fee
+    // is rounded down in favour of the unbonders, the utoken of the remainder are rounded down as
+    // for any batch
+    let fee_config = state.fee_config.load(deps.storage)?;
+    let ustake_fee = fee_config.unbond_fee.as_ref().map_or(Uint128::zero(), |fee| {
+        pending_batch.ustake_to_burn.multiply_ratio(fee.bps, BasicPoints::MAX)
+    });
+    let ustake_unbonded = pending_batch.ustake_to_burn.checked_sub(ustake_fee)?;
+
+    let utoken_undelegated = state.utoken_undelegated.may_load(deps.storage)?.unwrap_or_default();
+    let utoken_unvested = state.utoken_unvested(deps.storage, current_time)?;
+
+    let utoken_to_unbond = compute_unbond_amount(
+        ustake_supply,
+        ustake_unbonded,
+        &delegations,
+        utoken_undelegated,
+        utoken_unvested,
+    );
+
+    if let Some(express_epoch) = &express_epoch {
+        if utoken_to_unbond > express_epoch.max_utoken {
+            return Err(ContractError::ExpressBatchTooLarge(express_epoch.max_utoken));
+        }
+    }
+
+    // amounts already undelegated from removed validators are used first
+    let utoken_from_undelegated = utoken_to_unbond.min(utoken_undelegated);
+    state
+        .utoken_undelegated
+        .save(deps.storage, &utoken_undelegated.checked_sub(utoken_from_undelegated)?)?;
+    if !utoken_from_undelegated.is_zero() {
+        state.batch_utoken_undelegated.save(
+            deps.storage,
+            pending_batch.id,
+            &utoken_from_undelegated,
+        )?;
+    }
 
-    state.previous_batches.save(
+    let new_undelegations = compute_undelegations(
+        &state,
         deps.storage,
-        pending_batch.id,
-        &Batch {
-            id: pending_batch.id,
-            reconciled: false,
-            total_shares: pending_batch.ustake_to_burn,
-            utoken_unclaimed: utoken_to_unbond,
-            est_unbond_end_time: current_time + unbond_period,
-        },
+        utoken_to_unbond.checked_sub(utoken_from_undelegated)?,
+        &delegations,
+        validators,
+    )?;
+    record_undelegation_entries(
+        &state,
+        deps.storage,
+        &new_undelegations,
+        current_time,
+        unbond_period,
+        express_epoch.is_some(),
     )?;
 
+    let mut batch = Batch {
+        id: pending_batch.id,
+        reconciled: false,
+        total_shares: pending_batch.ustake_to_burn,
+        utoken_unclaimed: utoken_to_unbond,
+        est_unbond_end_time: current_time + unbond_period,
+        exchange_rate_submitted: None,
+        exchange_rate_reconciled: None,
+    };
+    batch.exchange_rate_submitted = batch.exchange_rate();
+    state.previous_batches.save(deps.storage, pending_batch.id, &batch)?;
+    state.update_utoken_unclaimed(deps.storage, utoken_to_unbond, Uint128::zero())?;
+
     let epoch_period = state.epoch_period.load(deps.storage)?;
     state.pending_batch.save(
         deps.storage,
@@ -589,98 +1446,362 @@ pub fn submit_batch(deps: DepsMut, env: Env) -> ContractResult {
             est_unbond_start_time: current_time + epoch_period,
         },
     )?;
+    state.last_batch_submit_time.save(deps.storage, &current_time)?;
 
     let undelegate_msgs = new_undelegations.iter().map(|d| d.to_cosmos_msg()).collect::<Vec<_>>();
 
+    // a fee for the stakers is burned with the batch, a fee for the fee contract is sent to it
+    let mut fee_msgs: Vec<CosmosMsg<KujiraMsg>> = vec![];
+    let mut ustake_to_burn = pending_batch.ustake_to_burn;
+    if let Some(UnbondFee {
+        recipient: UnbondFeeRecipient::FeeContract,
+        ..
+    }) = fee_config.unbond_fee
+    {
+        if !ustake_fee.is_zero() {
+            ustake_to_burn = ustake_unbonded;
+            fee_msgs.push(CosmosMsg::Bank(BankMsg::Send {
+                to_address: fee_config.protocol_fee_contract.into(),
+                amount: vec![Coin::new(ustake_fee.u128(), stake.denom.clone())],
+            }));
+        }
+    }
+
     // apply burn to the stored total supply and save state
-    stake.total_supply = stake.total_supply.checked_sub(pending_batch.ustake_to_burn)?;
+    stake.total_supply = stake.total_supply.checked_sub(ustake_to_burn)?;
     state.stake_token.save(deps.storage, &stake)?;
+    let ustake_escrowed = state.ustake_escrowed.may_load(deps.storage)?.unwrap_or_default();
+    state
+        .ustake_escrowed
+        .save(deps.storage, &ustake_escrowed.saturating_sub(pending_batch.ustake_to_burn))?;
     let burn_msg: CosmosMsg<KujiraMsg> = DenomMsg::Burn {
         denom: stake.denom.clone().into(),
-        amount: pending_batch.ustake_to_burn,
+        amount: ustake_to_burn,
     }
     .into();
 
     let event = Event::new("erishub/unbond_submitted")
         .add_attribute("id", pending_batch.id.to_string())
         .add_attribute("utoken_unbonded", utoken_to_unbond)
-        .add_attribute("ustake_burned", pending_batch.ustake_to_burn);
+        .add_attribute("utoken_from_undelegated", utoken_from_undelegated)
+        .add_attribute("ustake_burned", ustake_to_burn)
+        .add_attribute("ustake_fee", ustake_fee)
+        .add_attribute("express", express_epoch.is_some().to_string());
+
+    // the exchange rate before the burn, the stake tokens of the batch are still part of it
+    let exchange_rate = if ustake_supply.is_zero() {
+        None
+    } else {
+        Some(Decimal::from_ratio(
+            compute_utoken_bonded(&delegations, utoken_undelegated, utoken_unvested),
+            ustake_supply,
+        ))
+    };
+    let report_event = epoch_report_event(
+        pending_batch.id,
+        state.epoch_counters.may_load(deps.storage)?.unwrap_or_default(),
+        utoken_to_unbond,
+        exchange_rate,
+    );
+    state.epoch_counters.save(
+        deps.storage,
+        &EpochCounters {
+            exchange_rate_start: exchange_rate,
+            ..EpochCounters::default()
+        },
+    )?;
 
     Ok(Response::new()
         .add_messages(undelegate_msgs)
         .add_message(burn_msg)
+        .add_messages(fee_msgs)
         .add_message(check_received_coin_msg(&deps, &env, stake, None)?)
         .add_event(event)
+        .add_event(report_event)
         .add_attribute("action", "erishub/unbond"))
 }
 
-pub fn reconcile(deps: DepsMut, env: Env) -> ContractResult {
+/// Tracks the completion times of new undelegations per validator, dropping the completed ones.
+/// Express batches must leave one entry free for the next regular batch.
+fn record_undelegation_entries(
+    state: &State,
+    storage: &mut dyn Storage,
+    undelegations: &[Undelegation],
+    current_time: u64,
+    unbond_period: u64,
+    is_express: bool,
+) -> Result<(), ContractError> {
+    for undelegation in undelegations {
+        let mut entries = state
+            .undelegation_entries
+            .may_load(storage, &undelegation.validator)?
+            .unwrap_or_default();
+        entries.retain(|end_time| *end_time > current_time);
+
+        if is_express && entries.len() + 1 >= MAX_UNBONDING_ENTRIES {
+            return Err(ContractError::UndelegationEntriesExhausted(
+                undelegation.validator.clone(),
+            ));
+        }
+
+        entries.push(current_time + unbond_period);
+        state.undelegation_entries.save(storage, &undelegation.validator, &entries)?;
+    }
+    Ok(())
+}
+
+/// Consolidated accounting of an epoch, from the counters accumulated since the previous batch
+fn epoch_report_event(
+    id: u64,
+    counters: EpochCounters,
+    utoken_unbonded: Uint128,
+    exchange_rate: Option<Decimal>,
+) -> Event {
+    let mut event = Event::new("erishub/epoch_report")
+        .add_attribute("id", id.to_string())
+        .add_attribute("utoken_bonded", counters.utoken_bonded)
+        .add_attribute("utoken_rewards", counters.utoken_rewards)
+        .add_attribute("utoken_fees", counters.utoken_fees)
+        .add_attribute("utoken_unbonded", utoken_unbonded);
+
+    if let Some(exchange_rate) = exchange_rate {
+        event = event.add_attribute("exchange_rate", exchange_rate.to_string());
+        if let Some(start) = counters.exchange_rate_start {
+            let delta = if exchange_rate >= start {
+                (exchange_rate - start).to_string()
+            } else {
+                format!("-{}", start - exchange_rate)
+            };
+            event = event
+                .add_attribute("exchange_rate_start", start.to_string())
+                .add_attribute("exchange_rate_delta", delta);
+        }
+    }
+
+    event
+}
+
+/// Reconciles matured batches. Batches are processed by ascending id, so with a `limit` a keeper
+/// can reconcile in chunks. The shortfall is computed once over all matured batches when a round
+/// starts and kept in the reconcile cursor, so that chunked and unchunked rounds deduct the same.
+pub fn reconcile(deps: DepsMut, env: Env, limit: Option<u32>) -> ContractResult {
     let state = State::default();
     let current_time = env.block.time.seconds();
+    let utoken_balance = deps.querier.query_balance(&env.contract.address, CONTRACT_DENOM)?.amount;
+
+    let mut cursor = match state.reconcile_cursor.may_load(deps.storage)? {
+        Some(cursor) => cursor,
+        None => {
+            let mut utoken_unreconciled = Uint128::zero();
+            let mut utoken_matured = Uint128::zero();
+            for batch in unreconciled_batches(deps.storage, &state) {
+                let batch = batch?;
+                utoken_unreconciled += batch.utoken_unclaimed;
+                if current_time > batch.est_unbond_end_time {
+                    utoken_matured += batch.utoken_unclaimed;
+                }
+            }
 
-    // Load batches that have not been reconciled
-    let all_batches = state
-        .previous_batches
-        .idx
-        .reconciled
-        .prefix(false.into())
-        .range(deps.storage, None, None, Order::Ascending)
-        .map(|item| {
-            let (_, v) = item?;
-            Ok(v)
-        })
-        .collect::<StdResult<Vec<_>>>()?;
+            if utoken_matured.is_zero() {
+                // e.g. rewards claimed with undelegations arrive without any batch to reconcile
+                let utoken_surplus =
+                    credit_surplus(deps.storage, &state, utoken_balance, current_time)?;
+                if utoken_surplus.is_zero() {
+                    return Ok(Response::new());
+                }
+                return Ok(Response::new()
+                    .add_event(surplus_event(utoken_surplus))
+                    .add_attribute("action", "erishub/reconcile"));
+            }
 
-    let mut batches = all_batches
-        .into_iter()
-        .filter(|b| current_time > b.est_unbond_end_time)
-        .collect::<Vec<_>>();
+            // reconciled batches not yet fully withdrawn still hold their funds on the contract
+            let utoken_claimable = state
+                .utoken_unclaimed
+                .may_load(deps.storage)?
+                .unwrap_or_default()
+                .checked_sub(utoken_unreconciled)?;
+            let unlocked_coins = state.unlocked_coins.load(deps.storage)?;
+            let utoken_expected = utoken_matured
+                + utoken_claimable
+                + Coins(unlocked_coins).find(CONTRACT_DENOM).amount;
+
+            ReconcileCursor {
+                round_start: current_time,
+                utoken_matured,
+                utoken_shortfall: utoken_expected
+                    .saturating_sub(utoken_balance)
+                    .min(utoken_matured),
+                utoken_reconciled: Uint128::zero(),
+            }
+        },
+    };
+
+    let round_start = cursor.round_start;
+    // the batches are collected before they are written, the range borrows the storage
+    let (mut batches, has_more) = {
+        let mut matured_batches = unreconciled_batches(deps.storage, &state)
+            .filter(|b| b.as_ref().map_or(true, |b| round_start > b.est_unbond_end_time));
+        let batches = match limit {
+            Some(limit) => {
+                matured_batches.by_ref().take(limit as usize).collect::<StdResult<Vec<_>>>()?
+            },
+            None => matured_batches.by_ref().collect::<StdResult<Vec<_>>>()?,
+        };
+        (batches, matured_batches.next().is_some())
+    };
+
+    let mut utoken_deducted = Uint128::zero();
+    for batch in &mut batches {
+        utoken_deducted += reconcile_batch(&mut cursor, batch)?;
+        state.previous_batches.save(deps.storage, batch.id, batch)?;
+        state.batch_utoken_undelegated.remove(deps.storage, batch.id);
+    }
+    state.update_utoken_unclaimed(deps.storage, Uint128::zero(), utoken_deducted)?;
 
-    let utoken_expected_received: Uint128 = batches.iter().map(|b| b.utoken_unclaimed).sum();
+    let utoken_surplus = credit_surplus(deps.storage, &state, utoken_balance, current_time)?;
 
-    if utoken_expected_received.is_zero() {
-        return Ok(Response::new());
+    if has_more {
+        state.reconcile_cursor.save(deps.storage, &cursor)?;
+    } else {
+        state.reconcile_cursor.remove(deps.storage);
     }
 
-    let unlocked_coins = state.unlocked_coins.load(deps.storage)?;
-    let utoken_expected_unlocked = Coins(unlocked_coins).find(CONTRACT_DENOM).amount;
+    let ids = batches.iter().map(|b| b.id.to_string()).collect::<Vec<_>>().join(",");
 
-    let utoken_expected = utoken_expected_received + utoken_expected_unlocked;
-    let utoken_actual = deps.querier.query_balance(&env.contract.address, CONTRACT_DENOM)?.amount;
+    let event = Event::new("erishub/reconciled")
+        .add_attribute("ids", ids)
+        .add_attribute("utoken_deducted", utoken_deducted.to_string())
+        .add_attribute("has_more", has_more.to_string());
 
-    if utoken_actual >= utoken_expected {
-        mark_reconciled_batches(&mut batches);
-        for batch in &batches {
-            state.previous_batches.save(deps.storage, batch.id, batch)?;
-        }
-        let ids = batches.iter().map(|b| b.id.to_string()).collect::<Vec<_>>().join(",");
-        let event = Event::new("erishub/reconciled")
-            .add_attribute("ids", ids)
-            .add_attribute("utoken_deducted", "0");
-        return Ok(Response::new().add_event(event).add_attribute("action", "erishub/reconcile"));
+    let mut res = Response::new().add_event(event);
+    if !utoken_surplus.is_zero() {
+        res = res.add_event(surplus_event(utoken_surplus));
     }
 
-    let utoken_to_deduct = utoken_expected - utoken_actual;
+    Ok(res.add_attribute("action", "erishub/reconcile"))
+}
 
-    reconcile_batches(&mut batches, utoken_to_deduct);
+/// Unreconciled batches, oldest first
+fn unreconciled_batches<'a>(
+    storage: &'a dyn Storage,
+    state: &State<'a>,
+) -> impl Iterator<Item = StdResult<Batch>> + 'a {
+    state
+        .previous_batches
+        .idx
+        .reconciled
+        .prefix(false.into())
+        .range(storage, None, None, Order::Ascending)
+        .map(|item| item.map(|(_, v)| v))
+}
 
-    for batch in &batches {
-        state.previous_batches.save(deps.storage, batch.id, batch)?;
+/// Rounding of undelegations and withdrawals, as well as rewards claimed automatically with
+/// undelegations, leave utoken on the contract that no batch claims. Whatever the contract holds
+/// beyond the unlocked coins, the claims payable from the contract and the amounts undelegated from
+/// removed validators is credited to the unlocked coins, so that the next reinvest bonds it for
+/// the stakers instead of it sitting idle. Returns the credited surplus.
+fn credit_surplus(
+    storage: &mut dyn Storage,
+    state: &State,
+    utoken_balance: Uint128,
+    current_time: u64,
+) -> StdResult<Uint128> {
+    let mut unlocked_coins = Coins(state.unlocked_coins.load(storage)?);
+
+    // the claims of batches still unbonding are not on the contract, except for the part taken
+    // from the amounts undelegated from removed validators. Batches maturing in this block are
+    // payable, their funds may have arrived already
+    let mut utoken_unbonding = Uint128::zero();
+    for batch in unreconciled_batches(storage, state) {
+        let batch = batch?;
+        if batch.est_unbond_end_time > current_time {
+            let utoken_undelegated =
+                state.batch_utoken_undelegated.may_load(storage, batch.id)?.unwrap_or_default();
+            utoken_unbonding += batch.utoken_unclaimed.saturating_sub(utoken_undelegated);
+        }
+    }
+    let utoken_payable = state
+        .utoken_unclaimed
+        .may_load(storage)?
+        .unwrap_or_default()
+        .checked_sub(utoken_unbonding)?;
+
+    let utoken_held = unlocked_coins
+        .find(CONTRACT_DENOM)
+        .amount
+        .checked_add(utoken_payable)?
+        .checked_add(state.utoken_undelegated.may_load(storage)?.unwrap_or_default())?;
+    let utoken_surplus = utoken_balance.saturating_sub(utoken_held);
+
+    if !utoken_surplus.is_zero() {
+        unlocked_coins.add(&Coin::new(utoken_surplus.u128(), CONTRACT_DENOM))?;
+        state.unlocked_coins.save(storage, &unlocked_coins.0)?;
+
+        let utoken_dust_recycled =
+            state.utoken_dust_recycled.may_load(storage)?.unwrap_or_default();
+        state
+            .utoken_dust_recycled
+            .save(storage, &utoken_dust_recycled.checked_add(utoken_surplus)?)?;
     }
 
-    let ids = batches.iter().map(|b| b.id.to_string()).collect::<Vec<_>>().join(",");
+    Ok(utoken_surplus)
+}
 
-    let event = Event::new("erishub/reconciled")
-        .add_attribute("ids", ids)
-        .add_attribute("utoken_deducted", utoken_to_deduct.to_string());
+fn surplus_event(utoken_surplus: Uint128) -> Event {
+    Event::new("erishub/surplus_credited").add_attribute("utoken_surplus", utoken_surplus)
+}
+
+/// Repairs a drift of the tracked stake token supply from the bank supply up to
+/// `MAX_SUPPLY_DRIFT_BPS`. A larger drift points to an accounting bug, so instead of repairing it,
+/// bonding and unbonding are paused until the guardian clears the circuit breaker.
+pub fn sync_supply(deps: DepsMut) -> ContractResult {
+    let state = State::default();
+    state.assert_not_migrating(deps.storage)?;
+
+    let mut stake = state.stake_token.load(deps.storage)?;
+    let total_supply = stake.total_supply;
+    let bank_supply = deps.querier.query_supply(&stake.denom)?.amount;
+
+    let drift = if bank_supply > total_supply {
+        bank_supply - total_supply
+    } else {
+        total_supply - bank_supply
+    };
+    let max_drift = total_supply.multiply_ratio(MAX_SUPPLY_DRIFT_BPS, BasicPoints::MAX);
 
-    Ok(Response::new().add_event(event).add_attribute("action", "erishub/reconcile"))
+    let event = if drift > max_drift {
+        state.circuit_breaker_tripped.save(deps.storage, &true)?;
+        Event::new("erishub/supply_drift_alert")
+    } else {
+        stake.total_supply = bank_supply;
+        state.stake_token.save(deps.storage, &stake)?;
+        Event::new("erishub/supply_synced")
+    };
+
+    let event = event
+        .add_attribute("total_supply", total_supply)
+        .add_attribute("bank_supply", bank_supply)
+        .add_attribute("drift", drift);
+
+    Ok(Response::new().add_event(event).add_attribute("action", "erishub/sync_supply"))
 }
 
-pub fn withdraw_unbonded(deps: DepsMut, env: Env, user: Addr, receiver: Addr) -> ContractResult {
+pub fn withdraw_unbonded(
+    deps: DepsMut,
+    env: Env,
+    user: Addr,
+    receiver: Addr,
+    receiver_msg: Option<Binary>,
+) -> ContractResult {
     let state = State::default();
+    state.assert_not_migrating(deps.storage)?;
     let current_time = env.block.time.seconds();
 
+    // the hub would run the message as one of its own callbacks
+    if receiver_msg.is_some() {
+        validate_no_self_reference(&env.contract.address, [&receiver], "receiver")?;
+    }
+
     // NOTE: If the user has too many unclaimed requests, this may not fit in the WASM memory...
     // However, this is practically never going to happen. Who would create hundreds of unbonding
     // requests and never claim them?
@@ -723,6 +1844,7 @@ pub fn withdraw_unbonded(deps: DepsMut, env: Env, user: Addr, receiver: Addr) ->
                 }
 
                 state.unbond_requests.remove(deps.storage, (request.id, &user))?;
+                remove_from_unbond_requests_totals(deps.storage, &state, request)?;
             }
         }
     }
@@ -730,11 +1852,21 @@ pub fn withdraw_unbonded(deps: DepsMut, env: Env, user: Addr, receiver: Addr) ->
     if total_utoken_to_refund.is_zero() {
         return Err(ContractError::CantBeZero("withdrawable amount".into()));
     }
-
-    let refund_msg = CosmosMsg::Bank(BankMsg::Send {
-        to_address: receiver.clone().into(),
-        amount: vec![Coin::new(total_utoken_to_refund.u128(), CONTRACT_DENOM)],
-    });
+    state.update_utoken_unclaimed(deps.storage, Uint128::zero(), total_utoken_to_refund)?;
+
+    let refund = vec![Coin::new(total_utoken_to_refund.u128(), CONTRACT_DENOM)];
+    let refund_msg = match receiver_msg {
+        // lets receiver contracts process the withdrawn funds atomically
+        Some(msg) => CosmosMsg::Wasm(WasmMsg::Execute {
+            contract_addr: receiver.to_string(),
+            msg,
+            funds: refund,
+        }),
+        None => CosmosMsg::Bank(BankMsg::Send {
+            to_address: receiver.to_string(),
+            amount: refund,
+        }),
+    };
 
     let event = Event::new("erishub/unbonded_withdrawn")
         .add_attribute("ids", ids.join(","))
@@ -748,15 +1880,149 @@ pub fn withdraw_unbonded(deps: DepsMut, env: Env, user: Addr, receiver: Addr) ->
         .add_attribute("action", "erishub/withdraw_unbonded"))
 }
 
+/// Moves `shares` of the sender's unbonding request in batch `id` to the recipient, who can then
+/// withdraw them. With `msg`, the recipient contract is notified with `ReceiveUnbondRequest`.
+pub fn transfer_unbond_request(
+    deps: DepsMut,
+    sender: Addr,
+    id: u64,
+    recipient: Addr,
+    shares: Option<Uint128>,
+    msg: Option<Binary>,
+) -> ContractResult {
+    let state = State::default();
+    state.assert_not_migrating(deps.storage)?;
+
+    let mut request = state
+        .unbond_requests
+        .may_load(deps.storage, (id, &sender))?
+        .ok_or(ContractError::UnbondRequestNotFound(id))?;
+    let shares = shares.unwrap_or(request.shares);
+    if shares.is_zero() {
+        return Err(ContractError::CantBeZero("shares".into()));
+    }
+    if shares > request.shares {
+        return Err(ContractError::InsufficientUnbondShares(request.shares));
+    }
+
+    request.shares -= shares;
+    if request.shares.is_zero() {
+        state.unbond_requests.remove(deps.storage, (id, &sender))?;
+        remove_from_unbond_requests_totals(
+            deps.storage,
+            &state,
+            &UnbondRequest {
+                id,
+                user: sender.clone(),
+                shares,
+            },
+        )?;
+    } else {
+        state.unbond_requests.save(deps.storage, (id, &sender), &request)?;
+        let unbond_totals = state.unbond_totals_of(deps.storage, id, &sender)?;
+        let mut totals = unbond_totals.load(deps.storage, id)?;
+        totals.shares = totals.shares.checked_sub(shares)?;
+        unbond_totals.save(deps.storage, id, &totals)?;
+    }
+
+    let mut is_new_request = false;
+    state.unbond_requests.update(deps.storage, (id, &recipient), |x| -> StdResult<_> {
+        let mut request = x.unwrap_or_else(|| {
+            is_new_request = true;
+            UnbondRequest {
+                id,
+                user: recipient.clone(),
+                shares: Uint128::zero(),
+            }
+        });
+        request.shares += shares;
+        Ok(request)
+    })?;
+
+    let unbond_totals = if is_new_request {
+        state.count_new_unbond_request(deps.storage, id, &recipient)?
+    } else {
+        state.unbond_totals_of(deps.storage, id, &recipient)?
+    };
+    unbond_totals.update(deps.storage, id, |x| -> StdResult<_> {
+        let mut totals = x.unwrap_or_default();
+        if is_new_request {
+            totals.count += 1;
+        }
+        totals.shares = totals.shares.checked_add(shares)?;
+        Ok(totals)
+    })?;
+
+    let receive_msg = msg
+        .map(|msg| {
+            UnbondRequestReceiveMsg {
+                sender: sender.to_string(),
+                id,
+                shares,
+                msg,
+            }
+            .into_cosmos_msg(&recipient)
+        })
+        .transpose()?;
+
+    let event = Event::new("erishub/unbond_request_transferred")
+        .add_attribute("id", id.to_string())
+        .add_attribute("sender", sender)
+        .add_attribute("recipient", recipient)
+        .add_attribute("shares", shares);
+
+    Ok(Response::new()
+        .add_optional_message(receive_msg)
+        .add_event(event)
+        .add_attribute("action", "erishub/transfer_unbond_request"))
+}
+
+/// Removes a withdrawn request from the totals it is counted in, dropping them once they are empty
+fn remove_from_unbond_requests_totals(
+    storage: &mut dyn Storage,
+    state: &State,
+    request: &UnbondRequest,
+) -> StdResult<()> {
+    let unbond_totals = state.unbond_totals_of(storage, request.id, &request.user)?;
+    state.unbond_request_allowlisted.remove(storage, (request.id, &request.user));
+    let mut totals = unbond_totals.load(storage, request.id)?;
+    totals.count = totals
+        .count
+        .checked_sub(1)
+        .ok_or_else(|| OverflowError::new(OverflowOperation::Sub, totals.count, 1))?;
+    totals.shares = totals.shares.checked_sub(request.shares)?;
+
+    if totals.count == 0 {
+        unbond_totals.remove(storage, request.id);
+    } else {
+        unbond_totals.save(storage, request.id, &totals)?;
+    }
+    Ok(())
+}
+
 pub fn tune_delegations(deps: DepsMut, env: Env, sender: Addr) -> ContractResult {
     let state = State::default();
     state.assert_owner(deps.storage, &sender)?;
+    if let Some(DelegationStrategy::Performance) =
+        state.delegation_strategy.may_load(deps.storage)?
+    {
+        record_validator_performance(&state, deps.storage, &deps.querier)?;
+    }
     let (wanted_delegations, save) = get_wanted_delegations(
         &state,
         &env,
         deps.storage,
         &deps.querier, //, TuneInfoGaugeLoader {}
     )?;
+
+    let mut events = vec![];
+    if let Some(DelegationStrategy::Registry {
+        ..
+    }) = state.delegation_strategy.may_load(deps.storage)?
+    {
+        events = sync_registry_validators(deps.storage, &state, &wanted_delegations)?;
+    }
+
     let attributes = if save {
         state.delegation_goal.save(deps.storage, &wanted_delegations)?;
         wanted_delegations
@@ -770,10 +2036,57 @@ pub fn tune_delegations(deps: DepsMut, env: Env, sender: Addr) -> ContractResult
         vec![]
     };
     Ok(Response::new()
+        .add_events(events)
         .add_attribute("action", "erishub/tune_delegations")
         .add_attributes(attributes))
 }
 
+/// Adds an observation of each whitelisted validator for the performance delegation strategy
+fn record_validator_performance(
+    state: &State,
+    storage: &mut dyn Storage,
+    querier: &QuerierWrapper,
+) -> StdResult<()> {
+    for validator in state.validators.load(storage)? {
+        let mut performance =
+            state.validator_performance.may_load(storage, &validator)?.unwrap_or_default();
+        performance.samples += 1;
+        if let Some(info) = querier.query_validator(&validator)? {
+            performance.active_samples += 1;
+            performance.yield_sum =
+                performance.yield_sum.checked_add(Decimal::one() - info.commission)?;
+        }
+        state.validator_performance.save(storage, &validator, &performance)?;
+    }
+    Ok(())
+}
+
+/// Replaces the whitelisted validators with the ones of the registry. Added validators ramp up
+/// like the ones added by the owner, delegations of removed validators are moved by rebalancing.
+fn sync_registry_validators(
+    storage: &mut dyn Storage,
+    state: &State,
+    wanted_delegations: &WantedDelegationsShare,
+) -> StdResult<Vec<Event>> {
+    let previous = state.validators.load(storage)?;
+    let validators: Vec<String> =
+        wanted_delegations.shares.iter().map(|(validator, _)| validator.clone()).collect();
+    let pending_batch_id = state.pending_batch.load(storage)?.id;
+
+    let mut events = vec![];
+    for validator in validators.iter().filter(|v| !previous.contains(v)) {
+        state.ramping_validators.save(storage, validator, &pending_batch_id)?;
+        events.push(Event::new("erishub/validator_added").add_attribute("validator", validator));
+    }
+    for validator in previous.iter().filter(|v| !validators.contains(v)) {
+        state.ramping_validators.remove(storage, validator);
+        events.push(Event::new("erishub/validator_removed").add_attribute("validator", validator));
+    }
+
+    state.validators.save(storage, &validators)?;
+    Ok(events)
+}
+
 //--------------------------------------------------------------------------------------------------
 // Ownership and management logics
 //--------------------------------------------------------------------------------------------------
@@ -791,12 +2104,18 @@ pub fn rebalance(
     let validators = state.validators.load(deps.storage)?;
 
     let min_redelegation = min_redelegation.unwrap_or_default();
+    let tolerance = state.rebalance_tolerance.may_load(deps.storage)?.unwrap_or_default();
 
-    let new_redelegations =
-        compute_redelegations_for_rebalancing(&state, deps.storage, &delegations, validators)?
-            .into_iter()
-            .filter(|redelegation| redelegation.amount >= min_redelegation.u128())
-            .collect::<Vec<_>>();
+    let new_redelegations = compute_redelegations_for_rebalancing(
+        &state,
+        deps.storage,
+        &delegations,
+        validators,
+        tolerance,
+    )?
+    .into_iter()
+    .filter(|redelegation| redelegation.amount >= min_redelegation.u128())
+    .collect::<Vec<_>>();
 
     let redelegate_msgs = new_redelegations.iter().map(|rd| rd.to_cosmos_msg()).collect::<Vec<_>>();
 
@@ -832,6 +2151,10 @@ pub fn add_validator(deps: DepsMut, sender: Addr, validator: String) -> Contract
         Ok(validators)
     })?;
 
+    // the new validator only gets a growing fraction of its share over the next epochs
+    let pending_batch_id = state.pending_batch.load(deps.storage)?.id;
+    state.ramping_validators.save(deps.storage, &validator, &pending_batch_id)?;
+
     let event = Event::new("erishub/validator_added").add_attribute("validator", validator);
 
     Ok(Response::new().add_event(event).add_attribute("action", "erishub/add_validator"))
@@ -842,24 +2165,61 @@ pub fn remove_validator(
     env: Env,
     sender: Addr,
     validator: String,
+    mode: RemoveValidatorMode,
 ) -> ContractResult {
     let state = State::default();
 
     state.assert_owner(deps.storage, &sender)?;
 
-    let validators = state.validators.update(deps.storage, |mut validators| {
-        if !validators.contains(&validator) {
-            return Err(ContractError::ValidatorNotWhitelisted(validator.clone()));
-        }
-        validators.retain(|v| *v != validator);
-        Ok(validators)
-    })?;
-
     let delegation_strategy =
         state.delegation_strategy.may_load(deps.storage)?.unwrap_or(DelegationStrategy::Uniform);
+    if let DelegationStrategy::Defined {
+        shares_bps,
+    } = &delegation_strategy
+    {
+        if shares_bps.iter().any(|(v, _)| *v == validator) {
+            return Err(ContractError::ValidatorHasDefinedShare(validator));
+        }
+    }
+
+    let mut validators = state.validators.load(deps.storage)?;
+    if !validators.contains(&validator) {
+        return Err(ContractError::ValidatorNotWhitelisted(validator));
+    }
+    validators.retain(|v| *v != validator);
+
+    // everything is validated before the first write
+    let mut utoken_undelegated = None;
+    let redelegate_msgs = match (mode, delegation_strategy) {
+        (RemoveValidatorMode::Undelegate, _) => {
+            // the undelegated amount is used by the next unbonding batches, so it keeps counting
+            // towards the exchange rate until then
+            let delegation_to_remove =
+                query_delegation(&deps.querier, &validator, &env.contract.address)?;
+            if delegation_to_remove.amount == 0 {
+                vec![]
+            } else {
+                utoken_undelegated = Some(
+                    state
+                        .utoken_undelegated
+                        .may_load(deps.storage)?
+                        .unwrap_or_default()
+                        .checked_add(Uint128::new(delegation_to_remove.amount))?,
+                );
+                vec![Undelegation::new(&validator, delegation_to_remove.amount).to_cosmos_msg()]
+            }
+        },
+        (
+            RemoveValidatorMode::Redelegate,
+            DelegationStrategy::Uniform
+            | DelegationStrategy::Registry {
+                ..
+            },
+        ) => {
+            if validators.is_empty() {
+                return Err(ContractError::NoActiveValidator {});
+            }
 
-    let redelegate_msgs = match delegation_strategy {
-        DelegationStrategy::Uniform => {
             // only redelegate when old strategy
             let delegations = query_delegations(&deps.querier, &validators, &env.contract.address)?;
             let delegation_to_remove =
@@ -869,7 +2229,7 @@ pub fn remove_validator(
                 deps.storage,
                 &delegation_to_remove,
                 &delegations,
-                validators,
+                validators.clone(),
             )?;
 
             new_redelegations.iter().map(|d| d.to_cosmos_msg()).collect::<Vec<_>>()
@@ -878,14 +2238,24 @@ pub fn remove_validator(
         //     ..
         // }
         // |
-        DelegationStrategy::Defined {
-            ..
-        } => {
+        (
+            RemoveValidatorMode::Redelegate,
+            DelegationStrategy::Defined {
+                ..
+            }
+            | DelegationStrategy::Performance,
+        ) => {
             // removed validators can have a delegation until the next tune, to keep undelegations in sync.
             vec![]
         },
     };
 
+    state.validators.save(deps.storage, &validators)?;
+    state.ramping_validators.remove(deps.storage, &validator);
+    if let Some(utoken_undelegated) = utoken_undelegated {
+        state.utoken_undelegated.save(deps.storage, &utoken_undelegated)?;
+    }
+
     let event = Event::new("erishub/validator_removed").add_attribute("validator", validator);
 
     let check_msg = if !redelegate_msgs.is_empty() {
@@ -902,79 +2272,518 @@ pub fn remove_validator(
         .add_attribute("action", "erishub/remove_validator"))
 }
 
-pub fn transfer_ownership(deps: DepsMut, sender: Addr, new_owner: String) -> ContractResult {
+pub fn set_validator_payout(
+    deps: DepsMut,
+    sender: Addr,
+    validator: String,
+    payout: Option<String>,
+) -> ContractResult {
+    let state = State::default();
+
+    // validators manage their payout address themselves once the owner registered it
+    let current = state.validator_payouts.may_load(deps.storage, &validator)?;
+    if current.as_ref() != Some(&sender) {
+        state.assert_owner(deps.storage, &sender)?;
+    }
+
+    if !state.validators.load(deps.storage)?.contains(&validator) {
+        return Err(ContractError::ValidatorNotWhitelisted(validator));
+    }
+
+    let mut event =
+        Event::new("erishub/validator_payout_set").add_attribute("validator", &validator);
+    match payout {
+        Some(payout) => {
+            let payout = deps.api.addr_validate(&payout)?;
+            event = event.add_attribute("payout", &payout);
+            state.validator_payouts.save(deps.storage, &validator, &payout)?;
+        },
+        None => state.validator_payouts.remove(deps.storage, &validator),
+    }
+
+    Ok(Response::new().add_event(event).add_attribute("action", "erishub/set_validator_payout"))
+}
+
+pub fn set_withdraw_template(
+    deps: DepsMut,
+    env: Env,
+    sender: Addr,
+    name: String,
+    template: Option<WithdrawTemplate>,
+) -> ContractResult {
     let state = State::default();
 
     state.assert_owner(deps.storage, &sender)?;
-    state.new_owner.save(deps.storage, &deps.api.addr_validate(&new_owner)?)?;
 
-    Ok(Response::new().add_attribute("action", "erishub/transfer_ownership"))
+    let mut event = Event::new("erishub/withdraw_template_set").add_attribute("name", &name);
+    match template {
+        Some(template) => {
+            let contract = deps.api.addr_validate(&template.contract)?;
+            validate_external_contracts(
+                &deps.querier,
+                &env.contract.address,
+                [&contract],
+                "withdraw template",
+            )?;
+            event = event.add_attribute("contract", contract);
+            state.withdraw_templates.save(deps.storage, &name, &template)?;
+        },
+        None => state.withdraw_templates.remove(deps.storage, &name),
+    }
+
+    Ok(Response::new().add_event(event).add_attribute("action", "erishub/set_withdraw_template"))
+}
+
+pub fn transfer_ownership(deps: DepsMut, sender: Addr, new_owner: String) -> ContractResult {
+    Ok(State::default().ownership.transfer_ownership(deps, &sender, new_owner)?)
 }
 
 pub fn drop_ownership_proposal(deps: DepsMut, sender: Addr) -> ContractResult {
+    Ok(State::default().ownership.drop_ownership_proposal(deps, &sender)?)
+}
+
+pub fn accept_ownership(deps: DepsMut, sender: Addr) -> ContractResult {
+    Ok(State::default().ownership.accept_ownership(deps, &sender)?)
+}
+
+pub fn accept_operator(deps: DepsMut, sender: Addr) -> ContractResult {
+    let state = State::default();
+
+    let previous_operator = state.operator.load(deps.storage)?;
+    let new_operator = state
+        .new_operator
+        .may_load(deps.storage)?
+        .ok_or_else(|| ContractError::unauthorized(Role::NewOperator, &sender))?;
+
+    if sender != new_operator {
+        return Err(ContractError::unauthorized(Role::NewOperator, &sender));
+    }
+
+    state.operator.save(deps.storage, &sender)?;
+    state.new_operator.remove(deps.storage);
+
+    let event = Event::new("erishub/operator_transferred")
+        .add_attribute("new_operator", new_operator)
+        .add_attribute("previous_operator", previous_operator);
+
+    Ok(Response::new().add_event(event).add_attribute("action", "erishub/accept_operator"))
+}
+
+pub fn accept_vote_operator(deps: DepsMut, sender: Addr) -> ContractResult {
+    let state = State::default();
+
+    let new_vote_operator = state
+        .new_vote_operator
+        .may_load(deps.storage)?
+        .ok_or_else(|| ContractError::unauthorized(Role::NewVoteOperator, &sender))?;
+
+    if sender != new_vote_operator {
+        return Err(ContractError::unauthorized(Role::NewVoteOperator, &sender));
+    }
+
+    let previous_vote_operator = state.vote_operator.may_load(deps.storage)?;
+    state.vote_operator.save(deps.storage, &sender)?;
+    state.new_vote_operator.remove(deps.storage);
+
+    let event = Event::new("erishub/vote_operator_transferred")
+        .add_attribute("new_vote_operator", new_vote_operator)
+        .add_attribute(
+            "previous_vote_operator",
+            previous_vote_operator.map(|addr| addr.to_string()).unwrap_or_default(),
+        );
+
+    Ok(Response::new().add_event(event).add_attribute("action", "erishub/accept_vote_operator"))
+}
+
+pub fn clear_circuit_breaker(deps: DepsMut, sender: Addr) -> ContractResult {
+    let state = State::default();
+
+    state.assert_guardian(deps.storage, &sender)?;
+    state.circuit_breaker_tripped.save(deps.storage, &false)?;
+
+    let event = Event::new("erishub/circuit_breaker_cleared").add_attribute("sender", sender);
+
+    Ok(Response::new().add_event(event).add_attribute("action", "erishub/clear_circuit_breaker"))
+}
+
+/// Applies a complete config at once. Validators that still have delegations can't be dropped, they
+/// need to be removed through `RemoveValidator` so that their delegation is moved.
+pub fn import_config(
+    mut deps: DepsMut,
+    env: Env,
+    sender: Addr,
+    config: HubConfig,
+) -> ContractResult {
     let state = State::default();
 
     state.assert_owner(deps.storage, &sender)?;
-    state.new_owner.remove(deps.storage);
 
-    Ok(Response::new().add_attribute("action", "erishub/drop_ownership_proposal"))
+    validate_protocol_reward_fee(config.protocol_reward_fee)?;
+    validate_period(config.epoch_period, "epoch_period")?;
+    validate_period(config.unbond_period, "unbond_period")?;
+    if config.swap_max_spread > Decimal::one() {
+        return Err(ContractError::SwapMaxSpreadTooHigh {});
+    }
+    if matches!(config.exchange_rate_tolerance, Some(tolerance) if tolerance > Decimal::one()) {
+        return Err(ContractError::ExchangeRateToleranceTooHigh {});
+    }
+    if config.rebalance_tolerance > Decimal::one() {
+        return Err(ContractError::RebalanceToleranceTooHigh {});
+    }
+
+    let mut validators = config.validators;
+    dedupe(&mut validators);
+    assert_validators_exists(&deps.querier, &validators)?;
+    for delegation in query_all_delegations(&deps.querier, &env.contract.address)? {
+        if !validators.contains(&delegation.validator) {
+            return Err(ContractError::ValidatorStillDelegated(delegation.validator));
+        }
+    }
+
+    validate_no_utoken_or_ustake_swap(&Some(config.stages_preset.clone()), &state, deps.storage)?;
+    validate_no_utoken_or_ustake_swap(
+        &Some(vec![config.swap_allowlist.clone()]),
+        &state,
+        deps.storage,
+    )?;
+    validate_external_contracts(
+        &deps.querier,
+        &env.contract.address,
+        config.stages_preset.iter().flatten().map(|s| &s.0),
+        "stages",
+    )?;
+    validate_stage_routes(&deps.querier, &config.stages_preset)?;
+    validate_external_contracts(
+        &deps.querier,
+        &env.contract.address,
+        config.swap_allowlist.iter().map(|s| &s.0),
+        "swap_allowlist",
+    )?;
+
+    let protocol_fee_contract = deps.api.addr_validate(&config.protocol_fee_contract)?;
+    let fin_multi_contract = deps.api.addr_validate(&config.fin_multi_contract)?;
+    validate_external_contracts(
+        &deps.querier,
+        &env.contract.address,
+        [&protocol_fee_contract],
+        "protocol_fee_contract",
+    )?;
+    validate_external_contracts(
+        &deps.querier,
+        &env.contract.address,
+        [&fin_multi_contract],
+        "fin_multi_contract",
+    )?;
+
+    state.fin_multi.save(deps.storage, &FinMulti(fin_multi_contract))?;
+    // a different operator or vote operator is only proposed, it still has to accept the role
+    let operator = deps.api.addr_validate(&config.operator)?;
+    if state.operator.load(deps.storage)? == operator {
+        state.new_operator.remove(deps.storage);
+    } else {
+        state.new_operator.save(deps.storage, &operator)?;
+    }
+    match config.vote_operator {
+        Some(vote_operator) => {
+            let vote_operator = deps.api.addr_validate(&vote_operator)?;
+            if state.vote_operator.may_load(deps.storage)?.as_ref() == Some(&vote_operator) {
+                state.new_vote_operator.remove(deps.storage);
+            } else {
+                state.new_vote_operator.save(deps.storage, &vote_operator)?;
+            }
+        },
+        None => {
+            state.vote_operator.remove(deps.storage);
+            state.new_vote_operator.remove(deps.storage);
+        },
+    }
+    match config.guardian {
+        Some(guardian) => state.guardian.save(deps.storage, &deps.api.addr_validate(&guardian)?)?,
+        None => state.guardian.remove(deps.storage),
+    }
+
+    state.epoch_period.save(deps.storage, &config.epoch_period)?;
+    state.unbond_period.save(deps.storage, &config.unbond_period)?;
+    state.fee_config.save(
+        deps.storage,
+        &FeeConfig {
+            protocol_fee_contract,
+            protocol_reward_fee: config.protocol_reward_fee,
+            unbond_fee: validate_unbond_fee(config.unbond_fee)?,
+            validator_fee_share: validate_validator_fee_share(config.validator_fee_share)?,
+        },
+    )?;
+    state
+        .delegation_strategy
+        .save(deps.storage, &config.delegation_strategy.validate(deps.api, &validators)?)?;
+    state.validators.save(deps.storage, &validators)?;
+    state.stages_preset.save(deps.storage, &config.stages_preset)?;
+    state.swap_allowlist.save(deps.storage, &config.swap_allowlist)?;
+    state.swap_max_spread.save(deps.storage, &config.swap_max_spread)?;
+    state.allow_donations.save(deps.storage, &config.allow_donations)?;
+    match config.exchange_rate_tolerance {
+        Some(tolerance) => state.exchange_rate_tolerance.save(deps.storage, &tolerance)?,
+        None => state.exchange_rate_tolerance.remove(deps.storage),
+    }
+    match config.max_reinvest_per_tx.filter(|max| !max.is_zero()) {
+        Some(max_reinvest_per_tx) => {
+            state.max_reinvest_per_tx.save(deps.storage, &max_reinvest_per_tx)?
+        },
+        None => state.max_reinvest_per_tx.remove(deps.storage),
+    }
+    save_unbond_allowlist(&mut deps, &state, &env.contract.address, config.unbond_allowlist)?;
+    state.min_hold_seconds.save(deps.storage, &config.min_hold_seconds)?;
+    state.reward_smoothing_period.save(deps.storage, &config.reward_smoothing_period)?;
+    state.max_harvest_staleness.save(deps.storage, &config.max_harvest_staleness)?;
+    validate_protocol_fee_schedule(&config.protocol_fee_schedule)?;
+    state.protocol_fee_schedule.save(deps.storage, &config.protocol_fee_schedule)?;
+    validate_action_cooldowns(&config.action_cooldowns)?;
+    state.action_cooldowns.save(deps.storage, &config.action_cooldowns)?;
+    save_express_epoch(deps.storage, &state, config.express_epoch, config.epoch_period)?;
+    state.rebalance_tolerance.save(deps.storage, &config.rebalance_tolerance)?;
+
+    Ok(Response::new().add_attribute("action", "erishub/import_config"))
 }
 
-pub fn accept_ownership(deps: DepsMut, sender: Addr) -> ContractResult {
+pub fn set_state_export(deps: DepsMut, sender: Addr, enabled: bool) -> ContractResult {
+    let state = State::default();
+
+    state.assert_owner(deps.storage, &sender)?;
+
+    match (state.migration_mode.may_load(deps.storage)?, enabled) {
+        (Some(MigrationMode::Import), _) => return Err(ContractError::MigrationInProgress {}),
+        (_, true) => state.migration_mode.save(deps.storage, &MigrationMode::Export)?,
+        (_, false) => state.migration_mode.remove(deps.storage),
+    }
+
+    Ok(Response::new()
+        .add_attribute("action", "erishub/set_state_export")
+        .add_attribute("enabled", enabled.to_string()))
+}
+
+/// Imports a section of the state of another hub. The `utoken` of the imported batches has to be
+/// transferred from the other hub separately.
+pub fn import_state(deps: DepsMut, sender: Addr, export: StateExport) -> ContractResult {
     let state = State::default();
 
-    let previous_owner = state.owner.load(deps.storage)?;
-    let new_owner = state.new_owner.load(deps.storage)?;
+    state.assert_owner(deps.storage, &sender)?;
+    if state.migration_mode.may_load(deps.storage)? != Some(MigrationMode::Import) {
+        return Err(ContractError::NotImportingState {});
+    }
+
+    let pending_batch = state.pending_batch.load(deps.storage)?;
+    let imported = match export {
+        StateExport::PendingBatch(pending_batch) => {
+            if !pending_batch.ustake_to_burn.is_zero() {
+                return Err(ContractError::PendingBatchNotEmpty {});
+            }
+            state.pending_batch.save(deps.storage, &pending_batch)?;
+            1
+        },
+        StateExport::PreviousBatches(batches) => {
+            for batch in &batches {
+                if batch.id >= pending_batch.id {
+                    return Err(ContractError::BatchNotSubmitted(batch.id));
+                }
+                // a batch imported again replaces its previous claim in the total
+                let utoken_replaced = state
+                    .previous_batches
+                    .may_load(deps.storage, batch.id)?
+                    .map_or(Uint128::zero(), |b| b.utoken_unclaimed);
+                state.update_utoken_unclaimed(
+                    deps.storage,
+                    batch.utoken_unclaimed,
+                    utoken_replaced,
+                )?;
+                state.previous_batches.save(deps.storage, batch.id, batch)?;
+            }
+            batches.len()
+        },
+        StateExport::UnbondRequests(requests) => {
+            for request in &requests {
+                if state.previous_batches.may_load(deps.storage, request.id)?.is_none() {
+                    return Err(ContractError::BatchNotImported(request.id));
+                }
+                // importing a section again must not count its requests twice
+                let key = (request.id, &request.user);
+                if state.unbond_requests.may_load(deps.storage, key)?.is_some() {
+                    continue;
+                }
+                state.unbond_requests.save(deps.storage, key, request)?;
+
+                let unbond_totals =
+                    state.count_new_unbond_request(deps.storage, request.id, &request.user)?;
+                unbond_totals.update(deps.storage, request.id, |x| -> StdResult<_> {
+                    let mut totals = x.unwrap_or_default();
+                    totals.count += 1;
+                    totals.shares = totals.shares.checked_add(request.shares)?;
+                    Ok(totals)
+                })?;
+            }
+            requests.len()
+        },
+    };
+
+    Ok(Response::new()
+        .add_attribute("action", "erishub/import_state")
+        .add_attribute("imported", imported.to_string()))
+}
+
+pub fn complete_import(deps: DepsMut, sender: Addr) -> ContractResult {
+    let state = State::default();
 
-    if sender != new_owner {
-        return Err(ContractError::UnauthorizedSenderNotNewOwner {});
+    state.assert_owner(deps.storage, &sender)?;
+    if state.migration_mode.may_load(deps.storage)? != Some(MigrationMode::Import) {
+        return Err(ContractError::NotImportingState {});
     }
+    state.migration_mode.remove(deps.storage);
 
-    state.owner.save(deps.storage, &sender)?;
-    state.new_owner.remove(deps.storage);
+    Ok(Response::new().add_attribute("action", "erishub/complete_import"))
+}
 
-    let event = Event::new("erishub/ownership_transferred")
-        .add_attribute("new_owner", new_owner)
-        .add_attribute("previous_owner", previous_owner);
+/// Contracts can join or leave the unbond allowlist at any time, their outstanding requests stay
+/// counted in the totals they were added to.
+fn save_unbond_allowlist(
+    deps: &mut DepsMut,
+    state: &State,
+    contract: &Addr,
+    unbond_allowlist: Vec<String>,
+) -> Result<(), ContractError> {
+    let mut allowlist = unbond_allowlist
+        .iter()
+        .map(|addr| deps.api.addr_validate(addr))
+        .collect::<StdResult<Vec<_>>>()?;
+    allowlist.sort();
+    allowlist.dedup();
+    validate_external_contracts(&deps.querier, contract, &allowlist, "unbond_allowlist")?;
 
-    Ok(Response::new().add_event(event).add_attribute("action", "erishub/transfer_ownership"))
+    state.unbond_allowlist.save(deps.storage, &allowlist)?;
+    Ok(())
+}
+
+/// Removes the owner for good. Afterwards all owner-gated actions fail with `OwnershipRenounced`,
+/// so a guardian must be set to keep the circuit breaker clearable
+pub fn renounce_ownership(deps: DepsMut, sender: Addr, confirmation: String) -> ContractResult {
+    let state = State::default();
+
+    state.assert_owner(deps.storage, &sender)?;
+
+    if confirmation != RENOUNCE_OWNERSHIP_CONFIRMATION {
+        return Err(ContractError::InvalidRenounceConfirmation(
+            RENOUNCE_OWNERSHIP_CONFIRMATION.to_string(),
+        ));
+    }
+
+    // without a guardian, clearing the circuit breaker falls back to the owner
+    if state.guardian.may_load(deps.storage)?.is_none() {
+        return Err(ContractError::RenounceWithoutGuardian {});
+    }
+
+    Ok(state.ownership.renounce_ownership(deps, &sender)?)
 }
 
 #[allow(clippy::too_many_arguments)]
 pub fn update_config(
-    deps: DepsMut,
+    mut deps: DepsMut,
+    env: Env,
     sender: Addr,
     protocol_fee_contract: Option<String>,
     protocol_reward_fee: Option<Decimal>,
     operator: Option<String>,
     stages_preset: Option<Vec<Vec<(Addr, Denom)>>>,
+    swap_allowlist: Option<Vec<(Addr, Denom)>>,
+    swap_max_spread: Option<Decimal>,
     allow_donations: Option<bool>,
     delegation_strategy: Option<DelegationStrategy>,
     vote_operator: Option<String>,
+    guardian: Option<String>,
+    exchange_rate_tolerance: Option<Decimal>,
+    max_reinvest_per_tx: Option<Uint128>,
+    unbond_allowlist: Option<Vec<String>>,
+    unbond_fee: Option<UnbondFee>,
+    min_hold_seconds: Option<u64>,
+    reward_smoothing_period: Option<u64>,
+    validator_fee_share: Option<Decimal>,
+    max_harvest_staleness: Option<u64>,
+    protocol_fee_schedule: Option<Vec<ProtocolFeePeriod>>,
+    action_cooldowns: Option<Vec<ActionCooldown>>,
+    express_epoch: Option<ExpressEpoch>,
+    rebalance_tolerance: Option<Decimal>,
 ) -> ContractResult {
     let state = State::default();
 
     state.assert_owner(deps.storage, &sender)?;
 
-    if protocol_fee_contract.is_some() || protocol_reward_fee.is_some() {
+    if protocol_fee_contract.is_some()
+        || protocol_reward_fee.is_some()
+        || unbond_fee.is_some()
+        || validator_fee_share.is_some()
+    {
         let mut fee_config = state.fee_config.load(deps.storage)?;
 
         if let Some(protocol_fee_contract) = protocol_fee_contract {
-            fee_config.protocol_fee_contract = deps.api.addr_validate(&protocol_fee_contract)?;
+            let protocol_fee_contract = deps.api.addr_validate(&protocol_fee_contract)?;
+            validate_external_contracts(
+                &deps.querier,
+                &env.contract.address,
+                [&protocol_fee_contract],
+                "protocol_fee_contract",
+            )?;
+            fee_config.protocol_fee_contract = protocol_fee_contract;
         }
 
         if let Some(protocol_reward_fee) = protocol_reward_fee {
-            if protocol_reward_fee.gt(&get_reward_fee_cap()) {
-                return Err(ContractError::ProtocolRewardFeeTooHigh {});
-            }
+            validate_protocol_reward_fee(protocol_reward_fee)?;
             fee_config.protocol_reward_fee = protocol_reward_fee;
         }
 
+        if unbond_fee.is_some() {
+            fee_config.unbond_fee = validate_unbond_fee(unbond_fee)?;
+        }
+
+        if validator_fee_share.is_some() {
+            fee_config.validator_fee_share = validate_validator_fee_share(validator_fee_share)?;
+        }
+
         state.fee_config.save(deps.storage, &fee_config)?;
     }
 
+    if let Some(min_hold_seconds) = min_hold_seconds {
+        state.min_hold_seconds.save(deps.storage, &min_hold_seconds)?;
+    }
+
+    if let Some(reward_smoothing_period) = reward_smoothing_period {
+        state.reward_smoothing_period.save(deps.storage, &reward_smoothing_period)?;
+    }
+
+    if let Some(max_harvest_staleness) = max_harvest_staleness {
+        state.max_harvest_staleness.save(deps.storage, &max_harvest_staleness)?;
+    }
+
+    if let Some(protocol_fee_schedule) = protocol_fee_schedule {
+        validate_protocol_fee_schedule(&protocol_fee_schedule)?;
+        state.protocol_fee_schedule.save(deps.storage, &protocol_fee_schedule)?;
+    }
+
+    if let Some(action_cooldowns) = action_cooldowns {
+        validate_action_cooldowns(&action_cooldowns)?;
+        state.action_cooldowns.save(deps.storage, &action_cooldowns)?;
+    }
+
+    if let Some(express_epoch) = express_epoch {
+        let epoch_period = state.epoch_period.load(deps.storage)?;
+        save_express_epoch(deps.storage, &state, Some(express_epoch), epoch_period)?;
+    }
+
+    if let Some(rebalance_tolerance) = rebalance_tolerance {
+        if rebalance_tolerance > Decimal::one() {
+            return Err(ContractError::RebalanceToleranceTooHigh {});
+        }
+        state.rebalance_tolerance.save(deps.storage, &rebalance_tolerance)?;
+    }
+
     if let Some(operator) = operator {
-        state.operator.save(deps.storage, &deps.api.addr_validate(operator.as_str())?)?;
+        state.new_operator.save(deps.storage, &deps.api.addr_validate(operator.as_str())?)?;
     }
 
     if stages_preset.is_some() {
@@ -982,9 +2791,38 @@ pub fn update_config(
     }
 
     if let Some(stages_preset) = stages_preset {
+        validate_external_contracts(
+            &deps.querier,
+            &env.contract.address,
+            stages_preset.iter().flatten().map(|s| &s.0),
+            "stages",
+        )?;
+        validate_stage_routes(&deps.querier, &stages_preset)?;
         state.stages_preset.save(deps.storage, &stages_preset)?;
     }
 
+    if let Some(swap_allowlist) = swap_allowlist {
+        validate_no_utoken_or_ustake_swap(
+            &Some(vec![swap_allowlist.clone()]),
+            &state,
+            deps.storage,
+        )?;
+        validate_external_contracts(
+            &deps.querier,
+            &env.contract.address,
+            swap_allowlist.iter().map(|s| &s.0),
+            "swap_allowlist",
+        )?;
+        state.swap_allowlist.save(deps.storage, &swap_allowlist)?;
+    }
+
+    if let Some(swap_max_spread) = swap_max_spread {
+        if swap_max_spread > Decimal::one() {
+            return Err(ContractError::SwapMaxSpreadTooHigh {});
+        }
+        state.swap_max_spread.save(deps.storage, &swap_max_spread)?;
+    }
+
     if let Some(delegation_strategy) = delegation_strategy {
         let validators = state.validators.load(deps.storage)?;
         state
@@ -997,7 +2835,30 @@ pub fn update_config(
     }
 
     if let Some(vote_operator) = vote_operator {
-        state.vote_operator.save(deps.storage, &deps.api.addr_validate(&vote_operator)?)?;
+        state.new_vote_operator.save(deps.storage, &deps.api.addr_validate(&vote_operator)?)?;
+    }
+
+    if let Some(guardian) = guardian {
+        state.guardian.save(deps.storage, &deps.api.addr_validate(&guardian)?)?;
+    }
+
+    if let Some(exchange_rate_tolerance) = exchange_rate_tolerance {
+        if exchange_rate_tolerance > Decimal::one() {
+            return Err(ContractError::ExchangeRateToleranceTooHigh {});
+        }
+        state.exchange_rate_tolerance.save(deps.storage, &exchange_rate_tolerance)?;
+    }
+
+    if let Some(max_reinvest_per_tx) = max_reinvest_per_tx {
+        if max_reinvest_per_tx.is_zero() {
+            state.max_reinvest_per_tx.remove(deps.storage);
+        } else {
+            state.max_reinvest_per_tx.save(deps.storage, &max_reinvest_per_tx)?;
+        }
+    }
+
+    if let Some(unbond_allowlist) = unbond_allowlist {
+        save_unbond_allowlist(&mut deps, &state, &env.contract.address, unbond_allowlist)?;
     }
 
     Ok(Response::new().add_attribute("action", "erishub/update_config"))
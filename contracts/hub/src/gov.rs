@@ -1,6 +1,8 @@
 use cosmwasm_std::{
-    CosmosMsg, Decimal, DepsMut, Env, Event, Fraction, GovMsg, MessageInfo, Response,
+    CosmosMsg, Decimal, DepsMut, Env, Event, Fraction, GovMsg, MessageInfo, Response, StdResult,
+    Storage,
 };
+use eris::hub::VoteRecord;
 use itertools::Itertools;
 use kujira::msg::KujiraMsg;
 use protobuf::SpecialFields;
@@ -11,15 +13,37 @@ use crate::{
     state::State,
 };
 
+/// Records the latest vote on a proposal, replacing an earlier vote on the same proposal
+fn save_vote(
+    storage: &mut dyn Storage,
+    env: &Env,
+    info: &MessageInfo,
+    proposal_id: u64,
+    votes: Vec<(Decimal, cosmwasm_std::VoteOption)>,
+) -> StdResult<()> {
+    let state = State::default();
+    state.vote_history.save(
+        storage,
+        proposal_id,
+        &VoteRecord {
+            proposal_id,
+            votes,
+            operator: info.sender.clone(),
+            timestamp: env.block.time.seconds(),
+        },
+    )
+}
+
 pub fn vote(
     deps: DepsMut,
-    _env: Env,
+    env: Env,
     info: MessageInfo,
     proposal_id: u64,
     vote: cosmwasm_std::VoteOption,
 ) -> ContractResult {
     let state = State::default();
     state.assert_vote_operator(deps.storage, &info.sender)?;
+    save_vote(deps.storage, &env, &info, proposal_id, vec![(Decimal::one(), vote.clone())])?;
 
     let event = Event::new("erishub/voted").add_attribute("prop", proposal_id.to_string());
 
@@ -33,19 +57,20 @@ pub fn vote(
 
 pub fn vote_weighted(
     deps: DepsMut,
-    _env: Env,
+    env: Env,
     info: MessageInfo,
     proposal_id: u64,
     votes: Vec<(Decimal, cosmwasm_std::VoteOption)>,
 ) -> ContractResult {
     let state = State::default();
     state.assert_vote_operator(deps.storage, &info.sender)?;
+    save_vote(deps.storage, &env, &info, proposal_id, votes.clone())?;
 
     let event = Event::new("erishub/voted_weighted").add_attribute("prop", proposal_id.to_string());
 
     let vote = MsgVoteWeighted {
         proposal_id,
-        voter: _env.contract.address.to_string(),
+        voter: env.contract.address.to_string(),
         options: votes
             .into_iter()
             .map(|vote| WeightedVoteOption {
@@ -1,17 +1,31 @@
-use std::{collections::HashSet, convert::TryFrom};
+use std::{
+    cmp,
+    collections::{BTreeMap, HashSet},
+    convert::TryFrom,
+};
 
 use cosmwasm_std::{
-    Addr, Coin, Decimal, Env, QuerierWrapper, QueryRequest, StakingQuery, StdError, StdResult,
-    Storage, Uint128, ValidatorResponse,
+    Addr, Coin, ContractInfoResponse, Decimal, Env, Fraction, QuerierWrapper, QueryRequest,
+    StakingQuery, StdError, StdResult, Storage, Uint128, ValidatorResponse, WasmQuery,
 };
 use eris::{
+    adapters::validator_registry::ValidatorRegistry,
     governance_helper::get_period,
     helpers::bps::BasicPoints,
-    hub::{DelegationStrategy, WantedDelegationsShare},
+    hub::{DelegationStrategy, SimulatedSwap, WantedDelegationsShare},
+    querier::{query_fin_pair, simulate},
 };
 use itertools::Itertools;
+use kujira::{
+    asset::{Asset, AssetInfo},
+    denom::Denom,
+};
 
-use crate::{constants::CONTRACT_DENOM, state::State, types::Delegation};
+use crate::{
+    constants::{CONTRACT_DENOM, VALIDATOR_RAMP_UP_EPOCHS},
+    state::State,
+    types::Delegation,
+};
 
 /// Query the amounts of Luna a staker is delegating to a specific validator
 pub(crate) fn query_delegation(
@@ -28,6 +42,60 @@ pub(crate) fn query_delegation(
     })
 }
 
+/// Simulates the swaps of fin-multi: every stage offers the full balance of its denoms, the
+/// returned coins are available to the following stages.
+pub(crate) fn simulate_stages(
+    querier: &QuerierWrapper,
+    stages: &[Vec<(Addr, Denom)>],
+    balances: Vec<Coin>,
+) -> StdResult<Vec<SimulatedSwap>> {
+    let mut balances: BTreeMap<String, Uint128> =
+        balances.into_iter().map(|coin| (coin.denom, coin.amount)).collect();
+    let mut swaps = vec![];
+
+    for stage in stages {
+        for (pair, denom) in stage {
+            let offer_amount = balances.remove(&denom.to_string()).unwrap_or_default();
+            if offer_amount.is_zero() {
+                continue;
+            }
+
+            let ask_denom = query_fin_pair(querier, pair)?
+                .denoms
+                .iter()
+                .find(|d| *d != denom)
+                .cloned()
+                .ok_or_else(|| {
+                    StdError::generic_err(format!("{} is not a pair of {}", pair, denom))
+                })?;
+
+            let simulation = simulate(
+                querier,
+                pair.clone(),
+                &Asset {
+                    info: AssetInfo::NativeToken {
+                        denom: denom.clone(),
+                    },
+                    amount: offer_amount,
+                },
+            )?;
+            let return_amount = Uint128::try_from(simulation.return_amount)?;
+
+            let balance = balances.entry(ask_denom.to_string()).or_default();
+            *balance = balance.checked_add(return_amount)?;
+
+            swaps.push(SimulatedSwap {
+                pair: pair.clone(),
+                offer: Coin::new(offer_amount.u128(), denom.to_string()),
+                ask: Coin::new(return_amount.u128(), ask_denom.to_string()),
+                spread: Uint128::try_from(simulation.spread_amount)?,
+            });
+        }
+    }
+
+    Ok(swaps)
+}
+
 /// Query the amounts of Luna a staker is delegating to each of the validators specified
 pub(crate) fn query_delegations(
     querier: &QuerierWrapper,
@@ -90,6 +158,14 @@ pub fn assert_validator_exists(querier: &QuerierWrapper, validator: &String) ->
     Ok(())
 }
 
+pub fn assert_contract_exists(querier: &QuerierWrapper, contract_addr: &Addr) -> StdResult<()> {
+    let _result: ContractInfoResponse =
+        querier.query(&QueryRequest::Wasm(WasmQuery::ContractInfo {
+            contract_addr: contract_addr.to_string(),
+        }))?;
+    Ok(())
+}
+
 pub fn assert_validators_exists(
     querier: &QuerierWrapper,
     validators: &Vec<String>,
@@ -100,6 +176,22 @@ pub fn assert_validators_exists(
     Ok(())
 }
 
+/// Validators of the registry strategy. The pinned `fallback` validators are used when the
+/// registry can't be queried or has no validators.
+pub(crate) fn query_registry_validators(
+    querier: &QuerierWrapper,
+    registry: &str,
+    fallback: &[String],
+) -> Vec<String> {
+    let mut validators =
+        match ValidatorRegistry(Addr::unchecked(registry)).query_validators(querier) {
+            Ok(validators) if !validators.is_empty() => validators,
+            _ => fallback.to_vec(),
+        };
+    dedupe(&mut validators);
+    validators
+}
+
 /// Dedupes a Vector of strings using a hashset.
 pub fn dedupe(validators: &mut Vec<String>) {
     let mut set = HashSet::new();
@@ -107,6 +199,30 @@ pub fn dedupe(validators: &mut Vec<String>) {
     validators.retain(|x| set.insert(x.clone()));
 }
 
+/// Weight of each validator for the uniform strategy. Fully ramped up validators have a weight of
+/// `VALIDATOR_RAMP_UP_EPOCHS + 1`, a newly added validator starts at 1 and gains 1 each epoch.
+pub(crate) fn get_ramp_up_weights(
+    state: &State,
+    storage: &dyn Storage,
+    validators: &[String],
+) -> StdResult<Vec<(String, u128)>> {
+    let full_weight = VALIDATOR_RAMP_UP_EPOCHS + 1;
+    let pending_batch_id = state.pending_batch.load(storage)?.id;
+
+    validators
+        .iter()
+        .map(|validator| {
+            let weight = match state.ramping_validators.may_load(storage, validator)? {
+                Some(added_in) => {
+                    cmp::min(pending_batch_id.saturating_sub(added_in) + 1, full_weight)
+                },
+                None => full_weight,
+            };
+            Ok((validator.clone(), weight.into()))
+        })
+        .collect()
+}
+
 /// Calculates the wanted delegations based on the delegation strategy and the amp + emp gauges
 /// The source of the gauges is flexible via the loader
 /// This is only a read operation, so it can be used from queries aswell
@@ -114,7 +230,7 @@ pub(crate) fn get_wanted_delegations(
     state: &State,
     env: &Env,
     storage: &dyn Storage,
-    _querier: &QuerierWrapper,
+    querier: &QuerierWrapper,
     // loader: impl GaugeLoader,
 ) -> StdResult<(WantedDelegationsShare, bool)> {
     let delegation_strategy =
@@ -123,16 +239,16 @@ pub(crate) fn get_wanted_delegations(
     match delegation_strategy {
         DelegationStrategy::Uniform {} => {
             let validators = state.validators.load(storage)?;
-            let validator_count = Uint128::new(validators.len() as u128);
-            let share_per_validator = Decimal::from_ratio(Uint128::one(), validator_count);
+            let weights = get_ramp_up_weights(state, storage, &validators)?;
+            let total_weight: u128 = weights.iter().map(|(_, weight)| weight).sum();
 
             Ok((
                 WantedDelegationsShare {
                     tune_time: env.block.time.seconds(),
                     tune_period: get_period(env.block.time.seconds())?,
-                    shares: validators
+                    shares: weights
                         .into_iter()
-                        .map(|val| (val, share_per_validator))
+                        .map(|(val, weight)| (val, Decimal::from_ratio(weight, total_weight)))
                         .collect_vec(),
                 },
                 // no need to store it
@@ -153,6 +269,62 @@ pub(crate) fn get_wanted_delegations(
             // store it for get_utoken_per_validator
             true,
         )),
+        DelegationStrategy::Registry {
+            registry,
+            fallback,
+        } => {
+            let validators = query_registry_validators(querier, &registry, &fallback);
+            let count = validators.len() as u128;
+
+            Ok((
+                WantedDelegationsShare {
+                    tune_time: env.block.time.seconds(),
+                    tune_period: get_period(env.block.time.seconds())?,
+                    shares: validators
+                        .into_iter()
+                        .map(|val| (val, Decimal::from_ratio(1u128, count)))
+                        .collect_vec(),
+                },
+                // the validators replace the whitelist when tuning, which is then used uniformly
+                false,
+            ))
+        },
+        DelegationStrategy::Performance => {
+            let scores = state
+                .validators
+                .load(storage)?
+                .into_iter()
+                .map(|validator| {
+                    let score = state
+                        .validator_performance
+                        .may_load(storage, &validator)?
+                        .unwrap_or_default()
+                        .score();
+                    Ok((validator, score))
+                })
+                .collect::<StdResult<Vec<(String, Decimal)>>>()?;
+            let total_score = scores
+                .iter()
+                .try_fold(Decimal::zero(), |acc, (_, score)| acc.checked_add(*score))?;
+            if total_score.is_zero() {
+                return Err(StdError::generic_err("no validator has a performance score yet"));
+            }
+
+            Ok((
+                WantedDelegationsShare {
+                    tune_time: env.block.time.seconds(),
+                    tune_period: get_period(env.block.time.seconds())?,
+                    shares: scores
+                        .into_iter()
+                        .map(|(val, score)| {
+                            (val, Decimal::from_ratio(score.numerator(), total_score.numerator()))
+                        })
+                        .collect_vec(),
+                },
+                // store it for get_utoken_per_validator
+                true,
+            ))
+        },
         // DelegationStrategy::Gauges {
         //     amp_gauges,
         //     emp_gauges,
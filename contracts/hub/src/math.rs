@@ -2,17 +2,19 @@ use std::{
     cmp,
     cmp::Ordering,
     collections::{HashMap, HashSet},
+    convert::TryFrom,
 };
 
-use cosmwasm_std::{Addr, QuerierWrapper, StdResult, Storage, Uint128};
+use cosmwasm_std::{Addr, Decimal, QuerierWrapper, StdError, StdResult, Storage, Uint128};
 
 use eris::{
-    hub::{Batch, WantedDelegationsShare},
+    hub::{ApyResponse, Batch, ReconcileCursor, WantedDelegationsShare},
     DecimalCheckedOps,
 };
 
 use crate::{
-    helpers::query_all_delegations,
+    constants::SECONDS_PER_YEAR,
+    helpers::{get_ramp_up_weights, query_all_delegations},
     state::State,
     types::{Delegation, Redelegation, Undelegation},
 };
@@ -24,6 +26,17 @@ type UtokenPerValidator =
 // Minting/burning logics
 //--------------------------------------------------------------------------------------------------
 
+/// Amount of utoken backing the Stake token: the delegations and the amount undelegated from
+/// removed validators, without the reinvested rewards that are not released yet
+pub(crate) fn compute_utoken_bonded(
+    current_delegations: &[Delegation],
+    utoken_undelegated: Uint128,
+    utoken_unvested: Uint128,
+) -> u128 {
+    (current_delegations.iter().map(|d| d.amount).sum::<u128>() + utoken_undelegated.u128())
+        .saturating_sub(utoken_unvested.u128())
+}
+
 /// Compute the amount of Stake token to mint for a specific Token stake amount. If current total
 /// staked amount is zero, we use 1 ustake = 1 utoken; otherwise, we calculate base on the current
 /// utoken per ustake ratio.
@@ -31,8 +44,11 @@ pub(crate) fn compute_mint_amount(
     ustake_supply: Uint128,
     utoken_to_bond: Uint128,
     current_delegations: &[Delegation],
+    utoken_undelegated: Uint128,
+    utoken_unvested: Uint128,
 ) -> Uint128 {
-    let utoken_bonded: u128 = current_delegations.iter().map(|d| d.amount).sum();
+    let utoken_bonded =
+        compute_utoken_bonded(current_delegations, utoken_undelegated, utoken_unvested);
     if utoken_bonded == 0 {
         utoken_to_bond
     } else {
@@ -48,8 +64,11 @@ pub(crate) fn compute_unbond_amount(
     ustake_supply: Uint128,
     ustake_to_burn: Uint128,
     current_delegations: &[Delegation],
+    utoken_undelegated: Uint128,
+    utoken_unvested: Uint128,
 ) -> Uint128 {
-    let utoken_bonded: u128 = current_delegations.iter().map(|d| d.amount).sum();
+    let utoken_bonded =
+        compute_utoken_bonded(current_delegations, utoken_undelegated, utoken_unvested);
     Uint128::new(utoken_bonded).multiply_ratio(ustake_to_burn, ustake_supply)
 }
 
@@ -200,11 +219,15 @@ fn get_utoken_for_validator(
 /// this sentence makes sense)
 ///
 /// This algorithm does not guarantee the minimal number of moves, but is the best I can some up with...
+///
+/// Validators that deviate from their target by at most `tolerance` (relative to the target) are
+/// left alone, so that the drift caused by bonding and unbonding doesn't keep moving Token around.
 pub(crate) fn compute_redelegations_for_rebalancing(
     state: &State,
     storage: &dyn Storage,
     current_delegations: &[Delegation],
     validators: Vec<String>,
+    tolerance: Decimal,
 ) -> StdResult<Vec<Redelegation>> {
     let utoken_staked: u128 = current_delegations.iter().map(|d| d.amount).sum();
 
@@ -221,6 +244,11 @@ pub(crate) fn compute_redelegations_for_rebalancing(
         let utoken_for_validator =
             get_utoken_for_validator(&utoken_per_validator, d, &mut add, &mut remove);
 
+        let utoken_band = (Uint128::new(utoken_for_validator) * tolerance).u128();
+        if d.amount.abs_diff(utoken_for_validator) <= utoken_band {
+            continue;
+        }
+
         match d.amount.cmp(&utoken_for_validator) {
             Ordering::Greater => {
                 src_delegations
@@ -311,11 +339,20 @@ pub(crate) fn get_utoken_per_validator(
             None
         };
 
-    let utoken_per_validator = utoken_per_validator.unwrap_or_else(|| {
-        let validator_count = validators.len() as u128;
-        let utoken_per_validator = utoken_staked / validator_count;
-        validators.iter().map(|d| (d.clone(), Uint128::new(utoken_per_validator))).collect()
-    });
+    let utoken_per_validator = match utoken_per_validator {
+        Some(utoken_per_validator) => utoken_per_validator,
+        None => {
+            // uniform, except for validators that are still ramping up
+            let weights = get_ramp_up_weights(state, storage, validators)?;
+            let total_weight: u128 = weights.iter().map(|(_, weight)| weight).sum();
+            weights
+                .into_iter()
+                .map(|(validator, weight)| {
+                    (validator, utoken_staked_uint.multiply_ratio(weight, total_weight))
+                })
+                .collect()
+        },
+    };
     let total: u128 = utoken_per_validator.iter().map(|a| a.1.u128()).sum();
     let add = if total < utoken_staked {
         Some(utoken_staked - total)
@@ -335,76 +372,148 @@ pub(crate) fn get_utoken_per_validator(
 //--------------------------------------------------------------------------------------------------
 
 /// If the received utoken amount after the unbonding period is less than expected, e.g. due to rounding
-/// error or the validator(s) being slashed, then deduct the difference in amount evenly from each
-/// unreconciled batch.
+/// error or the validator(s) being slashed, then deduct the difference from the batches of the
+/// round pro rata to their expected amounts.
+///
+/// The deduction of a batch is the difference of the shortfall share of the expected amounts
+/// reconciled so far, before and after the batch. The rounding doesn't depend on how the round is
+/// chunked and the deductions add up to exactly the shortfall.
 ///
 /// The idea of "reconciling" is based on Stader's implementation:
 /// https://github.com/stader-labs/stader-liquid-token/blob/v0.2.1/contracts/staking/src/contract.rs#L968-L1048
-pub(crate) fn reconcile_batches(batches: &mut [Batch], utoken_to_deduct: Uint128) {
-    let batch_count = batches.len() as u128;
-    let utoken_per_batch = utoken_to_deduct.u128() / batch_count;
-    let remainder = utoken_to_deduct.u128() % batch_count;
-    let mut underflows: HashMap<usize, Uint128> = HashMap::default();
-
-    for (i, batch) in batches.iter_mut().enumerate() {
-        let remainder_for_batch: u128 = u128::from((i + 1) as u128 <= remainder);
-        let utoken_for_batch = utoken_per_batch + remainder_for_batch;
-        let utoken_for_batch = Uint128::new(utoken_for_batch);
-
-        // check for underflow
-        if batch.utoken_unclaimed < utoken_for_batch && batch_count > 1 {
-            underflows.insert(i, utoken_for_batch - batch.utoken_unclaimed);
-        }
+pub(crate) fn reconcile_batch(
+    cursor: &mut ReconcileCursor,
+    batch: &mut Batch,
+) -> StdResult<Uint128> {
+    let utoken_reconciled = cursor.utoken_reconciled.checked_add(batch.utoken_unclaimed)?;
+    let utoken_to_deduct = cursor
+        .utoken_shortfall
+        .multiply_ratio(utoken_reconciled, cursor.utoken_matured)
+        .checked_sub(
+            cursor.utoken_shortfall.multiply_ratio(cursor.utoken_reconciled, cursor.utoken_matured),
+        )?;
+    cursor.utoken_reconciled = utoken_reconciled;
+
+    batch.utoken_unclaimed = batch.utoken_unclaimed.checked_sub(utoken_to_deduct)?;
+    batch.reconciled = true;
+    batch.exchange_rate_reconciled = batch.exchange_rate();
+
+    Ok(utoken_to_deduct)
+}
 
-        batch.utoken_unclaimed = batch.utoken_unclaimed.saturating_sub(utoken_for_batch);
-        batch.reconciled = true;
-    }
+//--------------------------------------------------------------------------------------------------
+// Exchange rate logics
+//--------------------------------------------------------------------------------------------------
 
-    if !underflows.is_empty() {
-        let batch_count: u128 = batch_count - (underflows.len() as u128);
-        let to_deduct: Uint128 = underflows.iter().map(|v| v.1).sum();
-        let utoken_per_batch = to_deduct.u128() / batch_count;
-        let remainder = to_deduct.u128() % batch_count;
-        let mut remaining_underflow = Uint128::zero();
-        // distribute the underflows uniformly accross non-underflowing batches
-        for (i, batch) in batches.iter_mut().enumerate() {
-            if !batch.utoken_unclaimed.is_zero() {
-                let remainder_for_batch: u128 = u128::from((i + 1) as u128 <= remainder);
-                let utoken_for_batch = utoken_per_batch + remainder_for_batch;
-                let utoken_for_batch = Uint128::new(utoken_for_batch);
-
-                if batch.utoken_unclaimed < utoken_for_batch && batch_count > 1 {
-                    remaining_underflow += utoken_for_batch - batch.utoken_unclaimed;
-                }
-
-                batch.utoken_unclaimed = batch.utoken_unclaimed.saturating_sub(utoken_for_batch);
-            }
+/// Computes the time-weighted average of the exchange rate observations (oldest first) over the
+/// window ending at `now`. Each observation is valid until the next one, the latest until `now`.
+/// If the window reaches before the oldest observation, it is cut at the oldest observation.
+///
+/// Returns the average, the seconds covered and the number of observations used.
+pub(crate) fn compute_exchange_rate_twap(
+    observations: &[(u64, Decimal)],
+    now: u64,
+    window_seconds: u64,
+) -> StdResult<(Decimal, u64, u32)> {
+    let window_start = now.saturating_sub(window_seconds);
+
+    let mut weighted_sum = Decimal::zero();
+    let mut covered: u64 = 0;
+    let mut count: u32 = 0;
+
+    for (i, (time, rate)) in observations.iter().enumerate() {
+        let valid_until = observations.get(i + 1).map(|(next, _)| *next).unwrap_or(now).min(now);
+        let valid_from = (*time).max(window_start);
+
+        if valid_until <= valid_from {
+            continue;
         }
 
-        if !remaining_underflow.is_zero() {
-            // the remaining underflow will be applied by oldest batch first.
-            for (_, batch) in batches.iter_mut().enumerate() {
-                if !batch.utoken_unclaimed.is_zero() && !remaining_underflow.is_zero() {
-                    if batch.utoken_unclaimed >= remaining_underflow {
-                        batch.utoken_unclaimed -= remaining_underflow;
-                        remaining_underflow = Uint128::zero()
-                    } else {
-                        remaining_underflow -= batch.utoken_unclaimed;
-                        batch.utoken_unclaimed = Uint128::zero();
-                    }
-                }
-            }
+        let duration = valid_until - valid_from;
+        weighted_sum =
+            weighted_sum.checked_add(rate.checked_mul(Decimal::from_ratio(duration, 1u64))?)?;
+        covered += duration;
+        count += 1;
+    }
 
-            if !remaining_underflow.is_zero() {
-                // no way to reconcile right now, need to top up some funds.
-            }
-        }
+    if covered == 0 {
+        // observations only at the current time (or none at all)
+        return match observations.last() {
+            Some((_, rate)) => Ok((*rate, 0, 1)),
+            None => Err(StdError::generic_err("No exchange rate observations")),
+        };
     }
+
+    let twap = weighted_sum
+        .checked_div(Decimal::from_ratio(covered, 1u64))
+        .map_err(|e| StdError::generic_err(e.to_string()))?;
+
+    Ok((twap, covered, count))
+}
+
+/// Computes the APY from the exchange rate observations (oldest first) within the window ending at
+/// `now`. Each observation after the first one is a harvest. The trailing APY compounds the growth
+/// over the window at the average harvest interval, the projected APY compounds the growth of the
+/// last harvest at its own interval. Both are zero without at least two observations.
+pub(crate) fn compute_apy(
+    observations: &[(u64, Decimal)],
+    now: u64,
+    window_seconds: u64,
+) -> StdResult<ApyResponse> {
+    let window_start = now.saturating_sub(window_seconds);
+    let observations = observations
+        .iter()
+        .filter(|(time, _)| *time >= window_start && *time <= now)
+        .collect::<Vec<_>>();
+
+    let (first, prev, last) = match observations.as_slice() {
+        [first, .., prev, last] => (first, prev, last),
+        [first, last] => (first, first, last),
+        _ => {
+            return Ok(ApyResponse {
+                trailing_apy: Decimal::zero(),
+                projected_apy: Decimal::zero(),
+                harvests: 0,
+                compounding_interval: 0,
+                window_seconds: 0,
+            })
+        },
+    };
+
+    let harvests = (observations.len() - 1) as u32;
+    let covered = last.0 - first.0;
+
+    Ok(ApyResponse {
+        trailing_apy: compute_compounded_apy(first.1, last.1, covered, harvests)?,
+        projected_apy: compute_compounded_apy(prev.1, last.1, last.0 - prev.0, 1)?,
+        harvests,
+        compounding_interval: covered / harvests as u64,
+        window_seconds: covered,
+    })
 }
 
-/// If all funds are available we still need to mark batches as reconciled
-pub(crate) fn mark_reconciled_batches(batches: &mut [Batch]) {
-    for (_, batch) in batches.iter_mut().enumerate() {
-        batch.reconciled = true;
+/// Annualizes the growth from `from` to `to` over `duration` seconds, compounded `periods` times
+/// within the duration: `(1 + apr / n) ^ n - 1` with `n` compounding periods per year.
+/// A shrinking exchange rate results in a zero APY.
+fn compute_compounded_apy(
+    from: Decimal,
+    to: Decimal,
+    duration: u64,
+    periods: u32,
+) -> StdResult<Decimal> {
+    if to <= from || duration == 0 || from.is_zero() {
+        return Ok(Decimal::zero());
     }
+
+    let growth = (to - from).checked_div(from).map_err(|e| StdError::generic_err(e.to_string()))?;
+    let apr = growth.checked_mul(Decimal::from_ratio(SECONDS_PER_YEAR, duration))?;
+
+    let periods_per_year =
+        u32::try_from(SECONDS_PER_YEAR * periods as u64 / duration).unwrap_or(u32::MAX).max(1);
+    let rate_per_period = apr
+        .checked_div(Decimal::from_ratio(periods_per_year, 1u32))
+        .map_err(|e| StdError::generic_err(e.to_string()))?;
+
+    let compounded = (Decimal::one() + rate_per_period).checked_pow(periods_per_year)?;
+    Ok(compounded - Decimal::one())
 }
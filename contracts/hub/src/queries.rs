@@ -1,36 +1,53 @@
-use cosmwasm_std::{Addr, Decimal, Deps, Env, Order, StdResult, Uint128};
-use cw_storage_plus::Bound;
+use std::collections::HashMap;
+
+use cosmwasm_std::{Coin, Decimal, Deps, Env, Order, StdError, StdResult, Uint128};
 
 // use eris::governance_helper::get_period;
+use eris::helpers::pagination::{
+    get_bounds, start_after_bound, validate_start_after_addr, PaginatedResponse,
+};
 use eris::hub::{
-    Batch, ConfigResponse, PendingBatch, StateResponse, UnbondRequestsByBatchResponseItem,
-    UnbondRequestsByUserResponseItem, UnbondRequestsByUserResponseItemDetails,
-    WantedDelegationsResponse,
+    ApyResponse, AuditEntry, Batch, ConfigResponse, ExchangeRateTwapResponse, ExecuteMsg,
+    ExportSection, HealthResponse, HubConfig, LastHarvest, MigrationMode, OrderBy, PendingBatch,
+    PreparedMsg, SimulateHarvestResponse, SimulateRebalanceResponse, SimulatedRedelegation,
+    StakeTokenResponse, StateExport, StateResponse, UnbondRequestsByBatchResponseItem,
+    UnbondRequestsByBatchTotalsResponse, UnbondRequestsByUserResponseItem,
+    UnbondRequestsByUserResponseItemDetails, ValidatorPayout, VoteRecord,
+    WantedDelegationsResponse, WantedDelegationsShare, WithdrawTemplate,
 };
 use itertools::Itertools;
 
-use crate::constants::CONTRACT_DENOM;
-use crate::helpers::{get_wanted_delegations, query_delegations};
-use crate::math::get_utoken_per_validator_prepared;
+use crate::constants::{get_default_swap_max_spread, CONTRACT_DENOM, MAX_BATCHES_SKIPPED};
+use crate::helpers::{
+    get_wanted_delegations, query_all_delegations, query_delegations, simulate_stages,
+};
+use crate::math::{
+    compute_apy, compute_exchange_rate_twap, compute_redelegations_for_rebalancing,
+    compute_utoken_bonded, get_utoken_per_validator_prepared,
+};
 use crate::state::State;
 // use crate::types::gauges::PeriodGaugeLoader;
 
-const MAX_LIMIT: u32 = 30;
-const DEFAULT_LIMIT: u32 = 10;
-
 pub fn config(deps: Deps) -> StdResult<ConfigResponse> {
     let state = State::default();
+    let ownership = state.ownership.query(deps.storage)?;
 
     Ok(ConfigResponse {
-        owner: state.owner.load(deps.storage)?.into(),
+        owner: ownership.owner,
         operator: state.operator.load(deps.storage)?.into(),
-        new_owner: state.new_owner.may_load(deps.storage)?.map(|addr| addr.into()),
+        new_operator: state.new_operator.may_load(deps.storage)?.map(|addr| addr.into()),
+        new_owner: ownership.new_owner,
         stake_token: state.stake_token.load(deps.storage)?.denom,
         epoch_period: state.epoch_period.load(deps.storage)?,
         unbond_period: state.unbond_period.load(deps.storage)?,
         validators: state.validators.load(deps.storage)?,
         fee_config: state.fee_config.load(deps.storage)?,
         stages_preset: state.stages_preset.load(deps.storage)?,
+        swap_allowlist: state.swap_allowlist.may_load(deps.storage)?.unwrap_or_default(),
+        swap_max_spread: state
+            .swap_max_spread
+            .may_load(deps.storage)?
+            .unwrap_or_else(get_default_swap_max_spread),
         allow_donations: state.allow_donations.may_load(deps.storage)?.unwrap_or(false),
         delegation_strategy: match state
             .delegation_strategy
@@ -38,11 +55,21 @@ pub fn config(deps: Deps) -> StdResult<ConfigResponse> {
             .unwrap_or(eris::hub::DelegationStrategy::Uniform)
         {
             eris::hub::DelegationStrategy::Uniform => eris::hub::DelegationStrategy::Uniform,
+            eris::hub::DelegationStrategy::Performance => {
+                eris::hub::DelegationStrategy::Performance
+            },
             eris::hub::DelegationStrategy::Defined {
                 shares_bps,
             } => eris::hub::DelegationStrategy::Defined {
                 shares_bps,
             },
+            eris::hub::DelegationStrategy::Registry {
+                registry,
+                fallback,
+            } => eris::hub::DelegationStrategy::Registry {
+                registry,
+                fallback,
+            },
             // eris::hub::DelegationStrategy::Gauges {
             //     amp_gauges,
             //     emp_gauges,
@@ -60,9 +87,199 @@ pub fn config(deps: Deps) -> StdResult<ConfigResponse> {
             // },
         },
         vote_operator: state.vote_operator.may_load(deps.storage)?.map(|addr| addr.into()),
+        new_vote_operator: state.new_vote_operator.may_load(deps.storage)?.map(|addr| addr.into()),
+        guardian: state.guardian.may_load(deps.storage)?.map(|addr| addr.into()),
+        exchange_rate_tolerance: state.exchange_rate_tolerance.may_load(deps.storage)?,
+        circuit_breaker_tripped: state
+            .circuit_breaker_tripped
+            .may_load(deps.storage)?
+            .unwrap_or(false),
+        max_reinvest_per_tx: state.max_reinvest_per_tx.may_load(deps.storage)?,
+        unbond_allowlist: state
+            .unbond_allowlist
+            .may_load(deps.storage)?
+            .unwrap_or_default()
+            .into_iter()
+            .map(String::from)
+            .collect(),
+        min_hold_seconds: state.min_hold_seconds.may_load(deps.storage)?.unwrap_or_default(),
+        reward_smoothing_period: state
+            .reward_smoothing_period
+            .may_load(deps.storage)?
+            .unwrap_or_default(),
+        max_harvest_staleness: state
+            .max_harvest_staleness
+            .may_load(deps.storage)?
+            .unwrap_or_default(),
+        protocol_fee_schedule: state
+            .protocol_fee_schedule
+            .may_load(deps.storage)?
+            .unwrap_or_default(),
+        action_cooldowns: state.action_cooldowns.may_load(deps.storage)?.unwrap_or_default(),
+        express_epoch: state.express_epoch.may_load(deps.storage)?,
+        rebalance_tolerance: state.rebalance_tolerance.may_load(deps.storage)?.unwrap_or_default(),
+        max_query_limit: state.max_query_limit(deps.storage)?,
     })
 }
 
+pub fn build_bond_msg(
+    deps: Deps,
+    env: Env,
+    amount: Uint128,
+    receiver: Option<String>,
+    min_ustake_received: Option<Uint128>,
+) -> StdResult<PreparedMsg> {
+    if amount.is_zero() {
+        return Err(StdError::generic_err("amount can't be zero"));
+    }
+    let receiver = receiver.map(|addr| deps.api.addr_validate(&addr)).transpose()?;
+
+    Ok(PreparedMsg {
+        contract_addr: env.contract.address.into(),
+        msg: ExecuteMsg::Bond {
+            receiver: receiver.map(String::from),
+            min_ustake_received,
+        },
+        funds: vec![Coin::new(amount.u128(), CONTRACT_DENOM)],
+    })
+}
+
+pub fn build_queue_unbond_msg(
+    deps: Deps,
+    env: Env,
+    amount: Uint128,
+    receiver: Option<String>,
+) -> StdResult<PreparedMsg> {
+    let state = State::default();
+
+    if amount.is_zero() {
+        return Err(StdError::generic_err("amount can't be zero"));
+    }
+    let receiver = receiver.map(|addr| deps.api.addr_validate(&addr)).transpose()?;
+
+    Ok(PreparedMsg {
+        contract_addr: env.contract.address.into(),
+        msg: ExecuteMsg::QueueUnbond {
+            receiver: receiver.map(String::from),
+        },
+        funds: vec![Coin::new(amount.u128(), state.stake_token.load(deps.storage)?.denom)],
+    })
+}
+
+pub fn export_config(deps: Deps) -> StdResult<HubConfig> {
+    let state = State::default();
+    let fee_config = state.fee_config.load(deps.storage)?;
+
+    Ok(HubConfig {
+        fin_multi_contract: state.fin_multi.load(deps.storage)?.0.into(),
+        operator: state.operator.load(deps.storage)?.into(),
+        vote_operator: state.vote_operator.may_load(deps.storage)?.map(|addr| addr.into()),
+        guardian: state.guardian.may_load(deps.storage)?.map(|addr| addr.into()),
+        epoch_period: state.epoch_period.load(deps.storage)?,
+        unbond_period: state.unbond_period.load(deps.storage)?,
+        validators: state.validators.load(deps.storage)?,
+        protocol_fee_contract: fee_config.protocol_fee_contract.into(),
+        protocol_reward_fee: fee_config.protocol_reward_fee,
+        stages_preset: state.stages_preset.load(deps.storage)?,
+        swap_allowlist: state.swap_allowlist.may_load(deps.storage)?.unwrap_or_default(),
+        swap_max_spread: state
+            .swap_max_spread
+            .may_load(deps.storage)?
+            .unwrap_or_else(get_default_swap_max_spread),
+        allow_donations: state.allow_donations.may_load(deps.storage)?.unwrap_or(false),
+        delegation_strategy: state
+            .delegation_strategy
+            .may_load(deps.storage)?
+            .unwrap_or(eris::hub::DelegationStrategy::Uniform),
+        exchange_rate_tolerance: state.exchange_rate_tolerance.may_load(deps.storage)?,
+        max_reinvest_per_tx: state.max_reinvest_per_tx.may_load(deps.storage)?,
+        unbond_allowlist: state
+            .unbond_allowlist
+            .may_load(deps.storage)?
+            .unwrap_or_default()
+            .into_iter()
+            .map(String::from)
+            .collect(),
+        unbond_fee: fee_config.unbond_fee,
+        validator_fee_share: fee_config.validator_fee_share,
+        min_hold_seconds: state.min_hold_seconds.may_load(deps.storage)?.unwrap_or_default(),
+        reward_smoothing_period: state
+            .reward_smoothing_period
+            .may_load(deps.storage)?
+            .unwrap_or_default(),
+        max_harvest_staleness: state
+            .max_harvest_staleness
+            .may_load(deps.storage)?
+            .unwrap_or_default(),
+        protocol_fee_schedule: state
+            .protocol_fee_schedule
+            .may_load(deps.storage)?
+            .unwrap_or_default(),
+        action_cooldowns: state.action_cooldowns.may_load(deps.storage)?.unwrap_or_default(),
+        express_epoch: state.express_epoch.may_load(deps.storage)?,
+        rebalance_tolerance: state.rebalance_tolerance.may_load(deps.storage)?.unwrap_or_default(),
+    })
+}
+
+pub fn export_state(deps: Deps, section: ExportSection) -> StdResult<StateExport> {
+    let state = State::default();
+
+    if state.migration_mode.may_load(deps.storage)? != Some(MigrationMode::Export) {
+        return Err(StdError::generic_err("state export is not enabled by the owner"));
+    }
+
+    match section {
+        ExportSection::PendingBatch {} => {
+            let pending_batch = state.pending_batch.load(deps.storage)?;
+            if !pending_batch.ustake_to_burn.is_zero() {
+                return Err(StdError::generic_err(
+                    "the pending batch must be submitted before it is exported",
+                ));
+            }
+            Ok(StateExport::PendingBatch(pending_batch))
+        },
+        ExportSection::PreviousBatches {
+            start_after,
+            limit,
+        } => {
+            let limit = state.query_limit(deps.storage, limit)?;
+            let start = start_after_bound(start_after);
+
+            state
+                .previous_batches
+                .range(deps.storage, start, None, Order::Ascending)
+                .take(limit)
+                .map(|item| {
+                    let (_, v) = item?;
+                    Ok(v)
+                })
+                .collect::<StdResult<Vec<_>>>()
+                .map(StateExport::PreviousBatches)
+        },
+        ExportSection::UnbondRequests {
+            start_after,
+            limit,
+        } => {
+            let limit = state.query_limit(deps.storage, limit)?;
+            let start_after = start_after
+                .map(|(id, user)| -> StdResult<_> { Ok((id, deps.api.addr_validate(&user)?)) })
+                .transpose()?;
+            let start = start_after_bound(start_after.as_ref().map(|(id, user)| (*id, user)));
+
+            state
+                .unbond_requests
+                .range(deps.storage, start, None, Order::Ascending)
+                .take(limit)
+                .map(|item| {
+                    let (_, v) = item?;
+                    Ok(v)
+                })
+                .collect::<StdResult<Vec<_>>>()
+                .map(StateExport::UnbondRequests)
+        },
+    }
+}
+
 pub fn state(deps: Deps, env: Env) -> StdResult<StateResponse> {
     let state = State::default();
 
@@ -71,7 +288,14 @@ pub fn state(deps: Deps, env: Env) -> StdResult<StateResponse> {
 
     let validators = state.validators.load(deps.storage)?;
     let delegations = query_delegations(&deps.querier, &validators, &env.contract.address)?;
-    let total_utoken: u128 = delegations.iter().map(|d| d.amount).sum();
+    // amounts undelegated from removed validators count as bonded until used by a batch, rewards
+    // only once they are released
+    let utoken_unvested = state.utoken_unvested(deps.storage, env.block.time.seconds())?;
+    let total_utoken = compute_utoken_bonded(
+        &delegations,
+        state.utoken_undelegated.may_load(deps.storage)?.unwrap_or_default(),
+        utoken_unvested,
+    );
 
     // only not reconciled batches are relevant as they are still unbonding and estimated unbond time in the future.
     let unbonding: u128 = state
@@ -104,11 +328,37 @@ pub fn state(deps: Deps, env: Env) -> StdResult<StateResponse> {
         unbonding: Uint128::from(unbonding),
         available,
         tvl_utoken: Uint128::from(total_utoken)
+            .checked_add(utoken_unvested)?
             .checked_add(Uint128::from(unbonding))?
             .checked_add(available)?,
+        ustake_escrowed: state.ustake_escrowed.may_load(deps.storage)?.unwrap_or_default(),
+        utoken_unvested,
+        utoken_dust_recycled: state
+            .utoken_dust_recycled
+            .may_load(deps.storage)?
+            .unwrap_or_default(),
     })
 }
 
+pub fn stake_token(deps: Deps) -> StdResult<StakeTokenResponse> {
+    let state = State::default();
+    let stake_token = state.stake_token.load(deps.storage)?;
+
+    let bank_supply = deps.querier.query_supply(&stake_token.denom)?.amount;
+
+    Ok(StakeTokenResponse {
+        in_sync: stake_token.total_supply == bank_supply,
+        denom: stake_token.denom,
+        total_supply: stake_token.total_supply,
+        bank_supply,
+    })
+}
+
+pub fn delegation_goal(deps: Deps) -> StdResult<Option<WantedDelegationsShare>> {
+    let state = State::default();
+    state.delegation_goal.may_load(deps.storage)
+}
+
 pub fn wanted_delegations(deps: Deps, env: Env) -> StdResult<WantedDelegationsResponse> {
     let state = State::default();
 
@@ -174,35 +424,297 @@ fn sort_delegations(
         .collect()
 }
 
-pub fn pending_batch(deps: Deps) -> StdResult<PendingBatch> {
+pub fn exchange_rate_twap(
+    deps: Deps,
+    env: Env,
+    window_seconds: u64,
+) -> StdResult<ExchangeRateTwapResponse> {
     let state = State::default();
-    state.pending_batch.load(deps.storage)
+    let history = state.exchange_rate_history.may_load(deps.storage)?.unwrap_or_default();
+
+    let (twap, window_seconds, observations) =
+        compute_exchange_rate_twap(&history, env.block.time.seconds(), window_seconds)?;
+
+    Ok(ExchangeRateTwapResponse {
+        twap,
+        window_seconds,
+        observations,
+    })
 }
 
-pub fn previous_batch(deps: Deps, id: u64) -> StdResult<Batch> {
+pub fn apy(deps: Deps, env: Env, window: u64) -> StdResult<ApyResponse> {
     let state = State::default();
-    state.previous_batches.load(deps.storage, id)
+    let history = state.exchange_rate_history.may_load(deps.storage)?.unwrap_or_default();
+
+    compute_apy(&history, env.block.time.seconds(), window)
 }
 
-pub fn previous_batches(
+/// Same redelegations as `rebalance`. A redelegation is blocked when its source delegation can't be
+/// redelegated yet, because it is the destination of a redelegation that is still maturing.
+pub fn simulate_rebalance(
+    deps: Deps,
+    env: Env,
+    min_redelegation: Option<Uint128>,
+) -> StdResult<SimulateRebalanceResponse> {
+    let state = State::default();
+    let delegations = query_all_delegations(&deps.querier, &env.contract.address)?;
+    let validators = state.validators.load(deps.storage)?;
+    let min_redelegation = min_redelegation.unwrap_or_default();
+    let tolerance = state.rebalance_tolerance.may_load(deps.storage)?.unwrap_or_default();
+
+    let new_redelegations = compute_redelegations_for_rebalancing(
+        &state,
+        deps.storage,
+        &delegations,
+        validators,
+        tolerance,
+    )?
+    .into_iter()
+    .filter(|redelegation| redelegation.amount >= min_redelegation.u128())
+    .collect::<Vec<_>>();
+
+    let mut can_redelegate: HashMap<String, Uint128> = HashMap::new();
+    for rd in &new_redelegations {
+        if !can_redelegate.contains_key(&rd.src) {
+            let amount = deps
+                .querier
+                .query_delegation(&env.contract.address, &rd.src)?
+                .map(|fd| fd.can_redelegate.amount)
+                .unwrap_or_default();
+            can_redelegate.insert(rd.src.clone(), amount);
+        }
+    }
+
+    let mut redelegations = vec![];
+    for rd in new_redelegations {
+        let available = can_redelegate.entry(rd.src.clone()).or_default();
+        let amount = Uint128::new(rd.amount);
+        let blocked = amount > *available;
+        if !blocked {
+            *available -= amount;
+        }
+
+        redelegations.push(SimulatedRedelegation {
+            src: rd.src,
+            dst: rd.dst,
+            amount,
+            blocked,
+        });
+    }
+
+    Ok(SimulateRebalanceResponse {
+        utoken_moved: redelegations.iter().map(|rd| rd.amount).sum(),
+        redelegations,
+    })
+}
+
+pub fn simulate_harvest(deps: Deps, env: Env) -> StdResult<SimulateHarvestResponse> {
+    let state = State::default();
+    let stages = state.stages_preset.load(deps.storage)?;
+    let fee_config = state.fee_config.load(deps.storage)?;
+
+    let balances = deps.querier.query_all_balances(env.contract.address)?;
+    let swaps = simulate_stages(&deps.querier, &stages, balances)?;
+
+    // the contract balance of utoken also contains unbonded funds, only swap results are counted
+    let mut utoken_from_swaps = Uint128::zero();
+    for swap in swaps.iter().filter(|swap| swap.ask.denom == CONTRACT_DENOM) {
+        utoken_from_swaps = utoken_from_swaps.checked_add(swap.ask.amount)?;
+    }
+
+    let utoken_unlocked = state
+        .unlocked_coins
+        .load(deps.storage)?
+        .into_iter()
+        .find(|coin| coin.denom == CONTRACT_DENOM)
+        .map(|coin| coin.amount)
+        .unwrap_or_default();
+
+    let utoken_available = utoken_from_swaps.checked_add(utoken_unlocked)?;
+    let protocol_reward_fee =
+        state.protocol_reward_fee(deps.storage, &fee_config, env.block.time.seconds())?;
+    let utoken_protocol_fee = protocol_reward_fee * utoken_available;
+
+    Ok(SimulateHarvestResponse {
+        swaps,
+        utoken_from_swaps,
+        utoken_unlocked,
+        utoken_protocol_fee,
+        utoken_to_bond: utoken_available.saturating_sub(utoken_protocol_fee),
+    })
+}
+
+pub fn validator_payouts(deps: Deps) -> StdResult<Vec<ValidatorPayout>> {
+    let state = State::default();
+
+    let mut payouts = vec![];
+    for validator in state.validators.load(deps.storage)? {
+        if let Some(payout) = state.validator_payouts.may_load(deps.storage, &validator)? {
+            payouts.push(ValidatorPayout {
+                validator,
+                payout,
+            });
+        }
+    }
+    Ok(payouts)
+}
+
+pub fn withdraw_templates(deps: Deps) -> StdResult<Vec<(String, WithdrawTemplate)>> {
+    State::default().withdraw_templates.range(deps.storage, None, None, Order::Ascending).collect()
+}
+
+pub fn vote_history(
+    deps: Deps,
+    start_after: Option<u64>,
+    limit: Option<u32>,
+    order: Option<OrderBy>,
+) -> StdResult<PaginatedResponse<VoteRecord, u64>> {
+    let state = State::default();
+
+    let limit = state.query_limit(deps.storage, limit)?;
+    let order: Order = order.unwrap_or(OrderBy::Asc).into();
+    let (min, max) = get_bounds(start_after, order);
+
+    let votes = state
+        .vote_history
+        .range(deps.storage, min, max, order)
+        .take(limit + 1)
+        .map(|item| {
+            let (_, v) = item?;
+            Ok(v)
+        })
+        .collect::<StdResult<Vec<_>>>()?;
+
+    Ok(PaginatedResponse::new(votes, limit, |vote| vote.proposal_id))
+}
+
+pub fn audit_log(
     deps: Deps,
     start_after: Option<u64>,
     limit: Option<u32>,
-) -> StdResult<Vec<Batch>> {
+    order: Option<OrderBy>,
+) -> StdResult<PaginatedResponse<AuditEntry, u64>> {
     let state = State::default();
 
-    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
-    let start = start_after.map(Bound::exclusive);
+    let limit = state.query_limit(deps.storage, limit)?;
+    let order: Order = order.unwrap_or(OrderBy::Asc).into();
+    let (min, max) = get_bounds(start_after, order);
 
-    state
+    let entries = state
+        .audit_log
+        .range(deps.storage, min, max, order)
+        .take(limit + 1)
+        .map(|item| {
+            let (_, v) = item?;
+            Ok(v)
+        })
+        .collect::<StdResult<Vec<_>>>()?;
+
+    Ok(PaginatedResponse::new(entries, limit, |entry| entry.id))
+}
+
+pub fn last_harvest(deps: Deps) -> StdResult<Option<LastHarvest>> {
+    let state = State::default();
+    state.last_harvest.may_load(deps.storage)
+}
+
+pub fn health(deps: Deps, env: Env) -> StdResult<HealthResponse> {
+    let state = State::default();
+    let current_time = env.block.time.seconds();
+
+    let circuit_breaker_tripped =
+        state.circuit_breaker_tripped.may_load(deps.storage)?.unwrap_or(false);
+    let harvest_stale = state.harvest_staleness(deps.storage, current_time)?.is_some();
+
+    let unreconciled_batches = state
         .previous_batches
-        .range(deps.storage, start, None, Order::Ascending)
-        .take(limit)
+        .idx
+        .reconciled
+        .prefix(false.into())
+        .range(deps.storage, None, None, Order::Ascending)
         .map(|item| {
             let (_, v) = item?;
             Ok(v)
         })
-        .collect()
+        .collect::<StdResult<Vec<Batch>>>()?
+        .into_iter()
+        .filter(|batch| current_time > batch.est_unbond_end_time)
+        .count();
+
+    let mut jailed_validators = vec![];
+    for validator in state.validators.load(deps.storage)? {
+        if deps.querier.query_validator(&validator)?.is_none() {
+            jailed_validators.push(validator);
+        }
+    }
+
+    Ok(HealthResponse {
+        bonding_paused: circuit_breaker_tripped
+            || harvest_stale
+            || state.migration_mode.may_load(deps.storage)?.is_some(),
+        circuit_breaker_tripped,
+        seconds_since_last_harvest: state
+            .last_harvest
+            .may_load(deps.storage)?
+            .map(|last_harvest| current_time.saturating_sub(last_harvest.timestamp)),
+        harvest_stale,
+        pending_batch_overdue: current_time
+            >= state.pending_batch.load(deps.storage)?.est_unbond_start_time,
+        unreconciled_batches: unreconciled_batches as u32,
+        jailed_validators,
+    })
+}
+
+pub fn pending_batch(deps: Deps) -> StdResult<PendingBatch> {
+    let state = State::default();
+    state.pending_batch.load(deps.storage)
+}
+
+pub fn previous_batch(deps: Deps, id: u64) -> StdResult<Batch> {
+    let state = State::default();
+    state.previous_batches.load(deps.storage, id)
+}
+
+/// Enumerates previous batches by id. `start_after` is the exclusive cursor in the requested order,
+/// `start_time` (inclusive) and `end_time` (exclusive) filter on `est_unbond_end_time`.
+pub fn previous_batches(
+    deps: Deps,
+    start_after: Option<u64>,
+    limit: Option<u32>,
+    order: Option<OrderBy>,
+    start_time: Option<u64>,
+    end_time: Option<u64>,
+) -> StdResult<PaginatedResponse<Batch, u64>> {
+    let state = State::default();
+
+    let limit = state.query_limit(deps.storage, limit)?;
+    let order: Order = order.unwrap_or(OrderBy::Asc).into();
+    let (min, max) = get_bounds(start_after, order);
+
+    let mut batches = vec![];
+    let mut skipped = 0;
+    for item in state.previous_batches.range(deps.storage, min, max, order) {
+        let (id, batch) = item?;
+        if !matches!(start_time, Some(start) if batch.est_unbond_end_time < start)
+            && !matches!(end_time, Some(end) if batch.est_unbond_end_time >= end)
+        {
+            batches.push(batch);
+            if batches.len() > limit {
+                break;
+            }
+        } else if skipped == MAX_BATCHES_SKIPPED {
+            // the page ends early, the next one continues after the last skipped batch
+            return Ok(PaginatedResponse {
+                items: batches,
+                next_start_after: Some(id),
+                has_more: true,
+            });
+        } else {
+            skipped += 1;
+        }
+    }
+
+    Ok(PaginatedResponse::new(batches, limit, |batch| batch.id))
 }
 
 pub fn unbond_requests_by_batch(
@@ -210,30 +722,54 @@ pub fn unbond_requests_by_batch(
     id: u64,
     start_after: Option<String>,
     limit: Option<u32>,
-) -> StdResult<Vec<UnbondRequestsByBatchResponseItem>> {
+) -> StdResult<PaginatedResponse<UnbondRequestsByBatchResponseItem, String>> {
     let state = State::default();
 
-    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+    let limit = state.query_limit(deps.storage, limit)?;
 
-    let mut start: Option<Bound<&Addr>> = None;
-    let addr: Addr;
-    if let Some(start_after) = start_after {
-        if let Ok(start_after_addr) = deps.api.addr_validate(&start_after) {
-            addr = start_after_addr;
-            start = Some(Bound::exclusive(&addr));
-        }
-    }
+    let start_after = validate_start_after_addr(deps.api, start_after);
+    let start = start_after_bound(start_after.as_ref());
 
-    state
+    let requests = state
         .unbond_requests
         .prefix(id)
         .range(deps.storage, start, None, Order::Ascending)
-        .take(limit)
+        .take(limit + 1)
         .map(|item| {
             let (_, v) = item?;
             Ok(v.into())
         })
-        .collect()
+        .collect::<StdResult<Vec<UnbondRequestsByBatchResponseItem>>>()?;
+
+    Ok(PaginatedResponse::new(requests, limit, |request| request.user.clone()))
+}
+
+pub fn unbond_requests_by_batch_totals(
+    deps: Deps,
+    id: u64,
+) -> StdResult<UnbondRequestsByBatchTotalsResponse> {
+    let state = State::default();
+    let totals = state.unbond_requests_totals.may_load(deps.storage, id)?.unwrap_or_default();
+
+    Ok(UnbondRequestsByBatchTotalsResponse {
+        id,
+        count: totals.count,
+        shares: totals.shares,
+    })
+}
+
+pub fn allowlist_unbond_totals(
+    deps: Deps,
+    id: u64,
+) -> StdResult<UnbondRequestsByBatchTotalsResponse> {
+    let state = State::default();
+    let totals = state.allowlist_unbond_totals.may_load(deps.storage, id)?.unwrap_or_default();
+
+    Ok(UnbondRequestsByBatchTotalsResponse {
+        id,
+        count: totals.count,
+        shares: totals.shares,
+    })
 }
 
 pub fn unbond_requests_by_user(
@@ -241,26 +777,28 @@ pub fn unbond_requests_by_user(
     user: String,
     start_after: Option<u64>,
     limit: Option<u32>,
-) -> StdResult<Vec<UnbondRequestsByUserResponseItem>> {
+) -> StdResult<PaginatedResponse<UnbondRequestsByUserResponseItem, u64>> {
     let state = State::default();
 
-    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+    let limit = state.query_limit(deps.storage, limit)?;
     let addr = deps.api.addr_validate(&user)?;
-    let start = start_after.map(|id| Bound::exclusive((id, &addr)));
+    let start = start_after_bound(start_after.map(|id| (id, &addr)));
 
-    state
+    let requests = state
         .unbond_requests
         .idx
         .user
         .prefix(user)
         .range(deps.storage, start, None, Order::Ascending)
-        .take(limit)
+        .take(limit + 1)
         .map(|item| {
             let (_, v) = item?;
 
             Ok(v.into())
         })
-        .collect()
+        .collect::<StdResult<Vec<UnbondRequestsByUserResponseItem>>>()?;
+
+    Ok(PaginatedResponse::new(requests, limit, |request| request.id))
 }
 
 pub fn unbond_requests_by_user_details(
@@ -269,22 +807,22 @@ pub fn unbond_requests_by_user_details(
     start_after: Option<u64>,
     limit: Option<u32>,
     env: Env,
-) -> StdResult<Vec<UnbondRequestsByUserResponseItemDetails>> {
+) -> StdResult<PaginatedResponse<UnbondRequestsByUserResponseItemDetails, u64>> {
     let state = State::default();
 
-    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+    let limit = state.query_limit(deps.storage, limit)?;
     let addr = deps.api.addr_validate(&user)?;
-    let start = start_after.map(|id| Bound::exclusive((id, &addr)));
+    let start = start_after_bound(start_after.map(|id| (id, &addr)));
 
     let pending = state.pending_batch.load(deps.storage)?;
 
-    state
+    let requests = state
         .unbond_requests
         .idx
         .user
         .prefix(user)
         .range(deps.storage, start, None, Order::Ascending)
-        .take(limit)
+        .take(limit + 1)
         .map(|item| {
             let (_, v) = item?;
 
@@ -316,5 +854,7 @@ pub fn unbond_requests_by_user_details(
                 batch: previous,
             })
         })
-        .collect()
+        .collect::<StdResult<Vec<_>>>()?;
+
+    Ok(PaginatedResponse::new(requests, limit, |request| request.id))
 }
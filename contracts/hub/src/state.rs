@@ -1,28 +1,48 @@
-use cosmwasm_std::{Addr, Coin, Storage};
-use cw_storage_plus::{Index, IndexList, IndexedMap, Item, MultiIndex};
+use std::convert::TryFrom;
+
+use cosmwasm_std::{Addr, Binary, Coin, Decimal, Fraction, StdResult, Storage, Uint128};
+use cw_storage_plus::{Index, IndexList, IndexedMap, Item, Map, MultiIndex};
 
 use eris::{
     adapters::fin_multi::FinMulti,
+    helpers::{
+        bps::BasicPoints,
+        pagination::{get_limit_with_max, MAX_LIMIT},
+    },
     hub::{
-        Batch, DelegationStrategy, FeeConfig, PendingBatch, StakeToken, UnbondRequest,
-        WantedDelegationsShare,
+        ActionCooldown, AuditEntry, Batch, DelegationStrategy, EpochCounters, ExpressEpoch,
+        FeeConfig, LastHarvest, MigrationMode, PendingBatch, ProtocolFeePeriod, RateLimitedAction,
+        ReconcileCursor, RewardsSchedule, StakeToken, UnbondRequest, UnbondRequestsTotals,
+        ValidatorPerformance, VoteRecord, WantedDelegationsShare, WithdrawTemplate,
     },
+    ownership::Ownership,
 };
 use kujira::denom::Denom;
+use sha2::{Digest, Sha256};
 
-use crate::{error::ContractError, types::BooleanKey};
+use crate::{
+    constants::AUDIT_LOG_SIZE,
+    error::{ContractError, Role},
+    types::BooleanKey,
+};
 
 pub(crate) struct State<'a> {
     /// Fin Multi Contract
     pub fin_multi: Item<'a, FinMulti>,
-    /// Account who can call certain privileged functions
-    pub owner: Item<'a, Addr>,
+    /// Owner with its pending ownership transfer
+    pub ownership: Ownership<'a>,
     /// Account who can call harvest
     pub operator: Item<'a, Addr>,
+    /// Pending operator transfer, awaiting acceptance by the new operator
+    pub new_operator: Item<'a, Addr>,
     /// Stages that must be used by permissionless users
     pub stages_preset: Item<'a, Vec<Vec<(Addr, Denom)>>>,
-    /// Pending ownership transfer, awaiting acceptance by the new owner
-    pub new_owner: Item<'a, Addr>,
+    /// (pair, denom) swaps that permissionless users may use in custom stages
+    pub swap_allowlist: Item<'a, Vec<(Addr, Denom)>>,
+    /// Maximum spread of a simulated swap in permissionless custom stages
+    pub swap_max_spread: Item<'a, Decimal>,
+    /// Id of the pending batch when a validator was added, while it is ramping up
+    pub ramping_validators: Map<'a, &'a str, u64>,
     /// Denom and supply of the Liquid Staking token
     pub stake_token: Item<'a, StakeToken>,
     /// How often the unbonding queue is to be executed
@@ -37,8 +57,12 @@ pub(crate) struct State<'a> {
     pub pending_batch: Item<'a, PendingBatch>,
     /// Previous batches that have started unbonding but not yet finished
     pub previous_batches: IndexedMap<'a, u64, Batch, PreviousBatchesIndexes<'a>>,
+    /// Progress of a reconcile round that is executed in chunks
+    pub reconcile_cursor: Item<'a, ReconcileCursor>,
     /// Users' shares in unbonding batches
     pub unbond_requests: IndexedMap<'a, (u64, &'a Addr), UnbondRequest, UnbondRequestsIndexes<'a>>,
+    /// Number of outstanding requests and remaining shares per batch
+    pub unbond_requests_totals: Map<'a, u64, UnbondRequestsTotals>,
     /// Fee Config
     pub fee_config: Item<'a, FeeConfig>,
     /// Delegation Strategy
@@ -50,8 +74,84 @@ pub(crate) struct State<'a> {
     pub delegation_goal: Item<'a, WantedDelegationsShare>,
     /// Operator who is allowed to vote on props
     pub vote_operator: Item<'a, Addr>,
+    /// Pending vote operator transfer, awaiting acceptance by the new vote operator
+    pub new_vote_operator: Item<'a, Addr>,
     /// Specifies wether the contract allows donations
     pub allow_donations: Item<'a, bool>,
+    /// Metadata of the most recent harvest
+    pub last_harvest: Item<'a, LastHarvest>,
+    /// Account who can clear the circuit breaker, falls back to the owner
+    pub guardian: Item<'a, Addr>,
+    /// Maximum relative drop of the exchange rate before the circuit breaker trips
+    pub exchange_rate_tolerance: Item<'a, Decimal>,
+    /// Set when the exchange rate dropped beyond the tolerance, pauses bonding and unbonding
+    pub circuit_breaker_tripped: Item<'a, bool>,
+    /// utoken undelegated from removed validators, counted as bonded until used by unbonding batches
+    pub utoken_undelegated: Item<'a, Uint128>,
+    /// Part of the claim of each unreconciled batch taken from the utoken undelegated from removed
+    /// validators, which can arrive before the batch matures
+    pub batch_utoken_undelegated: Map<'a, u64, Uint128>,
+    /// Sum of `utoken_unclaimed` over the previous batches, i.e. utoken owed to unbonders
+    pub utoken_unclaimed: Item<'a, Uint128>,
+    /// Surplus (e.g. rounding dust) found by reconcile and credited to the unlocked coins, over the
+    /// contract lifetime
+    pub utoken_dust_recycled: Item<'a, Uint128>,
+    /// Maximum amount of utoken delegated per reinvest, the rest is carried over to the next one
+    pub max_reinvest_per_tx: Item<'a, Uint128>,
+    /// Ring buffer of (time, exchange_rate) observations recorded during reinvest, oldest first
+    pub exchange_rate_history: Item<'a, Vec<(u64, Decimal)>>,
+    /// Stake tokens received with unbonding requests, held until the pending batch burns them
+    pub ustake_escrowed: Item<'a, Uint128>,
+    /// Contracts whose unbonding requests are kept out of the batch statistics of the users
+    pub unbond_allowlist: Item<'a, Vec<Addr>>,
+    /// Number of outstanding requests and remaining shares per batch of the allow-listed contracts
+    pub allowlist_unbond_totals: Map<'a, u64, UnbondRequestsTotals>,
+    /// Whether each unbonding request is counted in the totals of the allow-listed contracts
+    pub unbond_request_allowlisted: Map<'a, (u64, &'a Addr), bool>,
+    /// Minimum time between the last bond of an address for itself and its next unbonding request.
+    /// Advisory only: bonds for other addresses and transfers of the stake token are not tracked
+    pub min_hold_seconds: Item<'a, u64>,
+    /// Time of the last bond per address that bonded for itself
+    pub last_bond_time: Map<'a, &'a Addr, u64>,
+    /// Period over which reinvested rewards are released into the exchange rate
+    pub reward_smoothing_period: Item<'a, u64>,
+    /// Reinvested rewards that are not yet part of the exchange rate
+    pub rewards_schedule: Item<'a, RewardsSchedule>,
+    /// Addresses receiving the validator share of the protocol fee, by validator
+    pub validator_payouts: Map<'a, &'a str, Addr>,
+    /// Payloads of the generic withdrawals, by name
+    pub withdraw_templates: Map<'a, &'a str, WithdrawTemplate>,
+    /// Periods with a different protocol reward fee, sorted by time
+    pub protocol_fee_schedule: Item<'a, Vec<ProtocolFeePeriod>>,
+    /// Minimum time between two executions of privileged actions
+    pub action_cooldowns: Item<'a, Vec<ActionCooldown>>,
+    /// Last execution time of the rate limited actions, by action key
+    pub last_action_time: Map<'a, &'a str, u64>,
+    /// Privileged actions by id, only the last `AUDIT_LOG_SIZE` are kept
+    pub audit_log: Map<'a, u64, AuditEntry>,
+    /// Id of the next entry of the audit log
+    pub audit_log_next_id: Item<'a, u64>,
+    /// Time since the last harvest after which bonding pauses until the next harvest
+    pub max_harvest_staleness: Item<'a, u64>,
+    /// Governance votes cast by the hub, by proposal id
+    pub vote_history: Map<'a, u64, VoteRecord>,
+    /// Set while the state is exported to or imported from another hub
+    pub migration_mode: Item<'a, MigrationMode>,
+    /// Amounts accumulated since the last submitted batch, for the epoch report
+    pub epoch_counters: Item<'a, EpochCounters>,
+    /// Shorter epoch after which small batches can already be submitted
+    pub express_epoch: Item<'a, ExpressEpoch>,
+    /// Time the last batch was submitted
+    pub last_batch_submit_time: Item<'a, u64>,
+    /// Completion times of the pending undelegations, by validator. The staking module only
+    /// allows `MAX_UNBONDING_ENTRIES` of them per validator
+    pub undelegation_entries: Map<'a, &'a str, Vec<u64>>,
+    /// Observations of the validators for the performance delegation strategy
+    pub validator_performance: Map<'a, &'a str, ValidatorPerformance>,
+    /// Maximum number of items returned by the paginated queries
+    pub max_query_limit: Item<'a, u32>,
+    /// Relative deviation from the delegation goal within which a validator is not rebalanced
+    pub rebalance_tolerance: Item<'a, Decimal>,
 }
 
 impl Default for State<'static> {
@@ -72,10 +172,13 @@ impl Default for State<'static> {
         };
         Self {
             fin_multi: Item::new("fin_multi"),
-            owner: Item::new("owner"),
-            new_owner: Item::new("new_owner"),
+            ownership: Ownership::new("erishub"),
             operator: Item::new("operator"),
+            new_operator: Item::new("new_operator"),
             stages_preset: Item::new("stages_preset"),
+            swap_allowlist: Item::new("swap_allowlist"),
+            swap_max_spread: Item::new("swap_max_spread"),
+            ramping_validators: Map::new("ramping_validators"),
             stake_token: Item::new("stake_token"),
             epoch_period: Item::new("epoch_period"),
             unbond_period: Item::new("unbond_period"),
@@ -83,24 +186,57 @@ impl Default for State<'static> {
             unlocked_coins: Item::new("unlocked_coins"),
             pending_batch: Item::new("pending_batch"),
             previous_batches: IndexedMap::new("previous_batches", pb_indexes),
+            reconcile_cursor: Item::new("reconcile_cursor"),
             unbond_requests: IndexedMap::new("unbond_requests", ubr_indexes),
+            unbond_requests_totals: Map::new("unbond_requests_totals"),
             fee_config: Item::new("fee_config"),
             delegation_strategy: Item::new("delegation_strategy"),
             delegation_goal: Item::new("delegation_goal"),
             vote_operator: Item::new("vote_operator"),
+            new_vote_operator: Item::new("new_vote_operator"),
             allow_donations: Item::new("allow_donations"),
+            last_harvest: Item::new("last_harvest"),
+            guardian: Item::new("guardian"),
+            exchange_rate_tolerance: Item::new("exchange_rate_tolerance"),
+            circuit_breaker_tripped: Item::new("circuit_breaker_tripped"),
+            utoken_undelegated: Item::new("utoken_undelegated"),
+            batch_utoken_undelegated: Map::new("batch_utoken_undelegated"),
+            utoken_unclaimed: Item::new("utoken_unclaimed"),
+            utoken_dust_recycled: Item::new("utoken_dust_recycled"),
+            max_reinvest_per_tx: Item::new("max_reinvest_per_tx"),
+            exchange_rate_history: Item::new("exchange_rate_history"),
+            ustake_escrowed: Item::new("ustake_escrowed"),
+            unbond_allowlist: Item::new("unbond_allowlist"),
+            allowlist_unbond_totals: Map::new("allowlist_unbond_totals"),
+            unbond_request_allowlisted: Map::new("unbond_request_allowlisted"),
+            min_hold_seconds: Item::new("min_hold_seconds"),
+            last_bond_time: Map::new("last_bond_time"),
+            reward_smoothing_period: Item::new("reward_smoothing_period"),
+            rewards_schedule: Item::new("rewards_schedule"),
+            validator_payouts: Map::new("validator_payouts"),
+            withdraw_templates: Map::new("withdraw_templates"),
+            protocol_fee_schedule: Item::new("protocol_fee_schedule"),
+            action_cooldowns: Item::new("action_cooldowns"),
+            last_action_time: Map::new("last_action_time"),
+            audit_log: Map::new("audit_log"),
+            audit_log_next_id: Item::new("audit_log_next_id"),
+            max_harvest_staleness: Item::new("max_harvest_staleness"),
+            vote_history: Map::new("vote_history"),
+            migration_mode: Item::new("migration_mode"),
+            epoch_counters: Item::new("epoch_counters"),
+            express_epoch: Item::new("express_epoch"),
+            last_batch_submit_time: Item::new("last_batch_submit_time"),
+            undelegation_entries: Map::new("undelegation_entries"),
+            validator_performance: Map::new("validator_performance"),
+            max_query_limit: Item::new("max_query_limit"),
+            rebalance_tolerance: Item::new("rebalance_tolerance"),
         }
     }
 }
 
 impl<'a> State<'a> {
     pub fn assert_owner(&self, storage: &dyn Storage, sender: &Addr) -> Result<(), ContractError> {
-        let owner = self.owner.load(storage)?;
-        if *sender == owner {
-            Ok(())
-        } else {
-            Err(ContractError::Unauthorized {})
-        }
+        Ok(self.ownership.assert_owner(storage, sender)?)
     }
 
     pub fn assert_operator(
@@ -112,7 +248,7 @@ impl<'a> State<'a> {
         if *sender == operator {
             Ok(())
         } else {
-            Err(ContractError::UnauthorizedSenderNotOperator {})
+            Err(ContractError::unauthorized(Role::Operator, sender))
         }
     }
 
@@ -127,7 +263,214 @@ impl<'a> State<'a> {
         if *sender == vote_operator {
             Ok(())
         } else {
-            Err(ContractError::UnauthorizedSenderNotVoteOperator {})
+            Err(ContractError::unauthorized(Role::VoteOperator, sender))
+        }
+    }
+
+    pub fn assert_guardian(
+        &self,
+        storage: &dyn Storage,
+        sender: &Addr,
+    ) -> Result<(), ContractError> {
+        match self.guardian.may_load(storage)? {
+            Some(guardian) if *sender == guardian => Ok(()),
+            Some(_) => Err(ContractError::unauthorized(Role::Guardian, sender)),
+            None => self.assert_owner(storage, sender),
+        }
+    }
+
+    /// Totals counting the unbonding request of `user` in batch `id`
+    pub fn unbond_totals_of(
+        &self,
+        storage: &dyn Storage,
+        id: u64,
+        user: &Addr,
+    ) -> StdResult<&Map<'a, u64, UnbondRequestsTotals>> {
+        if self.unbond_request_allowlisted.load(storage, (id, user))? {
+            Ok(&self.allowlist_unbond_totals)
+        } else {
+            Ok(&self.unbond_requests_totals)
+        }
+    }
+
+    /// Totals counting a new unbonding request of `user` in batch `id`. They are picked by the
+    /// current allow-list membership of `user` and kept until the request is removed.
+    pub fn count_new_unbond_request(
+        &self,
+        storage: &mut dyn Storage,
+        id: u64,
+        user: &Addr,
+    ) -> StdResult<&Map<'a, u64, UnbondRequestsTotals>> {
+        let allowlist = self.unbond_allowlist.may_load(storage)?.unwrap_or_default();
+        self.unbond_request_allowlisted.save(storage, (id, user), &allowlist.contains(user))?;
+        self.unbond_totals_of(storage, id, user)
+    }
+
+    /// Adds `added` to and removes `removed` from the unclaimed utoken of the previous batches
+    pub fn update_utoken_unclaimed(
+        &self,
+        storage: &mut dyn Storage,
+        added: Uint128,
+        removed: Uint128,
+    ) -> StdResult<()> {
+        let utoken_unclaimed = self.utoken_unclaimed.may_load(storage)?.unwrap_or_default();
+        self.utoken_unclaimed
+            .save(storage, &utoken_unclaimed.checked_add(added)?.checked_sub(removed)?)
+    }
+
+    /// Reinvested rewards that are staked, but not yet released into the exchange rate at `time`
+    pub fn utoken_unvested(&self, storage: &dyn Storage, time: u64) -> StdResult<Uint128> {
+        Ok(self
+            .rewards_schedule
+            .may_load(storage)?
+            .map_or(Uint128::zero(), |schedule| schedule.unvested(time)))
+    }
+
+    /// Fails if `user` bonded less than `min_hold_seconds` before `now`
+    pub fn assert_hold_period_elapsed(
+        &self,
+        storage: &dyn Storage,
+        user: &Addr,
+        now: u64,
+    ) -> Result<(), ContractError> {
+        let min_hold_seconds = self.min_hold_seconds.may_load(storage)?.unwrap_or_default();
+        if min_hold_seconds == 0 {
+            return Ok(());
+        }
+
+        match self.last_bond_time.may_load(storage, user)? {
+            Some(bond_time) if bond_time + min_hold_seconds > now => {
+                Err(ContractError::HoldPeriodNotElapsed(bond_time + min_hold_seconds))
+            },
+            _ => Ok(()),
+        }
+    }
+
+    /// Protocol reward fee at `time` in basis points: the one of the scheduled period containing
+    /// it, otherwise the configured fee
+    pub fn protocol_reward_fee(
+        &self,
+        storage: &dyn Storage,
+        fee_config: &FeeConfig,
+        time: u64,
+    ) -> StdResult<BasicPoints> {
+        let schedule = self.protocol_fee_schedule.may_load(storage)?.unwrap_or_default();
+        match schedule.iter().find(|period| period.start_time <= time && time < period.end_time) {
+            Some(period) => BasicPoints::try_from(period.bps),
+            // fees configured before the precision was enforced are rounded down
+            None => {
+                let fee = fee_config.protocol_reward_fee;
+                BasicPoints::from_ratio(fee.numerator(), fee.denominator())
+            },
+        }
+    }
+
+    pub fn max_query_limit(&self, storage: &dyn Storage) -> StdResult<u32> {
+        Ok(self.max_query_limit.may_load(storage)?.unwrap_or(MAX_LIMIT))
+    }
+
+    /// Number of items to return for a requested `limit`, capped at `max_query_limit`
+    pub fn query_limit(&self, storage: &dyn Storage, limit: Option<u32>) -> StdResult<usize> {
+        Ok(get_limit_with_max(limit, self.max_query_limit(storage)?))
+    }
+
+    /// Seconds since the last harvest if it is older than `max_harvest_staleness`
+    pub fn harvest_staleness(&self, storage: &dyn Storage, now: u64) -> StdResult<Option<u64>> {
+        let max_harvest_staleness =
+            self.max_harvest_staleness.may_load(storage)?.unwrap_or_default();
+        if max_harvest_staleness == 0 {
+            return Ok(None);
+        }
+
+        Ok(self
+            .last_harvest
+            .may_load(storage)?
+            .map(|last_harvest| now.saturating_sub(last_harvest.timestamp))
+            .filter(|since_last_harvest| *since_last_harvest > max_harvest_staleness))
+    }
+
+    pub fn assert_harvest_not_stale(
+        &self,
+        storage: &dyn Storage,
+        now: u64,
+    ) -> Result<(), ContractError> {
+        match self.harvest_staleness(storage, now)? {
+            Some(since_last_harvest) => Err(ContractError::HarvestStale(since_last_harvest)),
+            None => Ok(()),
+        }
+    }
+
+    /// Appends a privileged action to the audit log, dropping the oldest entry once it is full
+    pub fn record_audit(
+        &self,
+        storage: &mut dyn Storage,
+        action: &str,
+        sender: Addr,
+        timestamp: u64,
+        params: &Binary,
+    ) -> StdResult<()> {
+        let id = self.audit_log_next_id.may_load(storage)?.unwrap_or_default();
+        let params_hash =
+            Sha256::digest(params.as_slice())[..8].iter().map(|b| format!("{:02x}", b)).collect();
+
+        self.audit_log.save(
+            storage,
+            id,
+            &AuditEntry {
+                id,
+                action: action.to_string(),
+                sender,
+                timestamp,
+                params_hash,
+            },
+        )?;
+        if id >= AUDIT_LOG_SIZE {
+            self.audit_log.remove(storage, id - AUDIT_LOG_SIZE);
+        }
+        self.audit_log_next_id.save(storage, &(id + 1))
+    }
+
+    /// Fails if `action` was executed less than its cooldown ago, otherwise records the execution
+    pub fn enforce_cooldown(
+        &self,
+        storage: &mut dyn Storage,
+        action: RateLimitedAction,
+        now: u64,
+    ) -> Result<(), ContractError> {
+        let cooldown = self
+            .action_cooldowns
+            .may_load(storage)?
+            .unwrap_or_default()
+            .into_iter()
+            .find(|cooldown| cooldown.action == action);
+
+        if let Some(cooldown) = cooldown {
+            if let Some(last) = self.last_action_time.may_load(storage, action.key())? {
+                if last + cooldown.seconds > now {
+                    return Err(ContractError::ActionCooldown {
+                        action: action.key().to_string(),
+                        until: last + cooldown.seconds,
+                    });
+                }
+            }
+            self.last_action_time.save(storage, action.key(), &now)?;
+        }
+        Ok(())
+    }
+
+    pub fn assert_not_tripped(&self, storage: &dyn Storage) -> Result<(), ContractError> {
+        if self.circuit_breaker_tripped.may_load(storage)?.unwrap_or(false) {
+            Err(ContractError::CircuitBreakerTripped {})
+        } else {
+            Ok(())
+        }
+    }
+
+    pub fn assert_not_migrating(&self, storage: &dyn Storage) -> Result<(), ContractError> {
+        if self.migration_mode.may_load(storage)?.is_some() {
+            Err(ContractError::MigrationInProgress {})
+        } else {
+            Ok(())
         }
     }
 }
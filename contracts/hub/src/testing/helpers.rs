@@ -1,12 +1,12 @@
 use cosmwasm_std::testing::{mock_env, MockApi, MockStorage, MOCK_CONTRACT_ADDR};
 use cosmwasm_std::{
     coin, from_binary, to_binary, Addr, BlockInfo, ContractInfo, CosmosMsg, Deps, Env, OwnedDeps,
-    QuerierResult, SubMsg, SystemError, SystemResult, Timestamp, Uint128, WasmMsg,
+    Storage, SubMsg, Timestamp, Uint128, WasmMsg,
 };
 use kujira::msg::KujiraMsg;
 use serde::de::DeserializeOwned;
 
-use eris::hub::{CallbackMsg, ExecuteMsg, QueryMsg, StakeToken};
+use eris::hub::{Batch, CallbackMsg, ExecuteMsg, QueryMsg, StakeToken, UnbondRequest};
 
 use crate::constants::CONTRACT_DENOM;
 use crate::contract::query;
@@ -14,20 +14,8 @@ use crate::state::State;
 
 use super::custom_querier::CustomQuerier;
 
-pub(super) fn err_unsupported_query<T: std::fmt::Debug>(request: T) -> QuerierResult {
-    SystemResult::Err(SystemError::InvalidRequest {
-        error: format!("[mock] unsupported query: {:?}", request),
-        request: Default::default(),
-    })
-}
-
 pub(super) fn mock_dependencies() -> OwnedDeps<MockStorage, MockApi, CustomQuerier> {
-    OwnedDeps {
-        storage: MockStorage::default(),
-        api: MockApi::default(),
-        querier: CustomQuerier::default(),
-        custom_query_type: std::marker::PhantomData::default(),
-    }
+    eris_testing::mock_dependencies()
 }
 
 pub(super) fn mock_env_at_timestamp(timestamp: u64) -> Env {
@@ -73,6 +61,31 @@ pub(super) fn set_total_stake_supply(
         .unwrap();
 }
 
+/// Saves `batches` as previous batches, counting their claims like submitting them would
+pub(super) fn save_previous_batches(state: &State, storage: &mut dyn Storage, batches: &[Batch]) {
+    for batch in batches {
+        state.previous_batches.save(storage, batch.id, batch).unwrap();
+        state.update_utoken_unclaimed(storage, batch.utoken_unclaimed, Uint128::zero()).unwrap();
+    }
+}
+
+/// Saves `requests` as unbonding requests, counting them in the totals like queuing them would
+pub(super) fn save_unbond_requests(
+    state: &State,
+    storage: &mut dyn Storage,
+    requests: &[UnbondRequest],
+) {
+    for request in requests {
+        state.unbond_requests.save(storage, (request.id, &request.user), request).unwrap();
+        let unbond_totals =
+            state.count_new_unbond_request(storage, request.id, &request.user).unwrap();
+        let mut totals = unbond_totals.may_load(storage, request.id).unwrap().unwrap_or_default();
+        totals.count += 1;
+        totals.shares += request.shares;
+        unbond_totals.save(storage, request.id, &totals).unwrap();
+    }
+}
+
 pub fn check_received_coin(amount: u128, amount_stake: u128) -> SubMsg<KujiraMsg> {
     SubMsg::new(CosmosMsg::Wasm(WasmMsg::Execute {
         contract_addr: MOCK_CONTRACT_ADDR.to_string(),
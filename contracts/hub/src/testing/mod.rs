@@ -1,6 +1,11 @@
 mod custom_querier;
 mod helpers;
 pub mod test_defined;
+mod test_gas;
+mod test_messages;
+mod test_performance;
+mod test_registry;
+mod test_slashing;
 pub mod test_swap;
 mod tests_default;
 pub mod tests_gauges;
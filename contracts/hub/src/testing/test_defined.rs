@@ -6,13 +6,14 @@ use cosmwasm_std::{coin, Addr, Coin, CosmosMsg, Decimal, OwnedDeps, StdError, Su
 use eris::governance_helper::{EPOCH_START, WEEK};
 use eris::hub::{
     ConfigResponse, DelegationStrategy, ExecuteMsg, FeeConfig, InstantiateMsg, QueryMsg,
-    StakeToken, StateResponse, WantedDelegationsResponse, WantedDelegationsShare,
+    RemoveValidatorMode, SimulateRebalanceResponse, SimulatedRedelegation, StakeToken,
+    StateResponse, WantedDelegationsResponse, WantedDelegationsShare,
 };
 use kujira::msg::{DenomMsg, KujiraMsg};
 
 use crate::constants::CONTRACT_DENOM;
 use crate::contract::{execute, instantiate};
-use crate::error::ContractError;
+use crate::error::{ContractError, Role};
 use crate::state::State;
 use crate::testing::helpers::check_received_coin;
 use crate::types::{Delegation, Redelegation};
@@ -48,6 +49,8 @@ fn setup_test() -> OwnedDeps<MockStorage, MockApi, CustomQuerier> {
                 shares_bps: vec![("alice".into(), 6000), ("bob".into(), 4000)],
             }),
             vote_operator: None,
+            bootstrap: None,
+            max_query_limit: None,
         },
     )
     .unwrap();
@@ -111,7 +114,7 @@ fn proper_instantiation() {
     assert_eq!(
         res,
         ConfigResponse {
-            owner: "owner".to_string(),
+            owner: Some("owner".to_string()),
             new_owner: None,
             stake_token: STAKE_DENOM.to_string(),
             epoch_period: 259200,
@@ -119,15 +122,34 @@ fn proper_instantiation() {
             validators: vec!["alice".to_string(), "bob".to_string(), "charlie".to_string()],
             fee_config: FeeConfig {
                 protocol_fee_contract: Addr::unchecked("fee"),
-                protocol_reward_fee: Decimal::from_ratio(1u128, 100u128)
+                protocol_reward_fee: Decimal::from_ratio(1u128, 100u128),
+                unbond_fee: None,
+                validator_fee_share: None,
             },
             operator: "operator".to_string(),
+            new_operator: None,
             stages_preset: vec![],
             allow_donations: false,
             delegation_strategy: DelegationStrategy::Defined {
                 shares_bps: vec![("alice".into(), 6000), ("bob".into(), 4000)],
             },
-            vote_operator: None
+            vote_operator: None,
+            new_vote_operator: None,
+            guardian: None,
+            exchange_rate_tolerance: None,
+            circuit_breaker_tripped: false,
+            swap_allowlist: vec![],
+            swap_max_spread: Decimal::percent(5),
+            max_reinvest_per_tx: None,
+            unbond_allowlist: vec![],
+            min_hold_seconds: 0,
+            reward_smoothing_period: 0,
+            max_harvest_staleness: 0,
+            protocol_fee_schedule: vec![],
+            action_cooldowns: vec![],
+            express_epoch: None,
+            rebalance_tolerance: Decimal::zero(),
+            max_query_limit: 30,
         }
     );
 
@@ -142,6 +164,9 @@ fn proper_instantiation() {
             unbonding: Uint128::zero(),
             available: Uint128::zero(),
             tvl_utoken: Uint128::zero(),
+            ustake_escrowed: Uint128::zero(),
+            utoken_unvested: Uint128::zero(),
+            utoken_dust_recycled: Uint128::zero(),
         },
     );
 }
@@ -164,6 +189,21 @@ fn validate_update() {
                 shares_bps: vec![("abc".into(), 1000)],
             }),
             vote_operator: None,
+            guardian: None,
+            exchange_rate_tolerance: None,
+            swap_allowlist: None,
+            swap_max_spread: None,
+            max_reinvest_per_tx: None,
+            unbond_allowlist: None,
+            unbond_fee: None,
+            min_hold_seconds: None,
+            reward_smoothing_period: None,
+            validator_fee_share: None,
+            max_harvest_staleness: None,
+            protocol_fee_schedule: None,
+            action_cooldowns: None,
+            express_epoch: None,
+            rebalance_tolerance: None,
         },
     )
     .unwrap_err();
@@ -183,6 +223,21 @@ fn validate_update() {
                 shares_bps: vec![("alice".into(), 1000), ("alice".into(), 1000)],
             }),
             vote_operator: None,
+            guardian: None,
+            exchange_rate_tolerance: None,
+            swap_allowlist: None,
+            swap_max_spread: None,
+            max_reinvest_per_tx: None,
+            unbond_allowlist: None,
+            unbond_fee: None,
+            min_hold_seconds: None,
+            reward_smoothing_period: None,
+            validator_fee_share: None,
+            max_harvest_staleness: None,
+            protocol_fee_schedule: None,
+            action_cooldowns: None,
+            express_epoch: None,
+            rebalance_tolerance: None,
         },
     )
     .unwrap_err();
@@ -202,6 +257,21 @@ fn validate_update() {
                 shares_bps: vec![("alice".into(), 1000)],
             }),
             vote_operator: None,
+            guardian: None,
+            exchange_rate_tolerance: None,
+            swap_allowlist: None,
+            swap_max_spread: None,
+            max_reinvest_per_tx: None,
+            unbond_allowlist: None,
+            unbond_fee: None,
+            min_hold_seconds: None,
+            reward_smoothing_period: None,
+            validator_fee_share: None,
+            max_harvest_staleness: None,
+            protocol_fee_schedule: None,
+            action_cooldowns: None,
+            express_epoch: None,
+            rebalance_tolerance: None,
         },
     )
     .unwrap_err();
@@ -221,11 +291,61 @@ fn validate_update() {
                 shares_bps: vec![("alice".into(), 1000), ("charlie".into(), 9000)],
             }),
             vote_operator: None,
+            guardian: None,
+            exchange_rate_tolerance: None,
+            swap_allowlist: None,
+            swap_max_spread: None,
+            max_reinvest_per_tx: None,
+            unbond_allowlist: None,
+            unbond_fee: None,
+            min_hold_seconds: None,
+            reward_smoothing_period: None,
+            validator_fee_share: None,
+            max_harvest_staleness: None,
+            protocol_fee_schedule: None,
+            action_cooldowns: None,
+            express_epoch: None,
+            rebalance_tolerance: None,
         },
     )
     .unwrap();
 }
 
+#[test]
+fn removing_validator_with_defined_share() {
+    let mut deps = setup_test();
+
+    let remove_validator = |validator: &str| ExecuteMsg::RemoveValidator {
+        validator: validator.to_string(),
+        mode: Some(RemoveValidatorMode::Undelegate),
+    };
+
+    let err = execute(deps.as_mut(), mock_env(), mock_info("owner", &[]), remove_validator("bob"))
+        .unwrap_err();
+    assert_eq!(err, ContractError::ValidatorHasDefinedShare("bob".into()));
+
+    execute(deps.as_mut(), mock_env(), mock_info("owner", &[]), remove_validator("charlie"))
+        .unwrap();
+
+    let res: ConfigResponse = query_helper(deps.as_ref(), QueryMsg::Config {});
+    assert_eq!(res.validators, vec!["alice".to_string(), "bob".to_string()]);
+
+    // the validated shares of the last tune
+    let res: Option<WantedDelegationsShare> =
+        query_helper(deps.as_ref(), QueryMsg::DelegationGoal {});
+    assert_eq!(
+        res,
+        Some(WantedDelegationsShare {
+            tune_time: EPOCH_START + WEEK,
+            tune_period: 1,
+            shares: vec![
+                ("alice".into(), Decimal::from_str("0.6").unwrap()),
+                ("bob".into(), Decimal::from_str("0.4").unwrap())
+            ]
+        })
+    );
+}
+
 #[test]
 fn bonding() {
     let mut deps = setup_test();
@@ -240,6 +360,7 @@ fn bonding() {
         mock_info("user_1", &[Coin::new(1000000, CONTRACT_DENOM)]),
         ExecuteMsg::Bond {
             receiver: None,
+            min_ustake_received: None,
         },
     )
     .unwrap();
@@ -283,6 +404,7 @@ fn bonding() {
         mock_info("user_2", &[Coin::new(12345, CONTRACT_DENOM)]),
         ExecuteMsg::Bond {
             receiver: Some("user_3".to_string()),
+            min_ustake_received: None,
         },
     )
     .unwrap();
@@ -317,6 +439,9 @@ fn bonding() {
             unbonding: Uint128::zero(),
             available: Uint128::new(12567),
             tvl_utoken: Uint128::new(1037345 + 12567),
+            ustake_escrowed: Uint128::zero(),
+            utoken_unvested: Uint128::zero(),
+            utoken_dust_recycled: Uint128::zero(),
         }
     );
 
@@ -337,6 +462,68 @@ fn bonding() {
         },
     );
 
+    // charlie received a redelegation recently, so it can't be redelegated from yet
+    deps.querier.set_staking_delegations_locked(
+        &[
+            Delegation::new("alice", 341667),
+            Delegation::new("bob", 341667),
+            Delegation::new("charlie", 354011),
+        ],
+        &["charlie"],
+    );
+
+    let res: SimulateRebalanceResponse = query_helper(
+        deps.as_ref(),
+        QueryMsg::SimulateRebalance {
+            min_redelegation: Some(Uint128::new(100000)),
+        },
+    );
+    assert_eq!(
+        res,
+        SimulateRebalanceResponse {
+            redelegations: vec![SimulatedRedelegation {
+                src: "charlie".into(),
+                dst: "alice".into(),
+                amount: Uint128::new(280740),
+                blocked: true,
+            }],
+            utoken_moved: Uint128::new(280740),
+        }
+    );
+
+    deps.querier.set_staking_delegations(&[
+        Delegation::new("alice", 341667),
+        Delegation::new("bob", 341667),
+        Delegation::new("charlie", 354011),
+    ]);
+
+    let res: SimulateRebalanceResponse = query_helper(
+        deps.as_ref(),
+        QueryMsg::SimulateRebalance {
+            min_redelegation: None,
+        },
+    );
+    assert_eq!(
+        res,
+        SimulateRebalanceResponse {
+            redelegations: vec![
+                SimulatedRedelegation {
+                    src: "charlie".into(),
+                    dst: "alice".into(),
+                    amount: Uint128::new(280740),
+                    blocked: false,
+                },
+                SimulatedRedelegation {
+                    src: "charlie".into(),
+                    dst: "bob".into(),
+                    amount: Uint128::new(73271),
+                    blocked: false,
+                }
+            ],
+            utoken_moved: Uint128::new(354011),
+        }
+    );
+
     let res = execute(
         deps.as_mut(),
         mock_env(),
@@ -346,7 +533,13 @@ fn bonding() {
         },
     )
     .unwrap_err();
-    assert_eq!(res, ContractError::Unauthorized {});
+    assert_eq!(
+        res,
+        ContractError::Unauthorized {
+            required_role: Role::Owner,
+            sender: "alice".to_string()
+        }
+    );
 
     let res = execute(
         deps.as_mut(),
@@ -380,3 +573,140 @@ fn bonding() {
 
     assert_eq!(res.messages[2], check_received_coin(12567, 0));
 }
+
+#[test]
+fn rebalancing_skips_drift_within_tolerance() {
+    // The mock querier doesn't execute the returned messages, so the delegations after each bond,
+    // redelegation and unbonding are set by hand
+    let mut deps = setup_test();
+
+    let update_tolerance = |tolerance: Decimal| ExecuteMsg::UpdateConfig {
+        protocol_fee_contract: None,
+        protocol_reward_fee: None,
+        operator: None,
+        stages_preset: None,
+        swap_allowlist: None,
+        swap_max_spread: None,
+        allow_donations: None,
+        delegation_strategy: None,
+        vote_operator: None,
+        guardian: None,
+        exchange_rate_tolerance: None,
+        max_reinvest_per_tx: None,
+        unbond_allowlist: None,
+        unbond_fee: None,
+        min_hold_seconds: None,
+        reward_smoothing_period: None,
+        validator_fee_share: None,
+        max_harvest_staleness: None,
+        protocol_fee_schedule: None,
+        action_cooldowns: None,
+        express_epoch: None,
+        rebalance_tolerance: Some(tolerance),
+    };
+    let bond = |deps: &mut OwnedDeps<MockStorage, MockApi, CustomQuerier>, amount: u128| {
+        deps.querier.set_bank_balances(&[coin(amount, CONTRACT_DENOM)]);
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("user_1", &[Coin::new(amount, CONTRACT_DENOM)]),
+            ExecuteMsg::Bond {
+                receiver: None,
+                min_ustake_received: None,
+            },
+        )
+        .unwrap()
+    };
+    let rebalance = |deps: &mut OwnedDeps<MockStorage, MockApi, CustomQuerier>| {
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("owner", &[]),
+            ExecuteMsg::Rebalance {
+                min_redelegation: None,
+            },
+        )
+        .unwrap()
+    };
+
+    let err = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("owner", &[]),
+        update_tolerance(Decimal::percent(101)),
+    )
+    .unwrap_err();
+    assert_eq!(err, ContractError::RebalanceToleranceTooHigh {});
+
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("owner", &[]),
+        update_tolerance(Decimal::percent(2)),
+    )
+    .unwrap();
+    let res: ConfigResponse = query_helper(deps.as_ref(), QueryMsg::Config {});
+    assert_eq!(res.rebalance_tolerance, Decimal::percent(2));
+
+    // The delegations match the goal of 60% for alice and 40% for bob
+    deps.querier.set_staking_delegations(&[
+        Delegation::new("alice", 600000),
+        Delegation::new("bob", 400000),
+    ]);
+    assert!(rebalance(&mut deps).messages.is_empty());
+
+    // Defined bonds go to the smallest delegation, moving bob above his goal
+    let res = bond(&mut deps, 10000);
+    assert_eq!(res.messages[0], SubMsg::new(Delegation::new("bob", 10000).to_cosmos_msg()));
+    deps.querier.set_staking_delegations(&[
+        Delegation::new("alice", 600000),
+        Delegation::new("bob", 410000),
+    ]);
+
+    // Goal: alice 606,000, bob 404,000
+    // Both deviate by 6,000, within the bands of 12,120 and 8,080
+    assert!(rebalance(&mut deps).messages.is_empty());
+    let res: SimulateRebalanceResponse = query_helper(
+        deps.as_ref(),
+        QueryMsg::SimulateRebalance {
+            min_redelegation: None,
+        },
+    );
+    assert_eq!(res.utoken_moved, Uint128::zero());
+
+    // The drift keeps growing with the next bond
+    let res = bond(&mut deps, 40000);
+    assert_eq!(res.messages[0], SubMsg::new(Delegation::new("bob", 40000).to_cosmos_msg()));
+    deps.querier.set_staking_delegations(&[
+        Delegation::new("alice", 600000),
+        Delegation::new("bob", 450000),
+    ]);
+
+    // Goal: alice 630,000, bob 420,000
+    // Both deviate by 30,000, outside the bands of 12,600 and 8,400
+    let res = rebalance(&mut deps);
+    assert_eq!(res.messages.len(), 2);
+    assert_eq!(res.messages[0].msg, Redelegation::new("bob", "alice", 30000).to_cosmos_msg());
+    deps.querier.set_staking_delegations(&[
+        Delegation::new("alice", 630000),
+        Delegation::new("bob", 420000),
+    ]);
+
+    // Rebalancing again is a no-op
+    assert!(rebalance(&mut deps).messages.is_empty());
+
+    // An unbonding batch undelegated 10,000 from alice
+    // Goal: alice 624,000, bob 416,000
+    // Both deviate by 4,000, within the bands of 12,480 and 8,320
+    deps.querier.set_staking_delegations(&[
+        Delegation::new("alice", 620000),
+        Delegation::new("bob", 420000),
+    ]);
+    assert!(rebalance(&mut deps).messages.is_empty());
+
+    // Without a tolerance band, every drift is redelegated
+    execute(deps.as_mut(), mock_env(), mock_info("owner", &[]), update_tolerance(Decimal::zero()))
+        .unwrap();
+    let res = rebalance(&mut deps);
+    assert_eq!(res.messages[0].msg, Redelegation::new("bob", "alice", 4000).to_cosmos_msg());
+}
@@ -0,0 +1,295 @@
+use std::cell::RefCell;
+
+use cosmwasm_std::testing::{mock_info, MockApi, MockStorage};
+use cosmwasm_std::{
+    coin, Addr, Coin, Decimal, DepsMut, Order, OwnedDeps, Record, Response, Storage, Uint128,
+};
+use kujira::msg::KujiraMsg;
+
+use eris::hub::{Batch, ExecuteMsg, InstantiateMsg, PendingBatch, StakeToken, UnbondRequest};
+
+use crate::constants::CONTRACT_DENOM;
+use crate::contract::{execute, instantiate};
+use crate::error::ContractResult;
+use crate::state::State;
+use crate::types::Delegation;
+
+use super::custom_querier::CustomQuerier;
+use super::helpers::{mock_env_at_timestamp, save_previous_batches, save_unbond_requests};
+
+//--------------------------------------------------------------------------------------------------
+// Gas accounting
+//--------------------------------------------------------------------------------------------------
+
+/// Work done by a single execution. Storage operations dominate the gas cost of the hub, so these
+/// are used as a proxy for gas that does not depend on the gas schedule of the chain.
+#[derive(Default, Debug, Clone, Copy, PartialEq)]
+struct GasUsage {
+    /// Messages returned in the response
+    messages: usize,
+    /// Storage reads (`get`)
+    reads: usize,
+    /// Storage writes (`set` and `remove`)
+    writes: usize,
+    /// Iterators created (`range`)
+    scans: usize,
+    /// Records consumed from all iterators
+    scanned: usize,
+}
+
+/// Storage that counts the operations on the wrapped mock storage
+#[derive(Default)]
+struct CountingStorage {
+    inner: MockStorage,
+    usage: RefCell<GasUsage>,
+}
+
+impl Storage for CountingStorage {
+    fn get(&self, key: &[u8]) -> Option<Vec<u8>> {
+        self.usage.borrow_mut().reads += 1;
+        self.inner.get(key)
+    }
+
+    fn range<'a>(
+        &'a self,
+        start: Option<&[u8]>,
+        end: Option<&[u8]>,
+        order: Order,
+    ) -> Box<dyn Iterator<Item = Record> + 'a> {
+        self.usage.borrow_mut().scans += 1;
+        Box::new(
+            self.inner
+                .range(start, end, order)
+                .inspect(move |_| self.usage.borrow_mut().scanned += 1),
+        )
+    }
+
+    fn set(&mut self, key: &[u8], value: &[u8]) {
+        self.usage.get_mut().writes += 1;
+        self.inner.set(key, value)
+    }
+
+    fn remove(&mut self, key: &[u8]) {
+        self.usage.get_mut().writes += 1;
+        self.inner.remove(key)
+    }
+}
+
+type GasDeps = OwnedDeps<CountingStorage, MockApi, CustomQuerier>;
+
+/// Runs `f` and returns its response together with the work it did
+fn measure(
+    deps: &mut GasDeps,
+    f: impl FnOnce(DepsMut) -> ContractResult,
+) -> (Response<KujiraMsg>, GasUsage) {
+    deps.storage.usage.replace(GasUsage::default());
+    let res = f(deps.as_mut()).unwrap();
+
+    let mut usage = deps.storage.usage.replace(GasUsage::default());
+    usage.messages = res.messages.len();
+    (res, usage)
+}
+
+/// Fails if any counter of `usage` exceeds the one of `max`
+fn assert_within(usage: GasUsage, max: GasUsage) {
+    assert!(
+        usage.messages <= max.messages
+            && usage.reads <= max.reads
+            && usage.writes <= max.writes
+            && usage.scans <= max.scans
+            && usage.scanned <= max.scanned,
+        "gas regression: {:?} exceeds {:?}",
+        usage,
+        max
+    );
+}
+
+//--------------------------------------------------------------------------------------------------
+// Test setup
+//--------------------------------------------------------------------------------------------------
+
+const STAKE_DENOM: &str = "factory/cosmos2contract/stake";
+
+fn setup_test() -> GasDeps {
+    let mut deps = OwnedDeps {
+        storage: CountingStorage::default(),
+        api: MockApi::default(),
+        querier: CustomQuerier::default(),
+        custom_query_type: std::marker::PhantomData,
+    };
+
+    instantiate(
+        deps.as_mut(),
+        mock_env_at_timestamp(10000),
+        mock_info("deployer", &[]),
+        InstantiateMsg {
+            fin_multi_contract: "fin_multi".to_string(),
+            owner: "owner".to_string(),
+            denom: "stake".to_string(),
+            epoch_period: 259200,
+            unbond_period: 1814400,
+            validators: vec!["alice".to_string(), "bob".to_string(), "charlie".to_string()],
+            protocol_fee_contract: "fee".to_string(),
+            protocol_reward_fee: Decimal::percent(1),
+            operator: "operator".to_string(),
+            stages_preset: None,
+            delegation_strategy: None,
+            vote_operator: None,
+            bootstrap: None,
+            max_query_limit: None,
+        },
+    )
+    .unwrap();
+
+    deps.querier.set_staking_delegations(&[
+        Delegation::new("alice", 341667),
+        Delegation::new("bob", 341667),
+        Delegation::new("charlie", 341666),
+    ]);
+
+    State::default()
+        .stake_token
+        .save(
+            deps.as_mut().storage,
+            &StakeToken {
+                denom: STAKE_DENOM.to_string(),
+                total_supply: Uint128::new(1000000),
+            },
+        )
+        .unwrap();
+
+    deps
+}
+
+//--------------------------------------------------------------------------------------------------
+// Hot paths
+//--------------------------------------------------------------------------------------------------
+
+#[test]
+fn bond_gas() {
+    let mut deps = setup_test();
+    deps.querier.set_bank_balances(&[coin(12345, CONTRACT_DENOM)]);
+
+    let (_, usage) = measure(&mut deps, |deps| {
+        execute(
+            deps,
+            mock_env_at_timestamp(20000),
+            mock_info("user_1", &[Coin::new(12345, CONTRACT_DENOM)]),
+            ExecuteMsg::Bond {
+                receiver: None,
+                min_ustake_received: None,
+            },
+        )
+    });
+
+    // delegate, mint and the received coins check; the stake token supply, the bond time of the
+    // receiver and the epoch counters are written
+    assert_within(
+        usage,
+        GasUsage {
+            messages: 3,
+            reads: 24,
+            writes: 3,
+            scans: 0,
+            scanned: 0,
+        },
+    );
+}
+
+#[test]
+fn queue_unbond_gas() {
+    let mut deps = setup_test();
+
+    let (_, usage) = measure(&mut deps, |deps| {
+        execute(
+            deps,
+            mock_env_at_timestamp(20000),
+            mock_info("user_1", &[Coin::new(23456, STAKE_DENOM)]),
+            ExecuteMsg::QueueUnbond {
+                receiver: None,
+            },
+        )
+    });
+
+    // pending batch, escrowed stake, unbond request with its user index entry, the totals it is
+    // counted in and the batch totals
+    assert_within(
+        usage,
+        GasUsage {
+            messages: 0,
+            reads: 13,
+            writes: 6,
+            scans: 0,
+            scanned: 0,
+        },
+    );
+}
+
+#[test]
+fn withdraw_unbonded_gas() {
+    let mut deps = setup_test();
+    let state = State::default();
+
+    let user = Addr::unchecked("user_1");
+    for id in 1..=2u64 {
+        save_unbond_requests(
+            &state,
+            deps.as_mut().storage,
+            &[UnbondRequest {
+                id,
+                user: user.clone(),
+                shares: Uint128::new(10000),
+            }],
+        );
+        save_previous_batches(
+            &state,
+            deps.as_mut().storage,
+            &[Batch {
+                id,
+                reconciled: true,
+                total_shares: Uint128::new(20000),
+                utoken_unclaimed: Uint128::new(20500),
+                est_unbond_end_time: 10000 * id,
+                exchange_rate_submitted: None,
+                exchange_rate_reconciled: None,
+            }],
+        );
+    }
+    state
+        .pending_batch
+        .save(
+            deps.as_mut().storage,
+            &PendingBatch {
+                id: 3,
+                ustake_to_burn: Uint128::zero(),
+                est_unbond_start_time: 100000,
+            },
+        )
+        .unwrap();
+
+    let (_, usage) = measure(&mut deps, |deps| {
+        execute(
+            deps,
+            mock_env_at_timestamp(30000),
+            mock_info("user_1", &[]),
+            ExecuteMsg::WithdrawUnbonded {
+                receiver: None,
+                receiver_msg: None,
+            },
+        )
+    });
+
+    // a single scan over the requests of the user, writes per request: the batch with its
+    // reconciled index entry, the request with its user index entry and the totals it is counted
+    // in, the batch totals and the reconcile cursor
+    assert_within(
+        usage,
+        GasUsage {
+            messages: 1,
+            reads: 31,
+            writes: 15,
+            scans: 1,
+            scanned: 2,
+        },
+    );
+}
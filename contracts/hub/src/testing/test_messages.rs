@@ -0,0 +1,479 @@
+use std::collections::{BTreeSet, HashSet};
+use std::fs;
+use std::path::Path;
+
+use cosmwasm_schema::schemars::schema::{RootSchema, Schema};
+use cosmwasm_schema::{generate_api, QueryResponses};
+use cosmwasm_std::testing::{mock_env, mock_info};
+use cosmwasm_std::{
+    coin, from_slice, to_vec, Addr, Binary, Decimal, StdResult, Uint128, VoteOption,
+};
+use serde_json::Value;
+
+use eris::hub::{
+    CallbackMsg, DelegationStrategy, ExecuteMsg, HubConfig, InstantiateMsg, MigrateMsg, QueryMsg,
+    RemoveValidatorMode, StateExport, WithdrawTemplate, WithdrawType,
+};
+
+use crate::contract::execute;
+use crate::error::ContractError;
+
+use super::helpers::mock_dependencies;
+
+//--------------------------------------------------------------------------------------------------
+// Message samples
+//--------------------------------------------------------------------------------------------------
+
+/// Name of the variant on the wire. Exhaustive on purpose, so that every new variant needs to be
+/// added here and to `execute_msg_samples`.
+fn execute_msg_name(msg: &ExecuteMsg) -> &'static str {
+    match msg {
+        ExecuteMsg::Bond {
+            ..
+        } => "bond",
+        ExecuteMsg::Donate {} => "donate",
+        ExecuteMsg::WithdrawUnbonded {
+            ..
+        } => "withdraw_unbonded",
+        ExecuteMsg::TransferUnbondRequest {
+            ..
+        } => "transfer_unbond_request",
+        ExecuteMsg::SendUnbondRequest {
+            ..
+        } => "send_unbond_request",
+        ExecuteMsg::AddValidator {
+            ..
+        } => "add_validator",
+        ExecuteMsg::RemoveValidator {
+            ..
+        } => "remove_validator",
+        ExecuteMsg::SetValidatorPayout {
+            ..
+        } => "set_validator_payout",
+        ExecuteMsg::SetWithdrawTemplate {
+            ..
+        } => "set_withdraw_template",
+        ExecuteMsg::TransferOwnership {
+            ..
+        } => "transfer_ownership",
+        ExecuteMsg::AcceptOwnership {} => "accept_ownership",
+        ExecuteMsg::DropOwnershipProposal {} => "drop_ownership_proposal",
+        ExecuteMsg::AcceptOperator {} => "accept_operator",
+        ExecuteMsg::AcceptVoteOperator {} => "accept_vote_operator",
+        ExecuteMsg::RenounceOwnership {
+            ..
+        } => "renounce_ownership",
+        ExecuteMsg::Harvest {
+            ..
+        } => "harvest",
+        ExecuteMsg::Reinvest {
+            ..
+        } => "reinvest",
+        ExecuteMsg::TuneDelegations {} => "tune_delegations",
+        ExecuteMsg::Rebalance {
+            ..
+        } => "rebalance",
+        ExecuteMsg::Reconcile {
+            ..
+        } => "reconcile",
+        ExecuteMsg::SyncSupply {} => "sync_supply",
+        ExecuteMsg::SubmitBatch {} => "submit_batch",
+        ExecuteMsg::Vote {
+            ..
+        } => "vote",
+        ExecuteMsg::VoteWeighted {
+            ..
+        } => "vote_weighted",
+        ExecuteMsg::Callback(_) => "callback",
+        ExecuteMsg::UpdateConfig {
+            ..
+        } => "update_config",
+        ExecuteMsg::ClearCircuitBreaker {} => "clear_circuit_breaker",
+        ExecuteMsg::ImportConfig {
+            ..
+        } => "import_config",
+        ExecuteMsg::SetStateExport {
+            ..
+        } => "set_state_export",
+        ExecuteMsg::ImportState {
+            ..
+        } => "import_state",
+        ExecuteMsg::CompleteImport {} => "complete_import",
+        ExecuteMsg::QueueUnbond {
+            ..
+        } => "queue_unbond",
+    }
+}
+
+fn hub_config() -> HubConfig {
+    HubConfig {
+        fin_multi_contract: "fin_multi".to_string(),
+        operator: "operator".to_string(),
+        vote_operator: None,
+        guardian: None,
+        epoch_period: 259200,
+        unbond_period: 1814400,
+        validators: vec!["alice".to_string(), "bob".to_string()],
+        protocol_fee_contract: "fee".to_string(),
+        protocol_reward_fee: Decimal::percent(1),
+        stages_preset: vec![vec![(Addr::unchecked("fin1"), "test".into())]],
+        swap_allowlist: vec![],
+        swap_max_spread: Decimal::percent(5),
+        allow_donations: false,
+        delegation_strategy: DelegationStrategy::Uniform,
+        exchange_rate_tolerance: None,
+        max_reinvest_per_tx: None,
+        unbond_allowlist: vec![],
+        unbond_fee: None,
+        min_hold_seconds: 0,
+        reward_smoothing_period: 0,
+        validator_fee_share: None,
+        max_harvest_staleness: 0,
+        protocol_fee_schedule: vec![],
+        action_cooldowns: vec![],
+        express_epoch: None,
+        rebalance_tolerance: Decimal::zero(),
+    }
+}
+
+pub(super) fn callback_msg_samples() -> Vec<CallbackMsg> {
+    vec![
+        CallbackMsg::ClaimFunds {
+            withdrawals: Some(vec![(WithdrawType::Bow, Addr::unchecked("bow"), "lp".into())]),
+            withdraw_limits: None,
+        },
+        CallbackMsg::Swap {
+            sender: Addr::unchecked("operator"),
+            stages: Some(vec![vec![(Addr::unchecked("fin1"), "test".into())]]),
+            min_received: None,
+        },
+        CallbackMsg::AssertMinimumReceived {
+            snapshot: vec![coin(100, "utoken")],
+            min_received: vec![coin(10, "utoken")],
+        },
+        CallbackMsg::Reinvest {},
+        CallbackMsg::CheckReceivedCoin {
+            snapshot: coin(100, "utoken"),
+            snapshot_stake: coin(100, "ustake"),
+        },
+    ]
+}
+
+/// One sample per `ExecuteMsg` variant
+pub(super) fn execute_msg_samples() -> Vec<ExecuteMsg> {
+    let mut samples = vec![
+        ExecuteMsg::Bond {
+            receiver: Some("user_1".to_string()),
+            min_ustake_received: Some(Uint128::new(100)),
+        },
+        ExecuteMsg::Donate {},
+        ExecuteMsg::WithdrawUnbonded {
+            receiver: None,
+            receiver_msg: None,
+        },
+        ExecuteMsg::TransferUnbondRequest {
+            id: 1,
+            recipient: "user_2".to_string(),
+            shares: None,
+        },
+        ExecuteMsg::SendUnbondRequest {
+            id: 1,
+            contract: "market".to_string(),
+            shares: Some(Uint128::new(100)),
+            msg: Binary::default(),
+        },
+        ExecuteMsg::AddValidator {
+            validator: "dave".to_string(),
+        },
+        ExecuteMsg::RemoveValidator {
+            validator: "dave".to_string(),
+            mode: Some(RemoveValidatorMode::Undelegate),
+        },
+        ExecuteMsg::SetValidatorPayout {
+            validator: "alice".to_string(),
+            payout: Some("alice_payout".to_string()),
+        },
+        ExecuteMsg::SetWithdrawTemplate {
+            name: "unwrap".to_string(),
+            template: Some(WithdrawTemplate {
+                contract: "wrapper".to_string(),
+                msg: r#"{"unwrap":{"amount":"{amount}"}}"#.to_string(),
+                send_funds: true,
+            }),
+        },
+        ExecuteMsg::TransferOwnership {
+            new_owner: "jake".to_string(),
+        },
+        ExecuteMsg::AcceptOwnership {},
+        ExecuteMsg::DropOwnershipProposal {},
+        ExecuteMsg::AcceptOperator {},
+        ExecuteMsg::AcceptVoteOperator {},
+        ExecuteMsg::RenounceOwnership {
+            confirmation: "renounce ownership".to_string(),
+        },
+        ExecuteMsg::Harvest {
+            withdrawals: Some(vec![(
+                WithdrawType::BlackWhale,
+                Addr::unchecked("bw"),
+                "bw_lp".into(),
+            )]),
+            stages: None,
+            min_received: None,
+            withdraw_limits: None,
+        },
+        ExecuteMsg::Reinvest {
+            denoms: Some(vec!["test".to_string()]),
+        },
+        ExecuteMsg::TuneDelegations {},
+        ExecuteMsg::Rebalance {
+            min_redelegation: Some(Uint128::new(1000)),
+        },
+        ExecuteMsg::Reconcile {
+            limit: Some(5),
+        },
+        ExecuteMsg::SyncSupply {},
+        ExecuteMsg::SubmitBatch {},
+        ExecuteMsg::Vote {
+            proposal_id: 1,
+            vote: VoteOption::Yes,
+        },
+        ExecuteMsg::VoteWeighted {
+            proposal_id: 1,
+            votes: vec![
+                (Decimal::percent(60), VoteOption::Yes),
+                (Decimal::percent(40), VoteOption::No),
+            ],
+        },
+        ExecuteMsg::UpdateConfig {
+            protocol_fee_contract: None,
+            protocol_reward_fee: Some(Decimal::percent(2)),
+            operator: None,
+            stages_preset: None,
+            swap_allowlist: None,
+            swap_max_spread: None,
+            allow_donations: Some(true),
+            delegation_strategy: None,
+            vote_operator: None,
+            guardian: None,
+            exchange_rate_tolerance: None,
+            max_reinvest_per_tx: None,
+            unbond_allowlist: None,
+            unbond_fee: None,
+            min_hold_seconds: None,
+            reward_smoothing_period: None,
+            validator_fee_share: None,
+            max_harvest_staleness: None,
+            protocol_fee_schedule: None,
+            action_cooldowns: None,
+            express_epoch: None,
+            rebalance_tolerance: None,
+        },
+        ExecuteMsg::ClearCircuitBreaker {},
+        ExecuteMsg::ImportConfig {
+            config: hub_config(),
+        },
+        ExecuteMsg::SetStateExport {
+            enabled: true,
+        },
+        ExecuteMsg::ImportState {
+            export: StateExport::PreviousBatches(vec![]),
+        },
+        ExecuteMsg::CompleteImport {},
+        ExecuteMsg::QueueUnbond {
+            receiver: None,
+        },
+    ];
+
+    samples.extend(callback_msg_samples().into_iter().map(ExecuteMsg::Callback));
+    samples
+}
+
+/// Top-level keys of the `oneOf` variants of an enum schema
+fn schema_variant_names(schema: &RootSchema) -> BTreeSet<String> {
+    schema
+        .schema
+        .subschemas
+        .as_ref()
+        .and_then(|subschemas| subschemas.one_of.as_ref())
+        .expect("enum schema")
+        .iter()
+        .filter_map(|variant| match variant {
+            Schema::Object(object) => object.object.as_ref().map(|o| o.required.clone()),
+            Schema::Bool(_) => None,
+        })
+        .flatten()
+        .collect()
+}
+
+//--------------------------------------------------------------------------------------------------
+// Test cases
+//--------------------------------------------------------------------------------------------------
+
+#[test]
+fn execute_msgs_use_snake_case_names_and_round_trip() -> StdResult<()> {
+    for msg in execute_msg_samples() {
+        let json = String::from_utf8(to_vec(&msg)?).unwrap();
+        let name = execute_msg_name(&msg);
+        assert!(json.starts_with(&format!("{{\"{}\":", name)), "unexpected wire format: {}", json);
+
+        let parsed: ExecuteMsg = from_slice(json.as_bytes())?;
+        assert_eq!(parsed, msg);
+    }
+
+    Ok(())
+}
+
+#[test]
+fn execute_msg_samples_cover_all_variants() {
+    let names: HashSet<&str> = execute_msg_samples().iter().map(execute_msg_name).collect();
+    assert_eq!(names.len(), 32);
+}
+
+#[test]
+fn callbacks_are_only_dispatched_for_the_contract() {
+    let mut deps = mock_dependencies();
+
+    for callback in callback_msg_samples() {
+        let err = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("jake", &[]),
+            ExecuteMsg::Callback(callback),
+        )
+        .unwrap_err();
+        assert_eq!(err, ContractError::CallbackOnlyCalledByContract {});
+    }
+}
+
+#[test]
+fn generated_schema_matches_messages() -> StdResult<()> {
+    let api = generate_api! {
+        instantiate: InstantiateMsg,
+        query: QueryMsg,
+        execute: ExecuteMsg,
+        migrate: MigrateMsg
+    };
+
+    let execute_names = schema_variant_names(&api.execute.expect("execute schema"));
+    let sample_names: BTreeSet<String> =
+        execute_msg_samples().iter().map(|msg| execute_msg_name(msg).to_string()).collect();
+    assert_eq!(execute_names, sample_names);
+
+    // every sample serializes to a variant of the schema
+    for msg in execute_msg_samples() {
+        let json = String::from_utf8(to_vec(&msg)?).unwrap();
+        assert!(execute_names.iter().any(|name| json.starts_with(&format!("{{\"{}\":", name))));
+    }
+
+    // every query has a response schema
+    let query_names = schema_variant_names(&api.query.expect("query schema"));
+    let responses = api.responses.expect("response schemas");
+    for name in query_names.iter() {
+        assert!(responses.contains_key(name), "missing response schema for {}", name);
+    }
+    // `previous_batch` is a tuple variant
+    assert!(responses.contains_key("previous_batch"));
+
+    Ok(())
+}
+
+/// The checked-in schemas are the ones of `cargo schema`, split per message and response by
+/// `scripts/transform.ts`
+#[test]
+fn checked_in_schemas_are_up_to_date() {
+    let api = generate_api! {
+        instantiate: InstantiateMsg,
+        query: QueryMsg,
+        execute: ExecuteMsg,
+        migrate: MigrateMsg
+    };
+
+    let prefix = api.contract_name.replace('-', "_");
+    // rendered like `cargo schema` writes it, which names the messages after their role
+    let rendered: Value = serde_json::from_str(&api.render().to_string().unwrap()).unwrap();
+    let mut schemas = vec![];
+    for name in ["instantiate", "execute", "query", "migrate"] {
+        if let Some(schema) = rendered.get(name).filter(|schema| !schema.is_null()) {
+            schemas.push((name.to_string(), schema.clone()));
+        }
+    }
+    if let Some(Value::Object(responses)) = rendered.get("responses") {
+        schemas.extend(responses.clone());
+    }
+
+    let dir = Path::new(env!("CARGO_MANIFEST_DIR")).join("schema");
+    let mut expected_files = BTreeSet::new();
+    for (name, schema) in schemas {
+        let file = format!("{}_{}.json", prefix, name);
+        let checked_in = fs::read_to_string(dir.join(&file))
+            .unwrap_or_else(|_| panic!("{} is missing, run `yarn schema` in scripts", file));
+        let checked_in: RootSchema = serde_json::from_str(&checked_in).unwrap();
+        let schema: RootSchema = serde_json::from_value(schema).unwrap();
+        assert_eq!(checked_in, schema, "{} is outdated, run `yarn schema` in scripts", file);
+        expected_files.insert(file);
+    }
+
+    // schemas of removed messages and queries are deleted as well
+    let files: BTreeSet<String> = fs::read_dir(&dir)
+        .unwrap()
+        .map(|entry| entry.unwrap())
+        .filter(|entry| entry.file_type().unwrap().is_file())
+        .map(|entry| entry.file_name().into_string().unwrap())
+        .collect();
+    assert_eq!(files, expected_files);
+}
+
+#[test]
+fn past_execute_msgs_still_deserialize() -> StdResult<()> {
+    let bond: ExecuteMsg = from_slice(include_bytes!("fixtures/bond.json"))?;
+    assert_eq!(
+        bond,
+        ExecuteMsg::Bond {
+            receiver: Some("kujira1cyyzpxplxdzkeea7kwsydadg87357qnalx9dqz".to_string()),
+            min_ustake_received: None,
+        }
+    );
+
+    let bw = "kujira14hj2tavq8fpesdwxxcu44rty3hh90vhujrvcmstl4zr3txmfvw9sl4e867";
+    let bow = "kujira1suhgf5svhu4usrurvxzlgn54ksxmn8gljarjtxqnapv8kjnp4nrsqq4jjh";
+    let fin = "kujira1aakfpghcanxtc45gpqlx8j3rq0zcpyf49qmhm9mdjrfx036h4z5sfmexun";
+    let harvest: ExecuteMsg = from_slice(include_bytes!("fixtures/harvest_with_withdrawals.json"))?;
+    assert_eq!(
+        harvest,
+        ExecuteMsg::Harvest {
+            withdrawals: Some(vec![
+                (
+                    WithdrawType::BlackWhale,
+                    Addr::unchecked(bw),
+                    format!("factory/{}/ulp", bw).into()
+                ),
+                (WithdrawType::Bow, Addr::unchecked(bow), format!("factory/{}/ulp", bow).into()),
+            ]),
+            stages: Some(vec![vec![(
+                Addr::unchecked(fin),
+                "ibc/295548A78785A1007F232DE286149A6FF512F180AF5657780FC89C009E2C348F".into(),
+            )]]),
+            min_received: None,
+            withdraw_limits: None,
+        }
+    );
+
+    let queue_unbond: ExecuteMsg = from_slice(include_bytes!("fixtures/queue_unbond.json"))?;
+    assert_eq!(
+        queue_unbond,
+        ExecuteMsg::QueueUnbond {
+            receiver: None,
+        }
+    );
+
+    let withdraw: ExecuteMsg = from_slice(include_bytes!("fixtures/withdraw_unbonded.json"))?;
+    assert_eq!(
+        withdraw,
+        ExecuteMsg::WithdrawUnbonded {
+            receiver: Some("kujira1cyyzpxplxdzkeea7kwsydadg87357qnalx9dqz".to_string()),
+            receiver_msg: None,
+        }
+    );
+
+    Ok(())
+}
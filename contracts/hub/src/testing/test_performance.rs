@@ -0,0 +1,142 @@
+use std::str::FromStr;
+
+use cosmwasm_std::testing::{mock_info, MockApi, MockStorage};
+use cosmwasm_std::{coin, Coin, Decimal, OwnedDeps, StdError, SubMsg};
+
+use eris::governance_helper::{EPOCH_START, WEEK};
+use eris::hub::{
+    ConfigResponse, DelegationStrategy, ExecuteMsg, InstantiateMsg, QueryMsg, ValidatorPerformance,
+};
+
+use crate::constants::CONTRACT_DENOM;
+use crate::contract::{execute, instantiate};
+use crate::error::ContractResult;
+use crate::state::State;
+use crate::types::Delegation;
+
+use super::custom_querier::CustomQuerier;
+use super::helpers::{mock_dependencies, mock_env_at_timestamp, query_helper};
+
+//--------------------------------------------------------------------------------------------------
+// Test setup
+//--------------------------------------------------------------------------------------------------
+
+fn setup_test() -> OwnedDeps<MockStorage, MockApi, CustomQuerier> {
+    let mut deps = mock_dependencies();
+
+    instantiate(
+        deps.as_mut(),
+        mock_env_at_timestamp(EPOCH_START),
+        mock_info("deployer", &[]),
+        InstantiateMsg {
+            fin_multi_contract: "fin_multi".to_string(),
+            owner: "owner".to_string(),
+            denom: "stake".to_string(),
+            epoch_period: 259200,   // 3 * 24 * 60 * 60 = 3 days
+            unbond_period: 1814400, // 21 * 24 * 60 * 60 = 21 days
+            validators: vec!["alice".to_string(), "bob".to_string(), "charlie".to_string()],
+            protocol_fee_contract: "fee".to_string(),
+            protocol_reward_fee: Decimal::from_ratio(1u128, 100u128),
+            operator: "operator".to_string(),
+            stages_preset: None,
+            delegation_strategy: Some(DelegationStrategy::Performance),
+            vote_operator: None,
+            bootstrap: None,
+            max_query_limit: None,
+        },
+    )
+    .unwrap();
+
+    deps
+}
+
+fn tune_delegations(deps: &mut OwnedDeps<MockStorage, MockApi, CustomQuerier>) -> ContractResult {
+    execute(
+        deps.as_mut(),
+        mock_env_at_timestamp(EPOCH_START + WEEK),
+        mock_info("owner", &[]),
+        ExecuteMsg::TuneDelegations {},
+    )
+}
+
+fn goal_shares(deps: &OwnedDeps<MockStorage, MockApi, CustomQuerier>) -> Vec<(String, Decimal)> {
+    State::default().delegation_goal.load(deps.as_ref().storage).unwrap().shares
+}
+
+//--------------------------------------------------------------------------------------------------
+// Execution
+//--------------------------------------------------------------------------------------------------
+
+#[test]
+fn tuning_weights_validators_by_performance() {
+    let mut deps = setup_test();
+    let state = State::default();
+
+    let res: ConfigResponse = query_helper(deps.as_ref(), QueryMsg::Config {});
+    assert_eq!(res.delegation_strategy, DelegationStrategy::Performance);
+
+    // bob keeps half of the rewards, charlie is out of the active set
+    deps.querier.set_validator_commission("bob", Decimal::percent(50));
+    deps.querier.set_jailed_validators(&["charlie"]);
+
+    // alice: 0.95, bob: 0.5, charlie: 0
+    tune_delegations(&mut deps).unwrap();
+    assert_eq!(
+        goal_shares(&deps),
+        vec![
+            ("alice".to_string(), Decimal::from_str("0.655172413793103448").unwrap()),
+            ("bob".to_string(), Decimal::from_str("0.344827586206896551").unwrap()),
+            ("charlie".to_string(), Decimal::zero()),
+        ]
+    );
+
+    // validators without a share of the goal receive no new delegations, even if smaller
+    deps.querier.set_jailed_validators(&[]);
+    deps.querier.set_staking_delegations(&[
+        Delegation::new("alice", 100),
+        Delegation::new("bob", 100),
+        Delegation::new("charlie", 0),
+    ]);
+    deps.querier.set_bank_balances(&[coin(1000000, CONTRACT_DENOM)]);
+    let res = execute(
+        deps.as_mut(),
+        mock_env_at_timestamp(EPOCH_START + WEEK),
+        mock_info("user_1", &[Coin::new(1000000, CONTRACT_DENOM)]),
+        ExecuteMsg::Bond {
+            receiver: None,
+            min_ustake_received: None,
+        },
+    )
+    .unwrap();
+    assert_eq!(res.messages[0], SubMsg::new(Delegation::new("alice", 1000000).to_cosmos_msg()));
+
+    // charlie is back in the active set for one of two observations
+    // alice: 0.95, bob: 0.5, charlie: 0.475
+    tune_delegations(&mut deps).unwrap();
+    assert_eq!(
+        state.validator_performance.load(deps.as_ref().storage, "charlie").unwrap(),
+        ValidatorPerformance {
+            samples: 2,
+            active_samples: 1,
+            yield_sum: Decimal::percent(95),
+        }
+    );
+    assert_eq!(
+        goal_shares(&deps),
+        vec![
+            ("alice".to_string(), Decimal::from_str("0.493506493506493506").unwrap()),
+            ("bob".to_string(), Decimal::from_str("0.25974025974025974").unwrap()),
+            ("charlie".to_string(), Decimal::from_str("0.246753246753246753").unwrap()),
+        ]
+    );
+}
+
+#[test]
+fn tuning_without_active_validators() {
+    let mut deps = setup_test();
+
+    deps.querier.set_jailed_validators(&["alice", "bob", "charlie"]);
+
+    let err = tune_delegations(&mut deps).unwrap_err();
+    assert_eq!(err, StdError::generic_err("no validator has a performance score yet").into());
+}
@@ -0,0 +1,188 @@
+use cosmwasm_std::testing::{mock_env, mock_info, MockApi, MockStorage};
+use cosmwasm_std::{coin, Coin, Decimal, Event, OwnedDeps, StdError, SubMsg};
+
+use eris::governance_helper::{EPOCH_START, WEEK};
+use eris::hub::{ConfigResponse, DelegationStrategy, ExecuteMsg, InstantiateMsg, QueryMsg};
+
+use crate::constants::CONTRACT_DENOM;
+use crate::contract::{execute, instantiate};
+use crate::state::State;
+use crate::types::Delegation;
+
+use super::custom_querier::CustomQuerier;
+use super::helpers::{mock_dependencies, mock_env_at_timestamp, query_helper};
+
+//--------------------------------------------------------------------------------------------------
+// Test setup
+//--------------------------------------------------------------------------------------------------
+
+fn setup_test() -> OwnedDeps<MockStorage, MockApi, CustomQuerier> {
+    let mut deps = mock_dependencies();
+
+    instantiate(
+        deps.as_mut(),
+        mock_env_at_timestamp(EPOCH_START),
+        mock_info("deployer", &[]),
+        InstantiateMsg {
+            fin_multi_contract: "fin_multi".to_string(),
+            owner: "owner".to_string(),
+            denom: "stake".to_string(),
+            epoch_period: 259200,   // 3 * 24 * 60 * 60 = 3 days
+            unbond_period: 1814400, // 21 * 24 * 60 * 60 = 21 days
+            validators: vec!["alice".to_string(), "bob".to_string(), "charlie".to_string()],
+            protocol_fee_contract: "fee".to_string(),
+            protocol_reward_fee: Decimal::from_ratio(1u128, 100u128),
+            operator: "operator".to_string(),
+            stages_preset: None,
+            delegation_strategy: Some(DelegationStrategy::Registry {
+                registry: "registry".into(),
+                fallback: vec!["alice".into()],
+            }),
+            vote_operator: None,
+            bootstrap: None,
+            max_query_limit: None,
+        },
+    )
+    .unwrap();
+
+    deps
+}
+
+fn tune_delegations(deps: &mut OwnedDeps<MockStorage, MockApi, CustomQuerier>) -> Vec<Event> {
+    execute(
+        deps.as_mut(),
+        mock_env_at_timestamp(EPOCH_START + WEEK),
+        mock_info("owner", &[]),
+        ExecuteMsg::TuneDelegations {},
+    )
+    .unwrap()
+    .events
+}
+
+//--------------------------------------------------------------------------------------------------
+// Execution
+//--------------------------------------------------------------------------------------------------
+
+#[test]
+fn validate_update() {
+    let mut deps = setup_test();
+
+    let update_strategy = |fallback: Vec<&str>| ExecuteMsg::UpdateConfig {
+        protocol_fee_contract: None,
+        protocol_reward_fee: None,
+        operator: None,
+        stages_preset: None,
+        allow_donations: None,
+        delegation_strategy: Some(DelegationStrategy::Registry {
+            registry: "registry".into(),
+            fallback: fallback.into_iter().map(String::from).collect(),
+        }),
+        vote_operator: None,
+        guardian: None,
+        exchange_rate_tolerance: None,
+        swap_allowlist: None,
+        swap_max_spread: None,
+        max_reinvest_per_tx: None,
+        unbond_allowlist: None,
+        unbond_fee: None,
+        min_hold_seconds: None,
+        reward_smoothing_period: None,
+        validator_fee_share: None,
+        max_harvest_staleness: None,
+        protocol_fee_schedule: None,
+        action_cooldowns: None,
+        express_epoch: None,
+        rebalance_tolerance: None,
+    };
+
+    let err = execute(deps.as_mut(), mock_env(), mock_info("owner", &[]), update_strategy(vec![]))
+        .unwrap_err();
+    assert_eq!(err, StdError::generic_err("fallback validators can't be empty").into());
+
+    let err = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("owner", &[]),
+        update_strategy(vec!["alice", "alice"]),
+    )
+    .unwrap_err();
+    assert_eq!(err, StdError::generic_err("validator alice duplicated").into());
+
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("owner", &[]),
+        update_strategy(vec!["alice", "dave"]),
+    )
+    .unwrap();
+
+    let res: ConfigResponse = query_helper(deps.as_ref(), QueryMsg::Config {});
+    assert_eq!(
+        res.delegation_strategy,
+        DelegationStrategy::Registry {
+            registry: "registry".into(),
+            fallback: vec!["alice".into(), "dave".into()],
+        }
+    );
+}
+
+#[test]
+fn tuning_takes_validators_from_registry() {
+    let mut deps = setup_test();
+    let state = State::default();
+
+    deps.querier.set_validator_registry("registry", &["bob", "dave", "dave"]);
+
+    let events = tune_delegations(&mut deps);
+    assert_eq!(
+        events,
+        vec![
+            Event::new("erishub/validator_added").add_attribute("validator", "dave"),
+            Event::new("erishub/validator_removed").add_attribute("validator", "alice"),
+            Event::new("erishub/validator_removed").add_attribute("validator", "charlie"),
+        ]
+    );
+
+    let res: ConfigResponse = query_helper(deps.as_ref(), QueryMsg::Config {});
+    assert_eq!(res.validators, vec!["bob".to_string(), "dave".to_string()]);
+
+    // the new validator ramps up, the delegations are uniform otherwise
+    assert_eq!(state.ramping_validators.load(deps.as_ref().storage, "dave").unwrap(), 1);
+    assert!(state.delegation_goal.may_load(deps.as_ref().storage).unwrap().is_none());
+
+    // tuning again with the same registry changes nothing
+    let events = tune_delegations(&mut deps);
+    assert!(events.is_empty());
+}
+
+#[test]
+fn tuning_falls_back_to_pinned_validators() {
+    let mut deps = setup_test();
+
+    // the registry can't be queried
+    let events = tune_delegations(&mut deps);
+    assert_eq!(
+        events,
+        vec![
+            Event::new("erishub/validator_removed").add_attribute("validator", "bob"),
+            Event::new("erishub/validator_removed").add_attribute("validator", "charlie"),
+        ]
+    );
+
+    let res: ConfigResponse = query_helper(deps.as_ref(), QueryMsg::Config {});
+    assert_eq!(res.validators, vec!["alice".to_string()]);
+
+    // bonding only delegates to the fallback validators
+    deps.querier.set_bank_balances(&[coin(1000000, CONTRACT_DENOM)]);
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("user_1", &[Coin::new(1000000, CONTRACT_DENOM)]),
+        ExecuteMsg::Bond {
+            receiver: None,
+            min_ustake_received: None,
+        },
+    )
+    .unwrap();
+    assert_eq!(res.messages[0], SubMsg::new(Delegation::new("alice", 1000000).to_cosmos_msg()));
+}
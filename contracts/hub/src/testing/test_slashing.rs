@@ -0,0 +1,250 @@
+use cosmwasm_std::testing::{mock_info, MockApi, MockStorage, MOCK_CONTRACT_ADDR};
+use cosmwasm_std::{
+    coin, Addr, BankMsg, Coin, CosmosMsg, Decimal, Event, OwnedDeps, SubMsg, Uint128,
+};
+use kujira::msg::{DenomMsg, KujiraMsg};
+
+use eris::hub::{Batch, ExecuteMsg, InstantiateMsg, QueryMsg, StateResponse};
+
+use crate::constants::CONTRACT_DENOM;
+use crate::contract::{execute, instantiate};
+use crate::error::ContractResult;
+use crate::state::State;
+use crate::testing::helpers::{query_helper_env, set_total_stake_supply};
+use crate::types::{Delegation, Undelegation};
+
+use super::custom_querier::CustomQuerier;
+use super::helpers::{mock_dependencies, mock_env_at_timestamp};
+
+//--------------------------------------------------------------------------------------------------
+// Test setup
+//--------------------------------------------------------------------------------------------------
+
+pub const STAKE_DENOM: &str = "factory/cosmos2contract/stake";
+
+fn setup_test() -> OwnedDeps<MockStorage, MockApi, CustomQuerier> {
+    let mut deps = mock_dependencies();
+
+    instantiate(
+        deps.as_mut(),
+        mock_env_at_timestamp(10000),
+        mock_info("deployer", &[]),
+        InstantiateMsg {
+            fin_multi_contract: "fin_multi".to_string(),
+            owner: "owner".to_string(),
+            denom: "stake".to_string(),
+            epoch_period: 259200,   // 3 * 24 * 60 * 60 = 3 days
+            unbond_period: 1814400, // 21 * 24 * 60 * 60 = 21 days
+            validators: vec!["alice".to_string(), "bob".to_string(), "charlie".to_string()],
+            protocol_fee_contract: "fee".to_string(),
+            protocol_reward_fee: Decimal::from_ratio(1u128, 100u128),
+            operator: "operator".to_string(),
+            stages_preset: None,
+            delegation_strategy: None,
+            vote_operator: None,
+            bootstrap: None,
+            max_query_limit: None,
+        },
+    )
+    .unwrap();
+
+    // 1 Token per Stake
+    deps.querier.set_staking_delegations(&[
+        Delegation::new("alice", 1000000),
+        Delegation::new("bob", 1000000),
+        Delegation::new("charlie", 1000000),
+    ]);
+    set_total_stake_supply(&State::default(), &mut deps, 3000000);
+
+    deps
+}
+
+fn queue_unbond(
+    deps: &mut OwnedDeps<MockStorage, MockApi, CustomQuerier>,
+    timestamp: u64,
+    user: &str,
+    ustake: u128,
+) -> ContractResult {
+    execute(
+        deps.as_mut(),
+        mock_env_at_timestamp(timestamp),
+        mock_info(user, &[Coin::new(ustake, STAKE_DENOM)]),
+        ExecuteMsg::QueueUnbond {
+            receiver: None,
+        },
+    )
+}
+
+fn withdraw_unbonded(
+    deps: &mut OwnedDeps<MockStorage, MockApi, CustomQuerier>,
+    timestamp: u64,
+    user: &str,
+) -> ContractResult {
+    execute(
+        deps.as_mut(),
+        mock_env_at_timestamp(timestamp),
+        mock_info(user, &[]),
+        ExecuteMsg::WithdrawUnbonded {
+            receiver: None,
+            receiver_msg: None,
+        },
+    )
+}
+
+//--------------------------------------------------------------------------------------------------
+// Execution
+//--------------------------------------------------------------------------------------------------
+
+#[test]
+fn reconciling_a_slash_during_unbonding() {
+    // The mock querier doesn't execute the returned messages, so the slash and the funds of the
+    // unbonded batch are applied to the mocked delegations and balances by hand
+    let mut deps = setup_test();
+    let state = State::default();
+
+    // Users 1 and 2 unbond 300,000 and 600,000 ustake, the second request submits the batch
+    queue_unbond(&mut deps, 12345, "user_1", 300000).unwrap();
+    let res = queue_unbond(&mut deps, 269201, "user_2", 600000).unwrap();
+    assert_eq!(res.messages.len(), 1);
+
+    // utoken to unbond: 3,000,000 * 900,000 / 3,000,000 = 900,000
+    // Target: (3,000,000 - 900,000) / 3 = 700,000 per validator
+    let res = execute(
+        deps.as_mut(),
+        mock_env_at_timestamp(269201),
+        mock_info(MOCK_CONTRACT_ADDR, &[]),
+        ExecuteMsg::SubmitBatch {},
+    )
+    .unwrap();
+    assert_eq!(res.messages[0], SubMsg::new(Undelegation::new("alice", 300000).to_cosmos_msg()));
+    assert_eq!(res.messages[1], SubMsg::new(Undelegation::new("bob", 300000).to_cosmos_msg()));
+    assert_eq!(res.messages[2], SubMsg::new(Undelegation::new("charlie", 300000).to_cosmos_msg()));
+    assert_eq!(
+        res.messages[3],
+        SubMsg::new(CosmosMsg::Custom(KujiraMsg::Denom(DenomMsg::Burn {
+            denom: STAKE_DENOM.into(),
+            amount: Uint128::new(900000)
+        })))
+    );
+
+    let submitted_batch = Batch {
+        id: 1,
+        reconciled: false,
+        total_shares: Uint128::new(900000),
+        utoken_unclaimed: Uint128::new(900000),
+        est_unbond_end_time: 2083601, // 269,201 + 1,814,400
+        exchange_rate_submitted: Some(Decimal::one()),
+        exchange_rate_reconciled: None,
+    };
+    assert_eq!(state.previous_batches.load(deps.as_ref().storage, 1u64).unwrap(), submitted_batch);
+
+    // Alice is slashed by 10% while the batch is unbonding. The slash hits both her bonded amount
+    // (700,000 -> 630,000) and her unbonding entry (300,000 -> 270,000)
+    deps.querier.set_staking_delegations(&[
+        Delegation::new("alice", 630000),
+        Delegation::new("bob", 700000),
+        Delegation::new("charlie", 700000),
+    ]);
+
+    // The remaining stakers take the bonded part of the slash right away, the hub cannot observe
+    // the unbonding part until the batch is reconciled
+    let res: StateResponse = query_helper_env(deps.as_ref(), QueryMsg::State {}, 269202);
+    assert_eq!(res.total_ustake, Uint128::new(2100000));
+    assert_eq!(res.total_utoken, Uint128::new(2030000));
+    assert_eq!(res.exchange_rate, Decimal::from_ratio(2030000u128, 2100000u128));
+    assert_eq!(res.unbonding, Uint128::new(900000));
+
+    // New deposits are priced at the slashed exchange rate
+    // ustake to mint: 203,000 * 2,100,000 / 2,030,000 = 210,000
+    deps.querier.set_bank_balances(&[coin(203000, CONTRACT_DENOM)]);
+    let res = execute(
+        deps.as_mut(),
+        mock_env_at_timestamp(269202),
+        mock_info("user_3", &[Coin::new(203000, CONTRACT_DENOM)]),
+        ExecuteMsg::Bond {
+            receiver: None,
+            min_ustake_received: None,
+        },
+    )
+    .unwrap();
+    assert_eq!(res.messages[0], SubMsg::new(Delegation::new("alice", 203000).to_cosmos_msg()));
+    assert_eq!(
+        res.messages[1],
+        SubMsg::new(CosmosMsg::Custom(KujiraMsg::Denom(DenomMsg::Mint {
+            denom: STAKE_DENOM.into(),
+            amount: Uint128::new(210000),
+            recipient: Addr::unchecked("user_3")
+        })))
+    );
+
+    // Nothing can be withdrawn before the batch is reconciled
+    deps.querier.set_bank_balances(&[coin(870000, CONTRACT_DENOM)]);
+    let res = execute(
+        deps.as_mut(),
+        mock_env_at_timestamp(2083601),
+        mock_info("worker", &[]),
+        ExecuteMsg::Reconcile {
+            limit: None,
+        },
+    )
+    .unwrap();
+    assert!(res.events.is_empty());
+    assert!(withdraw_unbonded(&mut deps, 2083602, "user_1").is_err());
+
+    // Expected: 900,000
+    // Actual: 870,000
+    // Shortfall: 30,000, deducted from the only matured batch
+    let res = execute(
+        deps.as_mut(),
+        mock_env_at_timestamp(2083602),
+        mock_info("worker", &[]),
+        ExecuteMsg::Reconcile {
+            limit: None,
+        },
+    )
+    .unwrap();
+    assert_eq!(
+        res.events,
+        vec![Event::new("erishub/reconciled")
+            .add_attribute("ids", "1")
+            .add_attribute("utoken_deducted", "30000")
+            .add_attribute("has_more", "false")]
+    );
+    assert_eq!(
+        state.previous_batches.load(deps.as_ref().storage, 1u64).unwrap(),
+        Batch {
+            reconciled: true,
+            utoken_unclaimed: Uint128::new(870000),
+            exchange_rate_reconciled: Some(Decimal::from_ratio(870000u128, 900000u128)),
+            ..submitted_batch
+        }
+    );
+
+    // No surplus is credited to the stakers, the received funds all belong to the batch
+    assert_eq!(state.unlocked_coins.load(deps.as_ref().storage).unwrap(), vec![]);
+
+    // Unbonders share the slash pro rata
+    // user 1: 870,000 * 300,000 / 900,000 = 290,000
+    // user 2: 870,000 * 600,000 / 900,000 = 580,000
+    let res = withdraw_unbonded(&mut deps, 2083602, "user_1").unwrap();
+    assert_eq!(
+        res.messages[0],
+        SubMsg::new(BankMsg::Send {
+            to_address: "user_1".to_string(),
+            amount: vec![Coin::new(290000, CONTRACT_DENOM)]
+        })
+    );
+
+    deps.querier.set_bank_balances(&[coin(580000, CONTRACT_DENOM)]);
+    let res = withdraw_unbonded(&mut deps, 2083602, "user_2").unwrap();
+    assert_eq!(
+        res.messages[0],
+        SubMsg::new(BankMsg::Send {
+            to_address: "user_2".to_string(),
+            amount: vec![Coin::new(580000, CONTRACT_DENOM)]
+        })
+    );
+
+    // The batch is fully claimed
+    assert!(state.previous_batches.may_load(deps.as_ref().storage, 1u64).unwrap().is_none());
+}
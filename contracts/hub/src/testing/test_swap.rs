@@ -2,22 +2,24 @@ use std::vec;
 
 use cosmwasm_std::testing::{mock_env, mock_info, MockApi, MockStorage, MOCK_CONTRACT_ADDR};
 use cosmwasm_std::{
-    coin, to_binary, Addr, Coin, CosmosMsg, Decimal, DistributionMsg, OwnedDeps, StdResult, SubMsg,
-    Uint128, WasmMsg,
+    coin, to_binary, Addr, Coin, CosmosMsg, Decimal, DistributionMsg, OwnedDeps, StdError,
+    StdResult, SubMsg, Uint128, WasmMsg,
 };
 
 use eris::adapters::bow_vault::BowExecuteMsg;
 use eris::adapters::bw_vault::BlackwhaleExecuteMsg;
-use eris::adapters::fin_multi::FinMultiExecuteMsg;
+use eris::adapters::fin_multi::{FinMultiExecuteMsg, FinMultiStages};
 use eris::hub::{
-    CallbackMsg, ConfigResponse, DelegationStrategy, ExecuteMsg, FeeConfig, InstantiateMsg,
-    PendingBatch, QueryMsg, StateResponse, WithdrawType,
+    ActionCooldown, CallbackMsg, ConfigResponse, DelegationStrategy, ExecuteMsg, FeeConfig,
+    InstantiateMsg, PendingBatch, QueryMsg, RateLimitedAction, StateResponse, WithdrawLimit,
+    WithdrawTemplate, WithdrawType,
 };
+use kujira::denom::Denom;
 use kujira::msg::{DenomMsg, KujiraMsg};
 
 use crate::constants::CONTRACT_DENOM;
 use crate::contract::{execute, instantiate};
-use crate::error::ContractError;
+use crate::error::{ContractError, Role};
 use crate::testing::helpers::check_received_coin;
 use crate::types::Delegation;
 
@@ -36,6 +38,12 @@ pub const BOW_DENOM2: &str = "factory/anycontract/bow2";
 
 fn setup_test() -> OwnedDeps<MockStorage, MockApi, CustomQuerier> {
     let mut deps = mock_dependencies();
+    deps.querier.set_fin_pair(
+        "fin1",
+        ["test", CONTRACT_DENOM],
+        Decimal::from_ratio(2u128, 1u128),
+        Decimal::percent(1),
+    );
 
     let res = instantiate(
         deps.as_mut(),
@@ -54,6 +62,8 @@ fn setup_test() -> OwnedDeps<MockStorage, MockApi, CustomQuerier> {
             stages_preset: Some(vec![vec![(Addr::unchecked("fin1"), "test".into())]]),
             vote_operator: Some("vote_operator".to_string()),
             delegation_strategy: Some(DelegationStrategy::Uniform),
+            bootstrap: None,
+            max_query_limit: None,
         },
     )
     .unwrap();
@@ -81,7 +91,7 @@ fn proper_instantiation() {
     assert_eq!(
         res,
         ConfigResponse {
-            owner: "owner".to_string(),
+            owner: Some("owner".to_string()),
             new_owner: None,
             stake_token: STAKE_DENOM.to_string(),
             epoch_period: 259200,
@@ -89,13 +99,32 @@ fn proper_instantiation() {
             validators: vec!["alice".to_string(), "bob".to_string(), "charlie".to_string()],
             fee_config: FeeConfig {
                 protocol_fee_contract: Addr::unchecked("fee"),
-                protocol_reward_fee: Decimal::from_ratio(1u128, 100u128)
+                protocol_reward_fee: Decimal::from_ratio(1u128, 100u128),
+                unbond_fee: None,
+                validator_fee_share: None,
             },
             operator: "operator".to_string(),
+            new_operator: None,
             stages_preset: vec![vec![(Addr::unchecked("fin1"), "test".into())]],
             allow_donations: false,
             delegation_strategy: DelegationStrategy::Uniform,
-            vote_operator: Some("vote_operator".into())
+            vote_operator: Some("vote_operator".into()),
+            new_vote_operator: None,
+            guardian: None,
+            exchange_rate_tolerance: None,
+            circuit_breaker_tripped: false,
+            swap_allowlist: vec![],
+            swap_max_spread: Decimal::percent(5),
+            max_reinvest_per_tx: None,
+            unbond_allowlist: vec![],
+            min_hold_seconds: 0,
+            reward_smoothing_period: 0,
+            max_harvest_staleness: 0,
+            protocol_fee_schedule: vec![],
+            action_cooldowns: vec![],
+            express_epoch: None,
+            rebalance_tolerance: Decimal::zero(),
+            max_query_limit: 30,
         }
     );
 
@@ -110,6 +139,9 @@ fn proper_instantiation() {
             unbonding: Uint128::zero(),
             available: Uint128::zero(),
             tvl_utoken: Uint128::zero(),
+            ustake_escrowed: Uint128::zero(),
+            utoken_unvested: Uint128::zero(),
+            utoken_dust_recycled: Uint128::zero(),
         },
     );
 
@@ -147,6 +179,8 @@ fn harvesting_with_options() {
                 Addr::unchecked("bw1"),
                 BW_DENOM1.into(),
             )]),
+            min_received: None,
+            withdraw_limits: None,
         },
     )
     .unwrap();
@@ -181,6 +215,7 @@ fn harvesting_with_options() {
                     Addr::unchecked("bw1"),
                     BW_DENOM1.into()
                 )]),
+                withdraw_limits: None,
             }))
             .unwrap(),
             funds: vec![]
@@ -193,7 +228,8 @@ fn harvesting_with_options() {
             contract_addr: MOCK_CONTRACT_ADDR.to_string(),
             msg: to_binary(&ExecuteMsg::Callback(CallbackMsg::Swap {
                 stages: Some(vec![vec![(Addr::unchecked("fin1"), "test".into())]]),
-                sender: Addr::unchecked("worker")
+                sender: Addr::unchecked("worker"),
+                min_received: None,
             }))
             .unwrap(),
             funds: vec![]
@@ -212,6 +248,66 @@ fn harvesting_with_options() {
     );
 }
 
+#[test]
+fn reinvesting_selected_denoms() {
+    let mut deps = setup_test();
+
+    let reinvest = |deps: &mut OwnedDeps<MockStorage, MockApi, CustomQuerier>,
+                    sender: &str,
+                    denoms: Option<Vec<&str>>| {
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info(sender, &[]),
+            ExecuteMsg::Reinvest {
+                denoms: denoms.map(|denoms| denoms.into_iter().map(String::from).collect()),
+            },
+        )
+    };
+    let callback = |msg: CallbackMsg| {
+        SubMsg::new(msg.into_cosmos_msg(&Addr::unchecked(MOCK_CONTRACT_ADDR)).unwrap())
+    };
+
+    let err = reinvest(&mut deps, "worker", None).unwrap_err();
+    assert_eq!(
+        err,
+        ContractError::Unauthorized {
+            required_role: Role::Operator,
+            sender: "worker".into()
+        }
+    );
+
+    // the route of the selected denom is swapped
+    let res = reinvest(&mut deps, "operator", Some(vec!["test"])).unwrap();
+    assert_eq!(
+        res.messages,
+        vec![
+            callback(CallbackMsg::Swap {
+                stages: Some(vec![vec![(Addr::unchecked("fin1"), "test".into())]]),
+                sender: Addr::unchecked("operator"),
+                min_received: None,
+            }),
+            check_received_coin(0, 0),
+            callback(CallbackMsg::Reinvest {}),
+        ]
+    );
+
+    // only restaking utoken leaves the other denoms untouched
+    let res = reinvest(&mut deps, "operator", Some(vec![CONTRACT_DENOM])).unwrap();
+    assert_eq!(res.messages, vec![check_received_coin(0, 0), callback(CallbackMsg::Reinvest {})]);
+
+    // without denoms the whole preset is swapped
+    let res = reinvest(&mut deps, "operator", None).unwrap();
+    assert_eq!(
+        res.messages[0],
+        callback(CallbackMsg::Swap {
+            stages: None,
+            sender: Addr::unchecked("operator"),
+            min_received: None,
+        })
+    );
+}
+
 #[test]
 fn claim_funds() -> StdResult<()> {
     let mut deps = setup_test();
@@ -227,6 +323,7 @@ fn claim_funds() -> StdResult<()> {
                 Addr::unchecked("bw1"),
                 BW_DENOM1.into(),
             )]),
+            withdraw_limits: None,
         }),
     )
     .unwrap_err();
@@ -243,6 +340,7 @@ fn claim_funds() -> StdResult<()> {
                 (WithdrawType::Bow, Addr::unchecked("bow1"), BOW_DENOM1.into()),
                 (WithdrawType::Bow, Addr::unchecked("bow2"), BOW_DENOM2.into()),
             ]),
+            withdraw_limits: None,
         }),
     )
     .unwrap();
@@ -286,6 +384,299 @@ fn claim_funds() -> StdResult<()> {
     Ok(())
 }
 
+#[test]
+fn claim_funds_with_limits() -> StdResult<()> {
+    let mut deps = setup_test();
+    deps.querier.set_bank_balances(&[coin(100, BW_DENOM1), coin(101, BOW_DENOM1)]);
+
+    // at most 60 of the first vault, the second one keeps a working balance of 90
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info(MOCK_CONTRACT_ADDR, &[]),
+        ExecuteMsg::Callback(CallbackMsg::ClaimFunds {
+            withdrawals: Some(vec![
+                (WithdrawType::BlackWhale, Addr::unchecked("bw1"), BW_DENOM1.into()),
+                (WithdrawType::Bow, Addr::unchecked("bow1"), BOW_DENOM1.into()),
+            ]),
+            withdraw_limits: Some(vec![
+                WithdrawLimit {
+                    denom: BW_DENOM1.into(),
+                    amount: Some(Uint128::new(60)),
+                    leave: Some(Uint128::new(10)),
+                },
+                WithdrawLimit {
+                    denom: BOW_DENOM1.into(),
+                    amount: None,
+                    leave: Some(Uint128::new(90)),
+                },
+            ]),
+        }),
+    )
+    .unwrap();
+
+    assert_eq!(res.messages.len(), 2);
+    assert_eq!(
+        res.messages[0],
+        SubMsg::new(CosmosMsg::Wasm(WasmMsg::Execute {
+            contract_addr: "bw1".to_string(),
+            funds: vec![coin(60, BW_DENOM1)],
+            msg: to_binary(&BlackwhaleExecuteMsg::WithdrawLiquidity {
+                amount: Uint128::new(60),
+            })?,
+        }))
+    );
+    assert_eq!(
+        res.messages[1],
+        SubMsg::new(CosmosMsg::Wasm(WasmMsg::Execute {
+            contract_addr: "bow1".to_string(),
+            funds: vec![coin(11, BOW_DENOM1)],
+            msg: to_binary(&BowExecuteMsg::Withdraw {})?,
+        }))
+    );
+
+    // nothing is withdrawn when the balance is below the amount to leave
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info(MOCK_CONTRACT_ADDR, &[]),
+        ExecuteMsg::Callback(CallbackMsg::ClaimFunds {
+            withdrawals: Some(vec![(
+                WithdrawType::Bow,
+                Addr::unchecked("bow1"),
+                BOW_DENOM1.into(),
+            )]),
+            withdraw_limits: Some(vec![WithdrawLimit {
+                denom: BOW_DENOM1.into(),
+                amount: None,
+                leave: Some(Uint128::new(200)),
+            }]),
+        }),
+    )
+    .unwrap();
+    assert!(res.messages.is_empty());
+
+    Ok(())
+}
+
+#[test]
+fn claim_funds_with_template() -> StdResult<()> {
+    let mut deps = setup_test();
+    deps.querier.set_bank_balances(&[coin(100, BW_DENOM1)]);
+
+    let template = WithdrawTemplate {
+        contract: "wrapper".to_string(),
+        msg: r#"{"unwrap":{"denom":"{denom}","amount":"{amount}"}}"#.to_string(),
+        send_funds: true,
+    };
+    let set_template = ExecuteMsg::SetWithdrawTemplate {
+        name: "unwrap".to_string(),
+        template: Some(template.clone()),
+    };
+
+    let err = execute(deps.as_mut(), mock_env(), mock_info("jake", &[]), set_template.clone())
+        .unwrap_err();
+    assert_eq!(err, ContractError::unauthorized(Role::Owner, &Addr::unchecked("jake")));
+
+    execute(deps.as_mut(), mock_env(), mock_info("owner", &[]), set_template).unwrap();
+
+    let res: Vec<(String, WithdrawTemplate)> =
+        query_helper(deps.as_ref(), QueryMsg::WithdrawTemplates {});
+    assert_eq!(res, vec![("unwrap".to_string(), template)]);
+
+    let claim_funds = |name: &str, contract: &str| {
+        ExecuteMsg::Callback(CallbackMsg::ClaimFunds {
+            withdrawals: Some(vec![(
+                WithdrawType::Generic {
+                    msg_template: name.to_string(),
+                },
+                Addr::unchecked(contract),
+                BW_DENOM1.into(),
+            )]),
+            withdraw_limits: None,
+        })
+    };
+
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info(MOCK_CONTRACT_ADDR, &[]),
+        claim_funds("unwrap", "wrapper"),
+    )
+    .unwrap();
+
+    assert_eq!(res.messages.len(), 1);
+    assert_eq!(
+        res.messages[0],
+        SubMsg::new(CosmosMsg::Wasm(WasmMsg::Execute {
+            contract_addr: "wrapper".to_string(),
+            funds: vec![coin(100, BW_DENOM1)],
+            msg: format!(r#"{{"unwrap":{{"denom":"{}","amount":"100"}}}}"#, BW_DENOM1)
+                .into_bytes()
+                .into(),
+        }))
+    );
+
+    // a template only withdraws from its own contract
+    let err = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info(MOCK_CONTRACT_ADDR, &[]),
+        claim_funds("unwrap", "other"),
+    )
+    .unwrap_err();
+    assert_eq!(
+        err,
+        ContractError::WithdrawTemplateContractMismatch {
+            template: "unwrap".to_string(),
+            contract: "wrapper".to_string(),
+        }
+    );
+
+    let err = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info(MOCK_CONTRACT_ADDR, &[]),
+        claim_funds("unknown", "wrapper"),
+    )
+    .unwrap_err();
+    assert_eq!(err, ContractError::WithdrawTemplateNotFound("unknown".to_string()));
+
+    Ok(())
+}
+
+#[test]
+fn rate_limiting_operator_actions() {
+    let mut deps = setup_test();
+    deps.querier.set_bank_balances(&[coin(100, "test")]);
+
+    let update_cooldowns = |cooldowns: Vec<ActionCooldown>| ExecuteMsg::UpdateConfig {
+        protocol_fee_contract: None,
+        protocol_reward_fee: None,
+        operator: None,
+        stages_preset: None,
+        swap_allowlist: None,
+        swap_max_spread: None,
+        allow_donations: None,
+        delegation_strategy: None,
+        vote_operator: None,
+        guardian: None,
+        exchange_rate_tolerance: None,
+        max_reinvest_per_tx: None,
+        unbond_allowlist: None,
+        unbond_fee: None,
+        min_hold_seconds: None,
+        reward_smoothing_period: None,
+        validator_fee_share: None,
+        max_harvest_staleness: None,
+        protocol_fee_schedule: None,
+        action_cooldowns: Some(cooldowns),
+        express_epoch: None,
+        rebalance_tolerance: None,
+    };
+
+    let err = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("owner", &[]),
+        update_cooldowns(vec![
+            ActionCooldown {
+                action: RateLimitedAction::Reinvest,
+                seconds: 3600,
+            },
+            ActionCooldown {
+                action: RateLimitedAction::Reinvest,
+                seconds: 60,
+            },
+        ]),
+    )
+    .unwrap_err();
+    assert_eq!(err, ContractError::DuplicateActionCooldown("reinvest".to_string()));
+
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("owner", &[]),
+        update_cooldowns(vec![
+            ActionCooldown {
+                action: RateLimitedAction::Reinvest,
+                seconds: 3600,
+            },
+            ActionCooldown {
+                action: RateLimitedAction::OperatorSwap,
+                seconds: 86400,
+            },
+        ]),
+    )
+    .unwrap();
+
+    let reinvest = |deps: &mut OwnedDeps<MockStorage, MockApi, CustomQuerier>, time: u64| {
+        execute(
+            deps.as_mut(),
+            mock_env_at_timestamp(time),
+            mock_info("operator", &[]),
+            ExecuteMsg::Reinvest {
+                denoms: Some(vec!["test".to_string()]),
+            },
+        )
+    };
+
+    reinvest(&mut deps, 10000).unwrap();
+    let err = reinvest(&mut deps, 13599).unwrap_err();
+    assert_eq!(
+        err,
+        ContractError::ActionCooldown {
+            action: "reinvest".to_string(),
+            until: 13600,
+        }
+    );
+    reinvest(&mut deps, 13600).unwrap();
+
+    let harvest =
+        |deps: &mut OwnedDeps<MockStorage, MockApi, CustomQuerier>, sender: &str, time: u64| {
+            execute(
+                deps.as_mut(),
+                mock_env_at_timestamp(time),
+                mock_info(sender, &[]),
+                ExecuteMsg::Harvest {
+                    withdrawals: None,
+                    stages: Some(vec![vec![(Addr::unchecked("fin1"), "test".into())]]),
+                    min_received: None,
+                    withdraw_limits: None,
+                },
+            )
+        };
+
+    // custom stages of the operator skip the validation, so its harvests through them are rate
+    // limited
+    harvest(&mut deps, "operator", 10000).unwrap();
+    let err = harvest(&mut deps, "operator", 50000).unwrap_err();
+    assert_eq!(
+        err,
+        ContractError::ActionCooldown {
+            action: "operator_swap".to_string(),
+            until: 96400,
+        }
+    );
+    harvest(&mut deps, "operator", 96400).unwrap();
+
+    // harvests of others are not rate limited, their stages are validated instead
+    harvest(&mut deps, "worker", 96401).unwrap();
+
+    // the swap following the harvest doesn't count against the cooldown again
+    execute(
+        deps.as_mut(),
+        mock_env_at_timestamp(96400),
+        mock_info(MOCK_CONTRACT_ADDR, &[]),
+        ExecuteMsg::Callback(CallbackMsg::Swap {
+            stages: Some(vec![vec![(Addr::unchecked("fin1"), "test".into())]]),
+            sender: Addr::unchecked("operator"),
+            min_received: None,
+        }),
+    )
+    .unwrap();
+}
+
 #[test]
 fn swap() -> StdResult<()> {
     let mut deps = setup_test();
@@ -297,6 +688,7 @@ fn swap() -> StdResult<()> {
         ExecuteMsg::Callback(CallbackMsg::Swap {
             stages: Some(vec![vec![(Addr::unchecked("fin1"), "test".into())]]),
             sender: Addr::unchecked("worker"),
+            min_received: None,
         }),
     )
     .unwrap_err();
@@ -307,12 +699,19 @@ fn swap() -> StdResult<()> {
         mock_env(),
         mock_info(MOCK_CONTRACT_ADDR, &[]),
         ExecuteMsg::Callback(CallbackMsg::Swap {
-            stages: Some(vec![vec![(Addr::unchecked("fin1"), CONTRACT_DENOM.into())]]),
+            stages: Some(vec![vec![(Addr::unchecked("fin1"), "test".into())]]),
             sender: Addr::unchecked("worker"),
+            min_received: None,
         }),
     )
     .unwrap_err();
-    assert_eq!(err, ContractError::UnauthorizedSenderNotOperator {});
+    assert_eq!(
+        err,
+        ContractError::SwapNotAllowlisted {
+            pair: "fin1".to_string(),
+            denom: "test".to_string()
+        }
+    );
 
     let err = execute(
         deps.as_mut(),
@@ -321,6 +720,7 @@ fn swap() -> StdResult<()> {
         ExecuteMsg::Callback(CallbackMsg::Swap {
             stages: Some(vec![vec![(Addr::unchecked("fin1"), CONTRACT_DENOM.into())]]),
             sender: Addr::unchecked("operator"),
+            min_received: None,
         }),
     )
     .unwrap_err();
@@ -333,6 +733,7 @@ fn swap() -> StdResult<()> {
         ExecuteMsg::Callback(CallbackMsg::Swap {
             stages: Some(vec![vec![(Addr::unchecked("fin2"), STAKE_DENOM.into())]]),
             sender: Addr::unchecked("operator"),
+            min_received: None,
         }),
     )
     .unwrap_err();
@@ -344,11 +745,45 @@ fn swap() -> StdResult<()> {
         coin(1000, "not_relevant"),
     ]);
 
-    let stages = vec![
-        vec![(Addr::unchecked("fin1"), "test".into())],
-        vec![(Addr::unchecked("fin2"), "abc".into()), (Addr::unchecked("fin3"), "test2".into())],
-        vec![(Addr::unchecked("fin4"), "abc".into())],
-    ];
+    let stages = FinMultiStages::new()
+        .stage(vec![(Addr::unchecked("fin1"), "test".into())])
+        .stage(vec![
+            (Addr::unchecked("fin2"), "abc".into()),
+            (Addr::unchecked("fin3"), "test2".into()),
+            (Addr::unchecked("fin3"), "test2".into()),
+        ])
+        .stage(vec![(Addr::unchecked("fin4"), "abc".into())])
+        .into_stages();
+    assert_eq!(stages[1].len(), 2);
+
+    let err = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info(MOCK_CONTRACT_ADDR, &[]),
+        ExecuteMsg::Callback(CallbackMsg::Swap {
+            stages: Some(vec![vec![(Addr::unchecked("fin1"), "test".into())], vec![]]),
+            sender: Addr::unchecked("operator"),
+            min_received: None,
+        }),
+    )
+    .unwrap_err();
+    assert_eq!(err, StdError::generic_err("fin multi: stage 1 is empty").into());
+
+    let err = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info(MOCK_CONTRACT_ADDR, &[]),
+        ExecuteMsg::Callback(CallbackMsg::Swap {
+            stages: Some(vec![vec![(Addr::unchecked("fin5"), "missing".into())]]),
+            sender: Addr::unchecked("operator"),
+            min_received: None,
+        }),
+    )
+    .unwrap_err();
+    assert_eq!(
+        err,
+        StdError::generic_err("fin multi: no balance for any denom of the stages").into()
+    );
 
     let res = execute(
         deps.as_mut(),
@@ -357,6 +792,7 @@ fn swap() -> StdResult<()> {
         ExecuteMsg::Callback(CallbackMsg::Swap {
             stages: Some(stages.clone()),
             sender: Addr::unchecked("operator"),
+            min_received: None,
         }),
     )
     .unwrap();
@@ -383,6 +819,7 @@ fn swap() -> StdResult<()> {
         ExecuteMsg::Callback(CallbackMsg::Swap {
             stages: None,
             sender: Addr::unchecked("anyone"),
+            min_received: None,
         }),
     )
     .unwrap();
@@ -400,5 +837,508 @@ fn swap() -> StdResult<()> {
         }))
     );
 
+    // DEFAULT STAGES WITHOUT BALANCES ARE SKIPPED
+
+    deps.querier.set_bank_balances(&[coin(1000, "not_relevant")]);
+
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info(MOCK_CONTRACT_ADDR, &[]),
+        ExecuteMsg::Callback(CallbackMsg::Swap {
+            stages: None,
+            sender: Addr::unchecked("anyone"),
+            min_received: None,
+        }),
+    )
+    .unwrap();
+    assert_eq!(res.messages.len(), 0);
+
+    Ok(())
+}
+
+#[test]
+fn swap_with_minimum_received() -> StdResult<()> {
+    let mut deps = setup_test();
+
+    deps.querier.set_bank_balances(&[coin(100, "test"), coin(50, CONTRACT_DENOM)]);
+    let stages = vec![vec![(Addr::unchecked("fin1"), Denom::from("test"))]];
+
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info(MOCK_CONTRACT_ADDR, &[]),
+        ExecuteMsg::Callback(CallbackMsg::Swap {
+            stages: Some(stages),
+            sender: Addr::unchecked("operator"),
+            min_received: Some(vec![coin(90, CONTRACT_DENOM)]),
+        }),
+    )
+    .unwrap();
+
+    // the balance before the swap is the snapshot
+    let assert_msg = CallbackMsg::AssertMinimumReceived {
+        snapshot: vec![coin(50, CONTRACT_DENOM)],
+        min_received: vec![coin(90, CONTRACT_DENOM)],
+    };
+    assert_eq!(res.messages.len(), 2);
+    assert_eq!(
+        res.messages[1],
+        SubMsg::new(assert_msg.into_cosmos_msg(&Addr::unchecked(MOCK_CONTRACT_ADDR))?)
+    );
+
+    // the swap returned less than the minimum
+    deps.querier.set_bank_balances(&[coin(130, CONTRACT_DENOM)]);
+    let err = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info(MOCK_CONTRACT_ADDR, &[]),
+        ExecuteMsg::Callback(assert_msg.clone()),
+    )
+    .unwrap_err();
+    assert_eq!(
+        err,
+        ContractError::MinimumReceivedNotMet {
+            denom: CONTRACT_DENOM.into(),
+            expected: Uint128::new(90),
+            received: Uint128::new(80),
+        }
+    );
+
+    deps.querier.set_bank_balances(&[coin(140, CONTRACT_DENOM)]);
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info(MOCK_CONTRACT_ADDR, &[]),
+        ExecuteMsg::Callback(assert_msg),
+    )
+    .unwrap();
+
+    Ok(())
+}
+
+#[test]
+fn swap_permissionless_custom_stages() -> StdResult<()> {
+    let mut deps = setup_test();
+
+    deps.querier.set_bank_balances(&[coin(100, "test"), coin(200, "abc")]);
+    deps.querier.set_fin_pair(
+        "fin1",
+        ["test", CONTRACT_DENOM],
+        Decimal::from_ratio(2u128, 1u128),
+        Decimal::percent(1),
+    );
+    deps.querier.set_fin_pair(
+        "fin2",
+        ["abc", CONTRACT_DENOM],
+        Decimal::one(),
+        Decimal::percent(10),
+    );
+
+    let err = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("owner", &[]),
+        ExecuteMsg::UpdateConfig {
+            protocol_fee_contract: None,
+            protocol_reward_fee: None,
+            operator: None,
+            stages_preset: None,
+            allow_donations: None,
+            delegation_strategy: None,
+            vote_operator: None,
+            guardian: None,
+            exchange_rate_tolerance: None,
+            swap_allowlist: None,
+            swap_max_spread: Some(Decimal::percent(101)),
+            max_reinvest_per_tx: None,
+            unbond_allowlist: None,
+            unbond_fee: None,
+            min_hold_seconds: None,
+            reward_smoothing_period: None,
+            validator_fee_share: None,
+            max_harvest_staleness: None,
+            protocol_fee_schedule: None,
+            action_cooldowns: None,
+            express_epoch: None,
+            rebalance_tolerance: None,
+        },
+    )
+    .unwrap_err();
+    assert_eq!(err, ContractError::SwapMaxSpreadTooHigh {});
+
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("owner", &[]),
+        ExecuteMsg::UpdateConfig {
+            protocol_fee_contract: None,
+            protocol_reward_fee: None,
+            operator: None,
+            stages_preset: None,
+            allow_donations: None,
+            delegation_strategy: None,
+            vote_operator: None,
+            guardian: None,
+            exchange_rate_tolerance: None,
+            swap_allowlist: Some(vec![
+                (Addr::unchecked("fin1"), "test".into()),
+                (Addr::unchecked("fin2"), "abc".into()),
+            ]),
+            swap_max_spread: None,
+            max_reinvest_per_tx: None,
+            unbond_allowlist: None,
+            unbond_fee: None,
+            min_hold_seconds: None,
+            reward_smoothing_period: None,
+            validator_fee_share: None,
+            max_harvest_staleness: None,
+            protocol_fee_schedule: None,
+            action_cooldowns: None,
+            express_epoch: None,
+            rebalance_tolerance: None,
+        },
+    )
+    .unwrap();
+
+    let err = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info(MOCK_CONTRACT_ADDR, &[]),
+        ExecuteMsg::Callback(CallbackMsg::Swap {
+            stages: Some(vec![vec![(Addr::unchecked("fin3"), "test".into())]]),
+            sender: Addr::unchecked("anyone"),
+            min_received: None,
+        }),
+    )
+    .unwrap_err();
+    assert_eq!(
+        err,
+        ContractError::SwapNotAllowlisted {
+            pair: "fin3".to_string(),
+            denom: "test".to_string()
+        }
+    );
+
+    // fin2 has a spread of 10%, above the default max spread of 5%
+    let err = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info(MOCK_CONTRACT_ADDR, &[]),
+        ExecuteMsg::Callback(CallbackMsg::Swap {
+            stages: Some(vec![
+                vec![(Addr::unchecked("fin1"), "test".into())],
+                vec![(Addr::unchecked("fin2"), "abc".into())],
+            ]),
+            sender: Addr::unchecked("anyone"),
+            min_received: None,
+        }),
+    )
+    .unwrap_err();
+    assert_eq!(err, ContractError::SwapBelowMinimumOutput("fin2".to_string()));
+
+    // the operator is not bound by the allow-list
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info(MOCK_CONTRACT_ADDR, &[]),
+        ExecuteMsg::Callback(CallbackMsg::Swap {
+            stages: Some(vec![vec![(Addr::unchecked("fin2"), "abc".into())]]),
+            sender: Addr::unchecked("operator"),
+            min_received: None,
+        }),
+    )
+    .unwrap();
+
+    let stages = vec![vec![(Addr::unchecked("fin1"), "test".into())]];
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info(MOCK_CONTRACT_ADDR, &[]),
+        ExecuteMsg::Callback(CallbackMsg::Swap {
+            stages: Some(stages.clone()),
+            sender: Addr::unchecked("anyone"),
+            min_received: None,
+        }),
+    )
+    .unwrap();
+
+    assert_eq!(res.messages.len(), 1);
+    assert_eq!(
+        res.messages[0],
+        SubMsg::new(CosmosMsg::Wasm(WasmMsg::Execute {
+            contract_addr: "fin_multi".to_string(),
+            funds: vec![coin(100, "test")],
+            msg: to_binary(&FinMultiExecuteMsg {
+                stages,
+                recipient: None,
+            })?,
+        }))
+    );
+
     Ok(())
 }
+
+#[test]
+fn self_references_are_rejected() {
+    let mut deps = setup_test();
+
+    let err = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("worker", &[]),
+        ExecuteMsg::Harvest {
+            stages: None,
+            withdrawals: Some(vec![(
+                WithdrawType::Bow,
+                Addr::unchecked(MOCK_CONTRACT_ADDR),
+                BW_DENOM1.into(),
+            )]),
+            min_received: None,
+            withdraw_limits: None,
+        },
+    )
+    .unwrap_err();
+    assert_eq!(err, ContractError::SelfReference("withdrawals".into()));
+
+    let err = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("worker", &[]),
+        ExecuteMsg::Harvest {
+            stages: Some(vec![vec![(Addr::unchecked(MOCK_CONTRACT_ADDR), "test".into())]]),
+            withdrawals: None,
+            min_received: None,
+            withdraw_limits: None,
+        },
+    )
+    .unwrap_err();
+    assert_eq!(err, ContractError::SelfReference("stages".into()));
+
+    let err = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("owner", &[]),
+        ExecuteMsg::UpdateConfig {
+            protocol_fee_contract: None,
+            protocol_reward_fee: None,
+            operator: None,
+            stages_preset: Some(vec![vec![(Addr::unchecked(MOCK_CONTRACT_ADDR), "test".into())]]),
+            allow_donations: None,
+            delegation_strategy: None,
+            vote_operator: None,
+            guardian: None,
+            exchange_rate_tolerance: None,
+            swap_allowlist: None,
+            swap_max_spread: None,
+            max_reinvest_per_tx: None,
+            unbond_allowlist: None,
+            unbond_fee: None,
+            min_hold_seconds: None,
+            reward_smoothing_period: None,
+            validator_fee_share: None,
+            max_harvest_staleness: None,
+            protocol_fee_schedule: None,
+            action_cooldowns: None,
+            express_epoch: None,
+            rebalance_tolerance: None,
+        },
+    )
+    .unwrap_err();
+    assert_eq!(err, ContractError::SelfReference("stages".into()));
+
+    let err = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("owner", &[]),
+        ExecuteMsg::UpdateConfig {
+            protocol_fee_contract: None,
+            protocol_reward_fee: None,
+            operator: None,
+            stages_preset: None,
+            allow_donations: None,
+            delegation_strategy: None,
+            vote_operator: None,
+            guardian: None,
+            exchange_rate_tolerance: None,
+            swap_allowlist: Some(vec![(Addr::unchecked(MOCK_CONTRACT_ADDR), "test".into())]),
+            swap_max_spread: None,
+            max_reinvest_per_tx: None,
+            unbond_allowlist: None,
+            unbond_fee: None,
+            min_hold_seconds: None,
+            reward_smoothing_period: None,
+            validator_fee_share: None,
+            max_harvest_staleness: None,
+            protocol_fee_schedule: None,
+            action_cooldowns: None,
+            express_epoch: None,
+            rebalance_tolerance: None,
+        },
+    )
+    .unwrap_err();
+    assert_eq!(err, ContractError::SelfReference("swap_allowlist".into()));
+}
+
+#[test]
+fn configured_contracts_must_exist() {
+    let mut deps = setup_test();
+    deps.querier.set_account("wallet");
+
+    let update_config = |protocol_fee_contract: Option<&str>,
+                         stages_preset: Option<Vec<Vec<(Addr, Denom)>>>| {
+        ExecuteMsg::UpdateConfig {
+            protocol_fee_contract: protocol_fee_contract.map(|c| c.to_string()),
+            protocol_reward_fee: None,
+            operator: None,
+            stages_preset,
+            allow_donations: None,
+            delegation_strategy: None,
+            vote_operator: None,
+            guardian: None,
+            exchange_rate_tolerance: None,
+            swap_allowlist: None,
+            swap_max_spread: None,
+            max_reinvest_per_tx: None,
+            unbond_allowlist: None,
+            unbond_fee: None,
+            min_hold_seconds: None,
+            reward_smoothing_period: None,
+            validator_fee_share: None,
+            max_harvest_staleness: None,
+            protocol_fee_schedule: None,
+            action_cooldowns: None,
+            express_epoch: None,
+            rebalance_tolerance: None,
+        }
+    };
+
+    let err = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("owner", &[]),
+        update_config(Some(MOCK_CONTRACT_ADDR), None),
+    )
+    .unwrap_err();
+    assert_eq!(err, ContractError::SelfReference("protocol_fee_contract".into()));
+
+    let err = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("owner", &[]),
+        update_config(Some("wallet"), None),
+    )
+    .unwrap_err();
+    assert_eq!(
+        err,
+        ContractError::NotAContract {
+            field: "protocol_fee_contract".into(),
+            addr: "wallet".into()
+        }
+    );
+
+    let err = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("owner", &[]),
+        update_config(None, Some(vec![vec![(Addr::unchecked("wallet"), "test".into())]])),
+    )
+    .unwrap_err();
+    assert_eq!(
+        err,
+        ContractError::NotAContract {
+            field: "stages".into(),
+            addr: "wallet".into()
+        }
+    );
+
+    deps.querier.set_fin_pair("fin2", ["test", CONTRACT_DENOM], Decimal::one(), Decimal::zero());
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("owner", &[]),
+        update_config(Some("fee2"), Some(vec![vec![(Addr::unchecked("fin2"), "test".into())]])),
+    )
+    .unwrap();
+}
+
+#[test]
+fn validating_stage_routes() {
+    let mut deps = setup_test();
+    deps.querier.set_fin_pair("fin2", ["abc", "usk"], Decimal::one(), Decimal::zero());
+    deps.querier.set_fin_pair("fin3", ["usk", CONTRACT_DENOM], Decimal::one(), Decimal::zero());
+
+    let update_stages = |stages: Vec<Vec<(&str, &str)>>| ExecuteMsg::UpdateConfig {
+        protocol_fee_contract: None,
+        protocol_reward_fee: None,
+        operator: None,
+        stages_preset: Some(
+            stages
+                .into_iter()
+                .map(|stage| {
+                    stage
+                        .into_iter()
+                        .map(|(pair, denom)| (Addr::unchecked(pair), denom.into()))
+                        .collect()
+                })
+                .collect(),
+        ),
+        allow_donations: None,
+        delegation_strategy: None,
+        vote_operator: None,
+        guardian: None,
+        exchange_rate_tolerance: None,
+        swap_allowlist: None,
+        swap_max_spread: None,
+        max_reinvest_per_tx: None,
+        unbond_allowlist: None,
+        unbond_fee: None,
+        min_hold_seconds: None,
+        reward_smoothing_period: None,
+        validator_fee_share: None,
+        max_harvest_staleness: None,
+        protocol_fee_schedule: None,
+        action_cooldowns: None,
+        express_epoch: None,
+        rebalance_tolerance: None,
+    };
+
+    let cases = vec![
+        (vec![vec![("fin1", "test")], vec![("fin4", "usk")]], 1, "fin4 is not a FIN pair"),
+        (vec![vec![("fin1", "abc")]], 0, "fin1 does not trade abc"),
+        (vec![vec![("fin1", "test"), ("fin1", "test")]], 0, "test is already swapped in stage 0"),
+        (
+            vec![vec![("fin2", "abc")], vec![("fin3", "usk")], vec![("fin2", "abc")]],
+            2,
+            "abc is already swapped in stage 0",
+        ),
+        (vec![vec![("fin2", "abc")]], 0, "usk returned by fin2 is not swapped in a later stage"),
+        (
+            vec![vec![("fin2", "abc"), ("fin3", "usk")]],
+            0,
+            "usk returned by fin2 is not swapped in a later stage",
+        ),
+    ];
+
+    for (stages, stage, reason) in cases {
+        let err =
+            execute(deps.as_mut(), mock_env(), mock_info("owner", &[]), update_stages(stages))
+                .unwrap_err();
+        assert_eq!(
+            err,
+            ContractError::InvalidSwapStage {
+                stage,
+                reason: reason.to_string()
+            }
+        );
+    }
+
+    // abc is swapped to usk first, which is then swapped to utoken with the usk of the rewards
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("owner", &[]),
+        update_stages(vec![vec![("fin1", "test"), ("fin2", "abc")], vec![("fin3", "usk")]]),
+    )
+    .unwrap();
+}
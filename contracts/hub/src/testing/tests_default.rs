@@ -3,31 +3,41 @@ use std::str::FromStr;
 
 use cosmwasm_std::testing::{mock_env, mock_info, MockApi, MockStorage, MOCK_CONTRACT_ADDR};
 use cosmwasm_std::{
-    coin, to_binary, Addr, BankMsg, Coin, CosmosMsg, Decimal, DistributionMsg, Event, Fraction,
-    GovMsg, Order, OwnedDeps, StdError, StdResult, SubMsg, Uint128, VoteOption, WasmMsg,
+    attr, coin, to_binary, Addr, BankMsg, Coin, CosmosMsg, Decimal, DepsMut, DistributionMsg,
+    Empty, Event, Fraction, GovMsg, Order, OwnedDeps, StdError, StdResult, SubMsg, Uint128,
+    VoteOption, WasmMsg,
 };
+use eris::helpers::pagination::PaginatedResponse;
 use eris::DecimalCheckedOps;
 
 use eris::hub::{
-    Batch, CallbackMsg, ConfigResponse, DelegationStrategy, ExecuteMsg, FeeConfig, InstantiateMsg,
-    PendingBatch, QueryMsg, StakeToken, StateResponse, UnbondRequest,
-    UnbondRequestsByBatchResponseItem, UnbondRequestsByUserResponseItem,
-    UnbondRequestsByUserResponseItemDetails,
+    ApyResponse, AuditEntry, Batch, BootstrapFromExport, CallbackMsg, ConfigResponse,
+    DelegationStrategy, EpochCounters, ExchangeRateTwapResponse, ExecuteMsg, ExportSection,
+    ExpressEpoch, FeeConfig, HealthResponse, HubConfig, InstantiateMsg, LastHarvest, OrderBy,
+    PendingBatch, PreparedMsg, ProtocolFeePeriod, QueryMsg, ReconcileCursor, RemoveValidatorMode,
+    StakeToken, StakeTokenResponse, StateExport, StateResponse, UnbondFee, UnbondFeeRecipient,
+    UnbondRequest, UnbondRequestReceiveMsg, UnbondRequestReceiverExecuteMsg,
+    UnbondRequestsByBatchResponseItem, UnbondRequestsByBatchTotalsResponse,
+    UnbondRequestsByUserResponseItem, UnbondRequestsByUserResponseItemDetails,
+    UnbondRequestsTotals, ValidatorPayout, VoteRecord, WantedDelegationsResponse,
 };
 use itertools::Itertools;
 use kujira::msg::{DenomMsg, KujiraMsg};
 use protobuf::SpecialFields;
 
-use crate::constants::CONTRACT_DENOM;
-use crate::contract::{execute, instantiate};
-use crate::error::ContractError;
+use crate::constants::{AUDIT_LOG_SIZE, CONTRACT_DENOM, MAX_BATCHES_SKIPPED};
+use crate::contract::{execute, instantiate, migrate, privileged_action, query};
+use crate::error::{ContractError, Role};
 use crate::helpers::{dedupe, parse_received_fund};
 use crate::math::{
     compute_redelegations_for_rebalancing, compute_redelegations_for_removal, compute_undelegations,
 };
 use crate::protos::proto::{self, MsgVoteWeighted, WeightedVoteOption};
 use crate::state::State;
-use crate::testing::helpers::{check_received_coin, query_helper_env, set_total_stake_supply};
+use crate::testing::helpers::{
+    check_received_coin, query_helper_env, save_previous_batches, save_unbond_requests,
+    set_total_stake_supply,
+};
 use crate::types::{Coins, Delegation, Redelegation, SendFee, Undelegation};
 
 use super::custom_querier::CustomQuerier;
@@ -59,6 +69,8 @@ fn setup_test() -> OwnedDeps<MockStorage, MockApi, CustomQuerier> {
             stages_preset: None,
             delegation_strategy: None,
             vote_operator: None,
+            bootstrap: None,
+            max_query_limit: None,
         },
     )
     .unwrap();
@@ -86,7 +98,7 @@ fn proper_instantiation() {
     assert_eq!(
         res,
         ConfigResponse {
-            owner: "owner".to_string(),
+            owner: Some("owner".to_string()),
             new_owner: None,
             stake_token: STAKE_DENOM.to_string(),
             epoch_period: 259200,
@@ -94,13 +106,32 @@ fn proper_instantiation() {
             validators: vec!["alice".to_string(), "bob".to_string(), "charlie".to_string()],
             fee_config: FeeConfig {
                 protocol_fee_contract: Addr::unchecked("fee"),
-                protocol_reward_fee: Decimal::from_ratio(1u128, 100u128)
+                protocol_reward_fee: Decimal::from_ratio(1u128, 100u128),
+                unbond_fee: None,
+                validator_fee_share: None,
             },
             operator: "operator".to_string(),
+            new_operator: None,
             stages_preset: vec![],
             allow_donations: false,
             delegation_strategy: DelegationStrategy::Uniform,
-            vote_operator: None
+            vote_operator: None,
+            new_vote_operator: None,
+            guardian: None,
+            exchange_rate_tolerance: None,
+            circuit_breaker_tripped: false,
+            swap_allowlist: vec![],
+            swap_max_spread: Decimal::percent(5),
+            max_reinvest_per_tx: None,
+            unbond_allowlist: vec![],
+            min_hold_seconds: 0,
+            reward_smoothing_period: 0,
+            max_harvest_staleness: 0,
+            protocol_fee_schedule: vec![],
+            action_cooldowns: vec![],
+            express_epoch: None,
+            rebalance_tolerance: Decimal::zero(),
+            max_query_limit: 30,
         }
     );
 
@@ -115,6 +146,9 @@ fn proper_instantiation() {
             unbonding: Uint128::zero(),
             available: Uint128::zero(),
             tvl_utoken: Uint128::zero(),
+            ustake_escrowed: Uint128::zero(),
+            utoken_unvested: Uint128::zero(),
+            utoken_dust_recycled: Uint128::zero(),
         },
     );
 
@@ -129,6 +163,65 @@ fn proper_instantiation() {
     );
 }
 
+#[test]
+fn instantiating_reports_all_violations() {
+    let mut deps = mock_dependencies();
+    deps.querier.set_account("fee");
+
+    let err = instantiate(
+        deps.as_mut(),
+        mock_env_at_timestamp(10000),
+        mock_info("deployer", &[]),
+        InstantiateMsg {
+            fin_multi_contract: "fin_multi".to_string(),
+            owner: "OWNER".to_string(),
+            denom: "st ake".to_string(),
+            epoch_period: 0,
+            unbond_period: 1814400,
+            validators: vec!["alice".to_string(), "bob".to_string()],
+            protocol_fee_contract: "fee".to_string(),
+            protocol_reward_fee: Decimal::from_ratio(1u128, 2u128),
+            operator: "operator".to_string(),
+            stages_preset: None,
+            delegation_strategy: Some(DelegationStrategy::Defined {
+                shares_bps: vec![("alice".into(), 10000), ("dave".into(), 0)],
+            }),
+            vote_operator: None,
+            bootstrap: None,
+            max_query_limit: Some(0),
+        },
+    )
+    .unwrap_err();
+
+    let violations = match err {
+        ContractError::InvalidInstantiate(violations) => violations,
+        err => panic!("unexpected error: {}", err),
+    };
+    assert_eq!(
+        violations,
+        vec![
+            ContractError::ProtocolRewardFeeTooHigh {}.to_string(),
+            ContractError::CantBeZero("epoch_period".into()).to_string(),
+            ContractError::CantBeZero("max_query_limit".into()).to_string(),
+            StdError::generic_err(
+                "subdenom st ake must have 1 to 44 alphanumeric characters or one of / : . _ -",
+            )
+            .to_string(),
+            ContractError::InvalidAddress {
+                field: "owner".into(),
+                addr: "OWNER".into()
+            }
+            .to_string(),
+            ContractError::NotAContract {
+                field: "protocol_fee_contract".into(),
+                addr: "fee".into()
+            }
+            .to_string(),
+            StdError::generic_err("validator dave not whitelisted").to_string(),
+        ]
+    );
+}
+
 #[test]
 fn bonding() {
     let mut deps = setup_test();
@@ -143,6 +236,7 @@ fn bonding() {
         mock_info("user_1", &[Coin::new(1000000, CONTRACT_DENOM)]),
         ExecuteMsg::Bond {
             receiver: None,
+            min_ustake_received: None,
         },
     )
     .unwrap();
@@ -179,6 +273,25 @@ fn bonding() {
 
     // deps.querier.set_cw20_total_supply("stake_token", 1000000);
 
+    // The exchange rate moved, so less than the expected minimum would be minted
+    let err = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("user_2", &[Coin::new(12345, CONTRACT_DENOM)]),
+        ExecuteMsg::Bond {
+            receiver: Some("user_3".to_string()),
+            min_ustake_received: Some(Uint128::new(12345)),
+        },
+    )
+    .unwrap_err();
+    assert_eq!(
+        err,
+        ContractError::MinimumUstakeNotReceived {
+            expected: Uint128::new(12345),
+            received: Uint128::new(12043)
+        }
+    );
+
     // Charlie has the smallest amount of delegation, so the full deposit goes to him
     let res = execute(
         deps.as_mut(),
@@ -186,6 +299,7 @@ fn bonding() {
         mock_info("user_2", &[Coin::new(12345, CONTRACT_DENOM)]),
         ExecuteMsg::Bond {
             receiver: Some("user_3".to_string()),
+            min_ustake_received: Some(Uint128::new(12043)),
         },
     )
     .unwrap();
@@ -221,10 +335,108 @@ fn bonding() {
             unbonding: Uint128::zero(),
             available: Uint128::new(12567),
             tvl_utoken: Uint128::new(1037345 + 12567),
+            ustake_escrowed: Uint128::zero(),
+            utoken_unvested: Uint128::zero(),
+            utoken_dust_recycled: Uint128::zero(),
         }
     );
 }
 
+#[test]
+fn bonding_skips_inactive_validators() {
+    let mut deps = setup_test();
+    let state = State::default();
+
+    set_total_stake_supply(&state, &mut deps, 1000000);
+    deps.querier.set_bank_balances(&[coin(12345, CONTRACT_DENOM)]);
+    deps.querier.set_staking_delegations(&[
+        Delegation::new("alice", 341667),
+        Delegation::new("bob", 341667),
+        Delegation::new("charlie", 341666),
+    ]);
+
+    // charlie has the smallest delegation, but is jailed
+    deps.querier.set_jailed_validators(&["charlie"]);
+
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("user_1", &[Coin::new(12345, CONTRACT_DENOM)]),
+        ExecuteMsg::Bond {
+            receiver: None,
+            min_ustake_received: None,
+        },
+    )
+    .unwrap();
+    assert_eq!(res.messages[0], SubMsg::new(Delegation::new("alice", 12345).to_cosmos_msg()));
+
+    // nothing can be bonded while the whole validator set is jailed
+    deps.querier.set_jailed_validators(&["alice", "bob", "charlie"]);
+
+    let bond = ExecuteMsg::Bond {
+        receiver: None,
+        min_ustake_received: None,
+    };
+    let err = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("user_1", &[Coin::new(12345, CONTRACT_DENOM)]),
+        bond.clone(),
+    )
+    .unwrap_err();
+    assert_eq!(err, ContractError::NoActiveValidator {});
+
+    // the last validator can't be removed by redelegating
+    deps.querier.set_jailed_validators(&[]);
+    for validator in ["alice", "bob"] {
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("owner", &[]),
+            ExecuteMsg::RemoveValidator {
+                validator: validator.to_string(),
+                mode: Some(RemoveValidatorMode::Undelegate),
+            },
+        )
+        .unwrap();
+    }
+
+    let err = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("owner", &[]),
+        ExecuteMsg::RemoveValidator {
+            validator: "charlie".to_string(),
+            mode: Some(RemoveValidatorMode::Redelegate),
+        },
+    )
+    .unwrap_err();
+    assert_eq!(err, ContractError::NoActiveValidator {});
+    let validators = state.validators.load(deps.as_ref().storage).unwrap();
+    assert_eq!(validators, vec!["charlie".to_string()]);
+
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("owner", &[]),
+        ExecuteMsg::RemoveValidator {
+            validator: "charlie".to_string(),
+            mode: Some(RemoveValidatorMode::Undelegate),
+        },
+    )
+    .unwrap();
+
+    // without any validator left, bonding fails instead of panicking
+    let err = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("user_1", &[Coin::new(12345, CONTRACT_DENOM)]),
+        bond,
+    )
+    .unwrap_err();
+    assert_eq!(err, ContractError::NoActiveValidator {});
+}
+
 #[test]
 fn donating() {
     let mut deps = setup_test();
@@ -238,6 +450,7 @@ fn donating() {
         mock_info("user_1", &[Coin::new(1000000, CONTRACT_DENOM)]),
         ExecuteMsg::Bond {
             receiver: None,
+            min_ustake_received: None,
         },
     )
     .unwrap();
@@ -276,6 +489,9 @@ fn donating() {
             unbonding: Uint128::zero(),
             available: Uint128::new(100),
             tvl_utoken: Uint128::new(1025100),
+            ustake_escrowed: Uint128::zero(),
+            utoken_unvested: Uint128::zero(),
+            utoken_dust_recycled: Uint128::zero(),
         }
     );
 
@@ -303,6 +519,21 @@ fn donating() {
             allow_donations: Some(true),
             delegation_strategy: None,
             vote_operator: None,
+            guardian: None,
+            exchange_rate_tolerance: None,
+            swap_allowlist: None,
+            swap_max_spread: None,
+            max_reinvest_per_tx: None,
+            unbond_allowlist: None,
+            unbond_fee: None,
+            min_hold_seconds: None,
+            reward_smoothing_period: None,
+            validator_fee_share: None,
+            max_harvest_staleness: None,
+            protocol_fee_schedule: None,
+            action_cooldowns: None,
+            express_epoch: None,
+            rebalance_tolerance: None,
         },
     )
     .unwrap();
@@ -339,6 +570,9 @@ fn donating() {
             unbonding: Uint128::zero(),
             available: Uint128::new(100),
             tvl_utoken: Uint128::new(1037345 + 100),
+            ustake_escrowed: Uint128::zero(),
+            utoken_unvested: Uint128::zero(),
+            utoken_dust_recycled: Uint128::zero(),
         }
     );
 }
@@ -362,6 +596,8 @@ fn harvesting() {
         ExecuteMsg::Harvest {
             stages: None,
             withdrawals: None,
+            min_received: None,
+            withdraw_limits: None,
         },
     )
     .unwrap();
@@ -396,6 +632,41 @@ fn harvesting() {
             funds: vec![]
         }))
     );
+
+    let res: Option<LastHarvest> = query_helper(deps.as_ref(), QueryMsg::LastHarvest {});
+    assert_eq!(
+        res,
+        Some(LastHarvest {
+            timestamp: mock_env().block.time.seconds(),
+            caller: Addr::unchecked("worker"),
+            utoken_bonded: Uint128::zero(),
+            utoken_protocol_fee: Uint128::zero(),
+        })
+    );
+
+    // Harvesting after more than an epoch emits a warning
+    let timestamp = mock_env().block.time.seconds() + 259201;
+    let res = execute(
+        deps.as_mut(),
+        mock_env_at_timestamp(timestamp),
+        mock_info("worker", &[]),
+        ExecuteMsg::Harvest {
+            stages: None,
+            withdrawals: None,
+            min_received: None,
+            withdraw_limits: None,
+        },
+    )
+    .unwrap();
+
+    assert_eq!(
+        res.attributes,
+        vec![
+            attr("action", "erishub/harvest"),
+            attr("warning", "harvest_stale"),
+            attr("seconds_since_last_harvest", "259201"),
+        ]
+    );
 }
 
 #[test]
@@ -542,964 +813,3966 @@ fn reinvesting() {
 }
 
 #[test]
-fn queuing_unbond() {
+fn reinvesting_with_fee_schedule() {
     let mut deps = setup_test();
     let state = State::default();
 
-    // Only Stake token is accepted for unbonding requests
+    let update_schedule = |schedule: Vec<ProtocolFeePeriod>| ExecuteMsg::UpdateConfig {
+        protocol_fee_contract: None,
+        protocol_reward_fee: None,
+        operator: None,
+        stages_preset: None,
+        allow_donations: None,
+        delegation_strategy: None,
+        vote_operator: None,
+        guardian: None,
+        exchange_rate_tolerance: None,
+        swap_allowlist: None,
+        swap_max_spread: None,
+        max_reinvest_per_tx: None,
+        unbond_allowlist: None,
+        unbond_fee: None,
+        min_hold_seconds: None,
+        reward_smoothing_period: None,
+        validator_fee_share: None,
+        max_harvest_staleness: None,
+        protocol_fee_schedule: Some(schedule),
+        action_cooldowns: None,
+        express_epoch: None,
+        rebalance_tolerance: None,
+    };
+
     let err = execute(
         deps.as_mut(),
         mock_env(),
-        mock_info("random_sender", &[Coin::new(100, "random_token")]),
-        ExecuteMsg::QueueUnbond {
-            receiver: None,
-        },
+        mock_info("owner", &[]),
+        update_schedule(vec![
+            ProtocolFeePeriod {
+                start_time: 10000,
+                end_time: 20001,
+                bps: 0,
+            },
+            ProtocolFeePeriod {
+                start_time: 20000,
+                end_time: 30000,
+                bps: 50,
+            },
+        ]),
     )
     .unwrap_err();
+    assert_eq!(
+        err,
+        ContractError::InvalidProtocolFeeSchedule(
+            "periods must be sorted and must not overlap".to_string()
+        )
+    );
 
-    assert_eq!(err, ContractError::ExpectingStakeToken("random_token".into()));
-
-    // User 1 creates an unbonding request before `est_unbond_start_time` is reached. The unbond
-    // request is saved, but not the pending batch is not submitted for unbonding
-    let res = execute(
-        deps.as_mut(),
-        mock_env_at_timestamp(12345), // est_unbond_start_time = 269200
-        mock_info("user_1", &[Coin::new(23456, STAKE_DENOM)]),
-        ExecuteMsg::QueueUnbond {
-            receiver: None,
+    // no fee for the launch, then half of the configured 1% before it applies
+    let schedule = vec![
+        ProtocolFeePeriod {
+            start_time: 10000,
+            end_time: 20000,
+            bps: 0,
         },
-    )
-    .unwrap();
+        ProtocolFeePeriod {
+            start_time: 20000,
+            end_time: 30000,
+            bps: 50,
+        },
+    ];
+    execute(deps.as_mut(), mock_env(), mock_info("owner", &[]), update_schedule(schedule.clone()))
+        .unwrap();
 
-    assert_eq!(res.messages.len(), 0);
+    let res: ConfigResponse = query_helper(deps.as_ref(), QueryMsg::Config {});
+    assert_eq!(res.protocol_fee_schedule, schedule);
 
-    // User 2 creates an unbonding request after `est_unbond_start_time` is reached. The unbond
-    // request is saved, and the pending is automatically submitted for unbonding
-    let res = execute(
-        deps.as_mut(),
-        mock_env_at_timestamp(269201), // est_unbond_start_time = 269200
-        mock_info("user_2", &[Coin::new(69420, STAKE_DENOM)]),
-        ExecuteMsg::QueueUnbond {
-            receiver: Some("user_3".to_string()),
-        },
-    )
-    .unwrap();
+    deps.querier.set_staking_delegations(&[
+        Delegation::new("alice", 333334),
+        Delegation::new("bob", 333333),
+        Delegation::new("charlie", 333333),
+    ]);
 
-    assert_eq!(res.messages.len(), 1);
-    assert_eq!(
-        res.messages[0],
-        SubMsg::new(CosmosMsg::Wasm(WasmMsg::Execute {
-            contract_addr: MOCK_CONTRACT_ADDR.to_string(),
-            msg: to_binary(&ExecuteMsg::SubmitBatch {}).unwrap(),
-            funds: vec![]
-        }))
-    );
+    let mut reinvest_at = |time: u64| {
+        state
+            .unlocked_coins
+            .save(deps.as_mut().storage, &vec![Coin::new(10000, CONTRACT_DENOM)])
+            .unwrap();
+        execute(
+            deps.as_mut(),
+            mock_env_at_timestamp(time),
+            mock_info(MOCK_CONTRACT_ADDR, &[]),
+            ExecuteMsg::Callback(CallbackMsg::Reinvest {}),
+        )
+        .unwrap()
+    };
 
-    // The users' unbonding requests should have been saved
-    let ubr1 = state
-        .unbond_requests
-        .load(deps.as_ref().storage, (1u64, &Addr::unchecked("user_1")))
-        .unwrap();
-    let ubr2 = state
-        .unbond_requests
-        .load(deps.as_ref().storage, (1u64, &Addr::unchecked("user_3")))
-        .unwrap();
+    let res = reinvest_at(15000);
+    assert_eq!(res.messages.len(), 1);
+    assert_eq!(res.messages[0], SubMsg::new(Delegation::new("bob", 10000).to_cosmos_msg()));
 
+    let res = reinvest_at(20000);
+    assert_eq!(res.messages.len(), 2);
     assert_eq!(
-        ubr1,
-        UnbondRequest {
-            id: 1,
-            user: Addr::unchecked("user_1"),
-            shares: Uint128::new(23456)
-        }
-    );
-    assert_eq!(
-        ubr2,
-        UnbondRequest {
-            id: 1,
-            user: Addr::unchecked("user_3"),
-            shares: Uint128::new(69420)
-        }
+        res.messages[1],
+        SubMsg::new(SendFee::new(Addr::unchecked("fee"), 50).to_cosmos_msg())
     );
 
-    // Pending batch should have been updated
-    let pending_batch = state.pending_batch.load(deps.as_ref().storage).unwrap();
+    let res = reinvest_at(30000);
+    assert_eq!(res.messages.len(), 2);
     assert_eq!(
-        pending_batch,
-        PendingBatch {
-            id: 1,
-            ustake_to_burn: Uint128::new(92876), // 23,456 + 69,420
-            est_unbond_start_time: 269200
-        }
+        res.messages[1],
+        SubMsg::new(SendFee::new(Addr::unchecked("fee"), 100).to_cosmos_msg())
     );
 }
 
 #[test]
-fn submitting_batch() {
+fn reinvesting_rounds_fee_down() {
     let mut deps = setup_test();
     let state = State::default();
 
-    // utoken bonded: 1,037,345
-    // ustake supply: 1,012,043
-    // utoken per ustake: 1.025
+    let update_fee = |fee: Decimal| ExecuteMsg::UpdateConfig {
+        protocol_fee_contract: None,
+        protocol_reward_fee: Some(fee),
+        operator: None,
+        stages_preset: None,
+        allow_donations: None,
+        delegation_strategy: None,
+        vote_operator: None,
+        guardian: None,
+        exchange_rate_tolerance: None,
+        swap_allowlist: None,
+        swap_max_spread: None,
+        max_reinvest_per_tx: None,
+        unbond_allowlist: None,
+        unbond_fee: None,
+        min_hold_seconds: None,
+        reward_smoothing_period: None,
+        validator_fee_share: None,
+        max_harvest_staleness: None,
+        protocol_fee_schedule: None,
+        action_cooldowns: None,
+        express_epoch: None,
+        rebalance_tolerance: None,
+    };
+
+    // fees are whole basis points
+    let err = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("owner", &[]),
+        update_fee(Decimal::from_str("0.00015").unwrap()),
+    )
+    .unwrap_err();
+    assert_eq!(err, ContractError::ProtocolRewardFeePrecision {});
+
     deps.querier.set_staking_delegations(&[
-        Delegation::new("alice", 345782),
-        Delegation::new("bob", 345782),
-        Delegation::new("charlie", 345781),
+        Delegation::new("alice", 333334),
+        Delegation::new("bob", 333333),
+        Delegation::new("charlie", 333333),
     ]);
 
-    set_total_stake_supply(&state, &mut deps, 1012043);
+    let reinvest = |deps: DepsMut, utoken: u128| {
+        state.unlocked_coins.save(deps.storage, &vec![Coin::new(utoken, CONTRACT_DENOM)]).unwrap();
+        execute(
+            deps,
+            mock_env(),
+            mock_info(MOCK_CONTRACT_ADDR, &[]),
+            ExecuteMsg::Callback(CallbackMsg::Reinvest {}),
+        )
+        .unwrap()
+    };
 
-    // We continue from the contract state at the end of the last test
-    let unbond_requests = vec![
-        UnbondRequest {
-            id: 1,
-            user: Addr::unchecked("user_1"),
-            shares: Uint128::new(23456),
-        },
-        UnbondRequest {
-            id: 1,
-            user: Addr::unchecked("user_3"),
-            shares: Uint128::new(69420),
-        },
-    ];
+    // 1% of 99 utoken rounds down to no fee, the whole reward is bonded
+    let res = reinvest(deps.as_mut(), 99);
+    assert_eq!(res.messages, vec![SubMsg::new(Delegation::new("bob", 99).to_cosmos_msg())]);
 
-    for unbond_request in &unbond_requests {
-        state
-            .unbond_requests
-            .save(
-                deps.as_mut().storage,
-                (unbond_request.id, &Addr::unchecked(unbond_request.user.clone())),
-                unbond_request,
-            )
-            .unwrap();
-    }
+    let res = reinvest(deps.as_mut(), 1);
+    assert_eq!(res.messages, vec![SubMsg::new(Delegation::new("bob", 1).to_cosmos_msg())]);
+
+    // 1% of 199 utoken rounds down to 1 utoken
+    let res = reinvest(deps.as_mut(), 199);
+    assert_eq!(
+        res.messages,
+        vec![
+            SubMsg::new(Delegation::new("bob", 198).to_cosmos_msg()),
+            SubMsg::new(SendFee::new(Addr::unchecked("fee"), 1).to_cosmos_msg())
+        ]
+    );
+
+    // a fee configured before the precision was enforced is rounded down to whole basis points
+    let mut fee_config = state.fee_config.load(deps.as_ref().storage).unwrap();
+    fee_config.protocol_reward_fee = Decimal::from_str("0.019999").unwrap();
+    state.fee_config.save(deps.as_mut().storage, &fee_config).unwrap();
+
+    let res = reinvest(deps.as_mut(), 10000);
+    assert_eq!(
+        res.messages[1],
+        SubMsg::new(SendFee::new(Addr::unchecked("fee"), 199).to_cosmos_msg())
+    );
+}
+
+#[test]
+fn reinvesting_with_cap() {
+    let mut deps = setup_test();
+    let state = State::default();
+
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("owner", &[]),
+        ExecuteMsg::UpdateConfig {
+            protocol_fee_contract: None,
+            protocol_reward_fee: None,
+            operator: None,
+            stages_preset: None,
+            allow_donations: None,
+            delegation_strategy: None,
+            vote_operator: None,
+            guardian: None,
+            exchange_rate_tolerance: None,
+            swap_allowlist: None,
+            swap_max_spread: None,
+            max_reinvest_per_tx: Some(Uint128::new(200)),
+            unbond_allowlist: None,
+            unbond_fee: None,
+            min_hold_seconds: None,
+            reward_smoothing_period: None,
+            validator_fee_share: None,
+            max_harvest_staleness: None,
+            protocol_fee_schedule: None,
+            action_cooldowns: None,
+            express_epoch: None,
+            rebalance_tolerance: None,
+        },
+    )
+    .unwrap();
 
+    deps.querier.set_staking_delegations(&[
+        Delegation::new("alice", 333334),
+        Delegation::new("bob", 333333),
+        Delegation::new("charlie", 333333),
+    ]);
     state
-        .pending_batch
-        .save(
-            deps.as_mut().storage,
-            &PendingBatch {
-                id: 1,
-                ustake_to_burn: Uint128::new(92876), // 23,456 + 69,420
-                est_unbond_start_time: 269200,
-            },
-        )
+        .unlocked_coins
+        .save(deps.as_mut().storage, &vec![Coin::new(500, CONTRACT_DENOM)])
         .unwrap();
 
-    // Anyone can invoke `submit_batch`. Here we continue from the previous test and assume it is
-    // invoked automatically as user 2 submits the unbonding request
-    //
-    // ustake to burn: 23,456 + 69,420 = 92,876
-    // utoken to unbond: 1,037,345 * 92,876 / 1,012,043 = 95,197
-    //
-    // Target: (1,037,345 - 95,197) / 3 = 314,049
-    // Remainer: 1
-    // Alice:   345,782 - (314,049 + 1) = 31,732
-    // Bob:     345,782 - (314,049 + 0) = 31,733
-    // Charlie: 345,781 - (314,049 + 0) = 31,732
+    // only 200 are reinvested, the remaining 300 are carried over
     let res = execute(
         deps.as_mut(),
-        mock_env_at_timestamp(269201),
+        mock_env(),
         mock_info(MOCK_CONTRACT_ADDR, &[]),
-        ExecuteMsg::SubmitBatch {},
+        ExecuteMsg::Callback(CallbackMsg::Reinvest {}),
     )
     .unwrap();
 
-    assert_eq!(res.messages.len(), 5);
-    assert_eq!(res.messages[0], SubMsg::new(Undelegation::new("alice", 31732).to_cosmos_msg()));
-    assert_eq!(res.messages[1], SubMsg::new(Undelegation::new("bob", 31733).to_cosmos_msg()));
-    assert_eq!(res.messages[2], SubMsg::new(Undelegation::new("charlie", 31732).to_cosmos_msg()));
-    assert_eq!(
-        res.messages[3],
-        SubMsg::new(CosmosMsg::Custom(KujiraMsg::Denom(DenomMsg::Burn {
-            denom: STAKE_DENOM.into(),
-            amount: Uint128::new(92876)
-        })))
-    );
-    assert_eq!(res.messages[4], check_received_coin(0, 0));
-
-    // A new pending batch should have been created
-    let pending_batch = state.pending_batch.load(deps.as_ref().storage).unwrap();
+    assert_eq!(res.messages.len(), 2);
+    assert_eq!(res.messages[0], SubMsg::new(Delegation::new("bob", 198).to_cosmos_msg()));
     assert_eq!(
-        pending_batch,
-        PendingBatch {
-            id: 2,
-            ustake_to_burn: Uint128::zero(),
-            est_unbond_start_time: 528401 // 269,201 + 259,200
-        }
+        res.messages[1],
+        SubMsg::new(SendFee::new(Addr::unchecked("fee"), 2).to_cosmos_msg())
     );
-
-    // Previous batch should have been updated
-    let previous_batch = state.previous_batches.load(deps.as_ref().storage, 1u64).unwrap();
     assert_eq!(
-        previous_batch,
-        Batch {
-            id: 1,
-            reconciled: false,
-            total_shares: Uint128::new(92876),
-            utoken_unclaimed: Uint128::new(95197),
-            est_unbond_end_time: 2083601 // 269,201 + 1,814,400
-        }
+        res.events[0],
+        Event::new("erishub/harvested")
+            .add_attribute("utoken_bonded", "198")
+            .add_attribute("utoken_protocol_fee", "2")
+            .add_attribute("utoken_validator_fee", "0")
+            .add_attribute("utoken_carried_over", "300")
     );
 
-    let res: StateResponse = query_helper_env(deps.as_ref(), QueryMsg::State {}, 2083600);
+    let unlocked_coins = state.unlocked_coins.load(deps.as_ref().storage).unwrap();
+    assert_eq!(unlocked_coins, vec![Coin::new(300, CONTRACT_DENOM)]);
 
-    let total_ustake = Uint128::from(1012043u128).sub(Uint128::new(23456)).sub(Uint128::new(69420));
-    assert_eq!(
-        res,
-        StateResponse {
-            total_ustake,
-            total_utoken: Uint128::from(1037345u128),
-            exchange_rate: Decimal::from_ratio(1037345u128, total_ustake.u128()),
-            unlocked_coins: vec![],
-            unbonding: Uint128::from(95197u128),
-            available: Uint128::zero(),
-            tvl_utoken: Uint128::from(95197u128 + 1037345u128),
+    // removing the cap reinvests everything
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("owner", &[]),
+        ExecuteMsg::UpdateConfig {
+            protocol_fee_contract: None,
+            protocol_reward_fee: None,
+            operator: None,
+            stages_preset: None,
+            allow_donations: None,
+            delegation_strategy: None,
+            vote_operator: None,
+            guardian: None,
+            exchange_rate_tolerance: None,
+            swap_allowlist: None,
+            swap_max_spread: None,
+            max_reinvest_per_tx: Some(Uint128::zero()),
+            unbond_allowlist: None,
+            unbond_fee: None,
+            min_hold_seconds: None,
+            reward_smoothing_period: None,
+            validator_fee_share: None,
+            max_harvest_staleness: None,
+            protocol_fee_schedule: None,
+            action_cooldowns: None,
+            express_epoch: None,
+            rebalance_tolerance: None,
         },
-    );
+    )
+    .unwrap();
+
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info(MOCK_CONTRACT_ADDR, &[]),
+        ExecuteMsg::Callback(CallbackMsg::Reinvest {}),
+    )
+    .unwrap();
+
+    let unlocked_coins = state.unlocked_coins.load(deps.as_ref().storage).unwrap();
+    assert_eq!(unlocked_coins, vec![]);
 }
 
 #[test]
-fn reconciling() {
+fn reinvesting_with_reward_smoothing() {
     let mut deps = setup_test();
     let state = State::default();
 
-    let previous_batches = vec![
-        Batch {
-            id: 1,
-            reconciled: true,
-            total_shares: Uint128::new(92876),
-            utoken_unclaimed: Uint128::new(95197), // 1.025 Token per Stake
-            est_unbond_end_time: 10000,
-        },
-        Batch {
-            id: 2,
-            reconciled: false,
-            total_shares: Uint128::new(1345),
-            utoken_unclaimed: Uint128::new(1385), // 1.030 Token per Stake
-            est_unbond_end_time: 20000,
-        },
-        Batch {
-            id: 3,
-            reconciled: false,
-            total_shares: Uint128::new(1456),
-            utoken_unclaimed: Uint128::new(1506), // 1.035 Token per Stake
-            est_unbond_end_time: 30000,
-        },
-        Batch {
-            id: 4,
-            reconciled: false,
-            total_shares: Uint128::new(1567),
-            utoken_unclaimed: Uint128::new(1629), // 1.040 Token per Stake
-            est_unbond_end_time: 40000,           // not yet finished unbonding, ignored
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("owner", &[]),
+        ExecuteMsg::UpdateConfig {
+            protocol_fee_contract: None,
+            protocol_reward_fee: None,
+            operator: None,
+            stages_preset: None,
+            allow_donations: None,
+            delegation_strategy: None,
+            vote_operator: None,
+            guardian: None,
+            exchange_rate_tolerance: None,
+            swap_allowlist: None,
+            swap_max_spread: None,
+            max_reinvest_per_tx: None,
+            unbond_allowlist: None,
+            unbond_fee: None,
+            min_hold_seconds: None,
+            reward_smoothing_period: Some(1000),
+            validator_fee_share: None,
+            max_harvest_staleness: None,
+            protocol_fee_schedule: None,
+            action_cooldowns: None,
+            express_epoch: None,
+            rebalance_tolerance: None,
         },
-    ];
+    )
+    .unwrap();
 
-    for previous_batch in &previous_batches {
-        state
-            .previous_batches
-            .save(deps.as_mut().storage, previous_batch.id, previous_batch)
-            .unwrap();
-    }
+    let res: ConfigResponse = query_helper(deps.as_ref(), QueryMsg::Config {});
+    assert_eq!(res.reward_smoothing_period, 1000);
 
+    set_total_stake_supply(&state, &mut deps, 1000000);
+    deps.querier.set_staking_delegations(&[
+        Delegation::new("alice", 333334),
+        Delegation::new("bob", 333333),
+        Delegation::new("charlie", 333333),
+    ]);
     state
         .unlocked_coins
-        .save(
-            deps.as_mut().storage,
-            &vec![
-                Coin::new(10000, CONTRACT_DENOM),
-                Coin::new(234, "ukrw"),
-                Coin::new(345, "uusd"),
-                Coin::new(
-                    69420,
-                    "ibc/0471F1C4E7AFD3F07702BEF6DC365268D64570F7C1FDC98EA6098DD6DE59817B",
-                ),
-            ],
-        )
+        .save(deps.as_mut().storage, &vec![Coin::new(10100, CONTRACT_DENOM)])
         .unwrap();
 
-    deps.querier.set_bank_balances(&[
-        Coin::new(12345, CONTRACT_DENOM),
-        Coin::new(234, "ukrw"),
-        Coin::new(345, "uusd"),
-        Coin::new(69420, "ibc/0471F1C4E7AFD3F07702BEF6DC365268D64570F7C1FDC98EA6098DD6DE59817B"),
-    ]);
-
-    execute(
+    let res = execute(
         deps.as_mut(),
-        mock_env_at_timestamp(35000),
-        mock_info("worker", &[]),
-        ExecuteMsg::Reconcile {},
+        mock_env_at_timestamp(10000),
+        mock_info(MOCK_CONTRACT_ADDR, &[]),
+        ExecuteMsg::Callback(CallbackMsg::Reinvest {}),
     )
     .unwrap();
+    assert_eq!(res.messages[0], SubMsg::new(Delegation::new("bob", 9999).to_cosmos_msg()));
 
-    // Expected received: batch 2 + batch 3 = 1385 + 1506 = 2891
-    // Expected unlocked: 10000
-    // Expected: 12891
-    // Actual: 12345
-    // Shortfall: 12891 - 12345 = 456
-    //
-    // utoken per batch: 546 / 2 = 273
-    // remainder: 0
-    // batch 2: 1385 - 273 = 1112
-    // batch 3: 1506 - 273 = 1233
-    let batch = state.previous_batches.load(deps.as_ref().storage, 2u64).unwrap();
+    // the rewards are staked, but the exchange rate is unchanged
     assert_eq!(
-        batch,
-        Batch {
-            id: 2,
-            reconciled: true,
-            total_shares: Uint128::new(1345),
-            utoken_unclaimed: Uint128::new(1112), // 1385 - 273
-            est_unbond_end_time: 20000,
-        }
+        state.exchange_rate_history.load(deps.as_ref().storage).unwrap(),
+        vec![(10000, Decimal::one())]
     );
 
-    let batch = state.previous_batches.load(deps.as_ref().storage, 3u64).unwrap();
-    assert_eq!(
-        batch,
-        Batch {
-            id: 3,
-            reconciled: true,
-            total_shares: Uint128::new(1456),
-            utoken_unclaimed: Uint128::new(1233), // 1506 - 273
-            est_unbond_end_time: 30000,
-        }
-    );
+    deps.querier.set_staking_delegations(&[
+        Delegation::new("alice", 333334),
+        Delegation::new("bob", 343332),
+        Delegation::new("charlie", 333333),
+    ]);
 
-    // Batches 1 and 4 should not have changed
-    let batch = state.previous_batches.load(deps.as_ref().storage, 1u64).unwrap();
-    assert_eq!(batch, previous_batches[0]);
+    let res: StateResponse = query_helper_env(deps.as_ref(), QueryMsg::State {}, 10000);
+    assert_eq!(res.total_utoken, Uint128::new(1000000));
+    assert_eq!(res.utoken_unvested, Uint128::new(9999));
+    assert_eq!(res.exchange_rate, Decimal::one());
 
-    let batch = state.previous_batches.load(deps.as_ref().storage, 4u64).unwrap();
-    assert_eq!(batch, previous_batches[3]);
-}
+    // half of the rewards are released after half of the period
+    let res: StateResponse = query_helper_env(deps.as_ref(), QueryMsg::State {}, 10500);
+    assert_eq!(res.total_utoken, Uint128::new(1005000));
+    assert_eq!(res.utoken_unvested, Uint128::new(4999));
 
-#[test]
-fn reconciling_even_when_everything_ok() {
+    // bonding uses the released rewards only
+    deps.querier.set_bank_balances(&[coin(100500, CONTRACT_DENOM)]);
+    execute(
+        deps.as_mut(),
+        mock_env_at_timestamp(10500),
+        mock_info("user_1", &[Coin::new(100500, CONTRACT_DENOM)]),
+        ExecuteMsg::Bond {
+            receiver: None,
+            min_ustake_received: None,
+        },
+    )
+    .unwrap();
+    assert_eq!(
+        state.stake_token.load(deps.as_ref().storage).unwrap().total_supply,
+        Uint128::new(1100000)
+    );
+
+    // everything is released at the end of the period
+    let res: StateResponse = query_helper_env(deps.as_ref(), QueryMsg::State {}, 11000);
+    assert_eq!(res.total_utoken, Uint128::new(1009999));
+    assert_eq!(res.utoken_unvested, Uint128::zero());
+}
+
+#[test]
+fn reinvesting_with_validator_fee_share() {
     let mut deps = setup_test();
     let state = State::default();
 
-    let previous_batches = vec![
-        Batch {
-            id: 1,
-            reconciled: true,
-            total_shares: Uint128::new(100000),
-            utoken_unclaimed: Uint128::new(100000),
-            est_unbond_end_time: 10000,
-        },
-        Batch {
-            id: 2,
-            reconciled: false,
-            total_shares: Uint128::new(1000),
-            utoken_unclaimed: Uint128::new(1000),
-            est_unbond_end_time: 20000,
-        },
-        Batch {
-            id: 3,
-            reconciled: false,
-            total_shares: Uint128::new(1500),
-            utoken_unclaimed: Uint128::new(1500),
-            est_unbond_end_time: 30000,
-        },
-        Batch {
-            id: 4,
-            reconciled: false,
-            total_shares: Uint128::new(1500),
-            utoken_unclaimed: Uint128::new(1500),
-            est_unbond_end_time: 40000, // not yet finished unbonding, ignored
-        },
-    ];
+    let update_share = |share: Decimal| ExecuteMsg::UpdateConfig {
+        protocol_fee_contract: None,
+        protocol_reward_fee: None,
+        operator: None,
+        stages_preset: None,
+        allow_donations: None,
+        delegation_strategy: None,
+        vote_operator: None,
+        guardian: None,
+        exchange_rate_tolerance: None,
+        swap_allowlist: None,
+        swap_max_spread: None,
+        max_reinvest_per_tx: None,
+        unbond_allowlist: None,
+        unbond_fee: None,
+        min_hold_seconds: None,
+        reward_smoothing_period: None,
+        validator_fee_share: Some(share),
+        max_harvest_staleness: None,
+        protocol_fee_schedule: None,
+        action_cooldowns: None,
+        express_epoch: None,
+        rebalance_tolerance: None,
+    };
 
-    for previous_batch in &previous_batches {
-        state
-            .previous_batches
-            .save(deps.as_mut().storage, previous_batch.id, previous_batch)
+    let err = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("owner", &[]),
+        update_share(Decimal::percent(51)),
+    )
+    .unwrap_err();
+    assert_eq!(err, ContractError::ValidatorFeeShareTooHigh {});
+
+    execute(deps.as_mut(), mock_env(), mock_info("owner", &[]), update_share(Decimal::percent(50)))
+        .unwrap();
+
+    let set_payout = |validator: &str, payout: &str| ExecuteMsg::SetValidatorPayout {
+        validator: validator.to_string(),
+        payout: Some(payout.to_string()),
+    };
+
+    let err = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("jake", &[]),
+        set_payout("alice", "alice_payout"),
+    )
+    .unwrap_err();
+    assert_eq!(err, ContractError::unauthorized(Role::Owner, &Addr::unchecked("jake")));
+
+    let err = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("owner", &[]),
+        set_payout("dave", "dave_payout"),
+    )
+    .unwrap_err();
+    assert_eq!(err, ContractError::ValidatorNotWhitelisted("dave".into()));
+
+    for (validator, payout) in [("alice", "alice_payout"), ("bob", "bob_payout")] {
+        execute(deps.as_mut(), mock_env(), mock_info("owner", &[]), set_payout(validator, payout))
             .unwrap();
     }
 
+    // the validator manages the payout address afterwards
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("alice_payout", &[]),
+        set_payout("alice", "alice_payout_2"),
+    )
+    .unwrap();
+
+    let res: Vec<ValidatorPayout> = query_helper(deps.as_ref(), QueryMsg::ValidatorPayouts {});
+    assert_eq!(
+        res,
+        vec![
+            ValidatorPayout {
+                validator: "alice".into(),
+                payout: Addr::unchecked("alice_payout_2"),
+            },
+            ValidatorPayout {
+                validator: "bob".into(),
+                payout: Addr::unchecked("bob_payout"),
+            },
+        ]
+    );
+
+    deps.querier.set_staking_delegations(&[
+        Delegation::new("alice", 300000),
+        Delegation::new("bob", 100000),
+        Delegation::new("charlie", 333333),
+    ]);
     state
         .unlocked_coins
-        .save(deps.as_mut().storage, &vec![Coin::new(1000, CONTRACT_DENOM)])
+        .save(deps.as_mut().storage, &vec![Coin::new(100000, CONTRACT_DENOM)])
         .unwrap();
 
-    deps.querier.set_bank_balances(&[Coin::new(3500, CONTRACT_DENOM)]);
-
-    execute(
+    // half of the fee of 1000 goes to alice and bob pro-rata to their delegations, charlie has no
+    // payout address
+    let res = execute(
         deps.as_mut(),
-        mock_env_at_timestamp(35000),
-        mock_info("worker", &[]),
-        ExecuteMsg::Reconcile {},
+        mock_env(),
+        mock_info(MOCK_CONTRACT_ADDR, &[]),
+        ExecuteMsg::Callback(CallbackMsg::Reinvest {}),
     )
     .unwrap();
 
-    let batch = state.previous_batches.load(deps.as_ref().storage, 2u64).unwrap();
     assert_eq!(
-        batch,
-        Batch {
-            id: 2,
-            reconciled: true,
-            total_shares: Uint128::new(1000),
-            utoken_unclaimed: Uint128::new(1000),
-            est_unbond_end_time: 20000,
-        }
+        res.messages,
+        vec![
+            SubMsg::new(Delegation::new("bob", 99000).to_cosmos_msg()),
+            SubMsg::new(SendFee::new(Addr::unchecked("fee"), 500).to_cosmos_msg()),
+            SubMsg::new(SendFee::new(Addr::unchecked("alice_payout_2"), 375).to_cosmos_msg()),
+            SubMsg::new(SendFee::new(Addr::unchecked("bob_payout"), 125).to_cosmos_msg()),
+        ]
     );
-
-    let batch = state.previous_batches.load(deps.as_ref().storage, 3u64).unwrap();
     assert_eq!(
-        batch,
-        Batch {
-            id: 3,
-            reconciled: true,
-            total_shares: Uint128::new(1500),
-            utoken_unclaimed: Uint128::new(1500),
-            est_unbond_end_time: 30000,
-        }
+        res.events[0],
+        Event::new("erishub/harvested")
+            .add_attribute("utoken_bonded", "99000")
+            .add_attribute("utoken_protocol_fee", "1000")
+            .add_attribute("utoken_validator_fee", "500")
+            .add_attribute("utoken_carried_over", "0")
     );
-
-    // Batches 1 and 4 should not have changed
-    let batch = state.previous_batches.load(deps.as_ref().storage, 1u64).unwrap();
-    assert_eq!(batch, previous_batches[0]);
-
-    let batch = state.previous_batches.load(deps.as_ref().storage, 4u64).unwrap();
-    assert_eq!(batch, previous_batches[3]);
 }
 
 #[test]
-fn reconciling_underflow() {
+fn reinvesting_with_oracle_valuation() {
     let mut deps = setup_test();
     let state = State::default();
-    let previous_batches = vec![
-        Batch {
-            id: 1,
-            reconciled: true,
-            total_shares: Uint128::new(92876),
-            utoken_unclaimed: Uint128::new(95197), // 1.025 Token per Stake
-            est_unbond_end_time: 10000,
-        },
-        Batch {
-            id: 2,
-            reconciled: false,
-            total_shares: Uint128::new(1345),
-            utoken_unclaimed: Uint128::new(1385), // 1.030 Token per Stake
-            est_unbond_end_time: 20000,
-        },
-        Batch {
-            id: 3,
-            reconciled: false,
-            total_shares: Uint128::new(1456),
-            utoken_unclaimed: Uint128::new(1506), // 1.035 Token per Stake
-            est_unbond_end_time: 30000,
-        },
-        Batch {
-            id: 4,
-            reconciled: false,
-            total_shares: Uint128::new(1),
-            utoken_unclaimed: Uint128::new(1),
-            est_unbond_end_time: 30001,
-        },
-    ];
-    for previous_batch in &previous_batches {
-        state
-            .previous_batches
-            .save(deps.as_mut().storage, previous_batch.id, previous_batch)
-            .unwrap();
-    }
+
+    deps.querier.set_oracle_price("KUJI", Decimal::from_str("0.8").unwrap());
+    deps.querier.set_staking_delegations(&[
+        Delegation::new("alice", 333334),
+        Delegation::new("bob", 333333),
+        Delegation::new("charlie", 333333),
+    ]);
     state
         .unlocked_coins
-        .save(
-            deps.as_mut().storage,
-            &vec![
-                Coin::new(10000, CONTRACT_DENOM),
-                Coin::new(234, "ukrw"),
-                Coin::new(345, "uusd"),
-                Coin::new(
-                    69420,
-                    "ibc/0471F1C4E7AFD3F07702BEF6DC365268D64570F7C1FDC98EA6098DD6DE59817B",
-                ),
-            ],
-        )
+        .save(deps.as_mut().storage, &vec![Coin::new(250000, CONTRACT_DENOM)])
         .unwrap();
-    deps.querier.set_bank_balances(&[
-        Coin::new(12345, CONTRACT_DENOM),
-        Coin::new(234, "ukrw"),
-        Coin::new(345, "uusd"),
-        Coin::new(69420, "ibc/0471F1C4E7AFD3F07702BEF6DC365268D64570F7C1FDC98EA6098DD6DE59817B"),
-    ]);
-    execute(
+
+    let res = execute(
         deps.as_mut(),
-        mock_env_at_timestamp(35000),
-        mock_info("worker", &[]),
-        ExecuteMsg::Reconcile {},
+        mock_env(),
+        mock_info(MOCK_CONTRACT_ADDR, &[]),
+        ExecuteMsg::Callback(CallbackMsg::Reinvest {}),
     )
     .unwrap();
+
+    // a fee of 2500 ukuji is worth 0.0025 KUJI
+    assert_eq!(
+        res.events[0],
+        Event::new("erishub/harvested")
+            .add_attribute("utoken_bonded", "247500")
+            .add_attribute("utoken_protocol_fee", "2500")
+            .add_attribute("utoken_validator_fee", "0")
+            .add_attribute("utoken_carried_over", "0")
+            .add_attribute("fee_usd", "0.002")
+    );
 }
 
 #[test]
-fn reconciling_underflow_second() {
+fn tripping_circuit_breaker() {
     let mut deps = setup_test();
     let state = State::default();
-    let previous_batches = vec![
-        Batch {
-            id: 1,
-            reconciled: true,
-            total_shares: Uint128::new(92876),
-            utoken_unclaimed: Uint128::new(95197), // 1.025 Token per Stake
-            est_unbond_end_time: 10000,
-        },
-        Batch {
-            id: 2,
-            reconciled: false,
-            total_shares: Uint128::new(1345),
-            utoken_unclaimed: Uint128::new(1385), // 1.030 Token per Stake
-            est_unbond_end_time: 20000,
-        },
-        Batch {
-            id: 3,
-            reconciled: false,
-            total_shares: Uint128::new(176),
-            utoken_unclaimed: Uint128::new(183), // 1.035 Token per Stake
-            est_unbond_end_time: 30000,
-        },
-        Batch {
-            id: 4,
-            reconciled: false,
-            total_shares: Uint128::new(1),
-            utoken_unclaimed: Uint128::new(1),
-            est_unbond_end_time: 30001,
+
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("owner", &[]),
+        ExecuteMsg::UpdateConfig {
+            protocol_fee_contract: None,
+            protocol_reward_fee: None,
+            operator: None,
+            stages_preset: None,
+            allow_donations: None,
+            delegation_strategy: None,
+            vote_operator: None,
+            guardian: None,
+            exchange_rate_tolerance: Some(Decimal::from_ratio(1u128, 100u128)),
+            swap_allowlist: None,
+            swap_max_spread: None,
+            max_reinvest_per_tx: None,
+            unbond_allowlist: None,
+            unbond_fee: None,
+            min_hold_seconds: None,
+            reward_smoothing_period: None,
+            validator_fee_share: None,
+            max_harvest_staleness: None,
+            protocol_fee_schedule: None,
+            action_cooldowns: None,
+            express_epoch: None,
+            rebalance_tolerance: None,
         },
-    ];
-    for previous_batch in &previous_batches {
-        state
-            .previous_batches
-            .save(deps.as_mut().storage, previous_batch.id, previous_batch)
-            .unwrap();
-    }
+    )
+    .unwrap();
+
+    set_total_stake_supply(&state, &mut deps, 1000000);
+    deps.querier.set_staking_delegations(&[
+        Delegation::new("alice", 333334),
+        Delegation::new("bob", 333333),
+        Delegation::new("charlie", 333333),
+    ]);
+    state
+        .exchange_rate_history
+        .save(deps.as_mut().storage, &vec![(10000, Decimal::from_str("1.1").unwrap())])
+        .unwrap();
     state
         .unlocked_coins
-        .save(
-            deps.as_mut().storage,
-            &vec![
-                Coin::new(10000, CONTRACT_DENOM),
-                Coin::new(234, "ukrw"),
-                Coin::new(345, "uusd"),
-                Coin::new(
-                    69420,
-                    "ibc/0471F1C4E7AFD3F07702BEF6DC365268D64570F7C1FDC98EA6098DD6DE59817B",
-                ),
-            ],
-        )
+        .save(deps.as_mut().storage, &vec![Coin::new(234, CONTRACT_DENOM)])
         .unwrap();
-    deps.querier.set_bank_balances(&[
-        Coin::new(12345 - 1323, CONTRACT_DENOM),
-        Coin::new(234, "ukrw"),
-        Coin::new(345, "uusd"),
-        Coin::new(69420, "ibc/0471F1C4E7AFD3F07702BEF6DC365268D64570F7C1FDC98EA6098DD6DE59817B"),
-    ]);
+
+    // 1.000232 is more than 1% below 1.1
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info(MOCK_CONTRACT_ADDR, &[]),
+        ExecuteMsg::Callback(CallbackMsg::Reinvest {}),
+    )
+    .unwrap();
+
+    assert_eq!(
+        res.events[1],
+        Event::new("erishub/circuit_breaker_tripped")
+            .add_attribute("previous_exchange_rate", "1.1")
+            .add_attribute("exchange_rate", "1.000232")
+    );
+
+    deps.querier.set_bank_balances(&[coin(1000, CONTRACT_DENOM)]);
+    let err = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("user_1", &[Coin::new(1000, CONTRACT_DENOM)]),
+        ExecuteMsg::Bond {
+            receiver: None,
+            min_ustake_received: None,
+        },
+    )
+    .unwrap_err();
+    assert_eq!(err, ContractError::CircuitBreakerTripped {});
+
+    let err = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("user_1", &[Coin::new(1000, STAKE_DENOM)]),
+        ExecuteMsg::QueueUnbond {
+            receiver: None,
+        },
+    )
+    .unwrap_err();
+    assert_eq!(err, ContractError::CircuitBreakerTripped {});
+
+    // without a guardian the owner can clear the flag
+    let err = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("jake", &[]),
+        ExecuteMsg::ClearCircuitBreaker {},
+    )
+    .unwrap_err();
+    assert_eq!(
+        err,
+        ContractError::Unauthorized {
+            required_role: Role::Owner,
+            sender: "jake".to_string()
+        }
+    );
+
+    execute(deps.as_mut(), mock_env(), mock_info("owner", &[]), ExecuteMsg::ClearCircuitBreaker {})
+        .unwrap();
+
     execute(
         deps.as_mut(),
-        mock_env_at_timestamp(35000),
-        mock_info("worker", &[]),
-        ExecuteMsg::Reconcile {},
+        mock_env(),
+        mock_info("user_1", &[Coin::new(1000, CONTRACT_DENOM)]),
+        ExecuteMsg::Bond {
+            receiver: None,
+            min_ustake_received: None,
+        },
     )
     .unwrap();
 }
 
 #[test]
-fn withdrawing_unbonded() {
+fn queuing_unbond() {
     let mut deps = setup_test();
     let state = State::default();
 
-    // We simulate a most general case:
-    // - batches 1 and 2 have finished unbonding
-    // - batch 3 have been submitted for unbonding but have not finished
-    // - batch 4 is still pending
-    let unbond_requests = vec![
-        UnbondRequest {
-            id: 1,
-            user: Addr::unchecked("user_1"),
-            shares: Uint128::new(23456),
+    // Only Stake token is accepted for unbonding requests
+    let err = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("random_sender", &[Coin::new(100, "random_token")]),
+        ExecuteMsg::QueueUnbond {
+            receiver: None,
         },
-        UnbondRequest {
-            id: 1,
-            user: Addr::unchecked("user_3"),
-            shares: Uint128::new(69420),
+    )
+    .unwrap_err();
+
+    assert_eq!(err, ContractError::ExpectingStakeToken("random_token".into()));
+
+    // User 1 creates an unbonding request before `est_unbond_start_time` is reached. The unbond
+    // request is saved, but not the pending batch is not submitted for unbonding
+    let res = execute(
+        deps.as_mut(),
+        mock_env_at_timestamp(12345), // est_unbond_start_time = 269200
+        mock_info("user_1", &[Coin::new(23456, STAKE_DENOM)]),
+        ExecuteMsg::QueueUnbond {
+            receiver: None,
         },
-        UnbondRequest {
-            id: 2,
-            user: Addr::unchecked("user_1"),
-            shares: Uint128::new(34567),
+    )
+    .unwrap();
+
+    assert_eq!(res.messages.len(), 0);
+
+    // User 2 creates an unbonding request after `est_unbond_start_time` is reached. The unbond
+    // request is saved, and the pending is automatically submitted for unbonding
+    let res = execute(
+        deps.as_mut(),
+        mock_env_at_timestamp(269201), // est_unbond_start_time = 269200
+        mock_info("user_2", &[Coin::new(69420, STAKE_DENOM)]),
+        ExecuteMsg::QueueUnbond {
+            receiver: Some("user_3".to_string()),
         },
+    )
+    .unwrap();
+
+    assert_eq!(res.messages.len(), 1);
+    assert_eq!(
+        res.messages[0],
+        SubMsg::new(CosmosMsg::Wasm(WasmMsg::Execute {
+            contract_addr: MOCK_CONTRACT_ADDR.to_string(),
+            msg: to_binary(&ExecuteMsg::SubmitBatch {}).unwrap(),
+            funds: vec![]
+        }))
+    );
+
+    // The users' unbonding requests should have been saved
+    let ubr1 = state
+        .unbond_requests
+        .load(deps.as_ref().storage, (1u64, &Addr::unchecked("user_1")))
+        .unwrap();
+    let ubr2 = state
+        .unbond_requests
+        .load(deps.as_ref().storage, (1u64, &Addr::unchecked("user_3")))
+        .unwrap();
+
+    assert_eq!(
+        ubr1,
         UnbondRequest {
-            id: 3,
+            id: 1,
             user: Addr::unchecked("user_1"),
-            shares: Uint128::new(45678),
-        },
+            shares: Uint128::new(23456)
+        }
+    );
+    assert_eq!(
+        ubr2,
         UnbondRequest {
-            id: 4,
-            user: Addr::unchecked("user_1"),
-            shares: Uint128::new(56789),
-        },
-    ];
+            id: 1,
+            user: Addr::unchecked("user_3"),
+            shares: Uint128::new(69420)
+        }
+    );
 
-    for unbond_request in &unbond_requests {
-        state
-            .unbond_requests
-            .save(
-                deps.as_mut().storage,
-                (unbond_request.id, &Addr::unchecked(unbond_request.user.clone())),
-                unbond_request,
-            )
-            .unwrap();
-    }
+    // Pending batch should have been updated
+    let pending_batch = state.pending_batch.load(deps.as_ref().storage).unwrap();
+    assert_eq!(
+        pending_batch,
+        PendingBatch {
+            id: 1,
+            ustake_to_burn: Uint128::new(92876), // 23,456 + 69,420
+            est_unbond_start_time: 269200
+        }
+    );
 
-    let previous_batches = vec![
-        Batch {
+    // The stake tokens are held by the contract until the batch is submitted
+    let ustake_escrowed = state.ustake_escrowed.load(deps.as_ref().storage).unwrap();
+    assert_eq!(ustake_escrowed, Uint128::new(92876));
+
+    // Batch totals should have been updated
+    let res: UnbondRequestsByBatchTotalsResponse = query_helper(
+        deps.as_ref(),
+        QueryMsg::UnbondRequestsByBatchTotals {
             id: 1,
-            reconciled: true,
-            total_shares: Uint128::new(92876),
-            utoken_unclaimed: Uint128::new(95197), // 1.025 Token per Stake
-            est_unbond_end_time: 10000,
-        },
-        Batch {
-            id: 2,
-            reconciled: true,
-            total_shares: Uint128::new(34567),
-            utoken_unclaimed: Uint128::new(35604), // 1.030 Token per Stake
-            est_unbond_end_time: 20000,
-        },
-        Batch {
-            id: 3,
-            reconciled: false, // finished unbonding, but not reconciled; ignored
-            total_shares: Uint128::new(45678),
-            utoken_unclaimed: Uint128::new(47276), // 1.035 Token per Stake
-            est_unbond_end_time: 20000,
-        },
-        Batch {
-            id: 4,
-            reconciled: true,
-            total_shares: Uint128::new(56789),
-            utoken_unclaimed: Uint128::new(59060), // 1.040 Token per Stake
-            est_unbond_end_time: 30000, // reconciled, but not yet finished unbonding; ignored
         },
-    ];
+    );
+    assert_eq!(
+        res,
+        UnbondRequestsByBatchTotalsResponse {
+            id: 1,
+            count: 2,
+            shares: Uint128::new(92876)
+        }
+    );
+}
 
-    for previous_batch in &previous_batches {
-        state
-            .previous_batches
-            .save(deps.as_mut().storage, previous_batch.id, previous_batch)
-            .unwrap();
-    }
+#[test]
+fn queuing_unbond_for_allowlisted_contracts() {
+    let mut deps = setup_test();
+    let state = State::default();
 
-    state
-        .pending_batch
-        .save(
-            deps.as_mut().storage,
-            &PendingBatch {
-                id: 4,
-                ustake_to_burn: Uint128::new(56789),
-                est_unbond_start_time: 100000,
+    let update_allowlist = |unbond_allowlist: Vec<&str>| ExecuteMsg::UpdateConfig {
+        protocol_fee_contract: None,
+        protocol_reward_fee: None,
+        operator: None,
+        stages_preset: None,
+        swap_allowlist: None,
+        swap_max_spread: None,
+        allow_donations: None,
+        delegation_strategy: None,
+        vote_operator: None,
+        guardian: None,
+        exchange_rate_tolerance: None,
+        max_reinvest_per_tx: None,
+        unbond_allowlist: Some(unbond_allowlist.into_iter().map(String::from).collect()),
+        unbond_fee: None,
+        min_hold_seconds: None,
+        reward_smoothing_period: None,
+        validator_fee_share: None,
+        max_harvest_staleness: None,
+        protocol_fee_schedule: None,
+        action_cooldowns: None,
+        express_epoch: None,
+        rebalance_tolerance: None,
+    };
+
+    // only contracts can be allow-listed
+    deps.querier.set_account("user_2");
+    let err = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("owner", &[]),
+        update_allowlist(vec!["arb_vault", "user_2"]),
+    )
+    .unwrap_err();
+    assert_eq!(
+        err,
+        ContractError::NotAContract {
+            field: "unbond_allowlist".into(),
+            addr: "user_2".into()
+        }
+    );
+
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("owner", &[]),
+        update_allowlist(vec!["arb_vault", "arb_vault"]),
+    )
+    .unwrap();
+
+    let res: ConfigResponse = query_helper(deps.as_ref(), QueryMsg::Config {});
+    assert_eq!(res.unbond_allowlist, vec!["arb_vault".to_string()]);
+
+    for (sender, amount) in [("user_1", 23456), ("arb_vault", 10000), ("arb_vault", 5000)] {
+        execute(
+            deps.as_mut(),
+            mock_env_at_timestamp(12345),
+            mock_info(sender, &[Coin::new(amount, STAKE_DENOM)]),
+            ExecuteMsg::QueueUnbond {
+                receiver: None,
             },
         )
         .unwrap();
+    }
+
+    // both are unbonded in the same batch
+    let pending_batch = state.pending_batch.load(deps.as_ref().storage).unwrap();
+    assert_eq!(pending_batch.ustake_to_burn, Uint128::new(38456));
+
+    // but the contract is not part of the user statistics
+    let res: UnbondRequestsByBatchTotalsResponse = query_helper(
+        deps.as_ref(),
+        QueryMsg::UnbondRequestsByBatchTotals {
+            id: 1,
+        },
+    );
+    assert_eq!(
+        res,
+        UnbondRequestsByBatchTotalsResponse {
+            id: 1,
+            count: 1,
+            shares: Uint128::new(23456),
+        }
+    );
+
+    let res: UnbondRequestsByBatchTotalsResponse = query_helper(
+        deps.as_ref(),
+        QueryMsg::AllowlistUnbondTotals {
+            id: 1,
+        },
+    );
+    assert_eq!(
+        res,
+        UnbondRequestsByBatchTotalsResponse {
+            id: 1,
+            count: 1,
+            shares: Uint128::new(15000),
+        }
+    );
+
+    // a contract leaving the allowlist keeps its request in the totals it was added to
+    execute(deps.as_mut(), mock_env(), mock_info("owner", &[]), update_allowlist(vec![])).unwrap();
+    execute(
+        deps.as_mut(),
+        mock_env_at_timestamp(12345),
+        mock_info("arb_vault", &[Coin::new(1000, STAKE_DENOM)]),
+        ExecuteMsg::QueueUnbond {
+            receiver: None,
+        },
+    )
+    .unwrap();
+
+    let totals = state.unbond_requests_totals.load(deps.as_ref().storage, 1).unwrap();
+    assert_eq!(totals.count, 1);
+    assert_eq!(totals.shares, Uint128::new(23456));
+    let totals = state.allowlist_unbond_totals.load(deps.as_ref().storage, 1).unwrap();
+    assert_eq!(totals.count, 1);
+    assert_eq!(totals.shares, Uint128::new(16000));
+
+    // and is removed from them when the request moves to a user
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("arb_vault", &[]),
+        ExecuteMsg::TransferUnbondRequest {
+            id: 1,
+            recipient: "user_2".to_string(),
+            shares: None,
+        },
+    )
+    .unwrap();
+
+    let totals = state.unbond_requests_totals.load(deps.as_ref().storage, 1).unwrap();
+    assert_eq!(totals.count, 2);
+    assert_eq!(totals.shares, Uint128::new(39456));
+    assert!(state.allowlist_unbond_totals.may_load(deps.as_ref().storage, 1).unwrap().is_none());
+}
+
+#[test]
+fn queuing_unbond_before_hold_period() {
+    let mut deps = setup_test();
+
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("owner", &[]),
+        ExecuteMsg::UpdateConfig {
+            protocol_fee_contract: None,
+            protocol_reward_fee: None,
+            operator: None,
+            stages_preset: None,
+            swap_allowlist: None,
+            swap_max_spread: None,
+            allow_donations: None,
+            delegation_strategy: None,
+            vote_operator: None,
+            guardian: None,
+            exchange_rate_tolerance: None,
+            max_reinvest_per_tx: None,
+            unbond_allowlist: None,
+            unbond_fee: None,
+            min_hold_seconds: Some(100),
+            reward_smoothing_period: None,
+            validator_fee_share: None,
+            max_harvest_staleness: None,
+            protocol_fee_schedule: None,
+            action_cooldowns: None,
+            express_epoch: None,
+            rebalance_tolerance: None,
+        },
+    )
+    .unwrap();
+
+    let res: ConfigResponse = query_helper(deps.as_ref(), QueryMsg::Config {});
+    assert_eq!(res.min_hold_seconds, 100);
+
+    let bond = |deps: DepsMut, sender: &str, receiver: &str, timestamp: u64| {
+        execute(
+            deps,
+            mock_env_at_timestamp(timestamp),
+            mock_info(sender, &[Coin::new(1000000, CONTRACT_DENOM)]),
+            ExecuteMsg::Bond {
+                receiver: Some(receiver.to_string()),
+                min_ustake_received: None,
+            },
+        )
+    };
+    let queue_unbond = |deps: DepsMut, sender: &str, timestamp: u64| {
+        execute(
+            deps,
+            mock_env_at_timestamp(timestamp),
+            mock_info(sender, &[Coin::new(10000, STAKE_DENOM)]),
+            ExecuteMsg::QueueUnbond {
+                receiver: None,
+            },
+        )
+    };
+
+    // the hold period starts for an address bonding for itself
+    deps.querier.set_bank_balances(&[coin(1000000, CONTRACT_DENOM)]);
+    bond(deps.as_mut(), "user_2", "user_2", 10000).unwrap();
+
+    // bonding for someone else neither starts a hold period for the sender, nor extends the one
+    // of the receiver
+    bond(deps.as_mut(), "user_1", "user_3", 10000).unwrap();
+    bond(deps.as_mut(), "user_1", "user_2", 10090).unwrap();
+
+    queue_unbond(deps.as_mut(), "user_1", 10050).unwrap();
+    queue_unbond(deps.as_mut(), "user_3", 10050).unwrap();
+
+    let err = queue_unbond(deps.as_mut(), "user_2", 10099).unwrap_err();
+    assert_eq!(err, ContractError::HoldPeriodNotElapsed(10100));
+
+    queue_unbond(deps.as_mut(), "user_2", 10100).unwrap();
+}
+
+#[test]
+fn bonding_pauses_when_harvest_is_stale() {
+    let mut deps = setup_test();
+
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("owner", &[]),
+        ExecuteMsg::UpdateConfig {
+            protocol_fee_contract: None,
+            protocol_reward_fee: None,
+            operator: None,
+            stages_preset: None,
+            swap_allowlist: None,
+            swap_max_spread: None,
+            allow_donations: None,
+            delegation_strategy: None,
+            vote_operator: None,
+            guardian: None,
+            exchange_rate_tolerance: None,
+            max_reinvest_per_tx: None,
+            unbond_allowlist: None,
+            unbond_fee: None,
+            min_hold_seconds: None,
+            reward_smoothing_period: None,
+            validator_fee_share: None,
+            max_harvest_staleness: Some(1000),
+            protocol_fee_schedule: None,
+            action_cooldowns: None,
+            express_epoch: None,
+            rebalance_tolerance: None,
+        },
+    )
+    .unwrap();
+
+    let res: ConfigResponse = query_helper(deps.as_ref(), QueryMsg::Config {});
+    assert_eq!(res.max_harvest_staleness, 1000);
+
+    let harvest = |deps: DepsMut, timestamp: u64| {
+        execute(
+            deps,
+            mock_env_at_timestamp(timestamp),
+            mock_info("worker", &[]),
+            ExecuteMsg::Harvest {
+                withdrawals: None,
+                stages: None,
+                min_received: None,
+                withdraw_limits: None,
+            },
+        )
+        .unwrap()
+    };
+    let bond = |deps: DepsMut, timestamp: u64| {
+        execute(
+            deps,
+            mock_env_at_timestamp(timestamp),
+            mock_info("user_1", &[Coin::new(1000, CONTRACT_DENOM)]),
+            ExecuteMsg::Bond {
+                receiver: None,
+                min_ustake_received: None,
+            },
+        )
+    };
+
+    // without any harvest bonding is not paused
+    deps.querier.set_bank_balances(&[coin(1000, CONTRACT_DENOM)]);
+    bond(deps.as_mut(), 10000).unwrap();
+
+    let res = harvest(deps.as_mut(), 10000);
+    assert!(res.events.is_empty());
+    bond(deps.as_mut(), 11000).unwrap();
+
+    let err = bond(deps.as_mut(), 11001).unwrap_err();
+    assert_eq!(err, ContractError::HarvestStale(1001));
+
+    // the next harvest resumes bonding
+    let res = harvest(deps.as_mut(), 11001);
+    assert_eq!(
+        res.events,
+        vec![Event::new("erishub/bonding_resumed")
+            .add_attribute("seconds_since_last_harvest", "1001")]
+    );
+    bond(deps.as_mut(), 11001).unwrap();
+}
+
+#[test]
+fn submitting_batch() {
+    let mut deps = setup_test();
+    let state = State::default();
+
+    // utoken bonded: 1,037,345
+    // ustake supply: 1,012,043
+    // utoken per ustake: 1.025
+    deps.querier.set_staking_delegations(&[
+        Delegation::new("alice", 345782),
+        Delegation::new("bob", 345782),
+        Delegation::new("charlie", 345781),
+    ]);
+
+    set_total_stake_supply(&state, &mut deps, 1012043);
+
+    // We continue from the contract state at the end of the last test
+    let unbond_requests = vec![
+        UnbondRequest {
+            id: 1,
+            user: Addr::unchecked("user_1"),
+            shares: Uint128::new(23456),
+        },
+        UnbondRequest {
+            id: 1,
+            user: Addr::unchecked("user_3"),
+            shares: Uint128::new(69420),
+        },
+    ];
+
+    for unbond_request in &unbond_requests {
+        state
+            .unbond_requests
+            .save(
+                deps.as_mut().storage,
+                (unbond_request.id, &Addr::unchecked(unbond_request.user.clone())),
+                unbond_request,
+            )
+            .unwrap();
+    }
+
+    state
+        .pending_batch
+        .save(
+            deps.as_mut().storage,
+            &PendingBatch {
+                id: 1,
+                ustake_to_burn: Uint128::new(92876), // 23,456 + 69,420
+                est_unbond_start_time: 269200,
+            },
+        )
+        .unwrap();
+    state.ustake_escrowed.save(deps.as_mut().storage, &Uint128::new(92876)).unwrap();
+
+    // Anyone can invoke `submit_batch`. Here we continue from the previous test and assume it is
+    // invoked automatically as user 2 submits the unbonding request
+    //
+    // ustake to burn: 23,456 + 69,420 = 92,876
+    // utoken to unbond: 1,037,345 * 92,876 / 1,012,043 = 95,197
+    //
+    // Target: (1,037,345 - 95,197) / 3 = 314,049
+    // Remainer: 1
+    // Alice:   345,782 - (314,049 + 1) = 31,732
+    // Bob:     345,782 - (314,049 + 0) = 31,733
+    // Charlie: 345,781 - (314,049 + 0) = 31,732
+    let res = execute(
+        deps.as_mut(),
+        mock_env_at_timestamp(269201),
+        mock_info(MOCK_CONTRACT_ADDR, &[]),
+        ExecuteMsg::SubmitBatch {},
+    )
+    .unwrap();
+
+    assert_eq!(res.messages.len(), 5);
+    assert_eq!(res.messages[0], SubMsg::new(Undelegation::new("alice", 31732).to_cosmos_msg()));
+    assert_eq!(res.messages[1], SubMsg::new(Undelegation::new("bob", 31733).to_cosmos_msg()));
+    assert_eq!(res.messages[2], SubMsg::new(Undelegation::new("charlie", 31732).to_cosmos_msg()));
+    assert_eq!(
+        res.messages[3],
+        SubMsg::new(CosmosMsg::Custom(KujiraMsg::Denom(DenomMsg::Burn {
+            denom: STAKE_DENOM.into(),
+            amount: Uint128::new(92876)
+        })))
+    );
+    assert_eq!(res.messages[4], check_received_coin(0, 0));
+
+    // A new pending batch should have been created
+    let pending_batch = state.pending_batch.load(deps.as_ref().storage).unwrap();
+    assert_eq!(
+        pending_batch,
+        PendingBatch {
+            id: 2,
+            ustake_to_burn: Uint128::zero(),
+            est_unbond_start_time: 528401 // 269,201 + 259,200
+        }
+    );
+
+    // Previous batch should have been updated
+    let previous_batch = state.previous_batches.load(deps.as_ref().storage, 1u64).unwrap();
+    assert_eq!(
+        previous_batch,
+        Batch {
+            id: 1,
+            reconciled: false,
+            total_shares: Uint128::new(92876),
+            utoken_unclaimed: Uint128::new(95197),
+            est_unbond_end_time: 2083601, // 269,201 + 1,814,400
+            exchange_rate_submitted: Some(Decimal::from_ratio(95197u128, 92876u128)),
+            exchange_rate_reconciled: None,
+        }
+    );
+
+    let res: StateResponse = query_helper_env(deps.as_ref(), QueryMsg::State {}, 2083600);
+
+    let total_ustake = Uint128::from(1012043u128).sub(Uint128::new(23456)).sub(Uint128::new(69420));
+    assert_eq!(
+        res,
+        StateResponse {
+            total_ustake,
+            total_utoken: Uint128::from(1037345u128),
+            exchange_rate: Decimal::from_ratio(1037345u128, total_ustake.u128()),
+            unlocked_coins: vec![],
+            unbonding: Uint128::from(95197u128),
+            available: Uint128::zero(),
+            tvl_utoken: Uint128::from(95197u128 + 1037345u128),
+            ustake_escrowed: Uint128::zero(),
+            utoken_unvested: Uint128::zero(),
+            utoken_dust_recycled: Uint128::zero(),
+        },
+    );
+}
+
+#[test]
+fn submitting_batch_reports_epoch() {
+    let mut deps = setup_test();
+    let state = State::default();
+
+    deps.querier.set_staking_delegations(&[
+        Delegation::new("alice", 345782),
+        Delegation::new("bob", 345782),
+        Delegation::new("charlie", 345781),
+    ]);
+    set_total_stake_supply(&state, &mut deps, 1012043);
+
+    state
+        .epoch_counters
+        .save(
+            deps.as_mut().storage,
+            &EpochCounters {
+                utoken_bonded: Uint128::new(12345),
+                utoken_rewards: Uint128::new(234),
+                utoken_fees: Uint128::new(12),
+                exchange_rate_start: Some(Decimal::from_ratio(1025u128, 1000u128)),
+            },
+        )
+        .unwrap();
+    state
+        .pending_batch
+        .save(
+            deps.as_mut().storage,
+            &PendingBatch {
+                id: 1,
+                ustake_to_burn: Uint128::new(92876),
+                est_unbond_start_time: 269200,
+            },
+        )
+        .unwrap();
+
+    let res = execute(
+        deps.as_mut(),
+        mock_env_at_timestamp(269201),
+        mock_info(MOCK_CONTRACT_ADDR, &[]),
+        ExecuteMsg::SubmitBatch {},
+    )
+    .unwrap();
+
+    // exchange rate before the burn: 1,037,345 / 1,012,043
+    assert_eq!(
+        res.events[1],
+        Event::new("erishub/epoch_report")
+            .add_attribute("id", "1")
+            .add_attribute("utoken_bonded", "12345")
+            .add_attribute("utoken_rewards", "234")
+            .add_attribute("utoken_fees", "12")
+            .add_attribute("utoken_unbonded", "95197")
+            .add_attribute("exchange_rate", "1.025000913992784891")
+            .add_attribute("exchange_rate_start", "1.025")
+            .add_attribute("exchange_rate_delta", "0.000000913992784891")
+    );
+
+    // the counters start over from the reported exchange rate
+    assert_eq!(
+        state.epoch_counters.load(deps.as_ref().storage).unwrap(),
+        EpochCounters {
+            utoken_bonded: Uint128::zero(),
+            utoken_rewards: Uint128::zero(),
+            utoken_fees: Uint128::zero(),
+            exchange_rate_start: Some(Decimal::from_ratio(1037345u128, 1012043u128)),
+        }
+    );
+
+    // bonds and reinvests of the next epoch are counted
+    deps.querier.set_bank_balances(&[coin(1000, CONTRACT_DENOM)]);
+    execute(
+        deps.as_mut(),
+        mock_env_at_timestamp(269300),
+        mock_info("user_1", &[Coin::new(1000, CONTRACT_DENOM)]),
+        ExecuteMsg::Bond {
+            receiver: None,
+            min_ustake_received: None,
+        },
+    )
+    .unwrap();
+
+    let counters = state.epoch_counters.load(deps.as_ref().storage).unwrap();
+    assert_eq!(counters.utoken_bonded, Uint128::new(1000));
+}
+
+#[test]
+fn submitting_batch_with_unbond_fee() {
+    let update_unbond_fee = |bps: u16, recipient: UnbondFeeRecipient| ExecuteMsg::UpdateConfig {
+        protocol_fee_contract: None,
+        protocol_reward_fee: None,
+        operator: None,
+        stages_preset: None,
+        swap_allowlist: None,
+        swap_max_spread: None,
+        allow_donations: None,
+        delegation_strategy: None,
+        vote_operator: None,
+        guardian: None,
+        exchange_rate_tolerance: None,
+        max_reinvest_per_tx: None,
+        unbond_allowlist: None,
+        unbond_fee: Some(UnbondFee {
+            bps,
+            recipient,
+        }),
+        min_hold_seconds: None,
+        reward_smoothing_period: None,
+        validator_fee_share: None,
+        max_harvest_staleness: None,
+        protocol_fee_schedule: None,
+        action_cooldowns: None,
+        express_epoch: None,
+        rebalance_tolerance: None,
+    };
+
+    for recipient in [UnbondFeeRecipient::FeeContract, UnbondFeeRecipient::Stakers] {
+        let mut deps = setup_test();
+        let state = State::default();
+
+        let err = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("owner", &[]),
+            update_unbond_fee(501, recipient.clone()),
+        )
+        .unwrap_err();
+        assert_eq!(err, ContractError::UnbondFeeTooHigh(500));
+
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("owner", &[]),
+            update_unbond_fee(100, recipient.clone()),
+        )
+        .unwrap();
+
+        set_total_stake_supply(&state, &mut deps, 1000000);
+        deps.querier.set_staking_delegations(&[
+            Delegation::new("alice", 341667),
+            Delegation::new("bob", 341667),
+            Delegation::new("charlie", 341666),
+        ]);
+        state
+            .pending_batch
+            .save(
+                deps.as_mut().storage,
+                &PendingBatch {
+                    id: 1,
+                    ustake_to_burn: Uint128::new(100000),
+                    est_unbond_start_time: 269200,
+                },
+            )
+            .unwrap();
+        state.ustake_escrowed.save(deps.as_mut().storage, &Uint128::new(100000)).unwrap();
+
+        // ustake fee: 100,000 * 1% = 1,000, rounded down
+        // utoken to unbond: 1,025,000 * 99,000 / 1,000,000 = 101,475, rounded down
+        //
+        // Target: (1,025,000 - 101,475) / 3 = 307,841
+        // Remainder: 2, added to the target of the first validator
+        // Alice:   341,667 - (307,841 + 2) = 33,824
+        // Bob:     341,667 - (307,841 + 0) = 33,826
+        // Charlie: 341,666 - (307,841 + 0) = 33,825
+        let res = execute(
+            deps.as_mut(),
+            mock_env_at_timestamp(269201),
+            mock_info(MOCK_CONTRACT_ADDR, &[]),
+            ExecuteMsg::SubmitBatch {},
+        )
+        .unwrap();
+
+        assert_eq!(res.messages[0], SubMsg::new(Undelegation::new("alice", 33824).to_cosmos_msg()));
+        assert_eq!(res.messages[1], SubMsg::new(Undelegation::new("bob", 33826).to_cosmos_msg()));
+        assert_eq!(
+            res.messages[2],
+            SubMsg::new(Undelegation::new("charlie", 33825).to_cosmos_msg())
+        );
+
+        // the fee for the stakers is burned and stays bonded, the one for the fee contract is sent
+        let ustake_burned = match recipient {
+            UnbondFeeRecipient::FeeContract => {
+                assert_eq!(res.messages.len(), 6);
+                assert_eq!(
+                    res.messages[4],
+                    SubMsg::new(CosmosMsg::Bank(BankMsg::Send {
+                        to_address: "fee".to_string(),
+                        amount: vec![Coin::new(1000, STAKE_DENOM)]
+                    }))
+                );
+                99000
+            },
+            UnbondFeeRecipient::Stakers => {
+                assert_eq!(res.messages.len(), 5);
+                100000
+            },
+        };
+        assert_eq!(
+            res.messages[3],
+            SubMsg::new(CosmosMsg::Custom(KujiraMsg::Denom(DenomMsg::Burn {
+                denom: STAKE_DENOM.into(),
+                amount: Uint128::new(ustake_burned)
+            })))
+        );
+
+        let stake = state.stake_token.load(deps.as_ref().storage).unwrap();
+        assert_eq!(stake.total_supply, Uint128::new(1000000 - ustake_burned));
+
+        // users keep their shares, but only get the value of the stake tokens after the fee
+        let batch = state.previous_batches.load(deps.as_ref().storage, 1u64).unwrap();
+        assert_eq!(batch.total_shares, Uint128::new(100000));
+        assert_eq!(batch.utoken_unclaimed, Uint128::new(101475));
+    }
+}
+
+#[test]
+fn submitting_express_batch() {
+    let mut deps = setup_test();
+    let state = State::default();
+
+    let update_express_epoch = |period: u64| ExecuteMsg::UpdateConfig {
+        protocol_fee_contract: None,
+        protocol_reward_fee: None,
+        operator: None,
+        stages_preset: None,
+        swap_allowlist: None,
+        swap_max_spread: None,
+        allow_donations: None,
+        delegation_strategy: None,
+        vote_operator: None,
+        guardian: None,
+        exchange_rate_tolerance: None,
+        max_reinvest_per_tx: None,
+        unbond_allowlist: None,
+        unbond_fee: None,
+        min_hold_seconds: None,
+        reward_smoothing_period: None,
+        validator_fee_share: None,
+        max_harvest_staleness: None,
+        protocol_fee_schedule: None,
+        action_cooldowns: None,
+        express_epoch: Some(ExpressEpoch {
+            period,
+            max_utoken: Uint128::new(200000),
+        }),
+        rebalance_tolerance: None,
+    };
+
+    // the express epoch has to be shorter than the epoch
+    let err =
+        execute(deps.as_mut(), mock_env(), mock_info("owner", &[]), update_express_epoch(259200))
+            .unwrap_err();
+    assert_eq!(err, ContractError::InvalidExpressEpoch {});
+
+    execute(deps.as_mut(), mock_env(), mock_info("owner", &[]), update_express_epoch(86400))
+        .unwrap();
+
+    set_total_stake_supply(&state, &mut deps, 1000000);
+    deps.querier.set_staking_delegations(&[
+        Delegation::new("alice", 341667),
+        Delegation::new("bob", 341667),
+        Delegation::new("charlie", 341666),
+    ]);
+    let save_pending_batch = |deps: DepsMut, id: u64, ustake_to_burn: u128, start_time: u64| {
+        state
+            .pending_batch
+            .save(
+                deps.storage,
+                &PendingBatch {
+                    id,
+                    ustake_to_burn: Uint128::new(ustake_to_burn),
+                    est_unbond_start_time: start_time,
+                },
+            )
+            .unwrap()
+    };
+
+    // utoken to unbond: 1,025,000 * 200,000 / 1,000,000 = 205,000, too large for the express epoch
+    save_pending_batch(deps.as_mut(), 1, 200000, 269200);
+    let err = execute(
+        deps.as_mut(),
+        mock_env_at_timestamp(100000),
+        mock_info("user_1", &[]),
+        ExecuteMsg::SubmitBatch {},
+    )
+    .unwrap_err();
+    assert_eq!(err, ContractError::ExpressBatchTooLarge(Uint128::new(200000)));
+
+    // utoken to unbond: 1,025,000 * 100,000 / 1,000,000 = 102,500
+    save_pending_batch(deps.as_mut(), 1, 100000, 269200);
+    let res = execute(
+        deps.as_mut(),
+        mock_env_at_timestamp(100000),
+        mock_info("user_1", &[]),
+        ExecuteMsg::SubmitBatch {},
+    )
+    .unwrap();
+    assert!(res.events[0].attributes.contains(&attr("express", "true")));
+    assert_eq!(
+        state.previous_batches.load(deps.as_ref().storage, 1u64).unwrap().utoken_unclaimed,
+        Uint128::new(102500)
+    );
+    assert_eq!(
+        state.pending_batch.load(deps.as_ref().storage).unwrap().est_unbond_start_time,
+        359200
+    );
+    assert_eq!(
+        state.undelegation_entries.load(deps.as_ref().storage, "alice").unwrap(),
+        vec![100000 + 1814400]
+    );
+
+    // the next express batch has to wait for the express period
+    save_pending_batch(deps.as_mut(), 2, 50000, 359200);
+    let err = execute(
+        deps.as_mut(),
+        mock_env_at_timestamp(150000),
+        mock_info("user_1", &[]),
+        ExecuteMsg::SubmitBatch {},
+    )
+    .unwrap_err();
+    assert_eq!(err, ContractError::SubmitBatchAfter(359200));
+
+    // an express batch leaves the last undelegation entry to the regular batches
+    state
+        .undelegation_entries
+        .save(
+            deps.as_mut().storage,
+            "alice",
+            &vec![50000, 1914400, 1914401, 1914402, 1914403, 1914404, 1914405],
+        )
+        .unwrap();
+    let err = execute(
+        deps.as_mut(),
+        mock_env_at_timestamp(186400),
+        mock_info("user_1", &[]),
+        ExecuteMsg::SubmitBatch {},
+    )
+    .unwrap_err();
+    assert_eq!(err, ContractError::UndelegationEntriesExhausted("alice".to_string()));
+
+    let res = execute(
+        deps.as_mut(),
+        mock_env_at_timestamp(359200),
+        mock_info("user_1", &[]),
+        ExecuteMsg::SubmitBatch {},
+    )
+    .unwrap();
+    assert!(res.events[0].attributes.contains(&attr("express", "false")));
+    assert_eq!(state.undelegation_entries.load(deps.as_ref().storage, "alice").unwrap().len(), 7);
+}
+
+#[test]
+fn reconciling() {
+    let mut deps = setup_test();
+    let state = State::default();
+
+    let previous_batches = vec![
+        Batch {
+            id: 1,
+            reconciled: true,
+            total_shares: Uint128::new(92876),
+            utoken_unclaimed: Uint128::new(95197), // 1.025 Token per Stake
+            est_unbond_end_time: 10000,
+            exchange_rate_submitted: None,
+            exchange_rate_reconciled: None,
+        },
+        Batch {
+            id: 2,
+            reconciled: false,
+            total_shares: Uint128::new(1345),
+            utoken_unclaimed: Uint128::new(1385), // 1.030 Token per Stake
+            est_unbond_end_time: 20000,
+            exchange_rate_submitted: None,
+            exchange_rate_reconciled: None,
+        },
+        Batch {
+            id: 3,
+            reconciled: false,
+            total_shares: Uint128::new(1456),
+            utoken_unclaimed: Uint128::new(1506), // 1.035 Token per Stake
+            est_unbond_end_time: 30000,
+            exchange_rate_submitted: None,
+            exchange_rate_reconciled: None,
+        },
+        Batch {
+            id: 4,
+            reconciled: false,
+            total_shares: Uint128::new(1567),
+            utoken_unclaimed: Uint128::new(1629), // 1.040 Token per Stake
+            est_unbond_end_time: 40000,           // not yet finished unbonding, ignored
+            exchange_rate_submitted: None,
+            exchange_rate_reconciled: None,
+        },
+    ];
+
+    save_previous_batches(&state, deps.as_mut().storage, &previous_batches);
+
+    state
+        .unlocked_coins
+        .save(
+            deps.as_mut().storage,
+            &vec![
+                Coin::new(10000, CONTRACT_DENOM),
+                Coin::new(234, "ukrw"),
+                Coin::new(345, "uusd"),
+                Coin::new(
+                    69420,
+                    "ibc/0471F1C4E7AFD3F07702BEF6DC365268D64570F7C1FDC98EA6098DD6DE59817B",
+                ),
+            ],
+        )
+        .unwrap();
+
+    // batch 1 is reconciled, but not fully withdrawn yet
+    deps.querier.set_bank_balances(&[
+        Coin::new(107542, CONTRACT_DENOM),
+        Coin::new(234, "ukrw"),
+        Coin::new(345, "uusd"),
+        Coin::new(69420, "ibc/0471F1C4E7AFD3F07702BEF6DC365268D64570F7C1FDC98EA6098DD6DE59817B"),
+    ]);
+
+    execute(
+        deps.as_mut(),
+        mock_env_at_timestamp(35000),
+        mock_info("worker", &[]),
+        ExecuteMsg::Reconcile {
+            limit: None,
+        },
+    )
+    .unwrap();
+
+    // Expected received: batch 2 + batch 3 = 1385 + 1506 = 2891
+    // Expected claimable: batch 1 = 95197
+    // Expected unlocked: 10000
+    // Expected: 108088
+    // Actual: 107542
+    // Shortfall: 108088 - 107542 = 546
+    //
+    // Deducted pro rata to the expected amounts
+    // batch 2: 546 * 1385 / 2891 = 261
+    // batch 3: 546 - 261 = 285
+    let batch = state.previous_batches.load(deps.as_ref().storage, 2u64).unwrap();
+    assert_eq!(
+        batch,
+        Batch {
+            id: 2,
+            reconciled: true,
+            total_shares: Uint128::new(1345),
+            utoken_unclaimed: Uint128::new(1124), // 1385 - 261
+            est_unbond_end_time: 20000,
+            exchange_rate_submitted: None,
+            exchange_rate_reconciled: Some(Decimal::from_ratio(1124u128, 1345u128)),
+        }
+    );
+
+    let batch = state.previous_batches.load(deps.as_ref().storage, 3u64).unwrap();
+    assert_eq!(
+        batch,
+        Batch {
+            id: 3,
+            reconciled: true,
+            total_shares: Uint128::new(1456),
+            utoken_unclaimed: Uint128::new(1221), // 1506 - 285
+            est_unbond_end_time: 30000,
+            exchange_rate_submitted: None,
+            exchange_rate_reconciled: Some(Decimal::from_ratio(1221u128, 1456u128)),
+        }
+    );
+
+    // Batches 1 and 4 should not have changed
+    let batch = state.previous_batches.load(deps.as_ref().storage, 1u64).unwrap();
+    assert_eq!(batch, previous_batches[0]);
+
+    let batch = state.previous_batches.load(deps.as_ref().storage, 4u64).unwrap();
+    assert_eq!(batch, previous_batches[3]);
+
+    // The deductions are removed from the total claims of the batches
+    // 95197 + 1124 + 1221 + 1629 = 99171
+    let utoken_unclaimed = state.utoken_unclaimed.load(deps.as_ref().storage).unwrap();
+    assert_eq!(utoken_unclaimed, Uint128::new(99171));
+}
+
+#[test]
+fn reconciling_in_chunks() {
+    let mut deps = setup_test();
+    let state = State::default();
+
+    let previous_batches = vec![
+        Batch {
+            id: 1,
+            reconciled: false,
+            total_shares: Uint128::new(1345),
+            utoken_unclaimed: Uint128::new(1385),
+            est_unbond_end_time: 20000,
+            exchange_rate_submitted: None,
+            exchange_rate_reconciled: None,
+        },
+        Batch {
+            id: 2,
+            reconciled: false,
+            total_shares: Uint128::new(1456),
+            utoken_unclaimed: Uint128::new(1506),
+            est_unbond_end_time: 30000,
+            exchange_rate_submitted: None,
+            exchange_rate_reconciled: None,
+        },
+        Batch {
+            id: 3,
+            reconciled: false,
+            total_shares: Uint128::new(1567),
+            utoken_unclaimed: Uint128::new(1629),
+            est_unbond_end_time: 40000, // not yet finished unbonding, ignored
+            exchange_rate_submitted: None,
+            exchange_rate_reconciled: None,
+        },
+    ];
+
+    save_previous_batches(&state, deps.as_mut().storage, &previous_batches);
+
+    state
+        .unlocked_coins
+        .save(deps.as_mut().storage, &vec![Coin::new(10000, CONTRACT_DENOM)])
+        .unwrap();
+
+    deps.querier.set_bank_balances(&[Coin::new(12345, CONTRACT_DENOM)]);
+
+    // The shortfall is computed over all matured batches when the round starts, the chunks
+    // deduct the same amounts as a single reconcile would
+    // Expected: batch 1 + batch 2 + unlocked = 1385 + 1506 + 10000 = 12891
+    // Actual: 12345
+    // Shortfall: 546
+    // batch 1: 546 * 1385 / 2891 = 261
+    execute(
+        deps.as_mut(),
+        mock_env_at_timestamp(35000),
+        mock_info("worker", &[]),
+        ExecuteMsg::Reconcile {
+            limit: Some(1),
+        },
+    )
+    .unwrap();
+
+    let batch = state.previous_batches.load(deps.as_ref().storage, 1u64).unwrap();
+    assert_eq!(
+        batch,
+        Batch {
+            reconciled: true,
+            utoken_unclaimed: Uint128::new(1124), // 1385 - 261
+            exchange_rate_reconciled: Some(Decimal::from_ratio(1124u128, 1345u128)),
+            ..previous_batches[0].clone()
+        }
+    );
+    let batch = state.previous_batches.load(deps.as_ref().storage, 2u64).unwrap();
+    assert_eq!(batch, previous_batches[1]);
+
+    let cursor = state.reconcile_cursor.load(deps.as_ref().storage).unwrap();
+    assert_eq!(
+        cursor,
+        ReconcileCursor {
+            round_start: 35000,
+            utoken_matured: Uint128::new(2891),
+            utoken_shortfall: Uint128::new(546),
+            utoken_reconciled: Uint128::new(1385),
+        }
+    );
+
+    // The balance is not compared again, batch 2 takes the rest of the shortfall
+    // batch 2: 546 - 261 = 285
+    execute(
+        deps.as_mut(),
+        mock_env_at_timestamp(35000),
+        mock_info("worker", &[]),
+        ExecuteMsg::Reconcile {
+            limit: Some(1),
+        },
+    )
+    .unwrap();
+
+    let batch = state.previous_batches.load(deps.as_ref().storage, 2u64).unwrap();
+    assert_eq!(
+        batch,
+        Batch {
+            id: 2,
+            reconciled: true,
+            total_shares: Uint128::new(1456),
+            utoken_unclaimed: Uint128::new(1221), // 1506 - 285
+            est_unbond_end_time: 30000,
+            exchange_rate_submitted: None,
+            exchange_rate_reconciled: Some(Decimal::from_ratio(1221u128, 1456u128)),
+        }
+    );
+
+    // Round is complete, batch 3 has not finished unbonding
+    let cursor = state.reconcile_cursor.may_load(deps.as_ref().storage).unwrap();
+    assert_eq!(cursor, None);
+
+    let batch = state.previous_batches.load(deps.as_ref().storage, 3u64).unwrap();
+    assert_eq!(batch, previous_batches[2]);
+}
+
+#[test]
+fn reconciling_even_when_everything_ok() {
+    let mut deps = setup_test();
+    let state = State::default();
+
+    let previous_batches = vec![
+        Batch {
+            id: 1,
+            reconciled: true,
+            total_shares: Uint128::new(100000),
+            utoken_unclaimed: Uint128::new(100000),
+            est_unbond_end_time: 10000,
+            exchange_rate_submitted: None,
+            exchange_rate_reconciled: None,
+        },
+        Batch {
+            id: 2,
+            reconciled: false,
+            total_shares: Uint128::new(1000),
+            utoken_unclaimed: Uint128::new(1000),
+            est_unbond_end_time: 20000,
+            exchange_rate_submitted: None,
+            exchange_rate_reconciled: None,
+        },
+        Batch {
+            id: 3,
+            reconciled: false,
+            total_shares: Uint128::new(1500),
+            utoken_unclaimed: Uint128::new(1500),
+            est_unbond_end_time: 30000,
+            exchange_rate_submitted: None,
+            exchange_rate_reconciled: None,
+        },
+        Batch {
+            id: 4,
+            reconciled: false,
+            total_shares: Uint128::new(1500),
+            utoken_unclaimed: Uint128::new(1500),
+            est_unbond_end_time: 40000, // not yet finished unbonding, ignored
+            exchange_rate_submitted: None,
+            exchange_rate_reconciled: None,
+        },
+    ];
+
+    save_previous_batches(&state, deps.as_mut().storage, &previous_batches);
+
+    state
+        .unlocked_coins
+        .save(deps.as_mut().storage, &vec![Coin::new(1000, CONTRACT_DENOM)])
+        .unwrap();
+
+    // batch 1 + batch 2 + batch 3 + unlocked
+    deps.querier.set_bank_balances(&[Coin::new(103500, CONTRACT_DENOM)]);
+
+    execute(
+        deps.as_mut(),
+        mock_env_at_timestamp(35000),
+        mock_info("worker", &[]),
+        ExecuteMsg::Reconcile {
+            limit: None,
+        },
+    )
+    .unwrap();
+
+    let batch = state.previous_batches.load(deps.as_ref().storage, 2u64).unwrap();
+    assert_eq!(
+        batch,
+        Batch {
+            id: 2,
+            reconciled: true,
+            total_shares: Uint128::new(1000),
+            utoken_unclaimed: Uint128::new(1000),
+            est_unbond_end_time: 20000,
+            exchange_rate_submitted: None,
+            exchange_rate_reconciled: Some(Decimal::one()),
+        }
+    );
+
+    let batch = state.previous_batches.load(deps.as_ref().storage, 3u64).unwrap();
+    assert_eq!(
+        batch,
+        Batch {
+            id: 3,
+            reconciled: true,
+            total_shares: Uint128::new(1500),
+            utoken_unclaimed: Uint128::new(1500),
+            est_unbond_end_time: 30000,
+            exchange_rate_submitted: None,
+            exchange_rate_reconciled: Some(Decimal::one()),
+        }
+    );
+
+    // Batches 1 and 4 should not have changed
+    let batch = state.previous_batches.load(deps.as_ref().storage, 1u64).unwrap();
+    assert_eq!(batch, previous_batches[0]);
+
+    let batch = state.previous_batches.load(deps.as_ref().storage, 4u64).unwrap();
+    assert_eq!(batch, previous_batches[3]);
+}
+
+#[test]
+fn reconciling_recycles_dust() {
+    let mut deps = setup_test();
+    let state = State::default();
+
+    let previous_batches = vec![
+        Batch {
+            id: 1,
+            reconciled: true,
+            total_shares: Uint128::new(1000),
+            utoken_unclaimed: Uint128::new(1000),
+            est_unbond_end_time: 10000,
+            exchange_rate_submitted: None,
+            exchange_rate_reconciled: None,
+        },
+        Batch {
+            id: 2,
+            reconciled: false,
+            total_shares: Uint128::new(1500),
+            utoken_unclaimed: Uint128::new(1500),
+            est_unbond_end_time: 20000,
+            exchange_rate_submitted: None,
+            exchange_rate_reconciled: None,
+        },
+        Batch {
+            id: 3,
+            reconciled: false,
+            total_shares: Uint128::new(2000),
+            utoken_unclaimed: Uint128::new(2000),
+            est_unbond_end_time: 40000, // not yet finished unbonding
+            exchange_rate_submitted: None,
+            exchange_rate_reconciled: None,
+        },
+    ];
+
+    save_previous_batches(&state, deps.as_mut().storage, &previous_batches);
+
+    state
+        .unlocked_coins
+        .save(deps.as_mut().storage, &vec![Coin::new(500, CONTRACT_DENOM)])
+        .unwrap();
+    state.utoken_undelegated.save(deps.as_mut().storage, &Uint128::new(100)).unwrap();
+
+    // batch 1 + batch 2 + unlocked + undelegated + 7 dust
+    deps.querier.set_bank_balances(&[Coin::new(3107, CONTRACT_DENOM)]);
+
+    let res = execute(
+        deps.as_mut(),
+        mock_env_at_timestamp(35000),
+        mock_info("worker", &[]),
+        ExecuteMsg::Reconcile {
+            limit: None,
+        },
+    )
+    .unwrap();
+    // batch 3 has not finished unbonding, its claim is not on the contract yet
+    assert_eq!(
+        res.events,
+        vec![
+            Event::new("erishub/reconciled")
+                .add_attribute("ids", "2")
+                .add_attribute("utoken_deducted", "0")
+                .add_attribute("has_more", "false"),
+            Event::new("erishub/surplus_credited").add_attribute("utoken_surplus", "7")
+        ]
+    );
+
+    // the funds of batch 3 taken from a removed validator arrive early and are not taken for
+    // surplus
+    state.batch_utoken_undelegated.save(deps.as_mut().storage, 3, &Uint128::new(2000)).unwrap();
+    deps.querier.set_bank_balances(&[Coin::new(5107, CONTRACT_DENOM)]);
+
+    let res = execute(
+        deps.as_mut(),
+        mock_env_at_timestamp(35000),
+        mock_info("worker", &[]),
+        ExecuteMsg::Reconcile {
+            limit: None,
+        },
+    )
+    .unwrap();
+    assert!(res.events.is_empty());
+
+    // the dust is reinvested with the next rewards
+    let unlocked_coins = state.unlocked_coins.load(deps.as_ref().storage).unwrap();
+    assert_eq!(unlocked_coins, vec![Coin::new(507, CONTRACT_DENOM)]);
+
+    // the claims of the batches are untouched
+    let batch = state.previous_batches.load(deps.as_ref().storage, 2u64).unwrap();
+    assert_eq!(batch.utoken_unclaimed, Uint128::new(1500));
+    let batch = state.previous_batches.load(deps.as_ref().storage, 3u64).unwrap();
+    assert_eq!(batch.utoken_unclaimed, Uint128::new(2000));
+
+    // dust already recycled is part of the unlocked coins and not counted twice
+    save_previous_batches(
+        &state,
+        deps.as_mut().storage,
+        &[Batch {
+            id: 4,
+            reconciled: false,
+            total_shares: Uint128::new(10),
+            utoken_unclaimed: Uint128::new(10),
+            est_unbond_end_time: 30000,
+            exchange_rate_submitted: None,
+            exchange_rate_reconciled: None,
+        }],
+    );
+    deps.querier.set_bank_balances(&[Coin::new(5120, CONTRACT_DENOM)]);
+
+    execute(
+        deps.as_mut(),
+        mock_env_at_timestamp(35000),
+        mock_info("worker", &[]),
+        ExecuteMsg::Reconcile {
+            limit: None,
+        },
+    )
+    .unwrap();
+
+    let unlocked_coins = state.unlocked_coins.load(deps.as_ref().storage).unwrap();
+    assert_eq!(unlocked_coins, vec![Coin::new(510, CONTRACT_DENOM)]);
+    assert_eq!(state.utoken_dust_recycled.load(deps.as_ref().storage).unwrap(), Uint128::new(10));
+}
+
+#[test]
+fn reconciling_credits_surplus_without_batches() {
+    let mut deps = setup_test();
+    let state = State::default();
+
+    save_previous_batches(
+        &state,
+        deps.as_mut().storage,
+        &[Batch {
+            id: 1,
+            reconciled: true,
+            total_shares: Uint128::new(1000),
+            utoken_unclaimed: Uint128::new(1000),
+            est_unbond_end_time: 10000,
+            exchange_rate_submitted: None,
+            exchange_rate_reconciled: None,
+        }],
+    );
+    state.unlocked_coins.save(deps.as_mut().storage, &vec![Coin::new(234, "ukrw")]).unwrap();
+
+    // nothing to reconcile and nothing beyond the claims
+    deps.querier.set_bank_balances(&[Coin::new(1000, CONTRACT_DENOM), Coin::new(234, "ukrw")]);
+
+    let res = execute(
+        deps.as_mut(),
+        mock_env_at_timestamp(35000),
+        mock_info("worker", &[]),
+        ExecuteMsg::Reconcile {
+            limit: None,
+        },
+    )
+    .unwrap();
+    assert!(res.events.is_empty() && res.attributes.is_empty());
+
+    // rewards claimed automatically with an undelegation
+    deps.querier.set_bank_balances(&[Coin::new(1420, CONTRACT_DENOM), Coin::new(234, "ukrw")]);
+
+    let res = execute(
+        deps.as_mut(),
+        mock_env_at_timestamp(35000),
+        mock_info("worker", &[]),
+        ExecuteMsg::Reconcile {
+            limit: None,
+        },
+    )
+    .unwrap();
+    assert_eq!(
+        res.events,
+        vec![Event::new("erishub/surplus_credited").add_attribute("utoken_surplus", "420")]
+    );
+
+    let unlocked_coins = state.unlocked_coins.load(deps.as_ref().storage).unwrap();
+    assert_eq!(unlocked_coins, vec![Coin::new(234, "ukrw"), Coin::new(420, CONTRACT_DENOM)]);
+
+    let batch = state.previous_batches.load(deps.as_ref().storage, 1u64).unwrap();
+    assert_eq!(batch.utoken_unclaimed, Uint128::new(1000));
+}
+
+#[test]
+fn reconciling_underflow() {
+    let mut deps = setup_test();
+    let state = State::default();
+    let previous_batches = vec![
+        Batch {
+            id: 1,
+            reconciled: true,
+            total_shares: Uint128::new(92876),
+            utoken_unclaimed: Uint128::new(95197), // 1.025 Token per Stake
+            est_unbond_end_time: 10000,
+            exchange_rate_submitted: None,
+            exchange_rate_reconciled: None,
+        },
+        Batch {
+            id: 2,
+            reconciled: false,
+            total_shares: Uint128::new(1345),
+            utoken_unclaimed: Uint128::new(1385), // 1.030 Token per Stake
+            est_unbond_end_time: 20000,
+            exchange_rate_submitted: None,
+            exchange_rate_reconciled: None,
+        },
+        Batch {
+            id: 3,
+            reconciled: false,
+            total_shares: Uint128::new(1456),
+            utoken_unclaimed: Uint128::new(1506), // 1.035 Token per Stake
+            est_unbond_end_time: 30000,
+            exchange_rate_submitted: None,
+            exchange_rate_reconciled: None,
+        },
+        Batch {
+            id: 4,
+            reconciled: false,
+            total_shares: Uint128::new(1),
+            utoken_unclaimed: Uint128::new(1),
+            est_unbond_end_time: 30001,
+            exchange_rate_submitted: None,
+            exchange_rate_reconciled: None,
+        },
+    ];
+    save_previous_batches(&state, deps.as_mut().storage, &previous_batches);
+    state
+        .unlocked_coins
+        .save(
+            deps.as_mut().storage,
+            &vec![
+                Coin::new(10000, CONTRACT_DENOM),
+                Coin::new(234, "ukrw"),
+                Coin::new(345, "uusd"),
+                Coin::new(
+                    69420,
+                    "ibc/0471F1C4E7AFD3F07702BEF6DC365268D64570F7C1FDC98EA6098DD6DE59817B",
+                ),
+            ],
+        )
+        .unwrap();
+    deps.querier.set_bank_balances(&[
+        Coin::new(12345, CONTRACT_DENOM),
+        Coin::new(234, "ukrw"),
+        Coin::new(345, "uusd"),
+        Coin::new(69420, "ibc/0471F1C4E7AFD3F07702BEF6DC365268D64570F7C1FDC98EA6098DD6DE59817B"),
+    ]);
+    execute(
+        deps.as_mut(),
+        mock_env_at_timestamp(35000),
+        mock_info("worker", &[]),
+        ExecuteMsg::Reconcile {
+            limit: None,
+        },
+    )
+    .unwrap();
+}
+
+#[test]
+fn reconciling_underflow_second() {
+    let mut deps = setup_test();
+    let state = State::default();
+    let previous_batches = vec![
+        Batch {
+            id: 1,
+            reconciled: true,
+            total_shares: Uint128::new(92876),
+            utoken_unclaimed: Uint128::new(95197), // 1.025 Token per Stake
+            est_unbond_end_time: 10000,
+            exchange_rate_submitted: None,
+            exchange_rate_reconciled: None,
+        },
+        Batch {
+            id: 2,
+            reconciled: false,
+            total_shares: Uint128::new(1345),
+            utoken_unclaimed: Uint128::new(1385), // 1.030 Token per Stake
+            est_unbond_end_time: 20000,
+            exchange_rate_submitted: None,
+            exchange_rate_reconciled: None,
+        },
+        Batch {
+            id: 3,
+            reconciled: false,
+            total_shares: Uint128::new(176),
+            utoken_unclaimed: Uint128::new(183), // 1.035 Token per Stake
+            est_unbond_end_time: 30000,
+            exchange_rate_submitted: None,
+            exchange_rate_reconciled: None,
+        },
+        Batch {
+            id: 4,
+            reconciled: false,
+            total_shares: Uint128::new(1),
+            utoken_unclaimed: Uint128::new(1),
+            est_unbond_end_time: 30001,
+            exchange_rate_submitted: None,
+            exchange_rate_reconciled: None,
+        },
+    ];
+    save_previous_batches(&state, deps.as_mut().storage, &previous_batches);
+    state
+        .unlocked_coins
+        .save(
+            deps.as_mut().storage,
+            &vec![
+                Coin::new(10000, CONTRACT_DENOM),
+                Coin::new(234, "ukrw"),
+                Coin::new(345, "uusd"),
+                Coin::new(
+                    69420,
+                    "ibc/0471F1C4E7AFD3F07702BEF6DC365268D64570F7C1FDC98EA6098DD6DE59817B",
+                ),
+            ],
+        )
+        .unwrap();
+    deps.querier.set_bank_balances(&[
+        Coin::new(12345 - 1323, CONTRACT_DENOM),
+        Coin::new(234, "ukrw"),
+        Coin::new(345, "uusd"),
+        Coin::new(69420, "ibc/0471F1C4E7AFD3F07702BEF6DC365268D64570F7C1FDC98EA6098DD6DE59817B"),
+    ]);
+    execute(
+        deps.as_mut(),
+        mock_env_at_timestamp(35000),
+        mock_info("worker", &[]),
+        ExecuteMsg::Reconcile {
+            limit: None,
+        },
+    )
+    .unwrap();
+}
+
+#[test]
+fn withdrawing_unbonded() {
+    let mut deps = setup_test();
+    let state = State::default();
+
+    // We simulate a most general case:
+    // - batches 1 and 2 have finished unbonding
+    // - batch 3 have been submitted for unbonding but have not finished
+    // - batch 4 is still pending
+    let unbond_requests = vec![
+        UnbondRequest {
+            id: 1,
+            user: Addr::unchecked("user_1"),
+            shares: Uint128::new(23456),
+        },
+        UnbondRequest {
+            id: 1,
+            user: Addr::unchecked("user_3"),
+            shares: Uint128::new(69420),
+        },
+        UnbondRequest {
+            id: 2,
+            user: Addr::unchecked("user_1"),
+            shares: Uint128::new(34567),
+        },
+        UnbondRequest {
+            id: 3,
+            user: Addr::unchecked("user_1"),
+            shares: Uint128::new(45678),
+        },
+        UnbondRequest {
+            id: 4,
+            user: Addr::unchecked("user_1"),
+            shares: Uint128::new(56789),
+        },
+    ];
+
+    save_unbond_requests(&state, deps.as_mut().storage, &unbond_requests);
+
+    let previous_batches = vec![
+        Batch {
+            id: 1,
+            reconciled: true,
+            total_shares: Uint128::new(92876),
+            utoken_unclaimed: Uint128::new(95197), // 1.025 Token per Stake
+            est_unbond_end_time: 10000,
+            exchange_rate_submitted: None,
+            exchange_rate_reconciled: None,
+        },
+        Batch {
+            id: 2,
+            reconciled: true,
+            total_shares: Uint128::new(34567),
+            utoken_unclaimed: Uint128::new(35604), // 1.030 Token per Stake
+            est_unbond_end_time: 20000,
+            exchange_rate_submitted: None,
+            exchange_rate_reconciled: None,
+        },
+        Batch {
+            id: 3,
+            reconciled: false, // finished unbonding, but not reconciled; ignored
+            total_shares: Uint128::new(45678),
+            utoken_unclaimed: Uint128::new(47276), // 1.035 Token per Stake
+            est_unbond_end_time: 20000,
+            exchange_rate_submitted: None,
+            exchange_rate_reconciled: None,
+        },
+        Batch {
+            id: 4,
+            reconciled: true,
+            total_shares: Uint128::new(56789),
+            utoken_unclaimed: Uint128::new(59060), // 1.040 Token per Stake
+            est_unbond_end_time: 30000, // reconciled, but not yet finished unbonding; ignored
+            exchange_rate_submitted: None,
+            exchange_rate_reconciled: None,
+        },
+    ];
+
+    save_previous_batches(&state, deps.as_mut().storage, &previous_batches);
+
+    state
+        .pending_batch
+        .save(
+            deps.as_mut().storage,
+            &PendingBatch {
+                id: 4,
+                ustake_to_burn: Uint128::new(56789),
+                est_unbond_start_time: 100000,
+            },
+        )
+        .unwrap();
+
+    // Attempt to withdraw before any batch has completed unbonding. Should error
+    let err = execute(
+        deps.as_mut(),
+        mock_env_at_timestamp(5000),
+        mock_info("user_1", &[]),
+        ExecuteMsg::WithdrawUnbonded {
+            receiver: None,
+            receiver_msg: None,
+        },
+    )
+    .unwrap_err();
+
+    assert_eq!(err, ContractError::CantBeZero("withdrawable amount".into()));
+
+    // Attempt to withdraw once batches 1 and 2 have finished unbonding, but 3 has not yet
+    //
+    // Withdrawable from batch 1: 95,197 * 23,456 / 92,876 = 24,042
+    // Withdrawable from batch 2: 35,604
+    // Total withdrawable: 24,042 + 35,604 = 59,646
+    //
+    // Batch 1 should be updated:
+    // Total shares: 92,876 - 23,456 = 69,420
+    // Unclaimed utoken: 95,197 - 24,042 = 71,155
+    //
+    // Batch 2 is completely withdrawn, should be purged from storage
+    let res = execute(
+        deps.as_mut(),
+        mock_env_at_timestamp(25000),
+        mock_info("user_1", &[]),
+        ExecuteMsg::WithdrawUnbonded {
+            receiver: None,
+            receiver_msg: None,
+        },
+    )
+    .unwrap();
+
+    assert_eq!(res.messages.len(), 1);
+    assert_eq!(
+        res.messages[0],
+        SubMsg::new(CosmosMsg::Bank(BankMsg::Send {
+            to_address: "user_1".to_string(),
+            amount: vec![Coin::new(59646, CONTRACT_DENOM)]
+        }))
+    );
+
+    // Previous batches should have been updated
+    let batch = state.previous_batches.load(deps.as_ref().storage, 1u64).unwrap();
+    assert_eq!(
+        batch,
+        Batch {
+            id: 1,
+            reconciled: true,
+            total_shares: Uint128::new(69420),
+            utoken_unclaimed: Uint128::new(71155),
+            est_unbond_end_time: 10000,
+            exchange_rate_submitted: None,
+            exchange_rate_reconciled: None,
+        }
+    );
+
+    let err = state.previous_batches.load(deps.as_ref().storage, 2u64).unwrap_err();
+    assert_eq!(
+        err,
+        StdError::NotFound {
+            kind: "eris::hub::Batch".to_string()
+        }
+    );
+
+    // User 1's unbond requests in batches 1 and 2 should have been deleted
+    let err1 = state
+        .unbond_requests
+        .load(deps.as_ref().storage, (1u64, &Addr::unchecked("user_1")))
+        .unwrap_err();
+    let err2 = state
+        .unbond_requests
+        .load(deps.as_ref().storage, (1u64, &Addr::unchecked("user_1")))
+        .unwrap_err();
+
+    assert_eq!(
+        err1,
+        StdError::NotFound {
+            kind: "eris::hub::UnbondRequest".to_string()
+        }
+    );
+    assert_eq!(
+        err2,
+        StdError::NotFound {
+            kind: "eris::hub::UnbondRequest".to_string()
+        }
+    );
+
+    // User 3 attempt to withdraw; also specifying a receiver
+    let res = execute(
+        deps.as_mut(),
+        mock_env_at_timestamp(25000),
+        mock_info("user_3", &[]),
+        ExecuteMsg::WithdrawUnbonded {
+            receiver: Some("user_2".to_string()),
+            receiver_msg: None,
+        },
+    )
+    .unwrap();
+
+    assert_eq!(res.messages.len(), 1);
+    assert_eq!(
+        res.messages[0],
+        SubMsg::new(CosmosMsg::Bank(BankMsg::Send {
+            to_address: "user_2".to_string(),
+            amount: vec![Coin::new(71155, CONTRACT_DENOM)]
+        }))
+    );
+
+    // Batch 1 and user 2's unbonding request should have been purged from storage
+    let err = state.previous_batches.load(deps.as_ref().storage, 1u64).unwrap_err();
+    assert_eq!(
+        err,
+        StdError::NotFound {
+            kind: "eris::hub::Batch".to_string()
+        }
+    );
+
+    let err = state
+        .unbond_requests
+        .load(deps.as_ref().storage, (1u64, &Addr::unchecked("user_3")))
+        .unwrap_err();
+
+    assert_eq!(
+        err,
+        StdError::NotFound {
+            kind: "eris::hub::UnbondRequest".to_string()
+        }
+    );
+}
+
+#[test]
+fn withdrawing_unbonded_with_receiver_msg() {
+    let mut deps = setup_test();
+    let state = State::default();
+
+    save_unbond_requests(
+        &state,
+        deps.as_mut().storage,
+        &[UnbondRequest {
+            id: 1,
+            user: Addr::unchecked("user_1"),
+            shares: Uint128::new(10000),
+        }],
+    );
+    save_previous_batches(
+        &state,
+        deps.as_mut().storage,
+        &[Batch {
+            id: 1,
+            reconciled: true,
+            total_shares: Uint128::new(10000),
+            utoken_unclaimed: Uint128::new(10250),
+            est_unbond_end_time: 10000,
+            exchange_rate_submitted: None,
+            exchange_rate_reconciled: None,
+        }],
+    );
+
+    let res = execute(
+        deps.as_mut(),
+        mock_env_at_timestamp(25000),
+        mock_info("user_1", &[]),
+        ExecuteMsg::WithdrawUnbonded {
+            receiver: Some("arb_vault".to_string()),
+            receiver_msg: Some(to_binary(&"process_withdrawal").unwrap()),
+        },
+    )
+    .unwrap();
+
+    // the receiver contract gets the withdrawn funds with the message
+    assert_eq!(
+        res.messages,
+        vec![SubMsg::new(CosmosMsg::Wasm(WasmMsg::Execute {
+            contract_addr: "arb_vault".to_string(),
+            msg: to_binary(&"process_withdrawal").unwrap(),
+            funds: vec![Coin::new(10250, CONTRACT_DENOM)],
+        }))]
+    );
+
+    // the hub can't be the receiver of a message, it would execute its own callbacks
+    let err = execute(
+        deps.as_mut(),
+        mock_env_at_timestamp(25000),
+        mock_info("user_1", &[]),
+        ExecuteMsg::WithdrawUnbonded {
+            receiver: Some(MOCK_CONTRACT_ADDR.to_string()),
+            receiver_msg: Some(
+                to_binary(&ExecuteMsg::Callback(CallbackMsg::CheckReceivedCoin {
+                    snapshot: coin(0, CONTRACT_DENOM),
+                    snapshot_stake: coin(0, STAKE_DENOM),
+                }))
+                .unwrap(),
+            ),
+        },
+    )
+    .unwrap_err();
+    assert_eq!(err, ContractError::SelfReference("receiver".to_string()));
+}
+
+#[test]
+fn transferring_unbond_requests() {
+    let mut deps = setup_test();
+    let state = State::default();
+
+    execute(
+        deps.as_mut(),
+        mock_env_at_timestamp(12345),
+        mock_info("user_1", &[Coin::new(23456, STAKE_DENOM)]),
+        ExecuteMsg::QueueUnbond {
+            receiver: None,
+        },
+    )
+    .unwrap();
+
+    let err = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("user_1", &[]),
+        ExecuteMsg::TransferUnbondRequest {
+            id: 1,
+            recipient: "user_2".to_string(),
+            shares: Some(Uint128::new(30000)),
+        },
+    )
+    .unwrap_err();
+    assert_eq!(err, ContractError::InsufficientUnbondShares(Uint128::new(23456)));
+
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("user_1", &[]),
+        ExecuteMsg::TransferUnbondRequest {
+            id: 1,
+            recipient: "user_2".to_string(),
+            shares: Some(Uint128::new(3456)),
+        },
+    )
+    .unwrap();
+
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("user_2", &[]),
+        ExecuteMsg::SendUnbondRequest {
+            id: 1,
+            contract: "market".to_string(),
+            shares: None,
+            msg: to_binary(&"sell").unwrap(),
+        },
+    )
+    .unwrap();
+    assert_eq!(
+        res.messages,
+        vec![SubMsg::new(CosmosMsg::Wasm(WasmMsg::Execute {
+            contract_addr: "market".to_string(),
+            msg: to_binary(&UnbondRequestReceiverExecuteMsg::ReceiveUnbondRequest(
+                UnbondRequestReceiveMsg {
+                    sender: "user_2".to_string(),
+                    id: 1,
+                    shares: Uint128::new(3456),
+                    msg: to_binary(&"sell").unwrap(),
+                }
+            ))
+            .unwrap(),
+            funds: vec![],
+        }))]
+    );
+
+    let err = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("user_2", &[]),
+        ExecuteMsg::TransferUnbondRequest {
+            id: 1,
+            recipient: "user_3".to_string(),
+            shares: None,
+        },
+    )
+    .unwrap_err();
+    assert_eq!(err, ContractError::UnbondRequestNotFound(1));
+
+    let requests: Vec<UnbondRequestsByBatchResponseItem> =
+        query_helper::<PaginatedResponse<UnbondRequestsByBatchResponseItem, String>>(
+            deps.as_ref(),
+            QueryMsg::UnbondRequestsByBatch {
+                id: 1,
+                start_after: None,
+                limit: None,
+            },
+        )
+        .items;
+    assert_eq!(
+        requests,
+        vec![
+            UnbondRequestsByBatchResponseItem {
+                user: "market".to_string(),
+                shares: Uint128::new(3456),
+            },
+            UnbondRequestsByBatchResponseItem {
+                user: "user_1".to_string(),
+                shares: Uint128::new(20000),
+            },
+        ]
+    );
+
+    let totals = state.unbond_requests_totals.load(deps.as_ref().storage, 1).unwrap();
+    assert_eq!(totals.count, 2);
+    assert_eq!(totals.shares, Uint128::new(23456));
+}
+
+#[test]
+fn migrating_backfills_unbond_requests_totals() {
+    let mut deps = setup_test();
+    let state = State::default();
+
+    // requests queued before the totals were tracked, and a stale total of batch 2
+    for (id, user, shares) in [(1, "user_1", 10000), (1, "user_2", 2345), (2, "user_1", 500)] {
+        let user = Addr::unchecked(user);
+        let request = UnbondRequest {
+            id,
+            user: user.clone(),
+            shares: Uint128::new(shares),
+        };
+        state.unbond_requests.save(deps.as_mut().storage, (id, &user), &request).unwrap();
+    }
+    state
+        .unbond_requests_totals
+        .save(
+            deps.as_mut().storage,
+            2,
+            &UnbondRequestsTotals {
+                count: 3,
+                shares: Uint128::new(12345),
+            },
+        )
+        .unwrap();
+
+    migrate(deps.as_mut(), mock_env(), Empty {}).unwrap();
+
+    let totals = state.unbond_requests_totals.load(deps.as_ref().storage, 1).unwrap();
+    assert_eq!(totals.count, 2);
+    assert_eq!(totals.shares, Uint128::new(12345));
+    let totals = state.unbond_requests_totals.load(deps.as_ref().storage, 2).unwrap();
+    assert_eq!(totals.count, 1);
+    assert_eq!(totals.shares, Uint128::new(500));
+
+    // withdrawing a request of the migrated batch removes it from the backfilled totals
+    save_previous_batches(
+        &state,
+        deps.as_mut().storage,
+        &[Batch {
+            id: 1,
+            reconciled: true,
+            total_shares: Uint128::new(12345),
+            utoken_unclaimed: Uint128::new(12345),
+            est_unbond_end_time: 10000,
+            exchange_rate_submitted: None,
+            exchange_rate_reconciled: None,
+        }],
+    );
+    execute(
+        deps.as_mut(),
+        mock_env_at_timestamp(25000),
+        mock_info("user_2", &[]),
+        ExecuteMsg::WithdrawUnbonded {
+            receiver: None,
+            receiver_msg: None,
+        },
+    )
+    .unwrap();
+
+    let totals = state.unbond_requests_totals.load(deps.as_ref().storage, 1).unwrap();
+    assert_eq!(totals.count, 1);
+    assert_eq!(totals.shares, Uint128::new(10000));
+}
+
+#[test]
+fn adding_validator() {
+    let mut deps = setup_test();
+    let state = State::default();
+
+    let err = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("jake", &[]),
+        ExecuteMsg::AddValidator {
+            validator: "dave".to_string(),
+        },
+    )
+    .unwrap_err();
+
+    assert_eq!(
+        err,
+        ContractError::Unauthorized {
+            required_role: Role::Owner,
+            sender: "jake".to_string()
+        }
+    );
+
+    let err = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("owner", &[]),
+        ExecuteMsg::AddValidator {
+            validator: "alice".to_string(),
+        },
+    )
+    .unwrap_err();
+
+    assert_eq!(err, ContractError::ValidatorAlreadyWhitelisted("alice".into()));
+
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("owner", &[]),
+        ExecuteMsg::AddValidator {
+            validator: "dave".to_string(),
+        },
+    )
+    .unwrap();
+
+    assert_eq!(res.messages.len(), 0);
+
+    let validators = state.validators.load(deps.as_ref().storage).unwrap();
+    assert_eq!(
+        validators,
+        vec![
+            String::from("alice"),
+            String::from("bob"),
+            String::from("charlie"),
+            String::from("dave")
+        ],
+    );
+}
+
+#[test]
+fn ramping_up_new_validator() {
+    let mut deps = setup_test();
+    let state = State::default();
+
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("owner", &[]),
+        ExecuteMsg::AddValidator {
+            validator: "dave".to_string(),
+        },
+    )
+    .unwrap();
+
+    let ramping = state.ramping_validators.load(deps.as_ref().storage, "dave").unwrap();
+    assert_eq!(ramping, 1);
+
+    deps.querier.set_staking_delegations(&[
+        Delegation::new("alice", 100000),
+        Delegation::new("bob", 100000),
+        Delegation::new("charlie", 100000),
+        Delegation::new("dave", 30000),
+    ]);
+
+    // dave only gets 1/5 of the weight of the other validators in its first epoch
+    let res: WantedDelegationsResponse =
+        query_helper(deps.as_ref(), QueryMsg::WantedDelegations {});
+    assert_eq!(
+        res.delegations,
+        vec![
+            ("alice".to_string(), Uint128::new(103125)),
+            ("bob".to_string(), Uint128::new(103125)),
+            ("charlie".to_string(), Uint128::new(103125)),
+            ("dave".to_string(), Uint128::new(20625)),
+        ]
+    );
+
+    // dave is already above its ramped share, so the rewards go to alice
+    state
+        .unlocked_coins
+        .save(deps.as_mut().storage, &vec![Coin::new(1000, CONTRACT_DENOM)])
+        .unwrap();
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info(MOCK_CONTRACT_ADDR, &[]),
+        ExecuteMsg::Callback(CallbackMsg::Reinvest {}),
+    )
+    .unwrap();
+    assert_eq!(res.messages[0], SubMsg::new(Delegation::new("alice", 990).to_cosmos_msg()));
+
+    // after the ramp up epochs dave has the full weight
+    state
+        .pending_batch
+        .update(deps.as_mut().storage, |mut batch| -> StdResult<_> {
+            batch.id = 5;
+            Ok(batch)
+        })
+        .unwrap();
+    state
+        .unlocked_coins
+        .save(deps.as_mut().storage, &vec![Coin::new(1000, CONTRACT_DENOM)])
+        .unwrap();
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info(MOCK_CONTRACT_ADDR, &[]),
+        ExecuteMsg::Callback(CallbackMsg::Reinvest {}),
+    )
+    .unwrap();
+    assert_eq!(res.messages[0], SubMsg::new(Delegation::new("dave", 990).to_cosmos_msg()));
+
+    // removing the validator clears the ramp up
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("owner", &[]),
+        ExecuteMsg::RemoveValidator {
+            validator: "dave".to_string(),
+            mode: None,
+        },
+    )
+    .unwrap();
+    let ramping = state.ramping_validators.may_load(deps.as_ref().storage, "dave").unwrap();
+    assert_eq!(ramping, None);
+}
+
+#[test]
+fn removing_validator() {
+    let mut deps = setup_test();
+    let state = State::default();
+
+    deps.querier.set_staking_delegations(&[
+        Delegation::new("alice", 341667),
+        Delegation::new("bob", 341667),
+        Delegation::new("charlie", 341666),
+    ]);
+
+    let err = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("jake", &[]),
+        ExecuteMsg::RemoveValidator {
+            validator: "charlie".to_string(),
+            mode: None,
+        },
+    )
+    .unwrap_err();
+
+    assert_eq!(
+        err,
+        ContractError::Unauthorized {
+            required_role: Role::Owner,
+            sender: "jake".to_string()
+        }
+    );
+
+    let err = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("owner", &[]),
+        ExecuteMsg::RemoveValidator {
+            validator: "dave".to_string(),
+            mode: None,
+        },
+    )
+    .unwrap_err();
+
+    assert_eq!(err, ContractError::ValidatorNotWhitelisted("dave".into()));
+
+    // Target: (341667 + 341667 + 341666) / 2 = 512500
+    // Remainder: 0
+    // Alice:   512500 + 0 - 341667 = 170833
+    // Bob:     512500 + 0 - 341667 = 170833
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("owner", &[]),
+        ExecuteMsg::RemoveValidator {
+            validator: "charlie".to_string(),
+            mode: None,
+        },
+    )
+    .unwrap();
+
+    assert_eq!(res.messages.len(), 3);
+    assert_eq!(
+        res.messages[0],
+        SubMsg::new(Redelegation::new("charlie", "alice", 170833).to_cosmos_msg()),
+    );
+    assert_eq!(
+        res.messages[1],
+        SubMsg::new(Redelegation::new("charlie", "bob", 170833).to_cosmos_msg()),
+    );
+    assert_eq!(res.messages[2], check_received_coin(0, 0));
+
+    let validators = state.validators.load(deps.as_ref().storage).unwrap();
+    assert_eq!(validators, vec![String::from("alice"), String::from("bob")],);
+}
+
+#[test]
+fn removing_validator_by_undelegating() {
+    let mut deps = setup_test();
+    let state = State::default();
+
+    set_total_stake_supply(&state, &mut deps, 1000000);
+    deps.querier.set_staking_delegations(&[
+        Delegation::new("alice", 341667),
+        Delegation::new("bob", 341667),
+        Delegation::new("charlie", 341666),
+    ]);
+
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("owner", &[]),
+        ExecuteMsg::RemoveValidator {
+            validator: "charlie".to_string(),
+            mode: Some(RemoveValidatorMode::Undelegate),
+        },
+    )
+    .unwrap();
+
+    assert_eq!(res.messages.len(), 2);
+    assert_eq!(res.messages[0], SubMsg::new(Undelegation::new("charlie", 341666).to_cosmos_msg()),);
+    assert_eq!(res.messages[1], check_received_coin(0, 0));
+
+    let utoken_undelegated = state.utoken_undelegated.load(deps.as_ref().storage).unwrap();
+    assert_eq!(utoken_undelegated, Uint128::new(341666));
+
+    // the undelegated amount still counts towards the exchange rate
+    deps.querier.set_staking_delegations(&[
+        Delegation::new("alice", 341667),
+        Delegation::new("bob", 341667),
+    ]);
+
+    let res: StateResponse = query_helper(deps.as_ref(), QueryMsg::State {});
+    assert_eq!(res.total_utoken, Uint128::new(1025000));
+    assert_eq!(res.exchange_rate, Decimal::from_ratio(1025000u128, 1000000u128));
+
+    // the next batch is unbonded from the undelegated amount, without new undelegations
+    state
+        .pending_batch
+        .save(
+            deps.as_mut().storage,
+            &PendingBatch {
+                id: 1,
+                ustake_to_burn: Uint128::new(100000),
+                est_unbond_start_time: 0,
+            },
+        )
+        .unwrap();
+
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info(MOCK_CONTRACT_ADDR, &[]),
+        ExecuteMsg::SubmitBatch {},
+    )
+    .unwrap();
+
+    assert_eq!(res.messages.len(), 2);
+    assert_eq!(
+        res.messages[0],
+        SubMsg::new(CosmosMsg::Custom(KujiraMsg::Denom(DenomMsg::Burn {
+            denom: STAKE_DENOM.into(),
+            amount: Uint128::new(100000)
+        })))
+    );
+
+    let batch = state.previous_batches.load(deps.as_ref().storage, 1).unwrap();
+    assert_eq!(batch.utoken_unclaimed, Uint128::new(102500));
+
+    let utoken_undelegated = state.utoken_undelegated.load(deps.as_ref().storage).unwrap();
+    assert_eq!(utoken_undelegated, Uint128::new(239166));
+
+    // the claim of the batch can arrive with the undelegation from the removed validator
+    let utoken_undelegated = state.batch_utoken_undelegated.load(deps.as_ref().storage, 1).unwrap();
+    assert_eq!(utoken_undelegated, Uint128::new(102500));
+}
+
+#[test]
+fn transferring_ownership() {
+    let mut deps = setup_test();
+    let state = State::default();
+
+    let err = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("jake", &[]),
+        ExecuteMsg::TransferOwnership {
+            new_owner: "jake".to_string(),
+        },
+    )
+    .unwrap_err();
+
+    assert_eq!(
+        err,
+        ContractError::Unauthorized {
+            required_role: Role::Owner,
+            sender: "jake".to_string()
+        }
+    );
+
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("owner", &[]),
+        ExecuteMsg::TransferOwnership {
+            new_owner: "jake".to_string(),
+        },
+    )
+    .unwrap();
+
+    assert_eq!(res.messages.len(), 0);
+
+    let owner = state.ownership.owner.load(deps.as_ref().storage).unwrap();
+    assert_eq!(owner, Addr::unchecked("owner"));
+
+    let err = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("pumpkin", &[]),
+        ExecuteMsg::AcceptOwnership {},
+    )
+    .unwrap_err();
+
+    assert_eq!(
+        err,
+        ContractError::Unauthorized {
+            required_role: Role::NewOwner,
+            sender: "pumpkin".to_string()
+        }
+    );
+
+    let res =
+        execute(deps.as_mut(), mock_env(), mock_info("jake", &[]), ExecuteMsg::AcceptOwnership {})
+            .unwrap();
+
+    assert_eq!(res.messages.len(), 0);
+
+    let owner = state.ownership.owner.load(deps.as_ref().storage).unwrap();
+    assert_eq!(owner, Addr::unchecked("jake"));
+}
+
+#[test]
+fn recording_privileged_actions() {
+    let mut deps = setup_test();
+
+    let transfer_ownership = |new_owner: &str| ExecuteMsg::TransferOwnership {
+        new_owner: new_owner.to_string(),
+    };
+
+    execute(
+        deps.as_mut(),
+        mock_env_at_timestamp(10000),
+        mock_info("owner", &[]),
+        transfer_ownership("jake"),
+    )
+    .unwrap();
+
+    // failed actions are not recorded
+    execute(deps.as_mut(), mock_env(), mock_info("pumpkin", &[]), transfer_ownership("pumpkin"))
+        .unwrap_err();
+
+    execute(
+        deps.as_mut(),
+        mock_env_at_timestamp(10001),
+        mock_info("owner", &[]),
+        transfer_ownership("pumpkin"),
+    )
+    .unwrap();
+
+    let res: Vec<AuditEntry> = query_helper::<PaginatedResponse<AuditEntry, u64>>(
+        deps.as_ref(),
+        QueryMsg::AuditLog {
+            start_after: None,
+            limit: None,
+            order: None,
+        },
+    )
+    .items;
+    assert_eq!(res.len(), 2);
+    assert_eq!(
+        (res[0].id, res[0].action.as_str(), res[0].sender.as_str(), res[0].timestamp),
+        (0, "transfer_ownership", "owner", 10000)
+    );
+    assert_eq!(
+        (res[1].id, res[1].action.as_str(), res[1].sender.as_str(), res[1].timestamp),
+        (1, "transfer_ownership", "owner", 10001)
+    );
+    assert_eq!(res[0].params_hash.len(), 16);
+    assert_ne!(res[0].params_hash, res[1].params_hash);
+
+    // once the log is full, the oldest entries are dropped
+    for _ in 0..AUDIT_LOG_SIZE {
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("owner", &[]),
+            ExecuteMsg::DropOwnershipProposal {},
+        )
+        .unwrap();
+    }
+
+    let res: Vec<AuditEntry> = query_helper::<PaginatedResponse<AuditEntry, u64>>(
+        deps.as_ref(),
+        QueryMsg::AuditLog {
+            start_after: None,
+            limit: Some(1),
+            order: None,
+        },
+    )
+    .items;
+    assert_eq!(res[0].id, 2);
+
+    let res: Vec<AuditEntry> = query_helper::<PaginatedResponse<AuditEntry, u64>>(
+        deps.as_ref(),
+        QueryMsg::AuditLog {
+            start_after: None,
+            limit: Some(1),
+            order: Some(OrderBy::Desc),
+        },
+    )
+    .items;
+    assert_eq!((res[0].id, res[0].action.as_str()), (201, "drop_ownership_proposal"));
+}
+
+#[test]
+fn classifying_privileged_actions() {
+    let mut deps = setup_test();
+    let config: HubConfig = query_helper(deps.as_ref(), QueryMsg::ExportConfig {});
+
+    let privileged = vec![
+        (
+            ExecuteMsg::AddValidator {
+                validator: "dave".to_string(),
+            },
+            "add_validator",
+        ),
+        (
+            ExecuteMsg::RemoveValidator {
+                validator: "dave".to_string(),
+                mode: None,
+            },
+            "remove_validator",
+        ),
+        (
+            ExecuteMsg::SetValidatorPayout {
+                validator: "alice".to_string(),
+                payout: None,
+            },
+            "set_validator_payout",
+        ),
+        (
+            ExecuteMsg::SetWithdrawTemplate {
+                name: "vault".to_string(),
+                template: None,
+            },
+            "set_withdraw_template",
+        ),
+        (
+            ExecuteMsg::TransferOwnership {
+                new_owner: "jake".to_string(),
+            },
+            "transfer_ownership",
+        ),
+        (ExecuteMsg::AcceptOwnership {}, "accept_ownership"),
+        (ExecuteMsg::DropOwnershipProposal {}, "drop_ownership_proposal"),
+        (ExecuteMsg::AcceptOperator {}, "accept_operator"),
+        (ExecuteMsg::AcceptVoteOperator {}, "accept_vote_operator"),
+        (
+            ExecuteMsg::RenounceOwnership {
+                confirmation: "renounce ownership".to_string(),
+            },
+            "renounce_ownership",
+        ),
+        (
+            ExecuteMsg::Reinvest {
+                denoms: None,
+            },
+            "reinvest",
+        ),
+        (ExecuteMsg::TuneDelegations {}, "tune_delegations"),
+        (
+            ExecuteMsg::Rebalance {
+                min_redelegation: None,
+            },
+            "rebalance",
+        ),
+        (
+            ExecuteMsg::Vote {
+                proposal_id: 1,
+                vote: VoteOption::Yes,
+            },
+            "vote",
+        ),
+        (
+            ExecuteMsg::VoteWeighted {
+                proposal_id: 1,
+                votes: vec![],
+            },
+            "vote_weighted",
+        ),
+        (
+            ExecuteMsg::UpdateConfig {
+                protocol_fee_contract: None,
+                protocol_reward_fee: None,
+                operator: None,
+                stages_preset: None,
+                allow_donations: None,
+                delegation_strategy: None,
+                vote_operator: None,
+                guardian: None,
+                exchange_rate_tolerance: None,
+                swap_allowlist: None,
+                swap_max_spread: None,
+                max_reinvest_per_tx: None,
+                unbond_allowlist: None,
+                unbond_fee: None,
+                min_hold_seconds: None,
+                reward_smoothing_period: None,
+                validator_fee_share: None,
+                max_harvest_staleness: None,
+                protocol_fee_schedule: None,
+                action_cooldowns: None,
+                express_epoch: None,
+                rebalance_tolerance: None,
+            },
+            "update_config",
+        ),
+        (ExecuteMsg::ClearCircuitBreaker {}, "clear_circuit_breaker"),
+        (
+            ExecuteMsg::ImportConfig {
+                config,
+            },
+            "import_config",
+        ),
+        (
+            ExecuteMsg::SetStateExport {
+                enabled: true,
+            },
+            "set_state_export",
+        ),
+        (
+            ExecuteMsg::ImportState {
+                export: StateExport::PendingBatch(PendingBatch {
+                    id: 1,
+                    ustake_to_burn: Uint128::zero(),
+                    est_unbond_start_time: 0,
+                }),
+            },
+            "import_state",
+        ),
+        (ExecuteMsg::CompleteImport {}, "complete_import"),
+    ];
+    // recorded whoever sends them, the sender is only authorized by the executed message
+    for (msg, action) in privileged {
+        assert_eq!(
+            privileged_action(deps.as_ref().storage, &Addr::unchecked("jake"), &msg).unwrap(),
+            Some(action)
+        );
+    }
+
+    let public = vec![
+        ExecuteMsg::Donate {},
+        ExecuteMsg::SyncSupply {},
+        ExecuteMsg::SubmitBatch {},
+        ExecuteMsg::Reconcile {
+            limit: None,
+        },
+    ];
+    for msg in public {
+        assert_eq!(
+            privileged_action(deps.as_ref().storage, &Addr::unchecked("operator"), &msg).unwrap(),
+            None
+        );
+    }
+
+    // a harvest is only privileged when the operator skips the validation of its routes
+    let harvest = ExecuteMsg::Harvest {
+        stages: None,
+        withdrawals: None,
+        min_received: None,
+        withdraw_limits: None,
+    };
+    assert_eq!(
+        privileged_action(deps.as_ref().storage, &Addr::unchecked("worker"), &harvest).unwrap(),
+        None
+    );
+
+    execute(deps.as_mut(), mock_env_at_timestamp(10000), mock_info("operator", &[]), harvest)
+        .unwrap();
+
+    let res: Vec<AuditEntry> = query_helper::<PaginatedResponse<AuditEntry, u64>>(
+        deps.as_ref(),
+        QueryMsg::AuditLog {
+            start_after: None,
+            limit: None,
+            order: None,
+        },
+    )
+    .items;
+    assert_eq!(res.len(), 1);
+    assert_eq!(
+        (res[0].action.as_str(), res[0].sender.as_str(), res[0].timestamp),
+        ("harvest", "operator", 10000)
+    );
+}
+
+#[test]
+fn renouncing_ownership() {
+    let mut deps = setup_test();
+    let state = State::default();
+
+    let err = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("jake", &[]),
+        ExecuteMsg::RenounceOwnership {
+            confirmation: "renounce ownership".to_string(),
+        },
+    )
+    .unwrap_err();
+
+    assert_eq!(
+        err,
+        ContractError::Unauthorized {
+            required_role: Role::Owner,
+            sender: "jake".to_string()
+        }
+    );
+
+    let err = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("owner", &[]),
+        ExecuteMsg::RenounceOwnership {
+            confirmation: "yes".to_string(),
+        },
+    )
+    .unwrap_err();
+
+    assert_eq!(err, ContractError::InvalidRenounceConfirmation("renounce ownership".to_string()));
+
+    // nobody could clear the circuit breaker anymore
+    let err = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("owner", &[]),
+        ExecuteMsg::RenounceOwnership {
+            confirmation: "renounce ownership".to_string(),
+        },
+    )
+    .unwrap_err();
+
+    assert_eq!(err, ContractError::RenounceWithoutGuardian {});
+
+    state.guardian.save(deps.as_mut().storage, &Addr::unchecked("guardian")).unwrap();
+
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("owner", &[]),
+        ExecuteMsg::RenounceOwnership {
+            confirmation: "renounce ownership".to_string(),
+        },
+    )
+    .unwrap();
+
+    assert_eq!(res.messages.len(), 0);
+
+    let owner = state.ownership.owner.may_load(deps.as_ref().storage).unwrap();
+    assert_eq!(owner, None);
+
+    let res: ConfigResponse = query_helper(deps.as_ref(), QueryMsg::Config {});
+    assert_eq!(res.owner, None);
+
+    // Owner-gated actions are disabled for good
+    let err = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("owner", &[]),
+        ExecuteMsg::AddValidator {
+            validator: "dave".to_string(),
+        },
+    )
+    .unwrap_err();
+
+    assert_eq!(err, ContractError::OwnershipRenounced {});
 
-    // Attempt to withdraw before any batch has completed unbonding. Should error
     let err = execute(
         deps.as_mut(),
-        mock_env_at_timestamp(5000),
-        mock_info("user_1", &[]),
-        ExecuteMsg::WithdrawUnbonded {
-            receiver: None,
+        mock_env(),
+        mock_info("owner", &[]),
+        ExecuteMsg::TransferOwnership {
+            new_owner: "jake".to_string(),
         },
     )
     .unwrap_err();
 
-    assert_eq!(err, ContractError::CantBeZero("withdrawable amount".into()));
+    assert_eq!(err, ContractError::OwnershipRenounced {});
 
-    // Attempt to withdraw once batches 1 and 2 have finished unbonding, but 3 has not yet
-    //
-    // Withdrawable from batch 1: 95,197 * 23,456 / 92,876 = 24,042
-    // Withdrawable from batch 2: 35,604
-    // Total withdrawable: 24,042 + 35,604 = 59,646
-    //
-    // Batch 1 should be updated:
-    // Total shares: 92,876 - 23,456 = 69,420
-    // Unclaimed utoken: 95,197 - 24,042 = 71,155
-    //
-    // Batch 2 is completely withdrawn, should be purged from storage
-    let res = execute(
+    // The guardian still clears the circuit breaker
+    state.circuit_breaker_tripped.save(deps.as_mut().storage, &true).unwrap();
+    execute(
         deps.as_mut(),
-        mock_env_at_timestamp(25000),
-        mock_info("user_1", &[]),
-        ExecuteMsg::WithdrawUnbonded {
-            receiver: None,
-        },
+        mock_env(),
+        mock_info("guardian", &[]),
+        ExecuteMsg::ClearCircuitBreaker {},
     )
     .unwrap();
 
-    assert_eq!(res.messages.len(), 1);
-    assert_eq!(
-        res.messages[0],
-        SubMsg::new(CosmosMsg::Bank(BankMsg::Send {
-            to_address: "user_1".to_string(),
-            amount: vec![Coin::new(59646, CONTRACT_DENOM)]
-        }))
-    );
+    let tripped = state.circuit_breaker_tripped.load(deps.as_ref().storage).unwrap();
+    assert!(!tripped);
+}
 
-    // Previous batches should have been updated
-    let batch = state.previous_batches.load(deps.as_ref().storage, 1u64).unwrap();
+#[test]
+fn exporting_and_importing_config() {
+    let mut deps = setup_test();
+
+    let exported: HubConfig = query_helper(deps.as_ref(), QueryMsg::ExportConfig {});
     assert_eq!(
-        batch,
-        Batch {
-            id: 1,
-            reconciled: true,
-            total_shares: Uint128::new(69420),
-            utoken_unclaimed: Uint128::new(71155),
-            est_unbond_end_time: 10000,
+        exported,
+        HubConfig {
+            fin_multi_contract: "fin_multi".to_string(),
+            operator: "operator".to_string(),
+            vote_operator: None,
+            guardian: None,
+            epoch_period: 259200,
+            unbond_period: 1814400,
+            validators: vec!["alice".to_string(), "bob".to_string(), "charlie".to_string()],
+            protocol_fee_contract: "fee".to_string(),
+            protocol_reward_fee: Decimal::from_ratio(1u128, 100u128),
+            stages_preset: vec![],
+            swap_allowlist: vec![],
+            swap_max_spread: Decimal::percent(5),
+            allow_donations: false,
+            delegation_strategy: DelegationStrategy::Uniform,
+            exchange_rate_tolerance: None,
+            max_reinvest_per_tx: None,
+            unbond_allowlist: vec![],
+            unbond_fee: None,
+            min_hold_seconds: 0,
+            reward_smoothing_period: 0,
+            validator_fee_share: None,
+            max_harvest_staleness: 0,
+            protocol_fee_schedule: vec![],
+            action_cooldowns: vec![],
+            express_epoch: None,
+            rebalance_tolerance: Decimal::zero(),
         }
     );
 
-    let err = state.previous_batches.load(deps.as_ref().storage, 2u64).unwrap_err();
+    let config = HubConfig {
+        operator: "operator_2".to_string(),
+        vote_operator: Some("vote_operator".to_string()),
+        guardian: Some("guardian".to_string()),
+        epoch_period: 86400,
+        validators: vec!["alice".to_string(), "bob".to_string()],
+        allow_donations: true,
+        exchange_rate_tolerance: Some(Decimal::percent(1)),
+        max_reinvest_per_tx: Some(Uint128::new(1000000)),
+        unbond_allowlist: vec![],
+        unbond_fee: None,
+        min_hold_seconds: 0,
+        reward_smoothing_period: 0,
+        validator_fee_share: None,
+        ..exported
+    };
+
+    let err = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("jake", &[]),
+        ExecuteMsg::ImportConfig {
+            config: config.clone(),
+        },
+    )
+    .unwrap_err();
     assert_eq!(
         err,
-        StdError::NotFound {
-            kind: "eris::hub::Batch".to_string()
+        ContractError::Unauthorized {
+            required_role: Role::Owner,
+            sender: "jake".to_string()
         }
     );
 
-    // User 1's unbond requests in batches 1 and 2 should have been deleted
-    let err1 = state
-        .unbond_requests
-        .load(deps.as_ref().storage, (1u64, &Addr::unchecked("user_1")))
-        .unwrap_err();
-    let err2 = state
-        .unbond_requests
-        .load(deps.as_ref().storage, (1u64, &Addr::unchecked("user_1")))
-        .unwrap_err();
+    // charlie can't be dropped while it still has a delegation
+    deps.querier.set_staking_delegations(&[
+        Delegation::new("alice", 341667),
+        Delegation::new("bob", 341667),
+        Delegation::new("charlie", 341666),
+    ]);
 
-    assert_eq!(
-        err1,
-        StdError::NotFound {
-            kind: "eris::hub::UnbondRequest".to_string()
-        }
-    );
-    assert_eq!(
-        err2,
-        StdError::NotFound {
-            kind: "eris::hub::UnbondRequest".to_string()
-        }
-    );
+    let err = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("owner", &[]),
+        ExecuteMsg::ImportConfig {
+            config: config.clone(),
+        },
+    )
+    .unwrap_err();
+    assert_eq!(err, ContractError::ValidatorStillDelegated("charlie".to_string()));
 
-    // User 3 attempt to withdraw; also specifying a receiver
-    let res = execute(
+    let err = execute(
         deps.as_mut(),
-        mock_env_at_timestamp(25000),
-        mock_info("user_3", &[]),
-        ExecuteMsg::WithdrawUnbonded {
-            receiver: Some("user_2".to_string()),
+        mock_env(),
+        mock_info("owner", &[]),
+        ExecuteMsg::ImportConfig {
+            config: HubConfig {
+                epoch_period: 0,
+                ..config.clone()
+            },
         },
     )
-    .unwrap();
+    .unwrap_err();
+    assert_eq!(err, ContractError::CantBeZero("epoch_period".into()));
 
-    assert_eq!(res.messages.len(), 1);
-    assert_eq!(
-        res.messages[0],
-        SubMsg::new(CosmosMsg::Bank(BankMsg::Send {
-            to_address: "user_2".to_string(),
-            amount: vec![Coin::new(71155, CONTRACT_DENOM)]
-        }))
-    );
+    deps.querier.set_staking_delegations(&[
+        Delegation::new("alice", 512500),
+        Delegation::new("bob", 512500),
+    ]);
 
-    // Batch 1 and user 2's unbonding request should have been purged from storage
-    let err = state.previous_batches.load(deps.as_ref().storage, 1u64).unwrap_err();
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("owner", &[]),
+        ExecuteMsg::ImportConfig {
+            config: config.clone(),
+        },
+    )
+    .unwrap();
+
+    // the operator and vote operator are only proposed until they accept
+    let exported: HubConfig = query_helper(deps.as_ref(), QueryMsg::ExportConfig {});
     assert_eq!(
-        err,
-        StdError::NotFound {
-            kind: "eris::hub::Batch".to_string()
+        exported,
+        HubConfig {
+            operator: "operator".to_string(),
+            vote_operator: None,
+            ..config.clone()
         }
     );
 
-    let err = state
-        .unbond_requests
-        .load(deps.as_ref().storage, (1u64, &Addr::unchecked("user_3")))
-        .unwrap_err();
+    execute(deps.as_mut(), mock_env(), mock_info("operator_2", &[]), ExecuteMsg::AcceptOperator {})
+        .unwrap();
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("vote_operator", &[]),
+        ExecuteMsg::AcceptVoteOperator {},
+    )
+    .unwrap();
 
-    assert_eq!(
-        err,
-        StdError::NotFound {
-            kind: "eris::hub::UnbondRequest".to_string()
-        }
-    );
+    let exported: HubConfig = query_helper(deps.as_ref(), QueryMsg::ExportConfig {});
+    assert_eq!(exported, config);
 }
 
 #[test]
-fn adding_validator() {
+fn exporting_and_importing_state() {
     let mut deps = setup_test();
     let state = State::default();
 
+    let batch = Batch {
+        id: 1,
+        reconciled: false,
+        total_shares: Uint128::new(30000),
+        utoken_unclaimed: Uint128::new(30300),
+        est_unbond_end_time: 2000000,
+        exchange_rate_submitted: Some(Decimal::from_ratio(101u128, 100u128)),
+        exchange_rate_reconciled: None,
+    };
+    let requests = vec![
+        UnbondRequest {
+            id: 1,
+            user: Addr::unchecked("user_1"),
+            shares: Uint128::new(10000),
+        },
+        UnbondRequest {
+            id: 1,
+            user: Addr::unchecked("user_2"),
+            shares: Uint128::new(20000),
+        },
+    ];
+    state.previous_batches.save(deps.as_mut().storage, 1, &batch).unwrap();
+    for request in &requests {
+        state
+            .unbond_requests
+            .save(deps.as_mut().storage, (request.id, &request.user), request)
+            .unwrap();
+    }
+    let pending_batch = PendingBatch {
+        id: 2,
+        ustake_to_burn: Uint128::zero(),
+        est_unbond_start_time: 269200,
+    };
+    state.pending_batch.save(deps.as_mut().storage, &pending_batch).unwrap();
+
+    let err = query(
+        deps.as_ref(),
+        mock_env(),
+        QueryMsg::ExportState {
+            section: ExportSection::PendingBatch {},
+        },
+    )
+    .unwrap_err();
+    assert_eq!(err, StdError::generic_err("state export is not enabled by the owner"));
+
     let err = execute(
         deps.as_mut(),
         mock_env(),
         mock_info("jake", &[]),
-        ExecuteMsg::AddValidator {
-            validator: "dave".to_string(),
+        ExecuteMsg::SetStateExport {
+            enabled: true,
         },
     )
     .unwrap_err();
+    assert_eq!(err, ContractError::unauthorized(Role::Owner, &Addr::unchecked("jake")));
 
-    assert_eq!(err, ContractError::Unauthorized {});
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("owner", &[]),
+        ExecuteMsg::SetStateExport {
+            enabled: true,
+        },
+    )
+    .unwrap();
 
+    // the exported state must not change anymore
     let err = execute(
         deps.as_mut(),
         mock_env(),
-        mock_info("owner", &[]),
-        ExecuteMsg::AddValidator {
-            validator: "alice".to_string(),
+        mock_info("user_1", &[]),
+        ExecuteMsg::WithdrawUnbonded {
+            receiver: None,
+            receiver_msg: None,
         },
     )
     .unwrap_err();
+    assert_eq!(err, ContractError::MigrationInProgress {});
 
-    assert_eq!(err, ContractError::ValidatorAlreadyWhitelisted("alice".into()));
+    let exported_pending_batch: StateExport = query_helper(
+        deps.as_ref(),
+        QueryMsg::ExportState {
+            section: ExportSection::PendingBatch {},
+        },
+    );
+    assert_eq!(exported_pending_batch, StateExport::PendingBatch(pending_batch.clone()));
 
-    let res = execute(
+    let exported_batches: StateExport = query_helper(
+        deps.as_ref(),
+        QueryMsg::ExportState {
+            section: ExportSection::PreviousBatches {
+                start_after: None,
+                limit: None,
+            },
+        },
+    );
+    assert_eq!(exported_batches, StateExport::PreviousBatches(vec![batch.clone()]));
+
+    let exported_requests: StateExport = query_helper(
+        deps.as_ref(),
+        QueryMsg::ExportState {
+            section: ExportSection::UnbondRequests {
+                start_after: Some((1, "user_1".to_string())),
+                limit: None,
+            },
+        },
+    );
+    assert_eq!(exported_requests, StateExport::UnbondRequests(vec![requests[1].clone()]));
+
+    // bootstrap a new hub from the export
+    let mut deps = mock_dependencies();
+    instantiate(
         deps.as_mut(),
-        mock_env(),
-        mock_info("owner", &[]),
-        ExecuteMsg::AddValidator {
-            validator: "dave".to_string(),
+        mock_env_at_timestamp(10000),
+        mock_info("deployer", &[]),
+        InstantiateMsg {
+            fin_multi_contract: "fin_multi".to_string(),
+            owner: "owner".to_string(),
+            denom: "stake2".to_string(),
+            epoch_period: 259200,
+            unbond_period: 1814400,
+            validators: vec!["alice".to_string(), "bob".to_string(), "charlie".to_string()],
+            protocol_fee_contract: "fee".to_string(),
+            protocol_reward_fee: Decimal::from_ratio(1u128, 100u128),
+            operator: "operator".to_string(),
+            stages_preset: None,
+            delegation_strategy: None,
+            vote_operator: None,
+            bootstrap: Some(BootstrapFromExport {
+                pending_batch: pending_batch.clone(),
+            }),
+            max_query_limit: None,
         },
     )
     .unwrap();
 
-    assert_eq!(res.messages.len(), 0);
+    let res: PendingBatch = query_helper(deps.as_ref(), QueryMsg::PendingBatch {});
+    assert_eq!(res, pending_batch);
 
-    let validators = state.validators.load(deps.as_ref().storage).unwrap();
-    assert_eq!(
-        validators,
-        vec![
-            String::from("alice"),
-            String::from("bob"),
-            String::from("charlie"),
-            String::from("dave")
-        ],
-    );
-}
+    let err = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("owner", &[]),
+        ExecuteMsg::ImportState {
+            export: StateExport::UnbondRequests(requests.clone()),
+        },
+    )
+    .unwrap_err();
+    assert_eq!(err, ContractError::BatchNotImported(1));
+
+    // importing the batches twice does not count their claims twice
+    for _ in 0..2 {
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("owner", &[]),
+            ExecuteMsg::ImportState {
+                export: StateExport::PreviousBatches(vec![batch.clone()]),
+            },
+        )
+        .unwrap();
+    }
+    let utoken_unclaimed = state.utoken_unclaimed.load(deps.as_ref().storage).unwrap();
+    assert_eq!(utoken_unclaimed, batch.utoken_unclaimed);
+
+    // importing the requests twice does not count them twice
+    for _ in 0..2 {
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("owner", &[]),
+            ExecuteMsg::ImportState {
+                export: StateExport::UnbondRequests(requests.clone()),
+            },
+        )
+        .unwrap();
+    }
 
-#[test]
-fn removing_validator() {
-    let mut deps = setup_test();
-    let state = State::default();
+    let res: Batch = query_helper(deps.as_ref(), QueryMsg::PreviousBatch(1));
+    assert_eq!(res, batch);
 
-    deps.querier.set_staking_delegations(&[
-        Delegation::new("alice", 341667),
-        Delegation::new("bob", 341667),
-        Delegation::new("charlie", 341666),
-    ]);
+    let res: UnbondRequestsByBatchTotalsResponse = query_helper(
+        deps.as_ref(),
+        QueryMsg::UnbondRequestsByBatchTotals {
+            id: 1,
+        },
+    );
+    assert_eq!(
+        res,
+        UnbondRequestsByBatchTotalsResponse {
+            id: 1,
+            count: 2,
+            shares: Uint128::new(30000),
+        }
+    );
 
     let err = execute(
         deps.as_mut(),
-        mock_env(),
-        mock_info("jake", &[]),
-        ExecuteMsg::RemoveValidator {
-            validator: "charlie".to_string(),
+        mock_env_at_timestamp(20000),
+        mock_info("user_1", &[Coin::new(12345, CONTRACT_DENOM)]),
+        ExecuteMsg::Bond {
+            receiver: None,
+            min_ustake_received: None,
         },
     )
     .unwrap_err();
+    assert_eq!(err, ContractError::MigrationInProgress {});
 
-    assert_eq!(err, ContractError::Unauthorized {});
+    execute(deps.as_mut(), mock_env(), mock_info("owner", &[]), ExecuteMsg::CompleteImport {})
+        .unwrap();
 
     let err = execute(
         deps.as_mut(),
         mock_env(),
         mock_info("owner", &[]),
-        ExecuteMsg::RemoveValidator {
-            validator: "dave".to_string(),
+        ExecuteMsg::ImportState {
+            export: StateExport::PreviousBatches(vec![batch]),
         },
     )
     .unwrap_err();
+    assert_eq!(err, ContractError::NotImportingState {});
+}
 
-    assert_eq!(err, ContractError::ValidatorNotWhitelisted("dave".into()));
+#[test]
+fn transferring_operator() {
+    let mut deps = setup_test();
+    let state = State::default();
 
-    // Target: (341667 + 341667 + 341666) / 2 = 512500
-    // Remainder: 0
-    // Alice:   512500 + 0 - 341667 = 170833
-    // Bob:     512500 + 0 - 341667 = 170833
     let res = execute(
         deps.as_mut(),
         mock_env(),
         mock_info("owner", &[]),
-        ExecuteMsg::RemoveValidator {
-            validator: "charlie".to_string(),
+        ExecuteMsg::UpdateConfig {
+            protocol_fee_contract: None,
+            protocol_reward_fee: None,
+            operator: Some("new_operator".to_string()),
+            stages_preset: None,
+            allow_donations: None,
+            delegation_strategy: None,
+            vote_operator: None,
+            guardian: None,
+            exchange_rate_tolerance: None,
+            swap_allowlist: None,
+            swap_max_spread: None,
+            max_reinvest_per_tx: None,
+            unbond_allowlist: None,
+            unbond_fee: None,
+            min_hold_seconds: None,
+            reward_smoothing_period: None,
+            validator_fee_share: None,
+            max_harvest_staleness: None,
+            protocol_fee_schedule: None,
+            action_cooldowns: None,
+            express_epoch: None,
+            rebalance_tolerance: None,
         },
     )
     .unwrap();
 
-    assert_eq!(res.messages.len(), 3);
-    assert_eq!(
-        res.messages[0],
-        SubMsg::new(Redelegation::new("charlie", "alice", 170833).to_cosmos_msg()),
-    );
-    assert_eq!(
-        res.messages[1],
-        SubMsg::new(Redelegation::new("charlie", "bob", 170833).to_cosmos_msg()),
-    );
-    assert_eq!(res.messages[2], check_received_coin(0, 0));
-
-    let validators = state.validators.load(deps.as_ref().storage).unwrap();
-    assert_eq!(validators, vec![String::from("alice"), String::from("bob")],);
-}
+    assert_eq!(res.messages.len(), 0);
 
-#[test]
-fn transferring_ownership() {
-    let mut deps = setup_test();
-    let state = State::default();
+    // The operator only changes once the proposed operator accepts
+    let operator = state.operator.load(deps.as_ref().storage).unwrap();
+    assert_eq!(operator, Addr::unchecked("operator"));
 
     let err = execute(
         deps.as_mut(),
         mock_env(),
-        mock_info("jake", &[]),
-        ExecuteMsg::TransferOwnership {
-            new_owner: "jake".to_string(),
-        },
+        mock_info("pumpkin", &[]),
+        ExecuteMsg::AcceptOperator {},
     )
     .unwrap_err();
 
-    assert_eq!(err, ContractError::Unauthorized {});
+    assert_eq!(
+        err,
+        ContractError::Unauthorized {
+            required_role: Role::NewOperator,
+            sender: "pumpkin".to_string()
+        }
+    );
 
-    let res = execute(
+    execute(
         deps.as_mut(),
         mock_env(),
-        mock_info("owner", &[]),
-        ExecuteMsg::TransferOwnership {
-            new_owner: "jake".to_string(),
-        },
+        mock_info("new_operator", &[]),
+        ExecuteMsg::AcceptOperator {},
     )
     .unwrap();
 
-    assert_eq!(res.messages.len(), 0);
+    let operator = state.operator.load(deps.as_ref().storage).unwrap();
+    assert_eq!(operator, Addr::unchecked("new_operator"));
 
-    let owner = state.owner.load(deps.as_ref().storage).unwrap();
-    assert_eq!(owner, Addr::unchecked("owner"));
+    let new_operator = state.new_operator.may_load(deps.as_ref().storage).unwrap();
+    assert_eq!(new_operator, None);
 
     let err = execute(
         deps.as_mut(),
         mock_env(),
-        mock_info("pumpkin", &[]),
-        ExecuteMsg::AcceptOwnership {},
+        mock_info("vote_operator", &[]),
+        ExecuteMsg::AcceptVoteOperator {},
     )
     .unwrap_err();
 
-    assert_eq!(err, ContractError::UnauthorizedSenderNotNewOwner {});
-
-    let res =
-        execute(deps.as_mut(), mock_env(), mock_info("jake", &[]), ExecuteMsg::AcceptOwnership {})
-            .unwrap();
-
-    assert_eq!(res.messages.len(), 0);
-
-    let owner = state.owner.load(deps.as_ref().storage).unwrap();
-    assert_eq!(owner, Addr::unchecked("jake"));
+    assert_eq!(
+        err,
+        ContractError::Unauthorized {
+            required_role: Role::NewVoteOperator,
+            sender: "vote_operator".to_string()
+        }
+    );
 }
 
 //--------------------------------------------------------------------------------------------------
@@ -1516,7 +4789,9 @@ fn update_fee() {
         config,
         FeeConfig {
             protocol_fee_contract: Addr::unchecked("fee"),
-            protocol_reward_fee: Decimal::from_ratio(1u128, 100u128)
+            protocol_reward_fee: Decimal::from_ratio(1u128, 100u128),
+            unbond_fee: None,
+            validator_fee_share: None,
         }
     );
 
@@ -1532,10 +4807,31 @@ fn update_fee() {
             allow_donations: None,
             delegation_strategy: None,
             vote_operator: None,
+            guardian: None,
+            exchange_rate_tolerance: None,
+            swap_allowlist: None,
+            swap_max_spread: None,
+            max_reinvest_per_tx: None,
+            unbond_allowlist: None,
+            unbond_fee: None,
+            min_hold_seconds: None,
+            reward_smoothing_period: None,
+            validator_fee_share: None,
+            max_harvest_staleness: None,
+            protocol_fee_schedule: None,
+            action_cooldowns: None,
+            express_epoch: None,
+            rebalance_tolerance: None,
         },
     )
     .unwrap_err();
-    assert_eq!(err, ContractError::Unauthorized {});
+    assert_eq!(
+        err,
+        ContractError::Unauthorized {
+            required_role: Role::Owner,
+            sender: "jake".to_string()
+        }
+    );
 
     let err = execute(
         deps.as_mut(),
@@ -1549,6 +4845,21 @@ fn update_fee() {
             allow_donations: None,
             delegation_strategy: None,
             vote_operator: None,
+            guardian: None,
+            exchange_rate_tolerance: None,
+            swap_allowlist: None,
+            swap_max_spread: None,
+            max_reinvest_per_tx: None,
+            unbond_allowlist: None,
+            unbond_fee: None,
+            min_hold_seconds: None,
+            reward_smoothing_period: None,
+            validator_fee_share: None,
+            max_harvest_staleness: None,
+            protocol_fee_schedule: None,
+            action_cooldowns: None,
+            express_epoch: None,
+            rebalance_tolerance: None,
         },
     )
     .unwrap_err();
@@ -1566,6 +4877,21 @@ fn update_fee() {
             allow_donations: None,
             delegation_strategy: None,
             vote_operator: None,
+            guardian: None,
+            exchange_rate_tolerance: None,
+            swap_allowlist: None,
+            swap_max_spread: None,
+            max_reinvest_per_tx: None,
+            unbond_allowlist: None,
+            unbond_fee: None,
+            min_hold_seconds: None,
+            reward_smoothing_period: None,
+            validator_fee_share: None,
+            max_harvest_staleness: None,
+            protocol_fee_schedule: None,
+            action_cooldowns: None,
+            express_epoch: None,
+            rebalance_tolerance: None,
         },
     )
     .unwrap();
@@ -1577,7 +4903,9 @@ fn update_fee() {
         config,
         FeeConfig {
             protocol_fee_contract: Addr::unchecked("fee-new"),
-            protocol_reward_fee: Decimal::from_ratio(10u128, 100u128)
+            protocol_reward_fee: Decimal::from_ratio(10u128, 100u128),
+            unbond_fee: None,
+            validator_fee_share: None,
         }
     );
 }
@@ -1613,10 +4941,33 @@ fn vote() {
             vote_operator: Some("vote_operator".to_string()),
             operator: None,
             stages_preset: None,
+            guardian: None,
+            exchange_rate_tolerance: None,
+            swap_allowlist: None,
+            swap_max_spread: None,
+            max_reinvest_per_tx: None,
+            unbond_allowlist: None,
+            unbond_fee: None,
+            min_hold_seconds: None,
+            reward_smoothing_period: None,
+            validator_fee_share: None,
+            max_harvest_staleness: None,
+            protocol_fee_schedule: None,
+            action_cooldowns: None,
+            express_epoch: None,
+            rebalance_tolerance: None,
         },
     )
     .unwrap();
 
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("vote_operator", &[]),
+        ExecuteMsg::AcceptVoteOperator {},
+    )
+    .unwrap();
+
     let res = execute(
         deps.as_mut(),
         mock_env(),
@@ -1627,7 +4978,13 @@ fn vote() {
         },
     )
     .unwrap_err();
-    assert_eq!(res, ContractError::UnauthorizedSenderNotVoteOperator {});
+    assert_eq!(
+        res,
+        ContractError::Unauthorized {
+            required_role: Role::VoteOperator,
+            sender: "jake".to_string()
+        }
+    );
 
     let res = execute(
         deps.as_mut(),
@@ -1680,10 +5037,33 @@ fn vote_weighted() {
             vote_operator: Some("vote_operator".to_string()),
             operator: None,
             stages_preset: None,
+            guardian: None,
+            exchange_rate_tolerance: None,
+            swap_allowlist: None,
+            swap_max_spread: None,
+            max_reinvest_per_tx: None,
+            unbond_allowlist: None,
+            unbond_fee: None,
+            min_hold_seconds: None,
+            reward_smoothing_period: None,
+            validator_fee_share: None,
+            max_harvest_staleness: None,
+            protocol_fee_schedule: None,
+            action_cooldowns: None,
+            express_epoch: None,
+            rebalance_tolerance: None,
         },
     )
     .unwrap();
 
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("vote_operator", &[]),
+        ExecuteMsg::AcceptVoteOperator {},
+    )
+    .unwrap();
+
     let res = execute(
         deps.as_mut(),
         mock_env(),
@@ -1697,7 +5077,13 @@ fn vote_weighted() {
         },
     )
     .unwrap_err();
-    assert_eq!(res, ContractError::UnauthorizedSenderNotVoteOperator {});
+    assert_eq!(
+        res,
+        ContractError::Unauthorized {
+            required_role: Role::VoteOperator,
+            sender: "jake".to_string()
+        }
+    );
 
     let res = execute(
         deps.as_mut(),
@@ -1717,41 +5103,301 @@ fn vote_weighted() {
     assert_eq!(res.messages.len(), 1);
 
     assert_eq!(
-        res.messages[0].msg,
-        MsgVoteWeighted {
-            proposal_id: 3,
-            voter: MOCK_CONTRACT_ADDR.into(),
-            options: vec![
-                WeightedVoteOption {
-                    option: proto::VoteOption::VOTE_OPTION_YES.into(),
-                    weight: Decimal::from_str("0.1").unwrap().numerator().to_string(),
-                    special_fields: SpecialFields::default()
-                },
-                WeightedVoteOption {
-                    option: proto::VoteOption::VOTE_OPTION_NO.into(),
-                    weight: Decimal::from_str("0.2").unwrap().numerator().to_string(),
-                    special_fields: SpecialFields::default()
-                },
-                WeightedVoteOption {
-                    option: proto::VoteOption::VOTE_OPTION_ABSTAIN.into(),
-                    weight: Decimal::from_str("0.3").unwrap().numerator().to_string(),
-                    special_fields: SpecialFields::default()
-                },
-                WeightedVoteOption {
-                    option: proto::VoteOption::VOTE_OPTION_NO_WITH_VETO.into(),
-                    weight: Decimal::from_str("0.4").unwrap().numerator().to_string(),
-                    special_fields: SpecialFields::default()
-                },
-            ],
-            special_fields: SpecialFields::default()
+        res.messages[0].msg,
+        MsgVoteWeighted {
+            proposal_id: 3,
+            voter: MOCK_CONTRACT_ADDR.into(),
+            options: vec![
+                WeightedVoteOption {
+                    option: proto::VoteOption::VOTE_OPTION_YES.into(),
+                    weight: Decimal::from_str("0.1").unwrap().numerator().to_string(),
+                    special_fields: SpecialFields::default()
+                },
+                WeightedVoteOption {
+                    option: proto::VoteOption::VOTE_OPTION_NO.into(),
+                    weight: Decimal::from_str("0.2").unwrap().numerator().to_string(),
+                    special_fields: SpecialFields::default()
+                },
+                WeightedVoteOption {
+                    option: proto::VoteOption::VOTE_OPTION_ABSTAIN.into(),
+                    weight: Decimal::from_str("0.3").unwrap().numerator().to_string(),
+                    special_fields: SpecialFields::default()
+                },
+                WeightedVoteOption {
+                    option: proto::VoteOption::VOTE_OPTION_NO_WITH_VETO.into(),
+                    weight: Decimal::from_str("0.4").unwrap().numerator().to_string(),
+                    special_fields: SpecialFields::default()
+                },
+            ],
+            special_fields: SpecialFields::default()
+        }
+        .to_cosmos_msg()
+    );
+
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("vote_operator", &[]),
+        ExecuteMsg::Vote {
+            proposal_id: 5,
+            vote: VoteOption::No,
+        },
+    )
+    .unwrap();
+
+    let res: Vec<VoteRecord> = query_helper::<PaginatedResponse<VoteRecord, u64>>(
+        deps.as_ref(),
+        QueryMsg::VoteHistory {
+            start_after: None,
+            limit: None,
+            order: Some(OrderBy::Desc),
+        },
+    )
+    .items;
+    assert_eq!(
+        res,
+        vec![
+            VoteRecord {
+                proposal_id: 5,
+                votes: vec![(Decimal::one(), VoteOption::No)],
+                operator: Addr::unchecked("vote_operator"),
+                timestamp: mock_env().block.time.seconds(),
+            },
+            VoteRecord {
+                proposal_id: 3,
+                votes: vec![
+                    (Decimal::from_str("0.1").unwrap(), VoteOption::Yes),
+                    (Decimal::from_str("0.2").unwrap(), VoteOption::No),
+                    (Decimal::from_str("0.3").unwrap(), VoteOption::Abstain),
+                    (Decimal::from_str("0.4").unwrap(), VoteOption::NoWithVeto),
+                ],
+                operator: Addr::unchecked("vote_operator"),
+                timestamp: mock_env().block.time.seconds(),
+            },
+        ]
+    );
+
+    let res: Vec<VoteRecord> = query_helper::<PaginatedResponse<VoteRecord, u64>>(
+        deps.as_ref(),
+        QueryMsg::VoteHistory {
+            start_after: Some(3),
+            limit: None,
+            order: None,
+        },
+    )
+    .items;
+    assert_eq!(res.iter().map(|record| record.proposal_id).collect::<Vec<_>>(), vec![5]);
+}
+
+//--------------------------------------------------------------------------------------------------
+// Queries
+//--------------------------------------------------------------------------------------------------
+
+#[test]
+fn querying_health() {
+    let mut deps = setup_test();
+    let state = State::default();
+
+    deps.querier.set_jailed_validators(&["bob"]);
+
+    let res: HealthResponse = query_helper_env(deps.as_ref(), QueryMsg::Health {}, 10000);
+    assert_eq!(
+        res,
+        HealthResponse {
+            bonding_paused: false,
+            circuit_breaker_tripped: false,
+            seconds_since_last_harvest: None,
+            harvest_stale: false,
+            pending_batch_overdue: false,
+            unreconciled_batches: 0,
+            jailed_validators: vec!["bob".to_string()],
+        }
+    );
+
+    state.circuit_breaker_tripped.save(deps.as_mut().storage, &true).unwrap();
+    for (id, est_unbond_end_time) in [(1u64, 20000u64), (2, 400000)] {
+        state
+            .previous_batches
+            .save(
+                deps.as_mut().storage,
+                id,
+                &Batch {
+                    id,
+                    reconciled: false,
+                    total_shares: Uint128::new(1000),
+                    utoken_unclaimed: Uint128::new(1000),
+                    est_unbond_end_time,
+                    exchange_rate_submitted: None,
+                    exchange_rate_reconciled: None,
+                },
+            )
+            .unwrap();
+    }
+    execute(
+        deps.as_mut(),
+        mock_env_at_timestamp(20000),
+        mock_info("worker", &[]),
+        ExecuteMsg::Harvest {
+            withdrawals: None,
+            stages: None,
+            min_received: None,
+            withdraw_limits: None,
+        },
+    )
+    .unwrap();
+
+    // only the first batch has finished unbonding
+    let res: HealthResponse = query_helper_env(deps.as_ref(), QueryMsg::Health {}, 300000);
+    assert_eq!(
+        res,
+        HealthResponse {
+            bonding_paused: true,
+            circuit_breaker_tripped: true,
+            seconds_since_last_harvest: Some(280000),
+            harvest_stale: false,
+            pending_batch_overdue: true,
+            unreconciled_batches: 1,
+            jailed_validators: vec!["bob".to_string()],
+        }
+    );
+}
+
+#[test]
+fn querying_prepared_msgs() {
+    let deps = setup_test();
+
+    let res: PreparedMsg = query_helper(
+        deps.as_ref(),
+        QueryMsg::BuildBondMsg {
+            amount: Uint128::new(12345),
+            receiver: Some("user_2".to_string()),
+            min_ustake_received: Some(Uint128::new(12000)),
+        },
+    );
+    assert_eq!(
+        res,
+        PreparedMsg {
+            contract_addr: MOCK_CONTRACT_ADDR.to_string(),
+            msg: ExecuteMsg::Bond {
+                receiver: Some("user_2".to_string()),
+                min_ustake_received: Some(Uint128::new(12000)),
+            },
+            funds: vec![Coin::new(12345, CONTRACT_DENOM)],
+        }
+    );
+
+    let res: PreparedMsg = query_helper(
+        deps.as_ref(),
+        QueryMsg::BuildQueueUnbondMsg {
+            amount: Uint128::new(23456),
+            receiver: None,
+        },
+    );
+    assert_eq!(
+        res,
+        PreparedMsg {
+            contract_addr: MOCK_CONTRACT_ADDR.to_string(),
+            msg: ExecuteMsg::QueueUnbond {
+                receiver: None,
+            },
+            funds: vec![Coin::new(23456, STAKE_DENOM)],
+        }
+    );
+
+    let err = query(
+        deps.as_ref(),
+        mock_env(),
+        QueryMsg::BuildQueueUnbondMsg {
+            amount: Uint128::zero(),
+            receiver: None,
+        },
+    )
+    .unwrap_err();
+    assert_eq!(err, StdError::generic_err("amount can't be zero"));
+}
+
+#[test]
+fn querying_stake_token() {
+    let mut deps = setup_test();
+    let state = State::default();
+
+    set_total_stake_supply(&state, &mut deps, 1000000);
+    deps.querier.set_bank_balances(&[coin(1000000, STAKE_DENOM)]);
+
+    let res: StakeTokenResponse = query_helper(deps.as_ref(), QueryMsg::StakeToken {});
+    assert_eq!(
+        res,
+        StakeTokenResponse {
+            denom: STAKE_DENOM.to_string(),
+            total_supply: Uint128::new(1000000),
+            bank_supply: Uint128::new(1000000),
+            in_sync: true,
         }
-        .to_cosmos_msg()
     );
+
+    deps.querier.set_bank_balances(&[coin(999999, STAKE_DENOM)]);
+
+    let res: StakeTokenResponse = query_helper(deps.as_ref(), QueryMsg::StakeToken {});
+    assert_eq!(res.bank_supply, Uint128::new(999999));
+    assert!(!res.in_sync);
 }
 
-//--------------------------------------------------------------------------------------------------
-// Queries
-//--------------------------------------------------------------------------------------------------
+#[test]
+fn syncing_supply() {
+    let mut deps = setup_test();
+    let state = State::default();
+
+    set_total_stake_supply(&state, &mut deps, 1000000);
+    deps.querier.set_bank_balances(&[coin(1000000, STAKE_DENOM)]);
+
+    let res =
+        execute(deps.as_mut(), mock_env(), mock_info("anyone", &[]), ExecuteMsg::SyncSupply {})
+            .unwrap();
+    assert_eq!(
+        res.events,
+        vec![Event::new("erishub/supply_synced")
+            .add_attribute("total_supply", "1000000")
+            .add_attribute("bank_supply", "1000000")
+            .add_attribute("drift", "0")]
+    );
+
+    // a drift within 0.1% is repaired
+    deps.querier.set_bank_balances(&[coin(999000, STAKE_DENOM)]);
+
+    let res =
+        execute(deps.as_mut(), mock_env(), mock_info("anyone", &[]), ExecuteMsg::SyncSupply {})
+            .unwrap();
+    assert_eq!(
+        res.events,
+        vec![Event::new("erishub/supply_synced")
+            .add_attribute("total_supply", "1000000")
+            .add_attribute("bank_supply", "999000")
+            .add_attribute("drift", "1000")]
+    );
+    assert_eq!(
+        state.stake_token.load(deps.as_ref().storage).unwrap().total_supply,
+        Uint128::new(999000)
+    );
+
+    // a larger drift is not repaired, but pauses bonding and unbonding
+    deps.querier.set_bank_balances(&[coin(1000000, STAKE_DENOM)]);
+
+    let res =
+        execute(deps.as_mut(), mock_env(), mock_info("anyone", &[]), ExecuteMsg::SyncSupply {})
+            .unwrap();
+    assert_eq!(
+        res.events,
+        vec![Event::new("erishub/supply_drift_alert")
+            .add_attribute("total_supply", "999000")
+            .add_attribute("bank_supply", "1000000")
+            .add_attribute("drift", "1000")]
+    );
+    assert_eq!(
+        state.stake_token.load(deps.as_ref().storage).unwrap().total_supply,
+        Uint128::new(999000)
+    );
+    assert!(state.circuit_breaker_tripped.load(deps.as_ref().storage).unwrap());
+}
 
 #[test]
 fn querying_previous_batches() {
@@ -1764,6 +5410,8 @@ fn querying_previous_batches() {
             total_shares: Uint128::new(123),
             utoken_unclaimed: Uint128::new(678),
             est_unbond_end_time: 10000,
+            exchange_rate_submitted: None,
+            exchange_rate_reconciled: None,
         },
         Batch {
             id: 2,
@@ -1771,6 +5419,8 @@ fn querying_previous_batches() {
             total_shares: Uint128::new(234),
             utoken_unclaimed: Uint128::new(789),
             est_unbond_end_time: 15000,
+            exchange_rate_submitted: Some(Decimal::from_ratio(800u128, 234u128)),
+            exchange_rate_reconciled: Some(Decimal::from_ratio(789u128, 234u128)),
         },
         Batch {
             id: 3,
@@ -1778,6 +5428,8 @@ fn querying_previous_batches() {
             total_shares: Uint128::new(345),
             utoken_unclaimed: Uint128::new(890),
             est_unbond_end_time: 20000,
+            exchange_rate_submitted: None,
+            exchange_rate_reconciled: None,
         },
         Batch {
             id: 4,
@@ -1785,6 +5437,8 @@ fn querying_previous_batches() {
             total_shares: Uint128::new(456),
             utoken_unclaimed: Uint128::new(999),
             est_unbond_end_time: 25000,
+            exchange_rate_submitted: None,
+            exchange_rate_reconciled: None,
         },
     ];
 
@@ -1801,33 +5455,134 @@ fn querying_previous_batches() {
     assert_eq!(res, batches[1].clone());
 
     // Query multiple batches
-    let res: Vec<Batch> = query_helper(
+    let res: Vec<Batch> = query_helper::<PaginatedResponse<Batch, u64>>(
         deps.as_ref(),
         QueryMsg::PreviousBatches {
             start_after: None,
             limit: None,
+            order: None,
+            start_time: None,
+            end_time: None,
         },
-    );
+    )
+    .items;
     assert_eq!(res, batches);
 
-    let res: Vec<Batch> = query_helper(
+    let res: Vec<Batch> = query_helper::<PaginatedResponse<Batch, u64>>(
         deps.as_ref(),
         QueryMsg::PreviousBatches {
             start_after: Some(1),
             limit: None,
+            order: None,
+            start_time: None,
+            end_time: None,
         },
-    );
+    )
+    .items;
     assert_eq!(res, vec![batches[1].clone(), batches[2].clone(), batches[3].clone()]);
 
-    let res: Vec<Batch> = query_helper(
+    let res: Vec<Batch> = query_helper::<PaginatedResponse<Batch, u64>>(
         deps.as_ref(),
         QueryMsg::PreviousBatches {
             start_after: Some(4),
             limit: None,
+            order: None,
+            start_time: None,
+            end_time: None,
         },
-    );
+    )
+    .items;
     assert_eq!(res, vec![]);
 
+    // Query multiple batches in descending order
+    let res: Vec<Batch> = query_helper::<PaginatedResponse<Batch, u64>>(
+        deps.as_ref(),
+        QueryMsg::PreviousBatches {
+            start_after: None,
+            limit: Some(2),
+            order: Some(OrderBy::Desc),
+            start_time: None,
+            end_time: None,
+        },
+    )
+    .items;
+    assert_eq!(res, vec![batches[3].clone(), batches[2].clone()]);
+
+    let res: Vec<Batch> = query_helper::<PaginatedResponse<Batch, u64>>(
+        deps.as_ref(),
+        QueryMsg::PreviousBatches {
+            start_after: Some(3),
+            limit: None,
+            order: Some(OrderBy::Desc),
+            start_time: None,
+            end_time: None,
+        },
+    )
+    .items;
+    assert_eq!(res, vec![batches[1].clone(), batches[0].clone()]);
+
+    // Query multiple batches filtered by their estimated unbond end time
+    let res: Vec<Batch> = query_helper::<PaginatedResponse<Batch, u64>>(
+        deps.as_ref(),
+        QueryMsg::PreviousBatches {
+            start_after: None,
+            limit: None,
+            order: None,
+            start_time: Some(15000),
+            end_time: Some(25000),
+        },
+    )
+    .items;
+    assert_eq!(res, vec![batches[1].clone(), batches[2].clone()]);
+
+    let res: Vec<Batch> = query_helper::<PaginatedResponse<Batch, u64>>(
+        deps.as_ref(),
+        QueryMsg::PreviousBatches {
+            start_after: Some(3),
+            limit: None,
+            order: Some(OrderBy::Desc),
+            start_time: Some(12000),
+            end_time: None,
+        },
+    )
+    .items;
+    assert_eq!(res, vec![batches[1].clone()]);
+
+    // Query multiple batches page by page, with the cursor of the next page
+    state.max_query_limit.save(deps.as_mut().storage, &2).unwrap();
+    let query_page = |start_after: Option<u64>| -> PaginatedResponse<Batch, u64> {
+        query_helper(
+            deps.as_ref(),
+            QueryMsg::PreviousBatches {
+                start_after,
+                limit: None,
+                order: None,
+                start_time: None,
+                end_time: None,
+            },
+        )
+    };
+
+    let res = query_page(None);
+    assert_eq!(
+        res,
+        PaginatedResponse {
+            items: vec![batches[0].clone(), batches[1].clone()],
+            next_start_after: Some(2),
+            has_more: true,
+        }
+    );
+
+    let res = query_page(res.next_start_after);
+    assert_eq!(
+        res,
+        PaginatedResponse {
+            items: vec![batches[2].clone(), batches[3].clone()],
+            next_start_after: None,
+            has_more: false,
+        }
+    );
+
     // Query multiple batches, indexed by whether it has been reconciled
     let res = state
         .previous_batches
@@ -1856,6 +5611,192 @@ fn querying_previous_batches() {
         .collect::<Vec<_>>();
 
     assert_eq!(res, vec![batches[0].clone(), batches[2].clone()]);
+
+    // A page skips a limited number of batches outside the time range, the next page continues
+    // after the last skipped one
+    let late_batch = Batch {
+        id: 5 + MAX_BATCHES_SKIPPED as u64,
+        est_unbond_end_time: 30000,
+        ..batches[3].clone()
+    };
+    for id in 5..late_batch.id {
+        let batch = Batch {
+            id,
+            ..batches[3].clone()
+        };
+        state.previous_batches.save(deps.as_mut().storage, id, &batch).unwrap();
+    }
+    state.previous_batches.save(deps.as_mut().storage, late_batch.id, &late_batch).unwrap();
+
+    let query_late_page = |start_after: Option<u64>| -> PaginatedResponse<Batch, u64> {
+        query_helper(
+            deps.as_ref(),
+            QueryMsg::PreviousBatches {
+                start_after,
+                limit: None,
+                order: None,
+                start_time: Some(30000),
+                end_time: None,
+            },
+        )
+    };
+
+    let res = query_late_page(None);
+    assert_eq!(
+        res,
+        PaginatedResponse {
+            items: vec![],
+            next_start_after: Some(MAX_BATCHES_SKIPPED as u64 + 1),
+            has_more: true,
+        }
+    );
+
+    let res = query_late_page(res.next_start_after);
+    assert_eq!(
+        res,
+        PaginatedResponse {
+            items: vec![late_batch],
+            next_start_after: None,
+            has_more: false,
+        }
+    );
+}
+
+#[test]
+fn querying_exchange_rate_twap() {
+    let mut deps = mock_dependencies();
+    let state = State::default();
+
+    state
+        .exchange_rate_history
+        .save(
+            deps.as_mut().storage,
+            &vec![
+                (1000, Decimal::from_str("1.0").unwrap()),
+                (2000, Decimal::from_str("1.1").unwrap()),
+                (3000, Decimal::from_str("1.2").unwrap()),
+            ],
+        )
+        .unwrap();
+
+    // (1.1 * 1000 + 1.2 * 1000) / 2000
+    let res: ExchangeRateTwapResponse = query_helper_env(
+        deps.as_ref(),
+        QueryMsg::ExchangeRateTwap {
+            window_seconds: 2000,
+        },
+        4000,
+    );
+    assert_eq!(
+        res,
+        ExchangeRateTwapResponse {
+            twap: Decimal::from_str("1.15").unwrap(),
+            window_seconds: 2000,
+            observations: 2,
+        }
+    );
+
+    // window is cut at the oldest observation: (1.0 * 1000 + 1.1 * 1000 + 1.2 * 1000) / 3000
+    let res: ExchangeRateTwapResponse = query_helper_env(
+        deps.as_ref(),
+        QueryMsg::ExchangeRateTwap {
+            window_seconds: 10000,
+        },
+        4000,
+    );
+    assert_eq!(
+        res,
+        ExchangeRateTwapResponse {
+            twap: Decimal::from_str("1.1").unwrap(),
+            window_seconds: 3000,
+            observations: 3,
+        }
+    );
+}
+
+#[test]
+fn querying_apy() {
+    let mut deps = mock_dependencies();
+    let state = State::default();
+
+    // no observations yet
+    let res: ApyResponse = query_helper_env(
+        deps.as_ref(),
+        QueryMsg::Apy {
+            window: 86400,
+        },
+        100000,
+    );
+    assert_eq!(res.trailing_apy, Decimal::zero());
+    assert_eq!(res.projected_apy, Decimal::zero());
+    assert_eq!(res.harvests, 0);
+
+    // daily harvests, growing 1% and then 2%
+    state
+        .exchange_rate_history
+        .save(
+            deps.as_mut().storage,
+            &vec![
+                (0, Decimal::from_str("1.0").unwrap()),
+                (86400, Decimal::from_str("1.01").unwrap()),
+                (172800, Decimal::from_str("1.0302").unwrap()),
+            ],
+        )
+        .unwrap();
+
+    let res: ApyResponse = query_helper_env(
+        deps.as_ref(),
+        QueryMsg::Apy {
+            window: 172800,
+        },
+        172800,
+    );
+    assert_eq!(res.harvests, 2);
+    assert_eq!(res.compounding_interval, 86400);
+    assert_eq!(res.window_seconds, 172800);
+    // 1.51% per day over 365 days
+    assert_eq!(
+        res.trailing_apy,
+        (Decimal::from_str("1.0151").unwrap()).checked_pow(365).unwrap() - Decimal::one()
+    );
+    // 2% per day over 365 days
+    assert_eq!(
+        res.projected_apy,
+        (Decimal::from_str("1.02").unwrap()).checked_pow(365).unwrap() - Decimal::one()
+    );
+
+    // the window only covers the last harvest
+    let res: ApyResponse = query_helper_env(
+        deps.as_ref(),
+        QueryMsg::Apy {
+            window: 100000,
+        },
+        172800,
+    );
+    assert_eq!(res.harvests, 1);
+    assert_eq!(res.trailing_apy, res.projected_apy);
+
+    // a shrinking exchange rate has no yield
+    state
+        .exchange_rate_history
+        .save(
+            deps.as_mut().storage,
+            &vec![
+                (0, Decimal::from_str("1.0").unwrap()),
+                (86400, Decimal::from_str("0.99").unwrap()),
+            ],
+        )
+        .unwrap();
+
+    let res: ApyResponse = query_helper_env(
+        deps.as_ref(),
+        QueryMsg::Apy {
+            window: 86400,
+        },
+        86400,
+    );
+    assert_eq!(res.trailing_apy, Decimal::zero());
+    assert_eq!(res.projected_apy, Decimal::zero());
 }
 
 #[test]
@@ -1897,14 +5838,16 @@ fn querying_unbond_requests() {
             .unwrap();
     }
 
-    let res: Vec<UnbondRequestsByBatchResponseItem> = query_helper(
-        deps.as_ref(),
-        QueryMsg::UnbondRequestsByBatch {
-            id: 1,
-            start_after: None,
-            limit: None,
-        },
-    );
+    let res: Vec<UnbondRequestsByBatchResponseItem> =
+        query_helper::<PaginatedResponse<UnbondRequestsByBatchResponseItem, String>>(
+            deps.as_ref(),
+            QueryMsg::UnbondRequestsByBatch {
+                id: 1,
+                start_after: None,
+                limit: None,
+            },
+        )
+        .items;
     assert_eq!(
         res,
         vec![
@@ -1914,34 +5857,40 @@ fn querying_unbond_requests() {
         ]
     );
 
-    let res: Vec<UnbondRequestsByBatchResponseItem> = query_helper(
-        deps.as_ref(),
-        QueryMsg::UnbondRequestsByBatch {
-            id: 2,
-            start_after: None,
-            limit: None,
-        },
-    );
+    let res: Vec<UnbondRequestsByBatchResponseItem> =
+        query_helper::<PaginatedResponse<UnbondRequestsByBatchResponseItem, String>>(
+            deps.as_ref(),
+            QueryMsg::UnbondRequestsByBatch {
+                id: 2,
+                start_after: None,
+                limit: None,
+            },
+        )
+        .items;
     assert_eq!(res, vec![unbond_requests[3].clone().into()]);
 
-    let res: Vec<UnbondRequestsByUserResponseItem> = query_helper(
-        deps.as_ref(),
-        QueryMsg::UnbondRequestsByUser {
-            user: "alice".to_string(),
-            start_after: None,
-            limit: None,
-        },
-    );
+    let res: Vec<UnbondRequestsByUserResponseItem> =
+        query_helper::<PaginatedResponse<UnbondRequestsByUserResponseItem, u64>>(
+            deps.as_ref(),
+            QueryMsg::UnbondRequestsByUser {
+                user: "alice".to_string(),
+                start_after: None,
+                limit: None,
+            },
+        )
+        .items;
     assert_eq!(res, vec![unbond_requests[0].clone().into(), unbond_requests[3].clone().into(),]);
 
-    let res: Vec<UnbondRequestsByUserResponseItem> = query_helper(
-        deps.as_ref(),
-        QueryMsg::UnbondRequestsByUser {
-            user: "alice".to_string(),
-            start_after: Some(1u64),
-            limit: None,
-        },
-    );
+    let res: Vec<UnbondRequestsByUserResponseItem> =
+        query_helper::<PaginatedResponse<UnbondRequestsByUserResponseItem, u64>>(
+            deps.as_ref(),
+            QueryMsg::UnbondRequestsByUser {
+                user: "alice".to_string(),
+                start_after: Some(1u64),
+                limit: None,
+            },
+        )
+        .items;
     assert_eq!(res, vec![unbond_requests[3].clone().into()]);
 }
 
@@ -1993,6 +5942,8 @@ fn querying_unbond_requests_details() {
             total_shares: Uint128::new(123),
             utoken_unclaimed: Uint128::new(678),
             est_unbond_end_time: 10000,
+            exchange_rate_submitted: None,
+            exchange_rate_reconciled: None,
         },
         Batch {
             id: 2,
@@ -2000,6 +5951,8 @@ fn querying_unbond_requests_details() {
             total_shares: Uint128::new(234),
             utoken_unclaimed: Uint128::new(789),
             est_unbond_end_time: 15000,
+            exchange_rate_submitted: None,
+            exchange_rate_reconciled: None,
         },
     ];
 
@@ -2018,15 +5971,17 @@ fn querying_unbond_requests_details() {
             .unwrap();
     }
 
-    let res: Vec<UnbondRequestsByUserResponseItemDetails> = query_helper_env(
-        deps.as_ref(),
-        QueryMsg::UnbondRequestsByUserDetails {
-            user: "alice".to_string(),
-            start_after: None,
-            limit: None,
-        },
-        12000,
-    );
+    let res: Vec<UnbondRequestsByUserResponseItemDetails> =
+        query_helper_env::<PaginatedResponse<UnbondRequestsByUserResponseItemDetails, u64>>(
+            deps.as_ref(),
+            QueryMsg::UnbondRequestsByUserDetails {
+                user: "alice".to_string(),
+                start_after: None,
+                limit: None,
+            },
+            12000,
+        )
+        .items;
     assert_eq!(
         res,
         vec![
@@ -2061,8 +6016,17 @@ fn querying_unbond_requests_details() {
 
 #[test]
 fn computing_undelegations() -> StdResult<()> {
-    let deps = mock_dependencies();
+    let mut deps = mock_dependencies();
     let state = State::default();
+    // the ramp-up weights are counted from the id of the pending batch, nothing ramps up here
+    state.pending_batch.save(
+        deps.as_mut().storage,
+        &PendingBatch {
+            id: 1,
+            ustake_to_burn: Uint128::zero(),
+            est_unbond_start_time: 0,
+        },
+    )?;
     let current_delegations = vec![
         Delegation::new("alice", 400),
         Delegation::new("bob", 300),
@@ -2091,8 +6055,17 @@ fn computing_undelegations() -> StdResult<()> {
 
 #[test]
 fn computing_redelegations_for_removal() -> StdResult<()> {
-    let deps = mock_dependencies();
+    let mut deps = mock_dependencies();
     let state = State::default();
+    // the ramp-up weights are counted from the id of the pending batch, nothing ramps up here
+    state.pending_batch.save(
+        deps.as_mut().storage,
+        &PendingBatch {
+            id: 1,
+            ustake_to_burn: Uint128::zero(),
+            est_unbond_start_time: 0,
+        },
+    )?;
     let current_delegations = vec![
         Delegation::new("alice", 13000),
         Delegation::new("bob", 12000),
@@ -2125,8 +6098,17 @@ fn computing_redelegations_for_removal() -> StdResult<()> {
 
 #[test]
 fn computing_redelegations_for_rebalancing() -> StdResult<()> {
-    let deps = mock_dependencies();
+    let mut deps = mock_dependencies();
     let state = State::default();
+    // the ramp-up weights are counted from the id of the pending batch, nothing ramps up here
+    state.pending_batch.save(
+        deps.as_mut().storage,
+        &PendingBatch {
+            id: 1,
+            ustake_to_burn: Uint128::zero(),
+            est_unbond_start_time: 0,
+        },
+    )?;
     let current_delegations = vec![
         Delegation::new("alice", 69420),
         Delegation::new("bob", 1234),
@@ -2168,7 +6150,8 @@ fn computing_redelegations_for_rebalancing() -> StdResult<()> {
             &state,
             deps.as_ref().storage,
             &current_delegations,
-            current_delegations.iter().map(|a| a.validator.to_string()).collect_vec()
+            current_delegations.iter().map(|a| a.validator.to_string()).collect_vec(),
+            Decimal::zero(),
         )?,
         expected,
     );
@@ -2202,7 +6185,8 @@ fn computing_redelegations_for_rebalancing_complex() -> StdResult<()> {
             &state,
             deps.as_ref().storage,
             &current_delegations,
-            current_delegations.iter().map(|a| a.validator.to_string()).collect_vec()
+            current_delegations.iter().map(|a| a.validator.to_string()).collect_vec(),
+            Decimal::zero(),
         )?,
         vec![],
     );
@@ -2218,7 +6202,8 @@ fn computing_redelegations_for_rebalancing_complex() -> StdResult<()> {
             &state,
             deps.as_ref().storage,
             &current_delegations,
-            current_delegations.iter().map(|a| a.validator.to_string()).collect_vec()
+            current_delegations.iter().map(|a| a.validator.to_string()).collect_vec(),
+            Decimal::zero(),
         )?,
         vec![Redelegation::new("unlisted", "alice", 25000)],
     );
@@ -2234,7 +6219,8 @@ fn computing_redelegations_for_rebalancing_complex() -> StdResult<()> {
             &state,
             deps.as_ref().storage,
             &current_delegations,
-            current_delegations.iter().map(|a| a.validator.to_string()).collect_vec()
+            current_delegations.iter().map(|a| a.validator.to_string()).collect_vec(),
+            Decimal::zero(),
         )?,
         vec![
             Redelegation::new("unlisted", "alice", 25000),
@@ -2252,7 +6238,8 @@ fn computing_redelegations_for_rebalancing_complex() -> StdResult<()> {
             &state,
             deps.as_ref().storage,
             &current_delegations,
-            current_delegations.iter().map(|a| a.validator.to_string()).collect_vec()
+            current_delegations.iter().map(|a| a.validator.to_string()).collect_vec(),
+            Decimal::zero(),
         )?,
         vec![
             Redelegation::new("charlie", "alice", 27500),
@@ -0,0 +1,2 @@
+pub const CONTRACT_NAME: &str = "eris-prop-voting";
+pub const CONTRACT_VERSION: &str = env!("CARGO_PKG_VERSION");
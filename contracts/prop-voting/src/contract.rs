@@ -0,0 +1,74 @@
+use cosmwasm_std::{
+    entry_point, to_binary, Binary, Deps, DepsMut, Env, MessageInfo, Response, StdResult,
+};
+use cw2::set_contract_version;
+
+use eris::prop_voting::{ExecuteMsg, InstantiateMsg, MigrateMsg, QueryMsg};
+
+use crate::constants::{CONTRACT_NAME, CONTRACT_VERSION};
+use crate::error::ContractResult;
+use crate::{execute, queries};
+
+#[entry_point]
+pub fn instantiate(
+    deps: DepsMut,
+    _env: Env,
+    _info: MessageInfo,
+    msg: InstantiateMsg,
+) -> ContractResult {
+    set_contract_version(deps.storage, CONTRACT_NAME, CONTRACT_VERSION)?;
+    execute::instantiate(deps, msg)
+}
+
+#[entry_point]
+pub fn execute(deps: DepsMut, env: Env, info: MessageInfo, msg: ExecuteMsg) -> ContractResult {
+    match msg {
+        ExecuteMsg::OpenProposal {
+            proposal_id,
+            end_time,
+        } => execute::open_proposal(deps, env, info.sender, proposal_id, end_time),
+        ExecuteMsg::Vote {
+            proposal_id,
+            vote,
+        } => execute::vote(deps, env, info, proposal_id, vote),
+        ExecuteMsg::CastVote {
+            proposal_id,
+        } => execute::cast_vote(deps, env, proposal_id),
+        ExecuteMsg::Withdraw {
+            proposal_id,
+        } => execute::withdraw(deps, env, info.sender, proposal_id),
+        ExecuteMsg::AcceptVoteOperator {} => execute::accept_vote_operator(deps, info.sender),
+        ExecuteMsg::TransferOwnership {
+            new_owner,
+        } => execute::transfer_ownership(deps, info.sender, new_owner),
+        ExecuteMsg::AcceptOwnership {} => execute::accept_ownership(deps, info.sender),
+        ExecuteMsg::DropOwnershipProposal {} => execute::drop_ownership_proposal(deps, info.sender),
+    }
+}
+
+#[entry_point]
+pub fn query(deps: Deps, _env: Env, msg: QueryMsg) -> StdResult<Binary> {
+    match msg {
+        QueryMsg::Config {} => to_binary(&queries::config(deps)?),
+        QueryMsg::Proposal {
+            proposal_id,
+        } => to_binary(&queries::proposal(deps, proposal_id)?),
+        QueryMsg::Proposals {
+            start_after,
+            limit,
+        } => to_binary(&queries::proposals(deps, start_after, limit)?),
+        QueryMsg::Ballot {
+            proposal_id,
+            voter,
+        } => to_binary(&queries::ballot(deps, proposal_id, voter)?),
+    }
+}
+
+#[entry_point]
+pub fn migrate(deps: DepsMut, _env: Env, _msg: MigrateMsg) -> ContractResult {
+    set_contract_version(deps.storage, CONTRACT_NAME, CONTRACT_VERSION)?;
+
+    Ok(Response::new()
+        .add_attribute("new_contract_name", CONTRACT_NAME)
+        .add_attribute("new_contract_version", CONTRACT_VERSION))
+}
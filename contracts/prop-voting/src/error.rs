@@ -0,0 +1,92 @@
+use std::fmt;
+
+use cosmwasm_std::{Addr, OverflowError, Response, StdError};
+use eris::ownership::OwnershipError;
+use thiserror::Error;
+
+pub type ContractResult = Result<Response, ContractError>;
+
+/// Roles that can be required to execute privileged actions
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Role {
+    Owner,
+    NewOwner,
+}
+
+impl fmt::Display for Role {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Role::Owner => write!(f, "owner"),
+            Role::NewOwner => write!(f, "new owner"),
+        }
+    }
+}
+
+/// This enum describes prop voting contract errors
+#[derive(Error, Debug, PartialEq)]
+pub enum ContractError {
+    #[error("{0}")]
+    Std(#[from] StdError),
+
+    #[error("{0}")]
+    Overflow(#[from] OverflowError),
+
+    #[error("Unauthorized: sender {sender} is not {required_role}")]
+    Unauthorized {
+        required_role: Role,
+        sender: String,
+    },
+
+    #[error("Unauthorized: ownership has been renounced")]
+    OwnershipRenounced {},
+
+    #[error("Proposal {0} is already open for voting")]
+    ProposalAlreadyOpen(u64),
+
+    #[error("Proposal {0} is not open for voting")]
+    ProposalNotFound(u64),
+
+    #[error("End time {0} is not in the future")]
+    InvalidEndTime(u64),
+
+    #[error("Voting on proposal {0} has ended")]
+    VotingEnded(u64),
+
+    #[error("Voting on proposal {0} has not ended yet")]
+    VotingNotEnded(u64),
+
+    #[error("The vote on proposal {0} has already been cast")]
+    VoteAlreadyCast(u64),
+
+    #[error("Proposal {0} has no votes")]
+    NoVotes(u64),
+
+    #[error("No vote on proposal {0}")]
+    BallotNotFound(u64),
+}
+
+impl ContractError {
+    pub fn unauthorized(required_role: Role, sender: &Addr) -> Self {
+        ContractError::Unauthorized {
+            required_role,
+            sender: sender.to_string(),
+        }
+    }
+}
+
+impl From<OwnershipError> for ContractError {
+    fn from(err: OwnershipError) -> Self {
+        match err {
+            OwnershipError::Std(err) => ContractError::Std(err),
+            OwnershipError::NotOwner(sender) => ContractError::Unauthorized {
+                required_role: Role::Owner,
+                sender,
+            },
+            OwnershipError::NotNewOwner(sender) => ContractError::Unauthorized {
+                required_role: Role::NewOwner,
+                sender,
+            },
+            OwnershipError::Renounced {} => ContractError::OwnershipRenounced {},
+        }
+    }
+}
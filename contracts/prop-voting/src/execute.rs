@@ -0,0 +1,225 @@
+use cosmwasm_std::{
+    coins, to_binary, Addr, BankMsg, Coin, DepsMut, Env, Event, MessageInfo, Response, StdError,
+    StdResult, Uint128, VoteOption, WasmMsg,
+};
+
+use eris::hub::{ExecuteMsg as HubExecuteMsg, QueryMsg as HubQueryMsg, StakeTokenResponse};
+use eris::prop_voting::{Ballot, InstantiateMsg, Tally};
+
+use crate::error::{ContractError, ContractResult};
+use crate::state::{Proposal, State};
+
+//--------------------------------------------------------------------------------------------------
+// Instantiation
+//--------------------------------------------------------------------------------------------------
+
+pub fn instantiate(deps: DepsMut, msg: InstantiateMsg) -> ContractResult {
+    let state = State::default();
+
+    let hub = deps.api.addr_validate(&msg.hub)?;
+    let stake_token: StakeTokenResponse =
+        deps.querier.query_wasm_smart(&hub, &HubQueryMsg::StakeToken {})?;
+
+    state.ownership.initialize(deps.storage, deps.api, &msg.owner)?;
+    state.hub.save(deps.storage, &hub)?;
+    state.stake_denom.save(deps.storage, &stake_token.denom)?;
+
+    Ok(Response::new())
+}
+
+//--------------------------------------------------------------------------------------------------
+// Voting
+//--------------------------------------------------------------------------------------------------
+
+pub fn open_proposal(
+    deps: DepsMut,
+    env: Env,
+    sender: Addr,
+    proposal_id: u64,
+    end_time: u64,
+) -> ContractResult {
+    let state = State::default();
+
+    state.assert_owner(deps.storage, &sender)?;
+
+    if state.proposals.has(deps.storage, proposal_id) {
+        return Err(ContractError::ProposalAlreadyOpen(proposal_id));
+    }
+    if end_time <= env.block.time.seconds() {
+        return Err(ContractError::InvalidEndTime(end_time));
+    }
+
+    state.proposals.save(
+        deps.storage,
+        proposal_id,
+        &Proposal {
+            end_time,
+            tally: Tally::default(),
+            cast: false,
+        },
+    )?;
+
+    let event = Event::new("erisvoting/proposal_opened")
+        .add_attribute("proposal_id", proposal_id.to_string())
+        .add_attribute("end_time", end_time.to_string());
+
+    Ok(Response::new().add_event(event).add_attribute("action", "erisvoting/open_proposal"))
+}
+
+/// The stake token is locked with the vote instead of weighing the voter's balance, as the bank
+/// module keeps no balance snapshots the tally could rely on
+pub fn vote(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    proposal_id: u64,
+    vote: VoteOption,
+) -> ContractResult {
+    let state = State::default();
+
+    let mut proposal = state.load_proposal(deps.storage, proposal_id)?;
+    if env.block.time.seconds() >= proposal.end_time {
+        return Err(ContractError::VotingEnded(proposal_id));
+    }
+
+    let stake_denom = state.stake_denom.load(deps.storage)?;
+    let amount = parse_received_fund(&info.funds, &stake_denom)?;
+
+    let key = (proposal_id, &info.sender);
+    let mut ballot = match state.ballots.may_load(deps.storage, key)? {
+        Some(ballot) => {
+            let locked = proposal.tally.option_mut(&ballot.vote);
+            *locked = locked.checked_sub(ballot.amount)?;
+            ballot
+        },
+        None if amount.is_zero() => return Err(ContractError::BallotNotFound(proposal_id)),
+        None => Ballot {
+            vote: vote.clone(),
+            amount: Uint128::zero(),
+        },
+    };
+    ballot.vote = vote;
+    ballot.amount = ballot.amount.checked_add(amount)?;
+
+    let locked = proposal.tally.option_mut(&ballot.vote);
+    *locked = locked.checked_add(ballot.amount)?;
+
+    state.ballots.save(deps.storage, key, &ballot)?;
+    state.proposals.save(deps.storage, proposal_id, &proposal)?;
+
+    let event = Event::new("erisvoting/voted")
+        .add_attribute("proposal_id", proposal_id.to_string())
+        .add_attribute("voter", info.sender)
+        .add_attribute("vote", format!("{:?}", ballot.vote))
+        .add_attribute("amount", ballot.amount);
+
+    Ok(Response::new().add_event(event).add_attribute("action", "erisvoting/vote"))
+}
+
+pub fn cast_vote(deps: DepsMut, env: Env, proposal_id: u64) -> ContractResult {
+    let state = State::default();
+
+    let mut proposal = state.load_proposal(deps.storage, proposal_id)?;
+    if env.block.time.seconds() < proposal.end_time {
+        return Err(ContractError::VotingNotEnded(proposal_id));
+    }
+    if proposal.cast {
+        return Err(ContractError::VoteAlreadyCast(proposal_id));
+    }
+
+    let votes = proposal.tally.weighted_votes();
+    if votes.is_empty() {
+        return Err(ContractError::NoVotes(proposal_id));
+    }
+
+    proposal.cast = true;
+    state.proposals.save(deps.storage, proposal_id, &proposal)?;
+
+    let msg = WasmMsg::Execute {
+        contract_addr: state.hub.load(deps.storage)?.into(),
+        msg: to_binary(&HubExecuteMsg::VoteWeighted {
+            proposal_id,
+            votes,
+        })?,
+        funds: vec![],
+    };
+
+    let event = Event::new("erisvoting/vote_cast")
+        .add_attribute("proposal_id", proposal_id.to_string())
+        .add_attribute("total", proposal.tally.total());
+
+    Ok(Response::new()
+        .add_message(msg)
+        .add_event(event)
+        .add_attribute("action", "erisvoting/cast_vote"))
+}
+
+/// The tally of the proposal is kept, so that it can still be cast after voters withdrew
+pub fn withdraw(deps: DepsMut, env: Env, sender: Addr, proposal_id: u64) -> ContractResult {
+    let state = State::default();
+
+    let proposal = state.load_proposal(deps.storage, proposal_id)?;
+    if env.block.time.seconds() < proposal.end_time {
+        return Err(ContractError::VotingNotEnded(proposal_id));
+    }
+
+    let ballot = state
+        .ballots
+        .may_load(deps.storage, (proposal_id, &sender))?
+        .ok_or(ContractError::BallotNotFound(proposal_id))?;
+    state.ballots.remove(deps.storage, (proposal_id, &sender));
+
+    let msg = BankMsg::Send {
+        to_address: sender.to_string(),
+        amount: coins(ballot.amount.u128(), state.stake_denom.load(deps.storage)?),
+    };
+
+    let event = Event::new("erisvoting/withdrawn")
+        .add_attribute("proposal_id", proposal_id.to_string())
+        .add_attribute("voter", sender)
+        .add_attribute("amount", ballot.amount);
+
+    Ok(Response::new()
+        .add_message(msg)
+        .add_event(event)
+        .add_attribute("action", "erisvoting/withdraw"))
+}
+
+/// Without funds the amount is zero, so that voters can change their option
+fn parse_received_fund(funds: &[Coin], denom: &str) -> StdResult<Uint128> {
+    match funds {
+        [] => Ok(Uint128::zero()),
+        [fund] if fund.denom == denom => Ok(fund.amount),
+        _ => Err(StdError::generic_err(format!("must deposit {} only", denom))),
+    }
+}
+
+//--------------------------------------------------------------------------------------------------
+// Ownership and management logics
+//--------------------------------------------------------------------------------------------------
+
+pub fn accept_vote_operator(deps: DepsMut, sender: Addr) -> ContractResult {
+    let state = State::default();
+
+    state.assert_owner(deps.storage, &sender)?;
+
+    let msg = WasmMsg::Execute {
+        contract_addr: state.hub.load(deps.storage)?.into(),
+        msg: to_binary(&HubExecuteMsg::AcceptVoteOperator {})?,
+        funds: vec![],
+    };
+
+    Ok(Response::new().add_message(msg).add_attribute("action", "erisvoting/accept_vote_operator"))
+}
+
+pub fn transfer_ownership(deps: DepsMut, sender: Addr, new_owner: String) -> ContractResult {
+    Ok(State::default().ownership.transfer_ownership(deps, &sender, new_owner)?)
+}
+
+pub fn drop_ownership_proposal(deps: DepsMut, sender: Addr) -> ContractResult {
+    Ok(State::default().ownership.drop_ownership_proposal(deps, &sender)?)
+}
+
+pub fn accept_ownership(deps: DepsMut, sender: Addr) -> ContractResult {
+    Ok(State::default().ownership.accept_ownership(deps, &sender)?)
+}
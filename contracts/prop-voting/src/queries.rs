@@ -0,0 +1,61 @@
+use cosmwasm_std::{Deps, Order, StdResult};
+
+use eris::helpers::pagination::{get_limit, start_after_bound};
+use eris::prop_voting::{Ballot, ConfigResponse, ProposalResponse};
+
+use crate::state::{Proposal, State};
+
+pub fn config(deps: Deps) -> StdResult<ConfigResponse> {
+    let state = State::default();
+
+    Ok(ConfigResponse {
+        owner: state.ownership.owner.load(deps.storage)?.into(),
+        new_owner: state.ownership.query(deps.storage)?.new_owner,
+        hub: state.hub.load(deps.storage)?,
+        stake_denom: state.stake_denom.load(deps.storage)?,
+    })
+}
+
+pub fn proposal(deps: Deps, proposal_id: u64) -> StdResult<ProposalResponse> {
+    let state = State::default();
+
+    let proposal = state.proposals.load(deps.storage, proposal_id)?;
+    Ok(proposal_response(proposal_id, proposal))
+}
+
+pub fn proposals(
+    deps: Deps,
+    start_after: Option<u64>,
+    limit: Option<u32>,
+) -> StdResult<Vec<ProposalResponse>> {
+    let state = State::default();
+
+    let limit = get_limit(limit);
+    let start = start_after_bound(start_after);
+
+    state
+        .proposals
+        .range(deps.storage, start, None, Order::Ascending)
+        .take(limit)
+        .map(|item| {
+            let (proposal_id, proposal) = item?;
+            Ok(proposal_response(proposal_id, proposal))
+        })
+        .collect()
+}
+
+pub fn ballot(deps: Deps, proposal_id: u64, voter: String) -> StdResult<Option<Ballot>> {
+    let state = State::default();
+
+    let voter = deps.api.addr_validate(&voter)?;
+    state.ballots.may_load(deps.storage, (proposal_id, &voter))
+}
+
+fn proposal_response(proposal_id: u64, proposal: Proposal) -> ProposalResponse {
+    ProposalResponse {
+        proposal_id,
+        end_time: proposal.end_time,
+        tally: proposal.tally,
+        cast: proposal.cast,
+    }
+}
@@ -0,0 +1,57 @@
+use cosmwasm_schema::cw_serde;
+use cosmwasm_std::{Addr, Storage};
+use cw_storage_plus::{Item, Map};
+
+use eris::ownership::Ownership;
+use eris::prop_voting::{Ballot, Tally};
+
+use crate::error::ContractError;
+
+/// Chain proposal open for voting
+#[cw_serde]
+pub struct Proposal {
+    pub end_time: u64,
+    pub tally: Tally,
+    pub cast: bool,
+}
+
+pub(crate) struct State<'a> {
+    /// Account who can open proposals for voting, with its pending ownership transfer
+    pub ownership: Ownership<'a>,
+    /// Hub whose stake token is voted with
+    pub hub: Item<'a, Addr>,
+    /// Denom of the stake token
+    pub stake_denom: Item<'a, String>,
+    /// Proposals opened for voting, by id
+    pub proposals: Map<'a, u64, Proposal>,
+    /// Votes per proposal and voter, with the locked stake token
+    pub ballots: Map<'a, (u64, &'a Addr), Ballot>,
+}
+
+impl Default for State<'static> {
+    fn default() -> Self {
+        Self {
+            ownership: Ownership::new("erisvoting"),
+            hub: Item::new("hub"),
+            stake_denom: Item::new("stake_denom"),
+            proposals: Map::new("proposals"),
+            ballots: Map::new("ballots"),
+        }
+    }
+}
+
+impl<'a> State<'a> {
+    pub fn assert_owner(&self, storage: &dyn Storage, sender: &Addr) -> Result<(), ContractError> {
+        Ok(self.ownership.assert_owner(storage, sender)?)
+    }
+
+    pub fn load_proposal(
+        &self,
+        storage: &dyn Storage,
+        proposal_id: u64,
+    ) -> Result<Proposal, ContractError> {
+        self.proposals
+            .may_load(storage, proposal_id)?
+            .ok_or(ContractError::ProposalNotFound(proposal_id))
+    }
+}
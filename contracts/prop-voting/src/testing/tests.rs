@@ -0,0 +1,513 @@
+use std::str::FromStr;
+
+use cosmwasm_std::testing::{
+    mock_dependencies, mock_env, mock_info, MockApi, MockQuerier, MockStorage,
+};
+use cosmwasm_std::{
+    coin, coins, from_binary, to_binary, Addr, BankMsg, ContractResult, Decimal, Deps, Env,
+    OwnedDeps, StdError, SubMsg, SystemError, SystemResult, Timestamp, Uint128, VoteOption,
+    WasmMsg, WasmQuery,
+};
+use serde::de::DeserializeOwned;
+
+use eris::hub::{ExecuteMsg as HubExecuteMsg, QueryMsg as HubQueryMsg, StakeTokenResponse};
+use eris::prop_voting::{
+    Ballot, ConfigResponse, ExecuteMsg, InstantiateMsg, ProposalResponse, QueryMsg, Tally,
+};
+
+use crate::contract::{execute, instantiate, query};
+use crate::error::{ContractError, Role};
+
+//--------------------------------------------------------------------------------------------------
+// Test setup
+//--------------------------------------------------------------------------------------------------
+
+const STAKE_DENOM: &str = "factory/hub/ampKUJI";
+
+fn mock_env_at_timestamp(timestamp: u64) -> Env {
+    let mut env = mock_env();
+    env.block.time = Timestamp::from_seconds(timestamp);
+    env
+}
+
+fn setup_test() -> OwnedDeps<MockStorage, MockApi, MockQuerier> {
+    let mut deps = mock_dependencies();
+
+    deps.querier.update_wasm(|query| match query {
+        WasmQuery::Smart {
+            contract_addr,
+            msg,
+        } if contract_addr == "hub" => {
+            let res = match from_binary(msg).unwrap() {
+                HubQueryMsg::StakeToken {} => to_binary(&StakeTokenResponse {
+                    denom: STAKE_DENOM.to_string(),
+                    total_supply: Uint128::new(1000),
+                    bank_supply: Uint128::new(1000),
+                    in_sync: true,
+                }),
+                _ => panic!("unsupported hub query"),
+            };
+            SystemResult::Ok(ContractResult::Ok(res.unwrap()))
+        },
+        _ => SystemResult::Err(SystemError::UnsupportedRequest {
+            kind: "wasm".to_string(),
+        }),
+    });
+
+    instantiate(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("deployer", &[]),
+        InstantiateMsg {
+            owner: "owner".to_string(),
+            hub: "hub".to_string(),
+        },
+    )
+    .unwrap();
+
+    execute(
+        deps.as_mut(),
+        mock_env_at_timestamp(10000),
+        mock_info("owner", &[]),
+        ExecuteMsg::OpenProposal {
+            proposal_id: 1,
+            end_time: 20000,
+        },
+    )
+    .unwrap();
+
+    deps
+}
+
+fn query_helper<T: DeserializeOwned>(deps: Deps, msg: QueryMsg) -> T {
+    from_binary(&query(deps, mock_env(), msg).unwrap()).unwrap()
+}
+
+fn vote_msg(vote: VoteOption) -> ExecuteMsg {
+    ExecuteMsg::Vote {
+        proposal_id: 1,
+        vote,
+    }
+}
+
+fn tally(deps: Deps) -> Tally {
+    let res: ProposalResponse = query_helper(
+        deps,
+        QueryMsg::Proposal {
+            proposal_id: 1,
+        },
+    );
+    res.tally
+}
+
+//--------------------------------------------------------------------------------------------------
+// Execution
+//--------------------------------------------------------------------------------------------------
+
+#[test]
+fn proper_instantiation() {
+    let deps = setup_test();
+
+    let res: ConfigResponse = query_helper(deps.as_ref(), QueryMsg::Config {});
+    assert_eq!(
+        res,
+        ConfigResponse {
+            owner: "owner".to_string(),
+            new_owner: None,
+            hub: Addr::unchecked("hub"),
+            stake_denom: STAKE_DENOM.to_string(),
+        }
+    );
+}
+
+#[test]
+fn opening_proposal() {
+    let mut deps = setup_test();
+
+    let err = execute(
+        deps.as_mut(),
+        mock_env_at_timestamp(10000),
+        mock_info("jake", &[]),
+        ExecuteMsg::OpenProposal {
+            proposal_id: 2,
+            end_time: 20000,
+        },
+    )
+    .unwrap_err();
+    assert_eq!(err, ContractError::unauthorized(Role::Owner, &Addr::unchecked("jake")));
+
+    let err = execute(
+        deps.as_mut(),
+        mock_env_at_timestamp(10000),
+        mock_info("owner", &[]),
+        ExecuteMsg::OpenProposal {
+            proposal_id: 1,
+            end_time: 30000,
+        },
+    )
+    .unwrap_err();
+    assert_eq!(err, ContractError::ProposalAlreadyOpen(1));
+
+    let err = execute(
+        deps.as_mut(),
+        mock_env_at_timestamp(10000),
+        mock_info("owner", &[]),
+        ExecuteMsg::OpenProposal {
+            proposal_id: 2,
+            end_time: 10000,
+        },
+    )
+    .unwrap_err();
+    assert_eq!(err, ContractError::InvalidEndTime(10000));
+
+    execute(
+        deps.as_mut(),
+        mock_env_at_timestamp(10000),
+        mock_info("owner", &[]),
+        ExecuteMsg::OpenProposal {
+            proposal_id: 2,
+            end_time: 30000,
+        },
+    )
+    .unwrap();
+
+    let res: Vec<ProposalResponse> = query_helper(
+        deps.as_ref(),
+        QueryMsg::Proposals {
+            start_after: Some(1),
+            limit: None,
+        },
+    );
+    assert_eq!(
+        res,
+        vec![ProposalResponse {
+            proposal_id: 2,
+            end_time: 30000,
+            tally: Tally::default(),
+            cast: false,
+        }]
+    );
+}
+
+#[test]
+fn voting() {
+    let mut deps = setup_test();
+
+    let err = execute(
+        deps.as_mut(),
+        mock_env_at_timestamp(15000),
+        mock_info("alice", &[coin(100, "ukuji")]),
+        vote_msg(VoteOption::Yes),
+    )
+    .unwrap_err();
+    assert_eq!(
+        err,
+        ContractError::Std(StdError::generic_err(format!("must deposit {} only", STAKE_DENOM)))
+    );
+
+    // changing the option needs an existing vote
+    let err = execute(
+        deps.as_mut(),
+        mock_env_at_timestamp(15000),
+        mock_info("alice", &[]),
+        vote_msg(VoteOption::Yes),
+    )
+    .unwrap_err();
+    assert_eq!(err, ContractError::BallotNotFound(1));
+
+    let err = execute(
+        deps.as_mut(),
+        mock_env_at_timestamp(15000),
+        mock_info("alice", &[coin(100, STAKE_DENOM)]),
+        ExecuteMsg::Vote {
+            proposal_id: 2,
+            vote: VoteOption::Yes,
+        },
+    )
+    .unwrap_err();
+    assert_eq!(err, ContractError::ProposalNotFound(2));
+
+    for (voter, amount, vote) in [
+        ("alice", 100, VoteOption::Yes),
+        ("bob", 300, VoteOption::No),
+        ("alice", 50, VoteOption::Yes),
+    ] {
+        execute(
+            deps.as_mut(),
+            mock_env_at_timestamp(15000),
+            mock_info(voter, &[coin(amount, STAKE_DENOM)]),
+            vote_msg(vote),
+        )
+        .unwrap();
+    }
+
+    assert_eq!(
+        tally(deps.as_ref()),
+        Tally {
+            yes: Uint128::new(150),
+            no: Uint128::new(300),
+            abstain: Uint128::zero(),
+            no_with_veto: Uint128::zero(),
+        }
+    );
+
+    // the locked tokens move with the option
+    execute(
+        deps.as_mut(),
+        mock_env_at_timestamp(15000),
+        mock_info("bob", &[]),
+        vote_msg(VoteOption::Abstain),
+    )
+    .unwrap();
+
+    assert_eq!(
+        tally(deps.as_ref()),
+        Tally {
+            yes: Uint128::new(150),
+            no: Uint128::zero(),
+            abstain: Uint128::new(300),
+            no_with_veto: Uint128::zero(),
+        }
+    );
+
+    let res: Option<Ballot> = query_helper(
+        deps.as_ref(),
+        QueryMsg::Ballot {
+            proposal_id: 1,
+            voter: "bob".to_string(),
+        },
+    );
+    assert_eq!(
+        res,
+        Some(Ballot {
+            vote: VoteOption::Abstain,
+            amount: Uint128::new(300),
+        })
+    );
+
+    let err = execute(
+        deps.as_mut(),
+        mock_env_at_timestamp(20000),
+        mock_info("charlie", &[coin(100, STAKE_DENOM)]),
+        vote_msg(VoteOption::Yes),
+    )
+    .unwrap_err();
+    assert_eq!(err, ContractError::VotingEnded(1));
+}
+
+#[test]
+fn casting_vote() {
+    let mut deps = setup_test();
+
+    let err = execute(
+        deps.as_mut(),
+        mock_env_at_timestamp(20000),
+        mock_info("worker", &[]),
+        ExecuteMsg::CastVote {
+            proposal_id: 1,
+        },
+    )
+    .unwrap_err();
+    assert_eq!(err, ContractError::NoVotes(1));
+
+    for (voter, amount, vote) in [
+        ("alice", 100, VoteOption::Yes),
+        ("bob", 100, VoteOption::No),
+        ("charlie", 100, VoteOption::NoWithVeto),
+    ] {
+        execute(
+            deps.as_mut(),
+            mock_env_at_timestamp(15000),
+            mock_info(voter, &[coin(amount, STAKE_DENOM)]),
+            vote_msg(vote),
+        )
+        .unwrap();
+    }
+
+    let err = execute(
+        deps.as_mut(),
+        mock_env_at_timestamp(19999),
+        mock_info("worker", &[]),
+        ExecuteMsg::CastVote {
+            proposal_id: 1,
+        },
+    )
+    .unwrap_err();
+    assert_eq!(err, ContractError::VotingNotEnded(1));
+
+    let res = execute(
+        deps.as_mut(),
+        mock_env_at_timestamp(20000),
+        mock_info("worker", &[]),
+        ExecuteMsg::CastVote {
+            proposal_id: 1,
+        },
+    )
+    .unwrap();
+
+    // the last option takes the rounding, the weights add up to exactly one
+    let third = Decimal::from_str("0.333333333333333333").unwrap();
+    assert_eq!(
+        res.messages,
+        vec![SubMsg::new(WasmMsg::Execute {
+            contract_addr: "hub".to_string(),
+            msg: to_binary(&HubExecuteMsg::VoteWeighted {
+                proposal_id: 1,
+                votes: vec![
+                    (third, VoteOption::Yes),
+                    (third, VoteOption::No),
+                    (Decimal::from_str("0.333333333333333334").unwrap(), VoteOption::NoWithVeto),
+                ],
+            })
+            .unwrap(),
+            funds: vec![],
+        })]
+    );
+
+    let err = execute(
+        deps.as_mut(),
+        mock_env_at_timestamp(20000),
+        mock_info("worker", &[]),
+        ExecuteMsg::CastVote {
+            proposal_id: 1,
+        },
+    )
+    .unwrap_err();
+    assert_eq!(err, ContractError::VoteAlreadyCast(1));
+}
+
+#[test]
+fn withdrawing() {
+    let mut deps = setup_test();
+
+    execute(
+        deps.as_mut(),
+        mock_env_at_timestamp(15000),
+        mock_info("alice", &[coin(100, STAKE_DENOM)]),
+        vote_msg(VoteOption::Yes),
+    )
+    .unwrap();
+
+    let err = execute(
+        deps.as_mut(),
+        mock_env_at_timestamp(15000),
+        mock_info("alice", &[]),
+        ExecuteMsg::Withdraw {
+            proposal_id: 1,
+        },
+    )
+    .unwrap_err();
+    assert_eq!(err, ContractError::VotingNotEnded(1));
+
+    let res = execute(
+        deps.as_mut(),
+        mock_env_at_timestamp(20000),
+        mock_info("alice", &[]),
+        ExecuteMsg::Withdraw {
+            proposal_id: 1,
+        },
+    )
+    .unwrap();
+    assert_eq!(
+        res.messages,
+        vec![SubMsg::new(BankMsg::Send {
+            to_address: "alice".to_string(),
+            amount: coins(100, STAKE_DENOM),
+        })]
+    );
+
+    let err = execute(
+        deps.as_mut(),
+        mock_env_at_timestamp(20000),
+        mock_info("alice", &[]),
+        ExecuteMsg::Withdraw {
+            proposal_id: 1,
+        },
+    )
+    .unwrap_err();
+    assert_eq!(err, ContractError::BallotNotFound(1));
+
+    // the tally can still be cast after the voters withdrew
+    let res = execute(
+        deps.as_mut(),
+        mock_env_at_timestamp(20000),
+        mock_info("worker", &[]),
+        ExecuteMsg::CastVote {
+            proposal_id: 1,
+        },
+    )
+    .unwrap();
+    assert_eq!(
+        res.messages,
+        vec![SubMsg::new(WasmMsg::Execute {
+            contract_addr: "hub".to_string(),
+            msg: to_binary(&HubExecuteMsg::VoteWeighted {
+                proposal_id: 1,
+                votes: vec![(Decimal::one(), VoteOption::Yes)],
+            })
+            .unwrap(),
+            funds: vec![],
+        })]
+    );
+}
+
+#[test]
+fn accepting_vote_operator() {
+    let mut deps = setup_test();
+
+    let err = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("jake", &[]),
+        ExecuteMsg::AcceptVoteOperator {},
+    )
+    .unwrap_err();
+    assert_eq!(err, ContractError::unauthorized(Role::Owner, &Addr::unchecked("jake")));
+
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("owner", &[]),
+        ExecuteMsg::AcceptVoteOperator {},
+    )
+    .unwrap();
+    assert_eq!(
+        res.messages,
+        vec![SubMsg::new(WasmMsg::Execute {
+            contract_addr: "hub".to_string(),
+            msg: to_binary(&HubExecuteMsg::AcceptVoteOperator {}).unwrap(),
+            funds: vec![],
+        })]
+    );
+}
+
+#[test]
+fn transferring_ownership() {
+    let mut deps = setup_test();
+
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("owner", &[]),
+        ExecuteMsg::TransferOwnership {
+            new_owner: "jake".to_string(),
+        },
+    )
+    .unwrap();
+
+    let err = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("pumpkin", &[]),
+        ExecuteMsg::AcceptOwnership {},
+    )
+    .unwrap_err();
+    assert_eq!(err, ContractError::unauthorized(Role::NewOwner, &Addr::unchecked("pumpkin")));
+
+    execute(deps.as_mut(), mock_env(), mock_info("jake", &[]), ExecuteMsg::AcceptOwnership {})
+        .unwrap();
+
+    let res: ConfigResponse = query_helper(deps.as_ref(), QueryMsg::Config {});
+    assert_eq!(res.owner, "jake".to_string());
+    assert_eq!(res.new_owner, None);
+}
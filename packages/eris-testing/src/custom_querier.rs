@@ -0,0 +1,316 @@
+use std::collections::{HashMap, HashSet};
+
+use cosmwasm_std::testing::{BankQuerier, StakingQuerier, MOCK_CONTRACT_ADDR};
+use cosmwasm_std::{
+    from_binary, from_slice, to_binary, Addr, Binary, Coin, ContractResult, Decimal, Empty,
+    FullDelegation, Querier, QuerierResult, QueryRequest, StakingQuery, SystemError, SystemResult,
+    Uint256, WasmQuery,
+};
+use eris::adapters::validator_registry::{ValidatorRegistryQueryMsg, ValidatorsResponse};
+use eris::querier::{OracleExchangeRateQuery, OracleExchangeRateResponse, OracleQuery};
+use eris::DecimalCheckedOps;
+use kujira::asset::AssetInfo;
+use kujira::denom::Denom;
+use kujira::fin::{
+    ConfigResponse as FinConfigResponse, QueryMsg as FinQueryMsg, SimulationResponse,
+};
+use kujira::precision::Precision;
+
+use crate::err_unsupported_query;
+
+/// Delegation of the mocked contract (`MOCK_CONTRACT_ADDR`) to a validator
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct MockDelegation {
+    pub validator: String,
+    pub amount: u128,
+}
+
+impl MockDelegation {
+    pub fn new(validator: &str, amount: u128) -> Self {
+        Self {
+            validator: validator.to_string(),
+            amount,
+        }
+    }
+}
+
+pub struct CustomQuerier {
+    /// Denom of the staking module, defaults to `ukuji`
+    pub bonded_denom: String,
+    pub bank_querier: BankQuerier,
+    pub staking_querier: StakingQuerier,
+    /// FIN pairs by address: denoms, price of the ask denom per offer denom and spread
+    pub fin_pairs: HashMap<String, ([Denom; 2], Decimal, Decimal)>,
+    /// Addresses without a contract. Every other address is treated as an instantiated contract.
+    pub accounts: HashSet<String>,
+    /// Validators outside of the active set. Every other address is treated as an active validator.
+    pub jailed_validators: HashSet<String>,
+    /// Commissions of the validators, every other validator has a commission of 5%
+    pub validator_commissions: HashMap<String, Decimal>,
+    /// Validators by validator registry contract. Queries of other contracts fail.
+    pub validator_registries: HashMap<String, Vec<String>>,
+    /// USD prices of the oracle module by symbol. Without prices the oracle is not supported.
+    pub oracle_prices: HashMap<String, Decimal>,
+}
+
+impl Default for CustomQuerier {
+    fn default() -> Self {
+        Self::new("ukuji")
+    }
+}
+
+impl Querier for CustomQuerier {
+    fn raw_query(&self, bin_request: &[u8]) -> QuerierResult {
+        if let Ok(QueryRequest::Custom(query)) =
+            from_slice::<QueryRequest<OracleQuery>>(bin_request)
+        {
+            return self.handle_oracle_query(query);
+        }
+
+        let request: QueryRequest<_> = match from_slice(bin_request) {
+            Ok(v) => v,
+            Err(e) => {
+                return Err(SystemError::InvalidRequest {
+                    error: format!("Parsing query request: {}", e),
+                    request: bin_request.into(),
+                })
+                .into()
+            },
+        };
+        self.handle_query(&request)
+    }
+}
+
+impl CustomQuerier {
+    pub fn new(bonded_denom: &str) -> Self {
+        Self {
+            bonded_denom: bonded_denom.to_string(),
+            bank_querier: BankQuerier::default(),
+            staking_querier: StakingQuerier::new(bonded_denom, &[], &[]),
+            fin_pairs: HashMap::new(),
+            accounts: HashSet::new(),
+            jailed_validators: HashSet::new(),
+            validator_commissions: HashMap::new(),
+            validator_registries: HashMap::new(),
+            oracle_prices: HashMap::new(),
+        }
+    }
+
+    /// Sets the balances of the mocked contract. The bank supply of each denom equals its balance.
+    pub fn set_bank_balances(&mut self, balances: &[Coin]) {
+        self.set_account_balances(&[(MOCK_CONTRACT_ADDR, balances)])
+    }
+
+    /// Sets the balances of multiple accounts, the bank supply is the sum over all accounts, e.g.
+    /// to mock tokenfactory denoms held outside of the contract
+    pub fn set_account_balances(&mut self, balances: &[(&str, &[Coin])]) {
+        self.bank_querier = BankQuerier::new(balances)
+    }
+
+    pub fn set_staking_delegations<D: Clone + Into<MockDelegation>>(&mut self, delegations: &[D]) {
+        self.set_staking_delegations_locked(delegations, &[])
+    }
+
+    /// Delegations to `locked` validators can't be redelegated, as after a recent redelegation
+    pub fn set_staking_delegations_locked<D: Clone + Into<MockDelegation>>(
+        &mut self,
+        delegations: &[D],
+        locked: &[&str],
+    ) {
+        let fds = delegations
+            .iter()
+            .cloned()
+            .map(Into::into)
+            .map(|d: MockDelegation| FullDelegation {
+                delegator: Addr::unchecked(MOCK_CONTRACT_ADDR),
+                validator: d.validator.clone(),
+                amount: Coin::new(d.amount, &self.bonded_denom),
+                can_redelegate: Coin::new(
+                    if locked.contains(&d.validator.as_str()) {
+                        0
+                    } else {
+                        d.amount
+                    },
+                    &self.bonded_denom,
+                ),
+                accumulated_rewards: vec![],
+            })
+            .collect::<Vec<_>>();
+
+        self.staking_querier = StakingQuerier::new(&self.bonded_denom, &[], &fds);
+    }
+
+    pub fn set_fin_pair(&mut self, pair: &str, denoms: [&str; 2], price: Decimal, spread: Decimal) {
+        self.fin_pairs
+            .insert(pair.to_string(), ([denoms[0].into(), denoms[1].into()], price, spread));
+    }
+
+    /// Marks `addr` as a plain account, so that contract info queries fail
+    pub fn set_account(&mut self, addr: &str) {
+        self.accounts.insert(addr.to_string());
+    }
+
+    pub fn set_jailed_validators(&mut self, validators: &[&str]) {
+        self.jailed_validators = validators.iter().map(|v| v.to_string()).collect();
+    }
+
+    pub fn set_validator_commission(&mut self, validator: &str, commission: Decimal) {
+        self.validator_commissions.insert(validator.to_string(), commission);
+    }
+
+    pub fn set_validator_registry(&mut self, registry: &str, validators: &[&str]) {
+        self.validator_registries
+            .insert(registry.to_string(), validators.iter().map(|v| v.to_string()).collect());
+    }
+
+    fn handle_validator_registry_query(
+        &self,
+        contract_addr: &str,
+        query: ValidatorRegistryQueryMsg,
+    ) -> QuerierResult {
+        let validators = match self.validator_registries.get(contract_addr) {
+            Some(validators) => validators.clone(),
+            None => return err_unsupported_query(query),
+        };
+
+        let res = match query {
+            ValidatorRegistryQueryMsg::Validators {} => to_binary(&ValidatorsResponse {
+                validators,
+            }),
+        };
+
+        SystemResult::Ok(ContractResult::Ok(res.unwrap()))
+    }
+
+    pub fn set_oracle_price(&mut self, symbol: &str, price: Decimal) {
+        self.oracle_prices.insert(symbol.to_string(), price);
+    }
+
+    fn handle_oracle_query(&self, query: OracleQuery) -> QuerierResult {
+        let OracleQuery::Oracle(OracleExchangeRateQuery::ExchangeRate {
+            denom,
+        }) = &query;
+
+        match self.oracle_prices.get(denom) {
+            Some(rate) => SystemResult::Ok(ContractResult::Ok(
+                to_binary(&OracleExchangeRateResponse {
+                    rate: *rate,
+                })
+                .unwrap(),
+            )),
+            None => err_unsupported_query(query),
+        }
+    }
+
+    fn handle_validator_query(&self, address: &str) -> QuerierResult {
+        let res = if self.jailed_validators.contains(address) {
+            r#"{"validator":null}"#.to_string()
+        } else {
+            let commission = self
+                .validator_commissions
+                .get(address)
+                .copied()
+                .unwrap_or_else(|| Decimal::percent(5));
+            format!(
+                concat!(
+                    r#"{{"validator":{{"address":"{}","commission":"{}","#,
+                    r#""max_commission":"0.2","max_change_rate":"0.01"}}}}"#
+                ),
+                address, commission
+            )
+        };
+
+        SystemResult::Ok(ContractResult::Ok(Binary::from(res.as_bytes())))
+    }
+
+    fn handle_contract_info_query(&self, contract_addr: &str) -> QuerierResult {
+        if self.accounts.contains(contract_addr) {
+            return SystemResult::Err(SystemError::NoSuchContract {
+                addr: contract_addr.to_string(),
+            });
+        }
+
+        let res = Binary::from(
+            br#"{"code_id":1,"creator":"creator","admin":null,"pinned":false,"ibc_port":null}"#
+                .as_ref(),
+        );
+        SystemResult::Ok(ContractResult::Ok(res))
+    }
+
+    fn handle_fin_query(&self, contract_addr: &str, query: FinQueryMsg) -> QuerierResult {
+        let (denoms, price, spread) = match self.fin_pairs.get(contract_addr) {
+            Some(pair) => pair.clone(),
+            None => return err_unsupported_query(query),
+        };
+
+        let res = match query {
+            FinQueryMsg::Config {} => to_binary(&FinConfigResponse {
+                owner: Addr::unchecked("fin_owner"),
+                denoms,
+                price_precision: Precision::DecimalPlaces(4),
+                decimal_delta: 0,
+                is_bootstrapping: false,
+            }),
+            FinQueryMsg::Simulation {
+                offer_asset,
+            } => {
+                let AssetInfo::NativeToken {
+                    denom,
+                } = offer_asset.info;
+                // the price is quoted for the first denom, invert it when offering the second
+                let price = if denom == denoms[0] {
+                    price
+                } else {
+                    Decimal::one() / price
+                };
+                let expected = price.checked_mul_uint(offer_asset.amount).unwrap();
+                let spread_amount = spread.checked_mul_uint(expected).unwrap();
+                to_binary(&SimulationResponse {
+                    return_amount: Uint256::from(expected - spread_amount),
+                    spread_amount: Uint256::from(spread_amount),
+                    commission_amount: Uint256::zero(),
+                })
+            },
+            _ => return err_unsupported_query(query),
+        };
+
+        SystemResult::Ok(ContractResult::Ok(res.unwrap()))
+    }
+
+    pub fn handle_query(&self, request: &QueryRequest<Empty>) -> QuerierResult {
+        match request {
+            QueryRequest::Wasm(WasmQuery::Smart {
+                contract_addr,
+                msg,
+            }) => {
+                // if let Ok(query) = from_binary::<Cw20QueryMsg>(msg) {
+                //     return self.cw20_querier.handle_query(contract_addr, query);
+                // }
+
+                if let Ok(query) = from_binary::<FinQueryMsg>(msg) {
+                    return self.handle_fin_query(contract_addr, query);
+                }
+
+                if let Ok(query) = from_binary::<ValidatorRegistryQueryMsg>(msg) {
+                    return self.handle_validator_registry_query(contract_addr, query);
+                }
+
+                err_unsupported_query(msg)
+            },
+
+            QueryRequest::Wasm(WasmQuery::ContractInfo {
+                contract_addr,
+            }) => self.handle_contract_info_query(contract_addr),
+
+            QueryRequest::Bank(query) => self.bank_querier.query(query),
+
+            QueryRequest::Staking(StakingQuery::Validator {
+                address,
+            }) => self.handle_validator_query(address),
+
+            QueryRequest::Staking(query) => self.staking_querier.query(query),
+
+            _ => err_unsupported_query(request),
+        }
+    }
+}
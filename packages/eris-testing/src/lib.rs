@@ -0,0 +1,22 @@
+mod custom_querier;
+
+pub use custom_querier::{CustomQuerier, MockDelegation};
+
+use cosmwasm_std::testing::{MockApi, MockStorage};
+use cosmwasm_std::{OwnedDeps, QuerierResult, SystemError, SystemResult};
+
+pub fn err_unsupported_query<T: std::fmt::Debug>(request: T) -> QuerierResult {
+    SystemResult::Err(SystemError::InvalidRequest {
+        error: format!("[mock] unsupported query: {:?}", request),
+        request: Default::default(),
+    })
+}
+
+pub fn mock_dependencies() -> OwnedDeps<MockStorage, MockApi, CustomQuerier> {
+    OwnedDeps {
+        storage: MockStorage::default(),
+        api: MockApi::default(),
+        querier: CustomQuerier::default(),
+        custom_query_type: std::marker::PhantomData,
+    }
+}
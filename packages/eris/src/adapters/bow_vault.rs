@@ -1,16 +1,77 @@
 use cosmwasm_schema::cw_serde;
-use cosmwasm_std::{to_binary, Addr, Coin, CosmosMsg, StdResult, Uint128, WasmMsg};
+use cosmwasm_std::{
+    to_binary, Addr, Coin, CosmosMsg, Decimal, QuerierWrapper, QueryRequest, StdResult, Uint128,
+    WasmMsg, WasmQuery,
+};
 use kujira::{denom::Denom, msg::KujiraMsg};
+use serde::Deserialize;
 
 #[cw_serde]
 pub enum BowExecuteMsg {
+    Deposit {
+        max_slippage: Option<Decimal>,
+    },
     Withdraw {},
 }
 
+#[cw_serde]
+pub enum BowQueryMsg {
+    Config {},
+    Pool {},
+}
+
+/// Subset of the BOW config response. Not a `cw_serde` type, so that fields added by the vault are
+/// ignored instead of failing deserialization.
+#[derive(Deserialize, Clone, Debug, PartialEq)]
+pub struct BowConfigResponse {
+    pub denoms: [Denom; 2],
+}
+
+#[cw_serde]
+pub struct BowPoolResponse {
+    /// Amounts of both underlying denoms held by the vault, in the order of the config denoms
+    pub balances: [Uint128; 2],
+    /// Total amount of LP shares in circulation
+    pub lp_shares: Uint128,
+}
+
+impl BowPoolResponse {
+    /// Underlying amounts `shares` can be withdrawn for
+    pub fn share_value(&self, shares: Uint128) -> [Uint128; 2] {
+        if self.lp_shares.is_zero() {
+            return [Uint128::zero(), Uint128::zero()];
+        }
+
+        [
+            self.balances[0].multiply_ratio(shares, self.lp_shares),
+            self.balances[1].multiply_ratio(shares, self.lp_shares),
+        ]
+    }
+}
+
 #[cw_serde]
 pub struct BowVault(pub Addr);
 
 impl BowVault {
+    /// Deposits both sides of the pool. Coins are sorted by denom, as required for the funds of a
+    /// message.
+    pub fn deposit_msg(
+        &self,
+        coins: Vec<Coin>,
+        max_slippage: Option<Decimal>,
+    ) -> StdResult<CosmosMsg<KujiraMsg>> {
+        let mut funds: Vec<Coin> = coins.into_iter().filter(|c| !c.amount.is_zero()).collect();
+        funds.sort_by(|a, b| a.denom.cmp(&b.denom));
+
+        Ok(CosmosMsg::Wasm(WasmMsg::Execute {
+            contract_addr: self.0.to_string(),
+            funds,
+            msg: to_binary(&BowExecuteMsg::Deposit {
+                max_slippage,
+            })?,
+        }))
+    }
+
     pub fn withdraw_msg(&self, denom: Denom, amount: Uint128) -> StdResult<CosmosMsg<KujiraMsg>> {
         Ok(CosmosMsg::Wasm(WasmMsg::Execute {
             contract_addr: self.0.to_string(),
@@ -21,4 +82,38 @@ impl BowVault {
             msg: to_binary(&BowExecuteMsg::Withdraw {})?,
         }))
     }
+
+    pub fn query_config(&self, querier: &QuerierWrapper) -> StdResult<BowConfigResponse> {
+        querier.query(&QueryRequest::Wasm(WasmQuery::Smart {
+            contract_addr: self.0.to_string(),
+            msg: to_binary(&BowQueryMsg::Config {})?,
+        }))
+    }
+
+    pub fn query_pool(&self, querier: &QuerierWrapper) -> StdResult<BowPoolResponse> {
+        querier.query(&QueryRequest::Wasm(WasmQuery::Smart {
+            contract_addr: self.0.to_string(),
+            msg: to_binary(&BowQueryMsg::Pool {})?,
+        }))
+    }
+
+    /// Value of `shares` LP tokens in the underlying denoms of the vault
+    pub fn query_share_value(
+        &self,
+        querier: &QuerierWrapper,
+        shares: Uint128,
+    ) -> StdResult<Vec<Coin>> {
+        let config = self.query_config(querier)?;
+        let amounts = self.query_pool(querier)?.share_value(shares);
+
+        Ok(config
+            .denoms
+            .iter()
+            .zip(amounts)
+            .map(|(denom, amount)| Coin {
+                denom: denom.to_string(),
+                amount,
+            })
+            .collect())
+    }
 }
@@ -1,18 +1,80 @@
 use cosmwasm_schema::cw_serde;
-use cosmwasm_std::{to_binary, Addr, Coin, CosmosMsg, StdResult, Uint128, WasmMsg};
-use kujira::{denom::Denom, msg::KujiraMsg};
+use cosmwasm_std::{
+    to_binary, Addr, Coin, CosmosMsg, Decimal, QuerierWrapper, QueryRequest, StdResult, Uint128,
+    WasmMsg, WasmQuery,
+};
+use kujira::{
+    asset::{Asset, AssetInfo},
+    denom::Denom,
+    msg::KujiraMsg,
+};
 
 #[cw_serde]
 pub enum BlackwhaleExecuteMsg {
+    ProvideLiquidity {
+        asset: Asset,
+    },
     WithdrawLiquidity {
         amount: Uint128,
     },
 }
 
+#[cw_serde]
+pub enum BlackwhaleQueryMsg {
+    PoolState {},
+}
+
+#[cw_serde]
+pub struct BlackwhalePoolResponse {
+    /// Total amount of the underlying asset held by the vault
+    pub total_value: Uint128,
+    /// Total amount of vault shares in circulation
+    pub total_share: Uint128,
+}
+
+impl BlackwhalePoolResponse {
+    /// Amount of the underlying asset a single share can be redeemed for. An empty vault is priced
+    /// at 1, matching the ratio at which the first deposit is minted.
+    pub fn share_price(&self) -> Decimal {
+        if self.total_share.is_zero() {
+            Decimal::one()
+        } else {
+            Decimal::from_ratio(self.total_value, self.total_share)
+        }
+    }
+
+    /// Value of `shares` in the underlying asset
+    pub fn share_value(&self, shares: Uint128) -> Uint128 {
+        if self.total_share.is_zero() {
+            shares
+        } else {
+            shares.multiply_ratio(self.total_value, self.total_share)
+        }
+    }
+}
+
 #[cw_serde]
 pub struct BlackWhaleVault(pub Addr);
 
 impl BlackWhaleVault {
+    pub fn deposit_msg(&self, denom: Denom, amount: Uint128) -> StdResult<CosmosMsg<KujiraMsg>> {
+        Ok(CosmosMsg::Wasm(WasmMsg::Execute {
+            contract_addr: self.0.to_string(),
+            funds: vec![Coin {
+                amount,
+                denom: denom.to_string(),
+            }],
+            msg: to_binary(&BlackwhaleExecuteMsg::ProvideLiquidity {
+                asset: Asset {
+                    info: AssetInfo::NativeToken {
+                        denom,
+                    },
+                    amount,
+                },
+            })?,
+        }))
+    }
+
     pub fn withdraw_msg(&self, denom: Denom, amount: Uint128) -> StdResult<CosmosMsg<KujiraMsg>> {
         Ok(CosmosMsg::Wasm(WasmMsg::Execute {
             contract_addr: self.0.to_string(),
@@ -25,4 +87,15 @@ impl BlackWhaleVault {
             })?,
         }))
     }
+
+    pub fn query_pool_state(&self, querier: &QuerierWrapper) -> StdResult<BlackwhalePoolResponse> {
+        querier.query(&QueryRequest::Wasm(WasmQuery::Smart {
+            contract_addr: self.0.to_string(),
+            msg: to_binary(&BlackwhaleQueryMsg::PoolState {})?,
+        }))
+    }
+
+    pub fn query_share_price(&self, querier: &QuerierWrapper) -> StdResult<Decimal> {
+        Ok(self.query_pool_state(querier)?.share_price())
+    }
 }
@@ -1,7 +1,7 @@
 use std::collections::HashSet;
 
 use cosmwasm_schema::cw_serde;
-use cosmwasm_std::{to_binary, Addr, Coin, CosmosMsg, StdResult, WasmMsg};
+use cosmwasm_std::{to_binary, Addr, Coin, CosmosMsg, StdError, StdResult, WasmMsg};
 use kujira::{denom::Denom, msg::KujiraMsg};
 
 #[cw_serde]
@@ -10,28 +10,104 @@ pub struct FinMultiExecuteMsg {
     pub recipient: Option<Addr>,
 }
 
+/// Builder for the stages of a FinMulti swap. Every stage is a list of (pair, offer denom) swaps
+/// that are executed before the next stage starts.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct FinMultiStages {
+    stages: Vec<Vec<(Addr, Denom)>>,
+    recipient: Option<Addr>,
+}
+
+impl FinMultiStages {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn from_stages(stages: Vec<Vec<(Addr, Denom)>>) -> Self {
+        Self {
+            stages,
+            recipient: None,
+        }
+    }
+
+    /// Appends a stage, swaps that are already part of the stage are ignored
+    pub fn stage(mut self, swaps: Vec<(Addr, Denom)>) -> Self {
+        let mut stage: Vec<(Addr, Denom)> = vec![];
+        for swap in swaps {
+            if !stage.contains(&swap) {
+                stage.push(swap);
+            }
+        }
+        self.stages.push(stage);
+        self
+    }
+
+    /// Sends the swapped funds to `recipient` instead of the sender
+    pub fn recipient(mut self, recipient: Addr) -> Self {
+        self.recipient = Some(recipient);
+        self
+    }
+
+    pub fn stages(&self) -> &[Vec<(Addr, Denom)>] {
+        &self.stages
+    }
+
+    pub fn into_stages(self) -> Vec<Vec<(Addr, Denom)>> {
+        self.stages
+    }
+
+    /// Funds that need to be sent along with the swap: every balance offered in any of the stages,
+    /// once per denom. Zero balances are skipped.
+    pub fn funds(&self, balances: &[Coin]) -> Vec<Coin> {
+        let denoms: HashSet<String> =
+            self.stages.iter().flatten().map(|(_, denom)| denom.to_string()).collect();
+
+        let mut funds: Vec<Coin> = vec![];
+        for balance in balances.iter().filter(|b| denoms.contains(&b.denom) && !b.amount.is_zero())
+        {
+            match funds.iter_mut().find(|f| f.denom == balance.denom) {
+                Some(fund) => fund.amount += balance.amount,
+                None => funds.push(balance.clone()),
+            }
+        }
+        funds
+    }
+
+    /// Checks that no stage is empty and that the swap is funded by the provided balances.
+    pub fn validate(&self, balances: &[Coin]) -> StdResult<()> {
+        if self.stages.is_empty() {
+            return Err(StdError::generic_err("fin multi: no stages provided"));
+        }
+
+        if let Some(index) = self.stages.iter().position(|stage| stage.is_empty()) {
+            return Err(StdError::generic_err(format!("fin multi: stage {} is empty", index)));
+        }
+
+        if self.funds(balances).is_empty() {
+            return Err(StdError::generic_err("fin multi: no balance for any denom of the stages"));
+        }
+
+        Ok(())
+    }
+}
+
 #[cw_serde]
 pub struct FinMulti(pub Addr);
 
 impl FinMulti {
     pub fn swap_msg(
         &self,
-        stages: Vec<Vec<(Addr, Denom)>>,
+        stages: FinMultiStages,
         balances: Vec<Coin>,
     ) -> StdResult<CosmosMsg<KujiraMsg>> {
-        let mut set = HashSet::new();
-        for stage in stages.iter() {
-            for (_, denom) in stage {
-                set.insert(denom.to_string());
-            }
-        }
+        stages.validate(&balances)?;
 
         Ok(CosmosMsg::Wasm(WasmMsg::Execute {
             contract_addr: self.0.to_string(),
-            funds: balances.into_iter().filter(|b| set.contains(&b.denom)).collect(),
+            funds: stages.funds(&balances),
             msg: to_binary(&FinMultiExecuteMsg {
-                stages,
-                recipient: None,
+                stages: stages.stages,
+                recipient: stages.recipient,
             })?,
         }))
     }
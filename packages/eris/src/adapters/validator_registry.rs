@@ -0,0 +1,26 @@
+use cosmwasm_schema::cw_serde;
+use cosmwasm_std::{to_binary, Addr, QuerierWrapper, QueryRequest, StdResult, WasmQuery};
+
+#[cw_serde]
+pub enum ValidatorRegistryQueryMsg {
+    Validators {},
+}
+
+#[cw_serde]
+pub struct ValidatorsResponse {
+    /// Operator addresses of the validators in the registry
+    pub validators: Vec<String>,
+}
+
+#[cw_serde]
+pub struct ValidatorRegistry(pub Addr);
+
+impl ValidatorRegistry {
+    pub fn query_validators(&self, querier: &QuerierWrapper) -> StdResult<Vec<String>> {
+        let res: ValidatorsResponse = querier.query(&QueryRequest::Wasm(WasmQuery::Smart {
+            contract_addr: self.0.to_string(),
+            msg: to_binary(&ValidatorRegistryQueryMsg::Validators {})?,
+        }))?;
+        Ok(res.validators)
+    }
+}
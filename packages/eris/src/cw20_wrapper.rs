@@ -0,0 +1,141 @@
+use cosmwasm_schema::{cw_serde, QueryResponses};
+use cosmwasm_std::{Addr, Binary, Decimal, Uint128};
+use cw20::{
+    AllAccountsResponse, AllAllowancesResponse, AllowanceResponse, BalanceResponse, Expiration,
+    TokenInfoResponse,
+};
+
+#[cw_serde]
+pub struct InstantiateMsg {
+    /// Hub whose stake token is wrapped
+    pub hub: String,
+    /// Name of the cw20 token
+    pub name: String,
+    /// Symbol of the cw20 token
+    pub symbol: String,
+    /// Decimals of the cw20 token, should match the stake token
+    pub decimals: u8,
+}
+
+#[cw_serde]
+pub enum ExecuteMsg {
+    /// Lock the stake token sent with the message and mint the same amount of the cw20 token to
+    /// the receiver, the sender by default
+    Wrap {
+        receiver: Option<String>,
+    },
+    /// Burn `amount` of the sender's cw20 token and release the same amount of the stake token to
+    /// the receiver, the sender by default
+    Unwrap {
+        amount: Uint128,
+        receiver: Option<String>,
+    },
+    /// Transfer is a base message to move tokens to another account without triggering actions
+    Transfer {
+        recipient: String,
+        amount: Uint128,
+    },
+    /// Send is a base message to transfer tokens to a contract and trigger an action
+    /// on the receiving contract.
+    Send {
+        contract: String,
+        amount: Uint128,
+        msg: Binary,
+    },
+    /// Allows spender to access an additional amount tokens from the owner's account
+    IncreaseAllowance {
+        spender: String,
+        amount: Uint128,
+        expires: Option<Expiration>,
+    },
+    /// Lowers the spender's access of tokens from the owner's account
+    DecreaseAllowance {
+        spender: String,
+        amount: Uint128,
+        expires: Option<Expiration>,
+    },
+    /// Transfers amount tokens from owner -> recipient if the sender has sufficient allowance
+    TransferFrom {
+        owner: String,
+        recipient: String,
+        amount: Uint128,
+    },
+    /// Sends amount tokens from owner -> contract if the sender has sufficient allowance
+    SendFrom {
+        owner: String,
+        contract: String,
+        amount: Uint128,
+        msg: Binary,
+    },
+}
+
+#[cw_serde]
+#[derive(QueryResponses)]
+pub enum QueryMsg {
+    /// The wrapped hub and its stake token. Response: `ConfigResponse`
+    #[returns(ConfigResponse)]
+    Config {},
+    /// Exchange rate of the hub, in terms of utoken per cw20 token.
+    /// Response: `ExchangeRateResponse`
+    #[returns(ExchangeRateResponse)]
+    ExchangeRate {},
+    /// cw20 balance of an address and its value in utoken. Response: `UnderlyingBalanceResponse`
+    #[returns(UnderlyingBalanceResponse)]
+    UnderlyingBalance {
+        address: String,
+    },
+    /// cw20 balance of an address. Response: `cw20::BalanceResponse`
+    #[returns(BalanceResponse)]
+    Balance {
+        address: String,
+    },
+    /// Metadata and total supply of the cw20 token. Response: `cw20::TokenInfoResponse`
+    #[returns(TokenInfoResponse)]
+    TokenInfo {},
+    /// Allowance of a spender on the owner's tokens. Response: `cw20::AllowanceResponse`
+    #[returns(AllowanceResponse)]
+    Allowance {
+        owner: String,
+        spender: String,
+    },
+    /// Allowances granted by the owner. Response: `cw20::AllAllowancesResponse`
+    #[returns(AllAllowancesResponse)]
+    AllAllowances {
+        owner: String,
+        start_after: Option<String>,
+        limit: Option<u32>,
+    },
+    /// Accounts holding the cw20 token. Response: `cw20::AllAccountsResponse`
+    #[returns(AllAccountsResponse)]
+    AllAccounts {
+        start_after: Option<String>,
+        limit: Option<u32>,
+    },
+}
+
+#[cw_serde]
+pub struct MigrateMsg {}
+
+#[cw_serde]
+pub struct ConfigResponse {
+    /// Hub whose stake token is wrapped
+    pub hub: Addr,
+    /// Denom of the wrapped stake token
+    pub stake_denom: String,
+}
+
+#[cw_serde]
+pub struct ExchangeRateResponse {
+    /// Exchange rate of the hub, in terms of utoken per cw20 token
+    pub exchange_rate: Decimal,
+    /// Stake token locked by the contract, equal to the cw20 supply
+    pub total_wrapped: Uint128,
+}
+
+#[cw_serde]
+pub struct UnderlyingBalanceResponse {
+    /// cw20 balance of the address
+    pub balance: Uint128,
+    /// Value of the balance in utoken at the current exchange rate
+    pub utoken: Uint128,
+}
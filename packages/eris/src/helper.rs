@@ -1,6 +1,19 @@
-use cosmwasm_std::{Addr, Api, StdResult};
+use cosmwasm_std::{Addr, Api, Env, StdResult};
+use thiserror::Error;
 
 /// Returns a lowercased, validated address upon success if present.
 pub fn addr_opt_validate(api: &dyn Api, addr: &Option<String>) -> StdResult<Option<Addr>> {
     addr.as_ref().map(|addr| api.addr_validate(addr)).transpose()
 }
+
+#[derive(Error, Debug, PartialEq, Eq)]
+#[error("Callbacks can only be invoked by the contract itself")]
+pub struct CallbackUnauthorized {}
+
+/// Callbacks may only be invoked by the contract itself.
+pub fn assert_callback_sender(env: &Env, sender: &Addr) -> Result<(), CallbackUnauthorized> {
+    if env.contract.address != *sender {
+        return Err(CallbackUnauthorized {});
+    }
+    Ok(())
+}
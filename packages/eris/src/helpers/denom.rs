@@ -0,0 +1,98 @@
+use cosmwasm_std::{StdError, StdResult};
+
+/// Maximum length of a tokenfactory subdenom
+pub const MAX_SUBDENOM_LENGTH: usize = 44;
+
+/// Length of the hex encoded hash of an IBC denom trace
+const IBC_HASH_LENGTH: usize = 64;
+
+/// A denom split into its components
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ParsedDenom<'a> {
+    /// Denom of a native module, e.g. `ukuji`
+    Native(&'a str),
+    /// Tokenfactory denom `factory/{creator}/{subdenom}`
+    Factory {
+        creator: &'a str,
+        subdenom: &'a str,
+    },
+    /// IBC voucher `ibc/{hash}`
+    Ibc(&'a str),
+}
+
+impl<'a> ParsedDenom<'a> {
+    pub fn parse(denom: &'a str) -> StdResult<Self> {
+        if let Some(rest) = denom.strip_prefix("factory/") {
+            let (creator, subdenom) = rest.split_once('/').ok_or_else(|| {
+                StdError::generic_err(format!("denom {0} has no subdenom", denom))
+            })?;
+            if creator.is_empty() {
+                return Err(StdError::generic_err(format!("denom {0} has no creator", denom)));
+            }
+            validate_subdenom(subdenom)?;
+            return Ok(ParsedDenom::Factory {
+                creator,
+                subdenom,
+            });
+        }
+
+        if let Some(hash) = denom.strip_prefix("ibc/") {
+            if hash.len() != IBC_HASH_LENGTH || !hash.chars().all(|c| c.is_ascii_hexdigit()) {
+                return Err(StdError::generic_err(format!("denom {0} has an invalid hash", denom)));
+            }
+            return Ok(ParsedDenom::Ibc(hash));
+        }
+
+        if denom.len() < 3
+            || !denom.starts_with(|c: char| c.is_ascii_alphabetic())
+            || !denom.chars().all(is_denom_char)
+        {
+            return Err(StdError::generic_err(format!("denom {0} is invalid", denom)));
+        }
+        Ok(ParsedDenom::Native(denom))
+    }
+
+    /// Human readable symbol, e.g. `KUJI` for `ukuji`, `ampKUJI` for `factory/{hub}/ampKUJI` and
+    /// `IBC/27394F` for an IBC voucher
+    pub fn display_symbol(&self) -> String {
+        match *self {
+            ParsedDenom::Native(denom) => strip_micro_prefix(denom),
+            ParsedDenom::Factory {
+                subdenom,
+                ..
+            } => strip_micro_prefix(subdenom.rsplit('/').next().unwrap_or(subdenom)),
+            ParsedDenom::Ibc(hash) => format!("IBC/{0}", &hash[..6].to_uppercase()),
+        }
+    }
+}
+
+/// Checks a subdenom before it is passed to `DenomMsg::Create`
+pub fn validate_subdenom(subdenom: &str) -> StdResult<()> {
+    if subdenom.is_empty()
+        || subdenom.len() > MAX_SUBDENOM_LENGTH
+        || !subdenom.chars().all(is_denom_char)
+    {
+        return Err(StdError::generic_err(format!(
+            "subdenom {0} must have 1 to {1} alphanumeric characters or one of / : . _ -",
+            subdenom, MAX_SUBDENOM_LENGTH
+        )));
+    }
+    Ok(())
+}
+
+fn is_denom_char(c: char) -> bool {
+    c.is_ascii_alphanumeric() || matches!(c, '/' | ':' | '.' | '_' | '-')
+}
+
+/// `ukuji` is displayed as `KUJI`, denoms that are not lowercase micro units are kept as they are
+fn strip_micro_prefix(denom: &str) -> String {
+    match denom.strip_prefix('u') {
+        Some(symbol)
+            if !symbol.is_empty()
+                && symbol.chars().all(|c| c.is_ascii_lowercase() || c.is_ascii_digit()) =>
+        {
+            symbol.to_uppercase()
+        },
+        _ => denom.to_string(),
+    }
+}
@@ -1 +1,3 @@
 pub mod bps;
+pub mod denom;
+pub mod pagination;
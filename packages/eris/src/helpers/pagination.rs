@@ -0,0 +1,67 @@
+use cosmwasm_schema::cw_serde;
+use cosmwasm_std::{Addr, Api, Order};
+use cw_storage_plus::{Bound, PrimaryKey};
+
+pub const DEFAULT_LIMIT: u32 = 10;
+pub const MAX_LIMIT: u32 = 30;
+
+/// A page of a paginated query
+#[cw_serde]
+pub struct PaginatedResponse<T, C> {
+    pub items: Vec<T>,
+    /// Cursor to pass as `start_after` to query the next page, `None` on the last page
+    pub next_start_after: Option<C>,
+    /// Whether there are more items after this page
+    pub has_more: bool,
+}
+
+impl<T, C> PaginatedResponse<T, C> {
+    /// Builds a page from up to `limit + 1` items, the extra item only tells that there are more
+    pub fn new(mut items: Vec<T>, limit: usize, cursor: impl Fn(&T) -> C) -> Self {
+        let has_more = items.len() > limit;
+        items.truncate(limit);
+        let next_start_after = if has_more {
+            items.last().map(cursor)
+        } else {
+            None
+        };
+
+        Self {
+            items,
+            next_start_after,
+            has_more,
+        }
+    }
+}
+
+/// Number of items to return for a requested `limit`, capped at [`MAX_LIMIT`]
+pub fn get_limit(limit: Option<u32>) -> usize {
+    get_limit_with_max(limit, MAX_LIMIT)
+}
+
+/// Number of items to return for a requested `limit`, capped at `max_limit`
+pub fn get_limit_with_max(limit: Option<u32>, max_limit: u32) -> usize {
+    limit.unwrap_or(DEFAULT_LIMIT).min(max_limit) as usize
+}
+
+/// Exclusive range bound after the cursor `start_after`
+pub fn start_after_bound<'a, K: PrimaryKey<'a>>(start_after: Option<K>) -> Option<Bound<'a, K>> {
+    start_after.map(Bound::exclusive)
+}
+
+/// (min, max) range bounds for iterating after the cursor `start_after` in the given order
+pub fn get_bounds<'a, K: PrimaryKey<'a>>(
+    start_after: Option<K>,
+    order: Order,
+) -> (Option<Bound<'a, K>>, Option<Bound<'a, K>>) {
+    match order {
+        Order::Ascending => (start_after_bound(start_after), None),
+        Order::Descending => (None, start_after_bound(start_after)),
+    }
+}
+
+/// Validates an address cursor. Invalid addresses are ignored, so that the range starts from the
+/// beginning.
+pub fn validate_start_after_addr(api: &dyn Api, start_after: Option<String>) -> Option<Addr> {
+    start_after.and_then(|addr| api.addr_validate(&addr).ok())
+}
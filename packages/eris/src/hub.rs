@@ -2,14 +2,14 @@ use std::{collections::HashSet, convert::TryInto};
 
 use cosmwasm_schema::{cw_serde, QueryResponses};
 use cosmwasm_std::{
-    to_binary, Addr, Api, Coin, CosmosMsg, Decimal, Empty, StdError, StdResult, Uint128,
-    VoteOption, WasmMsg,
+    to_binary, Addr, Api, Binary, Coin, CosmosMsg, Decimal, Empty, Fraction, Order, StdError,
+    StdResult, Uint128, VoteOption, WasmMsg,
 };
 use kujira::{denom::Denom, msg::KujiraMsg};
 use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 
-use crate::helpers::bps::BasicPoints;
+use crate::helpers::{bps::BasicPoints, pagination::PaginatedResponse};
 
 #[cw_serde]
 pub enum DelegationStrategy
@@ -20,6 +20,16 @@ pub enum DelegationStrategy
     Defined {
         shares_bps: Vec<(String, u16)>,
     },
+    /// validators are taken from a registry contract when tuning, all receive the same
+    /// delegation. The pinned `fallback` validators are used if the registry can't be queried.
+    Registry {
+        registry: String,
+        fallback: Vec<String>,
+    },
+    /// validators receive delegations by their performance, the average share of the rewards
+    /// kept by the delegators over the harvests and tunes. Observations while out of the active
+    /// set count as zero.
+    Performance,
     // /// validators receive delegations based on community voting + merit points
     // Gauges {
     //     /// gauges based on vAmp voting
@@ -41,13 +51,14 @@ impl DelegationStrategy //<String>
 {
     pub fn validate(
         self,
-        _api: &dyn Api,
+        api: &dyn Api,
         validators: &[String],
     ) -> StdResult<
         DelegationStrategy, //<Addr>
     > {
         let result = match self {
             DelegationStrategy::Uniform {} => DelegationStrategy::Uniform {},
+            DelegationStrategy::Performance {} => DelegationStrategy::Performance {},
 
             // DelegationStrategy::Gauges {
             //     amp_gauges,
@@ -102,6 +113,29 @@ impl DelegationStrategy //<String>
                     shares_bps,
                 }
             },
+            DelegationStrategy::Registry {
+                registry,
+                fallback,
+            } => {
+                if fallback.is_empty() {
+                    Err(StdError::generic_err("fallback validators can't be empty"))?;
+                }
+
+                let mut duplicates = HashSet::new();
+                for validator in fallback.iter() {
+                    if !duplicates.insert(validator.to_string()) {
+                        return Err(StdError::generic_err(format!(
+                            "validator {0} duplicated",
+                            validator
+                        )));
+                    }
+                }
+
+                DelegationStrategy::Registry {
+                    registry: api.addr_validate(&registry)?.to_string(),
+                    fallback,
+                }
+            },
         };
         Ok(result)
     }
@@ -136,6 +170,17 @@ pub struct InstantiateMsg {
     pub delegation_strategy: Option<DelegationStrategy>,
     /// Contract address that is allowed to vote
     pub vote_operator: Option<String>,
+    /// Continues the batches of another hub, whose batches and unbonding requests are then
+    /// imported with `ImportState`
+    pub bootstrap: Option<BootstrapFromExport>,
+    /// Maximum number of items returned by the paginated queries, defaults to 30
+    pub max_query_limit: Option<u32>,
+}
+
+#[cw_serde]
+pub struct BootstrapFromExport {
+    /// Pending batch exported from the other hub, it must not contain unbonding requests
+    pub pending_batch: PendingBatch,
 }
 
 #[cw_serde]
@@ -143,12 +188,32 @@ pub enum ExecuteMsg {
     /// Bond specified amount of Token
     Bond {
         receiver: Option<String>,
+        /// Fails if less ustake would be minted, e.g. after a slash
+        min_ustake_received: Option<Uint128>,
     },
     /// Donates specified amount of Token to pool
     Donate {},
     /// Withdraw Token that have finished unbonding in previous batches
     WithdrawUnbonded {
         receiver: Option<String>,
+        /// Executed against the receiver contract with the withdrawn funds attached, instead of
+        /// sending them
+        receiver_msg: Option<Binary>,
+    },
+    /// Transfer the caller's unbonding request in a batch, or `shares` of it, to another account,
+    /// who can then withdraw it
+    TransferUnbondRequest {
+        id: u64,
+        recipient: String,
+        shares: Option<Uint128>,
+    },
+    /// Transfer the caller's unbonding request in a batch, or `shares` of it, to a contract and
+    /// notify it with `ReceiveUnbondRequest`
+    SendUnbondRequest {
+        id: u64,
+        contract: String,
+        shares: Option<Uint128>,
+        msg: Binary,
     },
     /// Add a validator to the whitelist; callable by the owner
     AddValidator {
@@ -157,6 +222,20 @@ pub enum ExecuteMsg {
     /// Remove a validator from the whitelist; callable by the owner
     RemoveValidator {
         validator: String,
+        /// How the delegation to the validator is moved, defaults to `Redelegate`
+        mode: Option<RemoveValidatorMode>,
+    },
+    /// Set the address receiving the validator share of the protocol fee for a whitelisted
+    /// validator, `None` removes it; callable by the owner or the current payout address
+    SetValidatorPayout {
+        validator: String,
+        payout: Option<String>,
+    },
+    /// Register the payload of `WithdrawType::Generic` withdrawals under a name, `None` removes
+    /// it; callable by the owner
+    SetWithdrawTemplate {
+        name: String,
+        template: Option<WithdrawTemplate>,
     },
     /// Transfer ownership to another account; will not take effect unless the new owner accepts
     TransferOwnership {
@@ -166,10 +245,30 @@ pub enum ExecuteMsg {
     AcceptOwnership {},
     /// Remove the ownership transfer proposal
     DropOwnershipProposal {},
+    /// Accept the operator role proposed by the owner through `UpdateConfig`
+    AcceptOperator {},
+    /// Accept the vote operator role proposed by the owner through `UpdateConfig`
+    AcceptVoteOperator {},
+    /// Permanently give up ownership, disabling all owner-gated actions. `confirmation` must equal
+    /// "renounce ownership", and a guardian must be set to clear the circuit breaker afterwards
+    RenounceOwnership {
+        confirmation: String,
+    },
     /// Claim staking rewards, swap all for Token, and restake
     Harvest {
         withdrawals: Option<Vec<(WithdrawType, Addr, Denom)>>,
         stages: Option<Vec<Vec<(Addr, Denom)>>>,
+        /// Minimum amounts per denom the swaps have to add to the balance, aborts the harvest
+        /// otherwise
+        min_received: Option<Vec<Coin>>,
+        /// Partial withdrawals, by denom. Withdrawals without a limit take the full balance
+        withdraw_limits: Option<Vec<WithdrawLimit>>,
+    },
+    /// Swap the given denoms through the routes of the stages preset and restake, without
+    /// claiming rewards. Other denoms are left untouched, `None` swaps the whole preset
+    /// (only allowed by the operator)
+    Reinvest {
+        denoms: Option<Vec<String>>,
     },
 
     TuneDelegations {},
@@ -177,8 +276,15 @@ pub enum ExecuteMsg {
     Rebalance {
         min_redelegation: Option<Uint128>,
     },
-    /// Update Token amounts in unbonding batches to reflect any slashing or rounding errors
-    Reconcile {},
+    /// Update Token amounts in unbonding batches to reflect any slashing or rounding errors.
+    /// `limit` reconciles at most that many matured batches (oldest first), continuing from the
+    /// stored cursor on the next call.
+    Reconcile {
+        limit: Option<u32>,
+    },
+    /// Cross-check the tracked supply of the stake token against the bank module. A small drift
+    /// is repaired, a large one trips the circuit breaker; callable by anyone
+    SyncSupply {},
     /// Submit the current pending batch of unbonding requests to be unbonded
     SubmitBatch {},
     /// Vote on a proposal (only allowed by the vote_operator)
@@ -200,17 +306,76 @@ pub enum ExecuteMsg {
         protocol_fee_contract: Option<String>,
         /// Fees that are being applied during reinvest of staking rewards
         protocol_reward_fee: Option<Decimal>, // "1 is 100%, 0.05 is 5%"
-        /// Sets a new operator
+        /// Proposes a new operator; will not take effect unless the new operator accepts
         operator: Option<String>,
         /// Sets the stages preset
         stages_preset: Option<Vec<Vec<(Addr, Denom)>>>,
+        /// Sets the (pair, denom) swaps permissionless users may use in custom stages
+        swap_allowlist: Option<Vec<(Addr, Denom)>>,
+        /// Maximum spread of each simulated swap in permissionless custom stages
+        swap_max_spread: Option<Decimal>,
         /// Specifies wether donations are allowed.
         allow_donations: Option<bool>,
         /// Strategy how delegations should be handled
         delegation_strategy: Option<DelegationStrategy>,
-        /// Update the vote_operator
+        /// Proposes a new vote_operator; will not take effect unless the new vote operator accepts
         vote_operator: Option<String>,
+        /// Account who can clear the circuit breaker
+        guardian: Option<String>,
+        /// Maximum relative drop of the exchange rate between reinvests before bonding and
+        /// unbonding are paused, e.g. 0.01 is 1%
+        exchange_rate_tolerance: Option<Decimal>,
+        /// Maximum amount of utoken delegated per reinvest, zero removes the cap
+        max_reinvest_per_tx: Option<Uint128>,
+        /// Contracts, e.g. the arb vault, whose unbonding requests are kept out of the batch
+        /// statistics of the users
+        unbond_allowlist: Option<Vec<String>>,
+        /// Fee applied to the stake tokens of each submitted batch, zero bps removes the fee
+        unbond_fee: Option<UnbondFee>,
+        /// Minimum time between the last bond of an address for itself and its next unbonding request.
+        /// Advisory only: bonds for other addresses and transfers of the stake token are not tracked
+        min_hold_seconds: Option<u64>,
+        /// Period over which reinvested rewards are released into the exchange rate, zero
+        /// releases them at once
+        reward_smoothing_period: Option<u64>,
+        /// Share of the protocol reward fee paid to the payout addresses of the validators, zero
+        /// removes it
+        validator_fee_share: Option<Decimal>,
+        /// Time since the last harvest after which bonding pauses until the next harvest, zero
+        /// disables the pause
+        max_harvest_staleness: Option<u64>,
+        /// Periods with a different protocol reward fee, e.g. a fee holiday after launch. An empty
+        /// list removes the schedule
+        protocol_fee_schedule: Option<Vec<ProtocolFeePeriod>>,
+        /// Minimum time between two executions of operator actions, bounding what a compromised
+        /// operator key can do before the owner rotates it. An empty list removes the cooldowns
+        action_cooldowns: Option<Vec<ActionCooldown>>,
+        /// Shorter epoch after which small batches can already be submitted, a zero period
+        /// removes it
+        express_epoch: Option<ExpressEpoch>,
+        /// Relative deviation from the delegation goal within which a validator is left out of
+        /// rebalancing, e.g. 0.01 is 1%. Zero rebalances every deviation
+        rebalance_tolerance: Option<Decimal>,
+    },
+    /// Resume bonding and unbonding after the circuit breaker tripped; callable by the guardian
+    ClearCircuitBreaker {},
+    /// Replaces the complete config, e.g. exported from another deployment; callable by the owner.
+    /// A different operator or vote operator is only proposed and still has to accept the role.
+    ImportConfig {
+        config: HubConfig,
+    },
+    /// Pauses bonding, unbonding and withdrawals, so that the state can be exported with
+    /// `ExportState` and migrated to another hub; callable by the owner
+    SetStateExport {
+        enabled: bool,
     },
+    /// Imports a section exported by `ExportState` while the hub is bootstrapped from an export;
+    /// callable by the owner
+    ImportState {
+        export: StateExport,
+    },
+    /// Ends the import of the state and unpauses the hub; callable by the owner
+    CompleteImport {},
 
     /// Submit an unbonding request to the current unbonding queue; automatically invokes `unbond`
     /// if `epoch_time` has elapsed since when the last unbonding queue was executed.
@@ -219,21 +384,94 @@ pub enum ExecuteMsg {
     },
 }
 
+#[cw_serde]
+pub enum RemoveValidatorMode {
+    /// Redelegate to the remaining validators
+    Redelegate,
+    /// Undelegate, e.g. when the validator is tombstoned. The undelegated amount still counts as
+    /// bonded and is used for the next unbonding batches.
+    Undelegate,
+}
+
 #[cw_serde]
 pub enum WithdrawType {
     BlackWhale,
     Bow,
+    /// Executes the withdraw template registered by the owner under the name `msg_template`
+    Generic {
+        msg_template: String,
+    },
+}
+
+/// Execute payload withdrawing a wrapped reward asset, for contracts without a dedicated adapter
+#[cw_serde]
+pub struct WithdrawTemplate {
+    /// Contract that is executed, withdrawals through the template must target it
+    pub contract: String,
+    /// JSON execute message, `{amount}` and `{denom}` are replaced with the withdrawn coin
+    pub msg: String,
+    /// Whether the withdrawn coin is sent along with the message
+    pub send_funds: bool,
+}
+
+impl WithdrawTemplate {
+    pub fn withdraw_msg(&self, denom: &Denom, amount: Uint128) -> CosmosMsg<KujiraMsg> {
+        let msg = self
+            .msg
+            .replace("{amount}", &amount.to_string())
+            .replace("{denom}", &denom.to_string());
+        let funds = if self.send_funds {
+            vec![Coin::new(amount.u128(), denom.to_string())]
+        } else {
+            vec![]
+        };
+
+        CosmosMsg::Wasm(WasmMsg::Execute {
+            contract_addr: self.contract.clone(),
+            msg: Binary::from(msg.into_bytes()),
+            funds,
+        })
+    }
+}
+
+/// Limits a withdrawal of `ClaimFunds`, e.g. to keep a working balance in the vault
+#[cw_serde]
+pub struct WithdrawLimit {
+    /// Denom of the withdrawal that is limited
+    pub denom: Denom,
+    /// Maximum amount withdrawn, the full balance if `None`
+    pub amount: Option<Uint128>,
+    /// Balance that is left in place, nothing is withdrawn below it
+    pub leave: Option<Uint128>,
+}
+
+impl WithdrawLimit {
+    /// Amount withdrawn out of `balance`
+    pub fn apply(&self, balance: Uint128) -> Uint128 {
+        let available = balance.saturating_sub(self.leave.unwrap_or_default());
+        match self.amount {
+            Some(amount) => available.min(amount),
+            None => available,
+        }
+    }
 }
 
 #[cw_serde]
 pub enum CallbackMsg {
     ClaimFunds {
         withdrawals: Option<Vec<(WithdrawType, Addr, Denom)>>,
+        withdraw_limits: Option<Vec<WithdrawLimit>>,
     },
     /// Swap remaining tokens held by the contract to Token
     Swap {
         sender: Addr,
         stages: Option<Vec<Vec<(Addr, Denom)>>>,
+        min_received: Option<Vec<Coin>>,
+    },
+    /// Following the swaps, check that the balances grew by at least the minimum amounts
+    AssertMinimumReceived {
+        snapshot: Vec<Coin>,
+        min_received: Vec<Coin>,
     },
     /// Following the swaps, stake the Token acquired to the whitelisted validators
     Reinvest {},
@@ -254,6 +492,34 @@ impl CallbackMsg {
     }
 }
 
+/// Sent to the contract receiving an unbonding request through `SendUnbondRequest`
+#[cw_serde]
+pub struct UnbondRequestReceiveMsg {
+    /// Account who sent the unbonding request
+    pub sender: String,
+    /// ID of the batch
+    pub id: u64,
+    /// Shares in the batch that were transferred
+    pub shares: Uint128,
+    pub msg: Binary,
+}
+
+impl UnbondRequestReceiveMsg {
+    pub fn into_cosmos_msg<T>(self, contract_addr: impl Into<String>) -> StdResult<CosmosMsg<T>> {
+        Ok(CosmosMsg::Wasm(WasmMsg::Execute {
+            contract_addr: contract_addr.into(),
+            msg: to_binary(&UnbondRequestReceiverExecuteMsg::ReceiveUnbondRequest(self))?,
+            funds: vec![],
+        }))
+    }
+}
+
+/// Execute message of the contracts receiving unbonding requests
+#[cw_serde]
+pub enum UnbondRequestReceiverExecuteMsg {
+    ReceiveUnbondRequest(UnbondRequestReceiveMsg),
+}
+
 #[cw_serde]
 #[derive(QueryResponses)]
 pub enum QueryMsg {
@@ -263,15 +529,74 @@ pub enum QueryMsg {
     /// The contract's current state. Response: `StateResponse`
     #[returns(StateResponse)]
     State {},
+    /// The stake token supply tracked by the contract, cross-checked against the bank module.
+    /// Response: `StakeTokenResponse`
+    #[returns(StakeTokenResponse)]
+    StakeToken {},
     /// The contract's current delegation distribution goal. Response: `WantedDelegationsResponse`
     #[returns(WantedDelegationsResponse)]
     WantedDelegations {},
+    /// Validated shares of the delegation distribution goal saved by the last tune.
+    /// Response: `Option<WantedDelegationsShare>`
+    #[returns(Option<WantedDelegationsShare>)]
+    DelegationGoal {},
     /// The contract's delegation distribution goal based on period. Response: `WantedDelegationsResponse`
     #[returns(WantedDelegationsResponse)]
     SimulateWantedDelegations {
         /// by default uses the next period to look into the future.
         period: Option<u64>,
     },
+    /// Redelegations a rebalance would execute right now, for dry runs. Response: `SimulateRebalanceResponse`
+    #[returns(SimulateRebalanceResponse)]
+    SimulateRebalance {
+        min_redelegation: Option<Uint128>,
+    },
+    /// Time-weighted average exchange rate over the last `window_seconds`, based on the
+    /// observations recorded during reinvest. Response: `ExchangeRateTwapResponse`
+    #[returns(ExchangeRateTwapResponse)]
+    ExchangeRateTwap {
+        window_seconds: u64,
+    },
+    /// Trailing APY over the last `window` seconds and the APY projected from the last harvest,
+    /// based on the exchange rate observations recorded during reinvest. Response: `ApyResponse`
+    #[returns(ApyResponse)]
+    Apy {
+        window: u64,
+    },
+    /// Simulates swapping the current balances through the stages preset and the resulting fee
+    /// split, without executing anything. Response: `SimulateHarvestResponse`
+    #[returns(SimulateHarvestResponse)]
+    SimulateHarvest {},
+    /// The message to bond `amount` utoken, with the exact funds to attach.
+    /// Response: `PreparedMsg`
+    #[returns(PreparedMsg)]
+    BuildBondMsg {
+        amount: Uint128,
+        receiver: Option<String>,
+        min_ustake_received: Option<Uint128>,
+    },
+    /// The message to queue `amount` ustake for unbonding, with the exact funds to attach.
+    /// Response: `PreparedMsg`
+    #[returns(PreparedMsg)]
+    BuildQueueUnbondMsg {
+        amount: Uint128,
+        receiver: Option<String>,
+    },
+    /// The complete config in the format accepted by `ImportConfig`. Response: `HubConfig`
+    #[returns(HubConfig)]
+    ExportConfig {},
+    /// A section of the state in the format accepted by `ImportState`, only available after the
+    /// owner enabled the export with `SetStateExport`. Response: `StateExport`
+    #[returns(StateExport)]
+    ExportState {
+        section: ExportSection,
+    },
+    /// Metadata of the most recent harvest. Response: `Option<LastHarvest>`
+    #[returns(Option<LastHarvest>)]
+    LastHarvest {},
+    /// Operational status of the contract for monitoring. Response: `HealthResponse`
+    #[returns(HealthResponse)]
+    Health {},
     /// The current batch on unbonding requests pending submission. Response: `PendingBatch`
     #[returns(PendingBatch)]
     PendingBatch {},
@@ -280,28 +605,76 @@ pub enum QueryMsg {
     #[returns(Batch)]
     PreviousBatch(u64),
     /// Enumerate all previous batches that have previously been submitted for unbonding but have not
-    /// yet fully withdrawn. Response: `Vec<Batch>`
-    #[returns(Vec<Batch>)]
+    /// yet fully withdrawn. A page skips at most 100 batches outside the time range, so it can
+    /// hold fewer items while `has_more` is set. Response: `PaginatedResponse<Batch, u64>`
+    #[returns(PaginatedResponse<Batch, u64>)]
     PreviousBatches {
         start_after: Option<u64>,
         limit: Option<u32>,
+        /// Iteration order over the batch ids, ascending by default
+        order: Option<OrderBy>,
+        /// Only include batches with `est_unbond_end_time >= start_time`
+        start_time: Option<u64>,
+        /// Only include batches with `est_unbond_end_time < end_time`
+        end_time: Option<u64>,
     },
-    /// Enumerate all outstanding unbonding requests in a given batch. Response: `Vec<UnbondRequestsByBatchResponseItem>`
-    #[returns(Vec<UnbondRequestsByBatchResponseItem>)]
+    /// Enumerate all outstanding unbonding requests in a given batch.
+    /// Response: `PaginatedResponse<UnbondRequestsByBatchResponseItem, String>`
+    #[returns(PaginatedResponse<UnbondRequestsByBatchResponseItem, String>)]
     UnbondRequestsByBatch {
         id: u64,
         start_after: Option<String>,
         limit: Option<u32>,
     },
-    /// Enumreate all outstanding unbonding requests from given a user. Response: `Vec<UnbondRequestsByUserResponseItem>`
-    #[returns(Vec<UnbondRequestsByUserResponseItem>)]
+    /// Number of outstanding unbonding requests and their remaining shares in a given batch.
+    /// Response: `UnbondRequestsByBatchTotalsResponse`
+    #[returns(UnbondRequestsByBatchTotalsResponse)]
+    UnbondRequestsByBatchTotals {
+        id: u64,
+    },
+    /// Number of outstanding unbonding requests and their remaining shares of the contracts in the
+    /// unbond allowlist in a given batch, not included in `UnbondRequestsByBatchTotals`.
+    /// Response: `UnbondRequestsByBatchTotalsResponse`
+    #[returns(UnbondRequestsByBatchTotalsResponse)]
+    AllowlistUnbondTotals {
+        id: u64,
+    },
+    /// Payout addresses of the whitelisted validators. Response: `Vec<ValidatorPayout>`
+    #[returns(Vec<ValidatorPayout>)]
+    ValidatorPayouts {},
+    /// Withdraw templates registered by the owner, by name.
+    /// Response: `Vec<(String, WithdrawTemplate)>`
+    #[returns(Vec<(String, WithdrawTemplate)>)]
+    WithdrawTemplates {},
+    /// Enumerate the governance votes cast by the hub, by proposal id.
+    /// Response: `PaginatedResponse<VoteRecord, u64>`
+    #[returns(PaginatedResponse<VoteRecord, u64>)]
+    VoteHistory {
+        start_after: Option<u64>,
+        limit: Option<u32>,
+        /// Iteration order over the proposal ids, ascending by default
+        order: Option<OrderBy>,
+    },
+    /// Enumerate the latest privileged actions executed on the hub, by id.
+    /// Response: `PaginatedResponse<AuditEntry, u64>`
+    #[returns(PaginatedResponse<AuditEntry, u64>)]
+    AuditLog {
+        start_after: Option<u64>,
+        limit: Option<u32>,
+        /// Iteration order over the ids, ascending by default
+        order: Option<OrderBy>,
+    },
+    /// Enumreate all outstanding unbonding requests from given a user.
+    /// Response: `PaginatedResponse<UnbondRequestsByUserResponseItem, u64>`
+    #[returns(PaginatedResponse<UnbondRequestsByUserResponseItem, u64>)]
     UnbondRequestsByUser {
         user: String,
         start_after: Option<u64>,
         limit: Option<u32>,
     },
-    /// Enumreate all outstanding unbonding requests from given a user. Response: `Vec<UnbondRequestsByUserResponseItemDetails>`
-    #[returns(Vec<UnbondRequestsByUserResponseItemDetails>)]
+    /// Enumreate all outstanding unbonding requests from given a user.
+    /// Response: `PaginatedResponse<UnbondRequestsByUserResponseItemDetails, u64>`
+    #[returns(PaginatedResponse<UnbondRequestsByUserResponseItemDetails, u64>)]
     UnbondRequestsByUserDetails {
         user: String,
         start_after: Option<u64>,
@@ -309,10 +682,25 @@ pub enum QueryMsg {
     },
 }
 
+#[cw_serde]
+pub enum OrderBy {
+    Asc,
+    Desc,
+}
+
+impl From<OrderBy> for Order {
+    fn from(order: OrderBy) -> Self {
+        match order {
+            OrderBy::Asc => Order::Ascending,
+            OrderBy::Desc => Order::Descending,
+        }
+    }
+}
+
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
 pub struct ConfigResponse {
-    /// Account who can call certain privileged functions
-    pub owner: String,
+    /// Account who can call certain privileged functions, `None` once ownership is renounced
+    pub owner: Option<String>,
     /// Pending ownership transfer, awaiting acceptance by the new owner
     pub new_owner: Option<String>,
     /// Address of the Stake token
@@ -330,8 +718,14 @@ pub struct ConfigResponse {
 
     /// Account who can call harvest
     pub operator: String,
+    /// Pending operator transfer, awaiting acceptance by the new operator
+    pub new_operator: Option<String>,
     /// Stages that must be used by permissionless users
     pub stages_preset: Vec<Vec<(Addr, Denom)>>,
+    /// (pair, denom) swaps permissionless users may use in custom stages
+    pub swap_allowlist: Vec<(Addr, Denom)>,
+    /// Maximum spread of each simulated swap in permissionless custom stages
+    pub swap_max_spread: Decimal,
     /// Specifies wether donations are allowed.
     pub allow_donations: bool,
 
@@ -339,6 +733,138 @@ pub struct ConfigResponse {
     pub delegation_strategy: DelegationStrategy, //<String>,
     /// Update the vote_operator
     pub vote_operator: Option<String>,
+    /// Pending vote_operator transfer, awaiting acceptance by the new vote operator
+    pub new_vote_operator: Option<String>,
+    /// Account who can clear the circuit breaker, defaults to the owner
+    pub guardian: Option<String>,
+    /// Maximum relative drop of the exchange rate before the circuit breaker trips
+    pub exchange_rate_tolerance: Option<Decimal>,
+    /// Whether bonding and unbonding are paused by the circuit breaker
+    pub circuit_breaker_tripped: bool,
+    /// Maximum amount of utoken delegated per reinvest
+    pub max_reinvest_per_tx: Option<Uint128>,
+    /// Contracts whose unbonding requests are kept out of the batch statistics of the users
+    pub unbond_allowlist: Vec<String>,
+    /// Minimum time between the last bond of an address for itself and its next unbonding request.
+    /// Advisory only: bonds for other addresses and transfers of the stake token are not tracked
+    pub min_hold_seconds: u64,
+    /// Period over which reinvested rewards are released into the exchange rate
+    pub reward_smoothing_period: u64,
+    /// Time since the last harvest after which bonding pauses until the next harvest
+    pub max_harvest_staleness: u64,
+    /// Periods with a different protocol reward fee
+    pub protocol_fee_schedule: Vec<ProtocolFeePeriod>,
+    /// Minimum time between two executions of operator actions
+    pub action_cooldowns: Vec<ActionCooldown>,
+    /// Shorter epoch after which small batches can already be submitted
+    pub express_epoch: Option<ExpressEpoch>,
+    /// Relative deviation from the delegation goal within which a validator is not rebalanced
+    pub rebalance_tolerance: Decimal,
+    /// Maximum number of items returned by the paginated queries
+    pub max_query_limit: u32,
+}
+
+/// Complete config of the hub, the owner and pending role transfers are not part of it
+#[cw_serde]
+pub struct HubConfig {
+    /// Contract address of the fin-multi router
+    pub fin_multi_contract: String,
+    /// Account who can call harvest with custom stages
+    pub operator: String,
+    /// Account who can vote on governance proposals
+    pub vote_operator: Option<String>,
+    /// Account who can clear the circuit breaker, defaults to the owner
+    pub guardian: Option<String>,
+    /// How often the unbonding queue is to be executed, in seconds
+    pub epoch_period: u64,
+    /// The staking module's unbonding time, in seconds
+    pub unbond_period: u64,
+    /// Initial set of validators who will receive the delegations
+    pub validators: Vec<String>,
+    /// Contract address where fees are sent
+    pub protocol_fee_contract: String,
+    /// Fees that are being applied during reinvest of staking rewards
+    pub protocol_reward_fee: Decimal,
+    /// Stages that must be used by permissionless users
+    pub stages_preset: Vec<Vec<(Addr, Denom)>>,
+    /// (pair, denom) swaps permissionless users may use in custom stages
+    pub swap_allowlist: Vec<(Addr, Denom)>,
+    /// Maximum spread of each simulated swap in permissionless custom stages
+    pub swap_max_spread: Decimal,
+    /// Specifies wether donations are allowed.
+    pub allow_donations: bool,
+    /// Strategy how delegations should be handled
+    pub delegation_strategy: DelegationStrategy,
+    /// Maximum relative drop of the exchange rate before the circuit breaker trips
+    pub exchange_rate_tolerance: Option<Decimal>,
+    /// Maximum amount of utoken delegated per reinvest
+    pub max_reinvest_per_tx: Option<Uint128>,
+    /// Contracts whose unbonding requests are kept out of the batch statistics of the users
+    #[serde(default)]
+    pub unbond_allowlist: Vec<String>,
+    /// Fee applied to the stake tokens of each submitted batch
+    pub unbond_fee: Option<UnbondFee>,
+    /// Minimum time between the last bond of an address for itself and its next unbonding request.
+    /// Advisory only: bonds for other addresses and transfers of the stake token are not tracked
+    #[serde(default)]
+    pub min_hold_seconds: u64,
+    /// Period over which reinvested rewards are released into the exchange rate
+    #[serde(default)]
+    pub reward_smoothing_period: u64,
+    /// Share of the protocol reward fee paid to the payout addresses of the validators
+    pub validator_fee_share: Option<Decimal>,
+    /// Time since the last harvest after which bonding pauses until the next harvest
+    #[serde(default)]
+    pub max_harvest_staleness: u64,
+    /// Periods with a different protocol reward fee
+    #[serde(default)]
+    pub protocol_fee_schedule: Vec<ProtocolFeePeriod>,
+    /// Minimum time between two executions of operator actions
+    #[serde(default)]
+    pub action_cooldowns: Vec<ActionCooldown>,
+    /// Shorter epoch after which small batches can already be submitted
+    pub express_epoch: Option<ExpressEpoch>,
+    /// Relative deviation from the delegation goal within which a validator is not rebalanced
+    #[serde(default)]
+    pub rebalance_tolerance: Decimal,
+}
+
+/// Whether the state of the hub is being migrated from or to another hub
+#[cw_serde]
+pub enum MigrationMode {
+    Export,
+    Import,
+}
+
+#[cw_serde]
+pub enum ExportSection {
+    /// The pending batch, the ids of later batches continue from it
+    PendingBatch {},
+    /// Batches that have been submitted for unbonding but not fully withdrawn, by id
+    PreviousBatches {
+        start_after: Option<u64>,
+        limit: Option<u32>,
+    },
+    /// Outstanding unbonding requests, by batch id and user
+    UnbondRequests {
+        start_after: Option<(u64, String)>,
+        limit: Option<u32>,
+    },
+}
+
+#[cw_serde]
+pub enum StateExport {
+    PendingBatch(PendingBatch),
+    PreviousBatches(Vec<Batch>),
+    UnbondRequests(Vec<UnbondRequest>),
+}
+
+#[cw_serde]
+pub struct ValidatorPayout {
+    /// Whitelisted validator
+    pub validator: String,
+    /// Address receiving the share of the protocol fee of the validator
+    pub payout: Addr,
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
@@ -357,6 +883,196 @@ pub struct StateResponse {
     pub available: Uint128,
     // Total amount of utoken within the contract (bonded + unbonding + available)
     pub tvl_utoken: Uint128,
+    /// Stake tokens of queued unbonding requests, held by the contract until the pending batch
+    /// is submitted and they are burned. Still part of `total_ustake`.
+    pub ustake_escrowed: Uint128,
+    /// Reinvested rewards that are staked but not yet released into `total_utoken`
+    pub utoken_unvested: Uint128,
+    /// Surplus left on the contract by unbonding, e.g. rounding dust, recycled into reinvest over
+    /// the lifetime of the contract
+    pub utoken_dust_recycled: Uint128,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
+pub struct StakeTokenResponse {
+    /// Denom of the stake token
+    pub denom: String,
+    /// Supply of the stake token tracked by the contract
+    pub total_supply: Uint128,
+    /// Supply of the stake token reported by the bank module
+    pub bank_supply: Uint128,
+    /// Whether both supplies match
+    pub in_sync: bool,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
+pub struct ExchangeRateTwapResponse {
+    /// Time-weighted average of the exchange rate, in terms of utoken per ustake
+    pub twap: Decimal,
+    /// Seconds actually covered by observations, can be less than the requested window
+    pub window_seconds: u64,
+    /// Number of observations used
+    pub observations: u32,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
+pub struct ApyResponse {
+    /// APY of the exchange rate growth over the window, compounded at the average harvest interval
+    pub trailing_apy: Decimal,
+    /// APY if the growth of the last harvest repeats at the same interval
+    pub projected_apy: Decimal,
+    /// Number of harvests within the window
+    pub harvests: u32,
+    /// Average seconds between the harvests within the window
+    pub compounding_interval: u64,
+    /// Seconds actually covered by observations, can be less than the requested window
+    pub window_seconds: u64,
+}
+
+/// Message to be executed against the hub, e.g. from multisig tooling
+#[cw_serde]
+pub struct PreparedMsg {
+    /// Address of the hub
+    pub contract_addr: String,
+    pub msg: ExecuteMsg,
+    /// Funds to attach to the message
+    pub funds: Vec<Coin>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
+pub struct SimulateHarvestResponse {
+    /// Simulated swaps, in the order they would be executed
+    pub swaps: Vec<SimulatedSwap>,
+    /// Amount of utoken expected from the swaps
+    pub utoken_from_swaps: Uint128,
+    /// Amount of utoken already unlocked and waiting to be reinvested
+    pub utoken_unlocked: Uint128,
+    /// Amount of utoken that would be sent to the protocol fee contract
+    pub utoken_protocol_fee: Uint128,
+    /// Amount of utoken that would be restaked
+    pub utoken_to_bond: Uint128,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
+pub struct SimulatedSwap {
+    /// FIN pair used for the swap
+    pub pair: Addr,
+    /// Coin offered to the pair
+    pub offer: Coin,
+    /// Coin expected back from the pair
+    pub ask: Coin,
+    /// Spread reported by the pair, in the ask denom
+    pub spread: Uint128,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
+pub struct SimulateRebalanceResponse {
+    /// Redelegations in the order they would be executed
+    pub redelegations: Vec<SimulatedRedelegation>,
+    /// Total amount of utoken that would be moved
+    pub utoken_moved: Uint128,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
+pub struct SimulatedRedelegation {
+    pub src: String,
+    pub dst: String,
+    pub amount: Uint128,
+    /// Whether the source delegation is still locked by a previous redelegation
+    pub blocked: bool,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
+pub struct LastHarvest {
+    /// Time of the harvest, in seconds
+    pub timestamp: u64,
+    /// Account that called the harvest
+    pub caller: Addr,
+    /// Amount of utoken that has been restaked
+    pub utoken_bonded: Uint128,
+    /// Amount of utoken that has been sent to the protocol fee contract
+    pub utoken_protocol_fee: Uint128,
+}
+
+#[cw_serde]
+pub struct HealthResponse {
+    /// Whether bonding is paused, by the circuit breaker, a stale harvest or a state migration
+    pub bonding_paused: bool,
+    /// Whether bonding and unbonding are paused by the circuit breaker
+    pub circuit_breaker_tripped: bool,
+    /// Seconds since the last harvest, `None` before the first harvest
+    pub seconds_since_last_harvest: Option<u64>,
+    /// Whether the last harvest is older than `max_harvest_staleness`
+    pub harvest_stale: bool,
+    /// Whether the pending batch could be submitted but has not been yet
+    pub pending_batch_overdue: bool,
+    /// Number of batches that finished unbonding but are not reconciled yet
+    pub unreconciled_batches: u32,
+    /// Whitelisted validators outside of the active set
+    pub jailed_validators: Vec<String>,
+}
+
+#[cw_serde]
+pub struct VoteRecord {
+    /// Id of the governance proposal
+    pub proposal_id: u64,
+    /// Options of the latest vote with their weights, a plain vote has a single option of weight 1
+    pub votes: Vec<(Decimal, VoteOption)>,
+    /// Vote operator that cast the vote
+    pub operator: Addr,
+    /// Time of the vote, in seconds
+    pub timestamp: u64,
+}
+
+/// Privileged action that was executed successfully
+#[cw_serde]
+pub struct AuditEntry {
+    /// Sequence number of the action
+    pub id: u64,
+    /// Name of the executed message, e.g. `update_config`
+    pub action: String,
+    /// Account that executed the action
+    pub sender: Addr,
+    /// Time of the action, in seconds
+    pub timestamp: u64,
+    /// First 8 bytes of the SHA-256 hash of the message, hex encoded
+    pub params_hash: String,
+}
+
+/// Reinvested rewards that are released linearly into the exchange rate
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
+pub struct RewardsSchedule {
+    /// Amount of utoken not yet released at `start_time`
+    pub utoken: Uint128,
+    /// Start of the release, in seconds
+    pub start_time: u64,
+    /// End of the release, in seconds
+    pub end_time: u64,
+}
+
+impl RewardsSchedule {
+    /// Amount of utoken that is still not released at `time`
+    pub fn unvested(&self, time: u64) -> Uint128 {
+        if time >= self.end_time {
+            Uint128::zero()
+        } else {
+            let remaining = self.end_time - time.max(self.start_time);
+            self.utoken.multiply_ratio(remaining, self.end_time - self.start_time)
+        }
+    }
+}
+
+/// Amounts accumulated during the current epoch, reported and reset when the batch is submitted
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq, Eq, JsonSchema)]
+pub struct EpochCounters {
+    /// Amount of utoken delegated through bonds and donations
+    pub utoken_bonded: Uint128,
+    /// Amount of utoken restaked from harvested rewards, after fees
+    pub utoken_rewards: Uint128,
+    /// Amount of utoken taken from harvested rewards as protocol fee
+    pub utoken_fees: Uint128,
+    /// Exchange rate when the previous batch was submitted, `None` before the first report
+    pub exchange_rate_start: Option<Decimal>,
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
@@ -372,6 +1088,32 @@ pub struct WantedDelegationsShare {
     pub shares: Vec<(String, Decimal)>,
 }
 
+/// Observations of a validator for the performance delegation strategy
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema, Default)]
+pub struct ValidatorPerformance {
+    /// Number of observations
+    pub samples: u64,
+    /// Number of observations in which the validator was in the active set
+    pub active_samples: u64,
+    /// Sum of the share of the rewards kept by the delegators, `1 - commission`, over the
+    /// observations in the active set
+    pub yield_sum: Decimal,
+}
+
+impl ValidatorPerformance {
+    /// Average share of the rewards kept by the delegators, zero while out of the active set
+    pub fn score(&self) -> Decimal {
+        if self.samples == 0 {
+            Decimal::zero()
+        } else {
+            Decimal::from_ratio(
+                self.yield_sum.numerator(),
+                self.yield_sum.denominator() * Uint128::from(self.samples),
+            )
+        }
+    }
+}
+
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
 pub struct PendingBatch {
     /// ID of this batch
@@ -396,6 +1138,82 @@ pub struct FeeConfig {
     pub protocol_fee_contract: Addr,
     /// Fees that are being applied during reinvest of staking rewards
     pub protocol_reward_fee: Decimal, // "1 is 100%, 0.05 is 5%"
+    /// Fee applied to the stake tokens of each submitted unbonding batch
+    pub unbond_fee: Option<UnbondFee>,
+    /// Share of the protocol reward fee paid pro-rata to the delegations of the whitelisted
+    /// validators with a payout address
+    pub validator_fee_share: Option<Decimal>,
+}
+
+/// Protocol reward fee applied instead of `protocol_reward_fee` during a period
+#[cw_serde]
+pub struct ProtocolFeePeriod {
+    /// Start of the period, in seconds
+    pub start_time: u64,
+    /// End of the period, exclusive, in seconds
+    pub end_time: u64,
+    /// Protocol reward fee during the period, in basis points
+    pub bps: u16,
+}
+
+impl ProtocolFeePeriod {
+    pub fn fee(&self) -> Decimal {
+        Decimal::from_ratio(self.bps, BasicPoints::MAX)
+    }
+}
+
+/// Actions of the operator that can be rate limited
+#[cw_serde]
+#[derive(Copy, Eq, Hash)]
+pub enum RateLimitedAction {
+    /// `Harvest` of the operator through custom stages, which are not validated
+    OperatorSwap,
+    /// `Reinvest` of the operator
+    Reinvest,
+}
+
+impl RateLimitedAction {
+    pub fn key(&self) -> &'static str {
+        match self {
+            RateLimitedAction::OperatorSwap => "operator_swap",
+            RateLimitedAction::Reinvest => "reinvest",
+        }
+    }
+}
+
+#[cw_serde]
+pub struct ActionCooldown {
+    pub action: RateLimitedAction,
+    /// Minimum time between two executions of the action, in seconds
+    pub seconds: u64,
+}
+
+/// Batches worth at most `max_utoken` can be submitted once `period` has passed since the previous
+/// batch, instead of waiting for the full epoch
+#[cw_serde]
+pub struct ExpressEpoch {
+    /// Minimum time between two submitted batches, in seconds
+    pub period: u64,
+    /// Largest batch that is submitted early, in utoken
+    pub max_utoken: Uint128,
+}
+
+#[cw_serde]
+#[derive(Eq)]
+pub struct UnbondFee {
+    /// Share of the stake tokens of a batch taken as fee, in basis points. The fee is rounded down
+    pub bps: u16,
+    /// Who is credited with the fee
+    pub recipient: UnbondFeeRecipient,
+}
+
+#[cw_serde]
+#[derive(Eq)]
+pub enum UnbondFeeRecipient {
+    /// The fee stays bonded, raising the exchange rate for the remaining stakers
+    Stakers,
+    /// The fee is sent as stake tokens to the protocol fee contract
+    FeeContract,
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
@@ -410,6 +1228,34 @@ pub struct Batch {
     pub utoken_unclaimed: Uint128,
     /// Estimated time when this batch will finish unbonding
     pub est_unbond_end_time: u64,
+    /// `utoken` per share when the batch was submitted for unbonding
+    pub exchange_rate_submitted: Option<Decimal>,
+    /// `utoken` per share after reconciliation. A lower rate than at submission is the loss to
+    /// slashing and rounding.
+    pub exchange_rate_reconciled: Option<Decimal>,
+}
+
+impl Batch {
+    /// `utoken` per remaining share, `None` once all shares are withdrawn
+    pub fn exchange_rate(&self) -> Option<Decimal> {
+        if self.total_shares.is_zero() {
+            None
+        } else {
+            Some(Decimal::from_ratio(self.utoken_unclaimed, self.total_shares))
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Default, Debug, PartialEq, Eq, JsonSchema)]
+pub struct ReconcileCursor {
+    /// Time the current round started, only batches that finished unbonding before belong to it
+    pub round_start: u64,
+    /// Amount of `utoken` the batches of the round expected to receive
+    pub utoken_matured: Uint128,
+    /// Amount of `utoken` missing when the round started, deducted pro rata from its batches
+    pub utoken_shortfall: Uint128,
+    /// Expected amount of the batches reconciled so far in the round, before deductions
+    pub utoken_reconciled: Uint128,
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
@@ -439,6 +1285,24 @@ impl From<UnbondRequest> for UnbondRequestsByBatchResponseItem {
     }
 }
 
+#[derive(Serialize, Deserialize, Clone, Default, Debug, PartialEq, Eq, JsonSchema)]
+pub struct UnbondRequestsTotals {
+    /// Number of outstanding unbonding requests in the batch
+    pub count: u64,
+    /// Sum of the shares of all outstanding unbonding requests in the batch
+    pub shares: Uint128,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
+pub struct UnbondRequestsByBatchTotalsResponse {
+    /// ID of the batch
+    pub id: u64,
+    /// Number of outstanding unbonding requests in the batch
+    pub count: u64,
+    /// Sum of the shares of all outstanding unbonding requests in the batch
+    pub shares: Uint128,
+}
+
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
 pub struct UnbondRequestsByUserResponseItem {
     /// ID of the batch
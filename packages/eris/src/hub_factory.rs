@@ -0,0 +1,135 @@
+use cosmwasm_schema::{cw_serde, QueryResponses};
+use cosmwasm_std::{Addr, Binary, Decimal};
+
+#[cw_serde]
+pub struct InstantiateMsg {
+    /// Account who can update the presets and guardrails
+    pub owner: String,
+    /// Code id of the hub contract that is instantiated
+    pub hub_code_id: u64,
+    /// Settings shared by all hubs created by the factory
+    pub hub_preset: HubPreset,
+    /// Limits for the settings chosen by the creator of a hub
+    pub guardrails: Guardrails,
+    /// If the factory is the admin of the created hubs and can migrate them, otherwise the
+    /// creator is the admin
+    pub manage_admin: bool,
+}
+
+#[cw_serde]
+pub struct HubPreset {
+    /// fin multi contract addr
+    pub fin_multi_contract: String,
+    /// Account who can call harvest on the created hubs
+    pub operator: String,
+    /// Contract address where fees are sent
+    pub protocol_fee_contract: String,
+    /// How often the unbonding queue is to be executed, in seconds
+    pub epoch_period: u64,
+    /// The staking module's unbonding time, in seconds
+    pub unbond_period: u64,
+}
+
+#[cw_serde]
+pub struct Guardrails {
+    /// Lowest protocol reward fee a creator can choose
+    pub min_protocol_reward_fee: Decimal,
+    /// Highest protocol reward fee a creator can choose
+    pub max_protocol_reward_fee: Decimal,
+    /// Maximum number of initial validators of a hub
+    pub max_validators: u32,
+}
+
+#[cw_serde]
+pub enum ExecuteMsg {
+    /// Instantiate a new hub with the preset settings. The funds are forwarded to the hub, e.g. to
+    /// pay the token factory fee of the new denom.
+    CreateHub {
+        /// Label of the hub contract
+        label: String,
+        /// Subdenom of the liquid staking token
+        denom: String,
+        /// Initial set of validators who will receive the delegations
+        validators: Vec<String>,
+        /// Fees that are being applied during reinvest of staking rewards
+        protocol_reward_fee: Decimal,
+    },
+    /// Update the presets and guardrails, only applies to hubs created afterwards
+    UpdateConfig {
+        hub_code_id: Option<u64>,
+        hub_preset: Option<HubPreset>,
+        guardrails: Option<Guardrails>,
+    },
+    /// Migrate a hub whose admin is managed by the factory to the current hub code id
+    MigrateHub {
+        hub: String,
+        msg: Binary,
+    },
+    /// Hand the admin of a hub managed by the factory over to another account
+    UpdateHubAdmin {
+        hub: String,
+        admin: String,
+    },
+    /// Transfer ownership to another account; will not take effect unless the new owner accepts
+    TransferOwnership {
+        new_owner: String,
+    },
+    /// Accept an ownership transfer
+    AcceptOwnership {},
+    /// Remove the ownership transfer proposal
+    DropOwnershipProposal {},
+}
+
+#[cw_serde]
+#[derive(QueryResponses)]
+pub enum QueryMsg {
+    /// The factory's configurations. Response: `ConfigResponse`
+    #[returns(ConfigResponse)]
+    Config {},
+    /// A hub created by the factory. Response: `HubInfo`
+    #[returns(HubInfo)]
+    Hub {
+        hub: String,
+    },
+    /// Hubs created by the factory, by address. Response: `Vec<HubInfo>`
+    #[returns(Vec<HubInfo>)]
+    Hubs {
+        start_after: Option<String>,
+        limit: Option<u32>,
+    },
+}
+
+#[cw_serde]
+pub struct MigrateMsg {}
+
+#[cw_serde]
+pub struct ConfigResponse {
+    /// Account who can update the presets and guardrails
+    pub owner: String,
+    /// Pending ownership transfer, awaiting acceptance by the new owner
+    pub new_owner: Option<String>,
+    /// Code id of the hub contract that is instantiated
+    pub hub_code_id: u64,
+    /// Settings shared by all hubs created by the factory
+    pub hub_preset: HubPreset,
+    /// Limits for the settings chosen by the creator of a hub
+    pub guardrails: Guardrails,
+    /// If the factory is the admin of the created hubs
+    pub manage_admin: bool,
+}
+
+#[cw_serde]
+pub struct HubInfo {
+    /// Address of the hub contract
+    pub hub: Addr,
+    /// Account who created the hub, and owns it
+    pub creator: Addr,
+    /// Denom of the liquid staking token
+    pub stake_denom: String,
+    /// Code id the hub currently runs
+    pub code_id: u64,
+    /// Time the hub was created, in seconds
+    pub created_at: u64,
+    /// If the factory is the admin of the hub
+    pub admin_managed: bool,
+}
@@ -1,11 +1,15 @@
 // pub mod asset;
 pub mod adapters;
 pub mod amp_gauges;
+pub mod cw20_wrapper;
 pub mod emp_gauges;
 pub mod governance_helper;
 pub mod helper;
 pub mod helpers;
 pub mod hub;
+pub mod hub_factory;
+pub mod ownership;
+pub mod prop_voting;
 pub mod querier;
 pub mod voting_escrow;
 
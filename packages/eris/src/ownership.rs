@@ -0,0 +1,146 @@
+use cosmwasm_schema::cw_serde;
+use cosmwasm_std::{Addr, Api, DepsMut, Event, Response, StdError, StdResult, Storage};
+use cw_storage_plus::Item;
+use thiserror::Error;
+
+/// Errors of the ownership handlers, contracts convert them into their own error type
+#[derive(Error, Debug, PartialEq)]
+pub enum OwnershipError {
+    #[error("{0}")]
+    Std(#[from] StdError),
+
+    #[error("Unauthorized: sender {0} is not owner")]
+    NotOwner(String),
+
+    #[error("Unauthorized: sender {0} is not new owner")]
+    NotNewOwner(String),
+
+    #[error("Unauthorized: ownership has been renounced")]
+    Renounced {},
+}
+
+#[cw_serde]
+pub struct OwnershipResponse {
+    /// Account who can call certain privileged functions, `None` once ownership is renounced
+    pub owner: Option<String>,
+    /// Pending ownership transfer, awaiting acceptance by the new owner
+    pub new_owner: Option<String>,
+}
+
+/// Owner of a contract with a two-step transfer: the owner proposes a new owner, who has to accept
+pub struct Ownership<'a> {
+    /// Account who can call certain privileged functions, removed once ownership is renounced
+    pub owner: Item<'a, Addr>,
+    /// Pending ownership transfer, awaiting acceptance by the new owner
+    pub new_owner: Item<'a, Addr>,
+    /// Prefix of the emitted events and actions, e.g. `erishub`
+    prefix: &'a str,
+}
+
+impl<'a> Ownership<'a> {
+    /// Stores the owner under the `owner` and `new_owner` keys used by all contracts
+    pub fn new(prefix: &'a str) -> Self {
+        Self {
+            owner: Item::new("owner"),
+            new_owner: Item::new("new_owner"),
+            prefix,
+        }
+    }
+
+    pub fn initialize(
+        &self,
+        storage: &mut dyn Storage,
+        api: &dyn Api,
+        owner: &str,
+    ) -> StdResult<()> {
+        self.owner.save(storage, &api.addr_validate(owner)?)
+    }
+
+    pub fn assert_owner(&self, storage: &dyn Storage, sender: &Addr) -> Result<(), OwnershipError> {
+        let owner = self.owner.may_load(storage)?.ok_or(OwnershipError::Renounced {})?;
+        if *sender == owner {
+            Ok(())
+        } else {
+            Err(OwnershipError::NotOwner(sender.to_string()))
+        }
+    }
+
+    pub fn query(&self, storage: &dyn Storage) -> StdResult<OwnershipResponse> {
+        Ok(OwnershipResponse {
+            owner: self.owner.may_load(storage)?.map(|addr| addr.into()),
+            new_owner: self.new_owner.may_load(storage)?.map(|addr| addr.into()),
+        })
+    }
+
+    pub fn transfer_ownership<T>(
+        &self,
+        deps: DepsMut,
+        sender: &Addr,
+        new_owner: String,
+    ) -> Result<Response<T>, OwnershipError> {
+        self.assert_owner(deps.storage, sender)?;
+        self.new_owner.save(deps.storage, &deps.api.addr_validate(&new_owner)?)?;
+
+        Ok(Response::new().add_attribute("action", self.action("transfer_ownership")))
+    }
+
+    pub fn drop_ownership_proposal<T>(
+        &self,
+        deps: DepsMut,
+        sender: &Addr,
+    ) -> Result<Response<T>, OwnershipError> {
+        self.assert_owner(deps.storage, sender)?;
+        self.new_owner.remove(deps.storage);
+
+        Ok(Response::new().add_attribute("action", self.action("drop_ownership_proposal")))
+    }
+
+    pub fn accept_ownership<T>(
+        &self,
+        deps: DepsMut,
+        sender: &Addr,
+    ) -> Result<Response<T>, OwnershipError> {
+        let previous_owner = self.owner.load(deps.storage)?;
+        let new_owner = self
+            .new_owner
+            .may_load(deps.storage)?
+            .ok_or_else(|| OwnershipError::NotNewOwner(sender.to_string()))?;
+
+        if *sender != new_owner {
+            return Err(OwnershipError::NotNewOwner(sender.to_string()));
+        }
+
+        self.owner.save(deps.storage, sender)?;
+        self.new_owner.remove(deps.storage);
+
+        let event = Event::new(self.action("ownership_transferred"))
+            .add_attribute("new_owner", new_owner)
+            .add_attribute("previous_owner", previous_owner);
+
+        Ok(Response::new()
+            .add_event(event)
+            .add_attribute("action", self.action("transfer_ownership")))
+    }
+
+    /// Removes the owner for good. Afterwards `assert_owner` fails with `Renounced`
+    pub fn renounce_ownership<T>(
+        &self,
+        deps: DepsMut,
+        sender: &Addr,
+    ) -> Result<Response<T>, OwnershipError> {
+        self.assert_owner(deps.storage, sender)?;
+        self.owner.remove(deps.storage);
+        self.new_owner.remove(deps.storage);
+
+        let event =
+            Event::new(self.action("ownership_renounced")).add_attribute("previous_owner", sender);
+
+        Ok(Response::new()
+            .add_event(event)
+            .add_attribute("action", self.action("renounce_ownership")))
+    }
+
+    fn action(&self, name: &str) -> String {
+        format!("{}/{}", self.prefix, name)
+    }
+}
@@ -0,0 +1,164 @@
+use cosmwasm_schema::{cw_serde, QueryResponses};
+use cosmwasm_std::{Addr, Decimal, Uint128, VoteOption};
+
+#[cw_serde]
+pub struct InstantiateMsg {
+    /// Account who can open proposals for voting
+    pub owner: String,
+    /// Hub whose stake token is voted with, the contract has to be its vote operator
+    pub hub: String,
+}
+
+#[cw_serde]
+pub enum ExecuteMsg {
+    /// Open a chain proposal for voting until `end_time`, which has to be before the end of the
+    /// chain voting period (only allowed by the owner)
+    OpenProposal {
+        proposal_id: u64,
+        end_time: u64,
+    },
+    /// Vote on an open proposal with the stake token sent with the message, which is locked until
+    /// the voting window ends. Voting again adds the sent tokens and moves all locked tokens of
+    /// the sender to the new option, no tokens are needed to only change the option
+    Vote {
+        proposal_id: u64,
+        vote: VoteOption,
+    },
+    /// Cast the tally of a proposal whose voting window ended as weighted vote of the hub;
+    /// callable by anyone
+    CastVote {
+        proposal_id: u64,
+    },
+    /// Release the stake token locked with the sender's vote, once the voting window ended
+    Withdraw {
+        proposal_id: u64,
+    },
+    /// Accept the vote operator role of the hub proposed by the hub owner
+    AcceptVoteOperator {},
+    /// Transfer ownership to another account; will not take effect unless the new owner accepts
+    TransferOwnership {
+        new_owner: String,
+    },
+    /// Accept an ownership transfer
+    AcceptOwnership {},
+    /// Remove the ownership transfer proposal
+    DropOwnershipProposal {},
+}
+
+#[cw_serde]
+#[derive(QueryResponses)]
+pub enum QueryMsg {
+    /// The contract's configurations. Response: `ConfigResponse`
+    #[returns(ConfigResponse)]
+    Config {},
+    /// A proposal with its live tally. Response: `ProposalResponse`
+    #[returns(ProposalResponse)]
+    Proposal {
+        proposal_id: u64,
+    },
+    /// Proposals with their live tallies, by id. Response: `Vec<ProposalResponse>`
+    #[returns(Vec<ProposalResponse>)]
+    Proposals {
+        start_after: Option<u64>,
+        limit: Option<u32>,
+    },
+    /// The vote of an address on a proposal. Response: `Option<Ballot>`
+    #[returns(Option<Ballot>)]
+    Ballot {
+        proposal_id: u64,
+        voter: String,
+    },
+}
+
+#[cw_serde]
+pub struct MigrateMsg {}
+
+#[cw_serde]
+pub struct ConfigResponse {
+    /// Account who can open proposals for voting
+    pub owner: String,
+    /// Pending ownership transfer, awaiting acceptance by the new owner
+    pub new_owner: Option<String>,
+    /// Hub whose stake token is voted with
+    pub hub: Addr,
+    /// Denom of the stake token
+    pub stake_denom: String,
+}
+
+#[cw_serde]
+#[derive(Default)]
+pub struct Tally {
+    /// Stake token locked with yes votes
+    pub yes: Uint128,
+    /// Stake token locked with no votes
+    pub no: Uint128,
+    /// Stake token locked with abstain votes
+    pub abstain: Uint128,
+    /// Stake token locked with no with veto votes
+    pub no_with_veto: Uint128,
+}
+
+impl Tally {
+    /// Stake token locked with `vote`
+    pub fn option_mut(&mut self, vote: &VoteOption) -> &mut Uint128 {
+        match vote {
+            VoteOption::Yes => &mut self.yes,
+            VoteOption::No => &mut self.no,
+            VoteOption::Abstain => &mut self.abstain,
+            VoteOption::NoWithVeto => &mut self.no_with_veto,
+        }
+    }
+
+    pub fn total(&self) -> Uint128 {
+        self.yes + self.no + self.abstain + self.no_with_veto
+    }
+
+    /// Weights of the options with votes, the last one takes the rounding so that they add up to
+    /// exactly one. Empty without any votes.
+    pub fn weighted_votes(&self) -> Vec<(Decimal, VoteOption)> {
+        let total = self.total();
+        let mut options = vec![
+            (self.yes, VoteOption::Yes),
+            (self.no, VoteOption::No),
+            (self.abstain, VoteOption::Abstain),
+            (self.no_with_veto, VoteOption::NoWithVeto),
+        ]
+        .into_iter()
+        .filter(|(amount, _)| !amount.is_zero())
+        .collect::<Vec<_>>();
+
+        let last = match options.pop() {
+            Some((_, vote)) => vote,
+            None => return vec![],
+        };
+
+        let mut votes = options
+            .into_iter()
+            .map(|(amount, vote)| (Decimal::from_ratio(amount, total), vote))
+            .filter(|(weight, _)| !weight.is_zero())
+            .collect::<Vec<_>>();
+        let assigned = votes.iter().fold(Decimal::zero(), |sum, (weight, _)| sum + *weight);
+        votes.push((Decimal::one() - assigned, last));
+        votes
+    }
+}
+
+#[cw_serde]
+pub struct ProposalResponse {
+    /// ID of the chain proposal
+    pub proposal_id: u64,
+    /// Time the voting window ends, in seconds
+    pub end_time: u64,
+    /// Stake token locked per option
+    pub tally: Tally,
+    /// If the tally has been cast as vote of the hub
+    pub cast: bool,
+}
+
+#[cw_serde]
+pub struct Ballot {
+    /// Option the voter chose
+    pub vote: VoteOption,
+    /// Stake token locked with the vote
+    pub amount: Uint128,
+}
@@ -1,6 +1,7 @@
+use cosmwasm_schema::cw_serde;
 use cosmwasm_std::{
-    to_binary, Addr, BalanceResponse, BankQuery, QuerierWrapper, QueryRequest, StdResult, Uint128,
-    WasmQuery,
+    from_binary, to_binary, to_vec, Addr, BalanceResponse, BankQuery, ContractResult, CustomQuery,
+    Decimal, QuerierWrapper, QueryRequest, StdError, StdResult, SystemResult, Uint128, WasmQuery,
 };
 use kujira::{
     asset::Asset,
@@ -32,6 +33,44 @@ pub fn query_balance(
     Ok(balance.amount.amount)
 }
 
+/// Query of the Kujira oracle module, serialized like the `KujiraQuery::Oracle` custom query, so
+/// that it can be sent through a querier without the Kujira custom query type
+#[cw_serde]
+pub enum OracleQuery {
+    Oracle(OracleExchangeRateQuery),
+}
+
+impl CustomQuery for OracleQuery {}
+
+#[cw_serde]
+pub enum OracleExchangeRateQuery {
+    ExchangeRate {
+        denom: String,
+    },
+}
+
+#[cw_serde]
+pub struct OracleExchangeRateResponse {
+    pub rate: Decimal,
+}
+
+/// USD price of the oracle `symbol` (e.g. "KUJI"). Fails if the chain does not support the oracle
+/// custom query or has no price for the symbol.
+pub fn query_oracle_rate(querier: &QuerierWrapper, symbol: &str) -> StdResult<Decimal> {
+    let request: QueryRequest<OracleQuery> =
+        QueryRequest::Custom(OracleQuery::Oracle(OracleExchangeRateQuery::ExchangeRate {
+            denom: symbol.to_string(),
+        }));
+
+    match querier.raw_query(&to_vec(&request)?) {
+        SystemResult::Ok(ContractResult::Ok(value)) => {
+            Ok(from_binary::<OracleExchangeRateResponse>(&value)?.rate)
+        },
+        SystemResult::Ok(ContractResult::Err(err)) => Err(StdError::generic_err(err)),
+        SystemResult::Err(err) => Err(StdError::generic_err(err.to_string())),
+    }
+}
+
 pub fn simulate(
     querier: &QuerierWrapper,
     pair_contract: Addr,
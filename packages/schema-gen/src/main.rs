@@ -0,0 +1,116 @@
+use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use cosmwasm_schema::{generate_api, Api, QueryResponses};
+use serde::{Deserialize, Serialize};
+
+/// Directory the schemas are written to, if none is passed as argument
+const DEFAULT_OUT_DIR: &str = "artifacts/schema";
+
+/// Entry of `index.json`, one per contract
+#[derive(Serialize)]
+struct IndexEntry {
+    /// Package name of the contract
+    contract_name: String,
+    /// Package version of the contract
+    contract_version: String,
+    /// Schema file of the contract, relative to the index
+    schema: String,
+}
+
+#[derive(Deserialize)]
+struct Manifest {
+    package: Package,
+}
+
+#[derive(Deserialize)]
+struct Package {
+    name: String,
+    version: String,
+}
+
+/// Names the api after the package of the contract, like `write_api!` run from the contract does
+fn with_package(mut api: Api, manifest: &str) -> Api {
+    let manifest: Manifest = toml::from_str(manifest).expect("invalid contract manifest");
+    api.contract_name = manifest.package.name;
+    api.contract_version = manifest.package.version;
+    api
+}
+
+fn hub() -> Api {
+    use eris::hub::{ExecuteMsg, InstantiateMsg, MigrateMsg, QueryMsg};
+
+    let api = generate_api! {
+        instantiate: InstantiateMsg,
+        query: QueryMsg,
+        execute: ExecuteMsg,
+        migrate: MigrateMsg,
+    };
+    with_package(api, include_str!("../../../contracts/hub/Cargo.toml"))
+}
+
+fn hub_factory() -> Api {
+    use eris::hub_factory::{ExecuteMsg, InstantiateMsg, MigrateMsg, QueryMsg};
+
+    let api = generate_api! {
+        instantiate: InstantiateMsg,
+        query: QueryMsg,
+        execute: ExecuteMsg,
+        migrate: MigrateMsg,
+    };
+    with_package(api, include_str!("../../../contracts/hub-factory/Cargo.toml"))
+}
+
+fn cw20_wrapper() -> Api {
+    use eris::cw20_wrapper::{ExecuteMsg, InstantiateMsg, MigrateMsg, QueryMsg};
+
+    let api = generate_api! {
+        instantiate: InstantiateMsg,
+        query: QueryMsg,
+        execute: ExecuteMsg,
+        migrate: MigrateMsg,
+    };
+    with_package(api, include_str!("../../../contracts/cw20-wrapper/Cargo.toml"))
+}
+
+fn prop_voting() -> Api {
+    use eris::prop_voting::{ExecuteMsg, InstantiateMsg, MigrateMsg, QueryMsg};
+
+    let api = generate_api! {
+        instantiate: InstantiateMsg,
+        query: QueryMsg,
+        execute: ExecuteMsg,
+        migrate: MigrateMsg,
+    };
+    with_package(api, include_str!("../../../contracts/prop-voting/Cargo.toml"))
+}
+
+fn write_schema(out_dir: &Path, api: Api) -> IndexEntry {
+    let entry = IndexEntry {
+        schema: format!("{}.json", api.contract_name),
+        contract_name: api.contract_name.clone(),
+        contract_version: api.contract_version.clone(),
+    };
+
+    let json = api.render().to_string().expect("failed to serialize the schema");
+    fs::write(out_dir.join(&entry.schema), json).expect("failed to write the schema");
+    println!("Exported {} {}", entry.contract_name, entry.contract_version);
+
+    entry
+}
+
+/// Writes the schema of every contract and an `index.json` listing them, so that client code
+/// generation consumes a single directory per release. Usage: `eris-schema-gen [out_dir]`
+fn main() {
+    let out_dir = env::args().nth(1).map(PathBuf::from).unwrap_or_else(|| DEFAULT_OUT_DIR.into());
+    fs::create_dir_all(&out_dir).expect("failed to create the output directory");
+
+    let index = vec![hub(), hub_factory(), cw20_wrapper(), prop_voting()]
+        .into_iter()
+        .map(|api| write_schema(&out_dir, api))
+        .collect::<Vec<_>>();
+
+    let json = serde_json::to_string_pretty(&index).expect("failed to serialize the index");
+    fs::write(out_dir.join("index.json"), json).expect("failed to write the index");
+}
@@ -12,6 +12,9 @@ module.exports = {
 
       create: "bash build_schema.sh",
 
+      // all contracts with an index of their names and versions, for ts-codegen
+      generate: "cd .. && cargo run -p eris-schema-gen -- artifacts/schema",
+
       hub: "cd .. && json2ts -i contracts/hub/**/*.json -o ../liquid-staking-scripts/types/kujira/hub",
 
       // ampz: "cd .. && json2ts -i contracts/ampz/schema/*.json -o ../liquid-staking-scripts/types/ampz",